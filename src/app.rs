@@ -1,136 +1,815 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
-use crossterm::event::KeyEvent;
+use anyhow::Context;
+use chrono::{DateTime, Local, Timelike, Utc};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::style::Print;
+use ratatui::layout::{Position, Rect};
 use ratatui::Frame;
 
-use crate::core::metrics;
-use crate::core::typing_session::TypingSession;
-use crate::input::handler::{AppAction, InputHandler};
-use crate::models::{AppConfig, TestResult};
-use crate::quotes::{QuoteManager, QuoteMode};
+use crate::continuous::ContinuousSource;
+use crate::core::achievements::{self, Achievement, Progress};
+use crate::core::anomaly::Anomaly;
+use crate::core::coach::{self, Suggestion};
+use crate::core::metrics::{self, SpeedUnit, WpmAnimator};
+use crate::core::replay::{Replay, ReplayPlayer};
+use crate::core::typing_session::{Confidence, Difficulty, TypingSession};
+use crate::input::handler::{AppAction, InputContext, InputHandler};
+use crate::keyboard::{self, FingerUsage, KeyboardLayout, KeyboardStyle};
+use crate::models::{
+    AppConfig, Bookmark, DailyGoal, DateWindow, PersonalBest, Ranking, RecentForm, SessionReport,
+    SortOrder, SourceSummary, TestResult, TodaySummary,
+};
+use crate::quotes::{
+    CodeLanguage, DrillSet, QuoteDifficulty, QuoteManager, QuoteMode, TextChunker,
+    FILE_CHUNK_CHARS, MAX_DIFFICULTY,
+};
+use crate::scheduler::{Scheduler, Task};
+use crate::series::SessionSeries;
+use crate::share::{self, Clipboard, ClipboardMethod};
+use crate::sound::{KeySounds, SoundStyle};
 use crate::state::{AppState, StateMachine};
 use crate::storage::config::ConfigManager;
 use crate::storage::db::Database;
+use crate::storage::paths::{check_profile_name, Paths};
+use crate::storage::{backup, report};
 use crate::theme::Theme;
-use crate::ui::results_view::ResultsView;
-use crate::ui::typing_view::TypingView;
+use crate::ui::marquee::{truncate_to_width, Marquee};
+use crate::ui::metric_style::Thresholds;
+use crate::ui::profile_picker::ProfilePicker;
+use crate::ui::quit_confirm::QuitConfirm;
+use crate::ui::quote_picker::QuotePicker;
+use crate::ui::results_view::{ResultsView, ResultsViewContext};
+use crate::ui::screensaver::Screensaver;
+use crate::ui::status_line::{StatusLevel, StatusLine};
+use crate::ui::theme_picker::ThemePicker;
+use crate::ui::too_small::draw_if_too_small;
+use crate::ui::typing_view::{self, TypingView, TypingViewContext};
+use crate::words;
+
+const WPM_RECALC_INTERVAL: Duration = Duration::from_millis(250);
+/// About 60 frames a second while the WPM counter is moving
+const ANIMATION_FRAME: Duration = Duration::from_millis(16);
+const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+/// A long attribution scrolls one cell per step
+const MARQUEE_STEP: Duration = Duration::from_millis(300);
+const PRESSED_KEY_FADE: Duration = Duration::from_millis(150);
+/// Most keys lit on the on-screen keyboard at once; older presses drop off first
+const PRESSED_KEY_CAP: usize = 16;
+/// Distinct mistyped words a practice test draws from
+const PRACTICE_WORDS: usize = 30;
+/// Worst words looked for in each quote
+const HARD_WORDS: usize = 100;
+/// Times a word must have been typed for its error rate to rank it
+const MIN_WORD_OCCURRENCES: u32 = 3;
+/// Fewer recorded words than this and practice falls back to random words
+const MIN_PRACTICE_WORDS: usize = 10;
+const ERROR_FLASH: Duration = Duration::from_millis(100);
+/// How long a status line message stays up
+const STATUS_TTL: Duration = Duration::from_secs(4);
+/// How long Ctrl+Z can bring back a test thrown away by a new quote or restart
+const UNDO_WINDOW: Duration = Duration::from_secs(10);
+/// Results in the strip under the typing header
+const RECENT_RESULTS: usize = 5;
+/// A restart this early is a false start: nothing to undo or record
+const FREE_RESTART_CHARS: usize = 5;
+const FREE_RESTART_TIME: Duration = Duration::from_secs(2);
+/// Repeats of the key behind an automatic restart arriving closer together
+/// than this mean it is being held down
+const AUTO_RESTART_DEBOUNCE: Duration = Duration::from_millis(400);
+/// How long an auto-advanced run's speed stays in the header
+const ADVANCE_FLASH: Duration = Duration::from_millis(1500);
+/// Tests in a mode before the results screen says where a run ranks
+const MIN_RANKED_TESTS: i64 = 10;
+/// Cells of the next quote shown under the quote box
+const NEXT_QUOTE_PREVIEW: usize = 40;
+/// Results in each window when comparing recent form with the one before
+pub const RECENT_FORM_RESULTS: usize = 10;
+
+/// Overrides from the command line; they apply to this run only and are
+/// never written back to the config
+#[derive(Debug, Default)]
+pub struct StartupOptions {
+    /// Whose results and settings to use instead of the config's `profile`
+    pub profile: Option<String>,
+    pub mode: Option<QuoteMode>,
+    pub theme: Option<String>,
+    pub custom_text: Option<CustomText>,
+}
+
+/// Text passed with `--file` or `--text`, typed in chunks instead of quotes
+#[derive(Debug)]
+pub struct CustomText {
+    chunks: Vec<String>,
+    source: String,
+    mode: &'static str,
+}
+
+impl CustomText {
+    /// Text from `--text`, saved under the `custom` mode
+    pub fn new(text: &str, source: String) -> Result<Self, String> {
+        Self::split(text, source, "custom")
+    }
+
+    /// The contents of a `--file`, saved under the `file` mode
+    pub fn file(text: &str, source: String) -> Result<Self, String> {
+        Self::split(text, source, "file")
+    }
+
+    fn split(text: &str, source: String, mode: &'static str) -> Result<Self, String> {
+        let chunks = TextChunker::new(FILE_CHUNK_CHARS).chunks(text);
+        if chunks.is_empty() {
+            return Err(format!("{} contains no text to type", source));
+        }
+        Ok(Self {
+            chunks,
+            source,
+            mode,
+        })
+    }
+}
+
+/// A test in progress that a new quote or restart threw away, kept for Ctrl+Z
+struct DiscardedTest {
+    /// Paused when it was thrown away, so the gap doesn't count
+    session: TypingSession,
+    quote_source: String,
+    quote_hash: String,
+    quote_id: Option<usize>,
+    quote_mode: QuoteMode,
+    /// Its row in `abandoned_tests`, removed again if it is brought back
+    abandoned_id: Option<i64>,
+    at: Instant,
+}
+
+/// Runs finished with auto-advance on, for the tally in the header
+#[derive(Debug, Default)]
+struct AdvanceTally {
+    tests: u32,
+    wpm_sum: f64,
+    /// Summed so the average converts with the texts' own word length
+    chars_per_word_sum: f64,
+}
+
+impl AdvanceTally {
+    /// "tests this session: 14, avg 87.2"; `None` before the first
+    fn label(&self, unit: SpeedUnit) -> Option<String> {
+        let tests = self.tests as f64;
+        (self.tests > 0).then(|| {
+            format!(
+                "tests this session: {}, avg {:.1}",
+                self.tests,
+                unit.from_wpm(self.wpm_sum / tests, self.chars_per_word_sum / tests)
+            )
+        })
+    }
+}
 
 pub struct App {
     // Core state
     state_machine: StateMachine,
     session: TypingSession,
     quote_source: String,
+    quote_hash: String,
+    /// Id of the current quote; `None` when the text isn't from the quote list
+    quote_id: Option<usize>,
     quote_mode: QuoteMode,
     quote_manager: QuoteManager,
+    /// `--file`/`--text` content being typed chunk by chunk instead of quotes
+    series: Option<SessionSeries>,
 
     // Configuration
+    paths: Paths,
+    /// Results saved from here on make up the session report
+    launched_at: DateTime<Utc>,
     pub db: Database,
     pub config: AppConfig,
     theme: Theme,
 
     // UI state
     typing_view: TypingView,
-    animated_wpm: f64,
-    last_wpm_for_animation: f64,
-    last_tick: Instant,
+    wpm_animator: WpmAnimator,
+    cursor_visible: bool,
+    /// Drives the scrolling of attributions too long for their line
+    marquee: Marquee,
+    scheduler: Scheduler,
+    warning: Option<String>,
+    /// Set when running on an in-memory database; shown whenever there is no other warning
+    db_warning: Option<String>,
+    screensaver: bool,
+    status_line: StatusLine,
+    /// The quote border is flashing after a wrong key
+    error_flash: bool,
+    sounds: KeySounds,
+
+    // Continuous source
+    continuous: Option<ContinuousSource>,
+    bookmark: Bookmark,
+    source_finished: Option<SourceSummary>,
+
+    quote_picker: Option<QuotePicker>,
+    theme_picker: Option<ThemePicker>,
+    profile_picker: Option<ProfilePicker>,
+    /// Key set of the current drill and its keys, which the keyboard leaves lit
+    drill_set: DrillSet,
+    drill_keys: Vec<char>,
+    /// Difficulty level of the quote being typed; `None` for other texts
+    quote_difficulty: Option<u8>,
+    /// Name the profile in the header; only worth it once there are several
+    show_profile: bool,
+    /// How far back Stats and History look; for this session only
+    date_window: DateWindow,
+    /// The last test thrown away mid-way, until the undo window passes
+    discarded: Option<DiscardedTest>,
+    /// The wrong key behind the last automatic restart and when it last
+    /// arrived; its repeats are ignored while it is held
+    restart_debounce: Option<(char, Instant)>,
+    /// The finished run fell below `min_wpm_to_save` or
+    /// `min_accuracy_to_save`; it is saved only if S is pressed
+    below_threshold: bool,
+    advance_tally: AdvanceTally,
+    /// Speed and accuracy of the run just auto-advanced past, briefly
+    advance_flash: Option<String>,
 
     // Input handling
     input_handler: InputHandler,
-    pressed_keys: Vec<char>,
-    pressed_key_timestamp: Option<Instant>,
+    /// Keyboard labels lit by recent keystrokes, oldest first
+    pressed_keys: Vec<(char, Instant)>,
 
     // Results
     pub last_result: Option<TestResult>,
+    /// Results the database refused, saved again after the next test or on exit
+    pending_results: Vec<TestResult>,
+    /// Outcome of copying the share card, with the card itself if it
+    /// couldn't be copied
+    share_status: Option<(String, Option<String>)>,
+    clipboard: Clipboard,
+    personal_best: Option<PersonalBest>,
+    /// Where the last saved result places in its mode, once there are enough to rank
+    ranking: Option<Ranking>,
+    /// The last saved result's mode, latest results against the ones before
+    recent_form: Option<RecentForm>,
+    /// Tips for the finished run, empty when coaching is off
+    suggestions: Vec<Suggestion>,
+    /// Keystrokes per finger in the finished test
+    finger_usage: FingerUsage,
+    /// Pace of the ghost cursor for the current test, when it is enabled
+    ghost_wpm: Option<f64>,
+    /// Average WPM for the mode, looked up once per quote for the time estimate
+    avg_wpm: Option<f64>,
+    /// Worst words that appear in the current quote, looked up once per quote
+    hard_words: HashSet<String>,
+    /// Characters of the quote that belong to `hard_words`
+    hard_chars: Vec<bool>,
+    /// Opening of the quote the next test will use, shown under the quote box
+    next_quote_preview: Option<String>,
+    /// Latest results, oldest first, for the strip under the header
+    recent_results: Vec<TestResult>,
+    /// Average WPM over every result, which the strip colors against
+    overall_avg_wpm: Option<f64>,
+    /// Best WPM on the quote just finished, this run included
+    quote_best: Option<f64>,
+    /// Days in a row with at least one completed test
+    streak: u32,
+    today: TodaySummary,
+    /// The last test completed the daily goal
+    goal_reached: bool,
+    /// Achievements the last test unlocked, for the results screen
+    achievements: Vec<&'static Achievement>,
+    /// Playback shown while in the Replay screen
+    replay_player: Option<ReplayPlayer>,
 }
 
 impl App {
-    pub fn new() -> anyhow::Result<Self> {
-        let proj_dirs = directories::ProjectDirs::from("", "", "TypingTUI")
-            .ok_or_else(|| anyhow::anyhow!("No home dir"))?;
-        let data_dir = proj_dirs.data_dir();
-        std::fs::create_dir_all(data_dir)?;
-        let db_path = data_dir.join("typing.db");
-        let db = Database::open(db_path.to_str().unwrap())?;
-        let config_mgr = ConfigManager::new()?;
-        let config = config_mgr.load()?;
+    pub fn new(options: StartupOptions) -> anyhow::Result<Self> {
+        Self::with_paths(options, Paths::resolve()?)
+    }
+
+    /// The app with its files under `paths` instead of the resolved dirs
+    pub fn with_paths(options: StartupOptions, paths: Paths) -> anyhow::Result<Self> {
+        // `--profile` wins over the config; a profile's own settings are
+        // layered over the shared ones
+        let shared = ConfigManager::for_paths(&paths)?.load()?;
+        let profile = options
+            .profile
+            .clone()
+            .unwrap_or_else(|| shared.profile.clone());
+        check_profile_name(&profile).context("config.toml")?;
+        let paths = paths.with_profile(&profile);
+        let show_profile = !paths.is_default_profile() || paths.profiles().len() > 1;
+        let config = if paths.is_default_profile() {
+            shared
+        } else {
+            ConfigManager::for_paths(&paths)?.load()?
+        };
+
+        // A broken database shouldn't stop anyone typing; run without saving
+        let (db, db_warning) = match open_database(&paths) {
+            Ok(db) => (db, None),
+            Err(e) => (
+                Database::open_in_memory()?,
+                Some(format!("{:#}. Results won't be saved this session", e)),
+            ),
+        };
 
         // Initialize quote manager
-        let quote_manager = QuoteManager::new()?;
-        let quote_mode = QuoteMode::Medium;
+        // Only the configured language is parsed; the built-in one is the fallback
+        let (mut quote_manager, language_warning) =
+            match QuoteManager::with_language(Some(paths.quotes_dir()), &config.language) {
+                Ok(manager) => (manager, None),
+                Err(e) => (QuoteManager::new()?, Some(format!("{:#}", e))),
+            };
+        quote_manager.set_ratings(db.get_quote_ratings().unwrap_or_default());
+        quote_manager.set_rating_weight(config.quote_rating_weight);
+        quote_manager.set_ascii_only(config.ascii_only);
+        quote_manager.set_languages_dir(paths.quotes_dir());
+        // `--mode` wins over the config; an unknown config value falls back to medium
+        let (quote_mode, mode_warning) = match options.mode {
+            Some(mode) => (mode, None),
+            None => match config.default_mode.parse::<QuoteMode>() {
+                Ok(mode) => (mode, None),
+                Err(e) => (QuoteMode::Medium, Some(format!("config.toml: {}", e))),
+            },
+        };
 
         // Get initial quote
         let quote_obj = quote_manager
             .get_random_quote(quote_mode)
             .ok_or_else(|| anyhow::anyhow!("No quotes available"))?;
 
-        // Load theme from config
-        let theme = Theme::from_name(&config.theme);
+        // Load theme from config, keeping the app usable if a user theme is broken
+        let theme_name = options.theme.as_deref().unwrap_or(&config.theme);
+        let (theme, warning) = match Theme::try_from_name(theme_name) {
+            Ok(theme) => (theme, None),
+            Err(e) => (Theme::dark(), Some(format!("{:#}", e))),
+        };
+        let warning = warning.or(language_warning).or(mode_warning);
 
-        let session = TypingSession::new(quote_obj.text.clone());
-        let typing_view = TypingView::new(false, quote_mode);
+        let mut session = TypingSession::new(quote_obj.text.clone());
+        session.set_strict_completion(config.strict_completion);
+        session.set_auto_indent(config.code_auto_indent);
+        session.set_space_skips_word(config.space_skips_word);
+        session.set_difficulty(Difficulty::from_config(&config.difficulty));
+        session.set_confidence(Confidence::from_config(&config.confidence));
+        let typing_view = TypingView::new(config.show_keyboard, quote_mode);
 
-        Ok(Self {
+        let mut app = Self {
             state_machine: StateMachine::new(AppState::Testing),
             session,
             quote_source: quote_obj.source.clone(),
+            quote_hash: quote_obj.hash.clone(),
+            quote_id: Some(quote_obj.id),
             quote_mode,
             quote_manager,
+            series: None,
+            paths,
+            launched_at: Utc::now(),
             db,
-            config,
             theme,
             typing_view,
-            animated_wpm: 0.0,
-            last_wpm_for_animation: 0.0,
-            last_tick: Instant::now(),
-            input_handler: InputHandler::new(),
+            wpm_animator: WpmAnimator::new(),
+            cursor_visible: true,
+            marquee: Marquee::default(),
+            scheduler: Scheduler::new(),
+            warning,
+            db_warning,
+            screensaver: false,
+            status_line: StatusLine::new(),
+            error_flash: false,
+            sounds: KeySounds::silent(),
+            continuous: None,
+            bookmark: Bookmark::default(),
+            source_finished: None,
+            quote_picker: None,
+            theme_picker: None,
+            profile_picker: None,
+            drill_set: DrillSet::HomeRow,
+            drill_keys: Vec::new(),
+            quote_difficulty: None,
+            show_profile,
+            date_window: DateWindow::All,
+            discarded: None,
+            restart_debounce: None,
+            below_threshold: false,
+            advance_tally: AdvanceTally::default(),
+            advance_flash: None,
+            input_handler: InputHandler::new(
+                zen_end_key(&config.zen_end_key),
+                config.shift_uppercase,
+            ),
             pressed_keys: Vec::new(),
-            pressed_key_timestamp: None,
             last_result: None,
-        })
+            pending_results: Vec::new(),
+            share_status: None,
+            clipboard: Clipboard::default(),
+            personal_best: None,
+            ranking: None,
+            recent_form: None,
+            suggestions: Vec::new(),
+            finger_usage: FingerUsage::default(),
+            ghost_wpm: None,
+            avg_wpm: None,
+            hard_words: HashSet::new(),
+            hard_chars: Vec::new(),
+            next_quote_preview: None,
+            recent_results: Vec::new(),
+            overall_avg_wpm: None,
+            quote_best: None,
+            streak: 0,
+            today: TodaySummary::default(),
+            goal_reached: false,
+            achievements: Vec::new(),
+            replay_player: None,
+            config,
+        };
+        app.load_continuous_source();
+        app.refresh_daily();
+        app.pick_ghost();
+        app.load_hard_words();
+        app.load_avg_wpm();
+        app.load_recent_results();
+        if let Some(custom) = options.custom_text {
+            app.series = Some(SessionSeries::new(
+                custom.source,
+                custom.mode,
+                custom.chunks,
+            ));
+            app.reset();
+        } else if matches!(
+            quote_mode,
+            QuoteMode::Zen
+                | QuoteMode::Continuous
+                | QuoteMode::Practice
+                | QuoteMode::Code
+                | QuoteMode::Drill
+        ) {
+            app.reset();
+        }
+        if let Some(problems) = app.db.validate().ok().and_then(|r| r.message()) {
+            app.set_message(problems, StatusLevel::Error);
+        }
+        app.open_sounds();
+        let now = Instant::now();
+        app.arm_cursor_blink(now);
+        app.arm_screensaver(now);
+        Ok(app)
+    }
+
+    /// The key a mouse event stands for in a screen filling `area`: the wheel
+    /// scrolls lists and a click on a header hint presses its key. The wheel
+    /// does nothing while typing.
+    pub fn mouse_key(&self, mouse: MouseEvent, area: Rect) -> Option<KeyEvent> {
+        let scrollable = matches!(
+            self.state(),
+            AppState::History
+                | AppState::HistoryDetail
+                | AppState::Stats
+                | AppState::QuotePicker
+                | AppState::ThemePicker
+                | AppState::ProfilePicker
+        );
+        match mouse.kind {
+            MouseEventKind::ScrollUp if scrollable => Some(KeyEvent::from(KeyCode::Up)),
+            MouseEventKind::ScrollDown if scrollable => Some(KeyEvent::from(KeyCode::Down)),
+            MouseEventKind::Down(MouseButton::Left)
+                if self.state() == AppState::Testing && !self.screensaver =>
+            {
+                let position = Position::new(mouse.column, mouse.row);
+                typing_view::header_buttons(area, self.config.focus_mode)
+                    .into_iter()
+                    .find(|(rect, _)| rect.contains(position))
+                    .map(|(_, key)| key)
+            }
+            _ => None,
+        }
+    }
+
+    /// Terminal window title for the current screen, e.g. "TUItype — 82 WPM"
+    /// after a test
+    pub fn window_title(&self) -> String {
+        let screen = match self.state() {
+            AppState::Testing | AppState::QuitConfirm => return "TUItype".to_string(),
+            AppState::Results => {
+                let unit = self.speed_unit();
+                format!(
+                    "{:.0} {}",
+                    unit.from_wpm(self.session.wpm(), self.session.chars_per_word()),
+                    unit.label()
+                )
+            }
+            AppState::History | AppState::HistoryDetail => "History".to_string(),
+            AppState::Stats => "Stats".to_string(),
+            AppState::Heatmap => "Heatmap".to_string(),
+            AppState::Settings => "Settings".to_string(),
+            AppState::QuotePicker => "Quotes".to_string(),
+            AppState::Replay => "Replay".to_string(),
+            AppState::ThemePicker => "Theme".to_string(),
+            AppState::ProfilePicker => "Profile".to_string(),
+        };
+        format!("TUItype — {}", screen)
+    }
+
+    pub fn mouse(&self) -> bool {
+        self.config.mouse
     }
 
     pub fn handle_input(&mut self, key: KeyEvent) -> Option<AppAction> {
-        let action = self
-            .input_handler
-            .handle(key, self.state(), self.session.is_complete());
+        self.arm_screensaver(Instant::now());
+        // The key that wakes the screensaver is not typing input
+        if self.screensaver {
+            self.screensaver = false;
+            return None;
+        }
+
+        let ctx = InputContext {
+            state: self.state(),
+            is_complete: self.session.is_complete(),
+            expected: self.session.expected_char(),
+            mode: self.quote_mode,
+        };
+        let action = self.input_handler.handle(key, &ctx);
 
         match &action {
+            // Quitting mid-test asks first; the prompt's own "yes" falls through
+            AppAction::Quit
+                if self.state() == AppState::Testing
+                    && self.session.is_started()
+                    && !self.session.is_complete() =>
+            {
+                self.session.pause();
+                self.state_machine.transition(AppState::QuitConfirm);
+                return None;
+            }
+            AppAction::Quit => {
+                self.record_abandonment();
+            }
+            // Enter only types in text that has line breaks
+            AppAction::TypeChar('\n') if !self.session.quote().contains('\n') => {
+                return None;
+            }
             AppAction::TypeChar(c) => {
+                let now = Instant::now();
+                if let Some((held, at)) = self.restart_debounce.take()
+                    && held == *c
+                    && now.saturating_duration_since(at) < AUTO_RESTART_DEBOUNCE
+                {
+                    self.restart_debounce = Some((held, now));
+                    return None;
+                }
+                let mistakes = self.session.mistakes();
                 let is_complete = self.session.type_char(*c);
-                self.pressed_keys.clear();
-                self.pressed_keys.push(*c);
-                self.pressed_key_timestamp = Some(Instant::now());
+                if self.session.mistakes() > mistakes {
+                    self.sounds.error();
+                    self.error_feedback(now);
+                    if self.config.auto_restart_on_first_error && !self.session.is_zen() {
+                        self.restart_debounce = Some((*c, now));
+                        self.restart_session();
+                        return None;
+                    }
+                } else {
+                    self.sounds.key();
+                }
+                let (key, shift) = keyboard::base_key(*c);
+                if shift {
+                    self.press_key(keyboard::SHIFT_LABEL, now);
+                }
+                self.press_key(key, now);
+                if !self.scheduler.is_scheduled(Task::WpmRecalc) {
+                    self.scheduler
+                        .every(Task::WpmRecalc, WPM_RECALC_INTERVAL, now);
+                }
+                // Keep the cursor solid while typing; blinking resumes from here
+                self.arm_cursor_blink(now);
+                if self.config.afk_seconds > 0 {
+                    self.scheduler.once(
+                        Task::AfkCheck,
+                        Duration::from_secs(self.config.afk_seconds),
+                        now,
+                    );
+                }
 
                 if is_complete {
                     self.finish_test();
                 }
             }
+            AppAction::EndTest if self.session.is_zen() && self.session.finish() => {
+                self.finish_test();
+            }
             AppAction::Backspace => {
-                self.session.backspace();
+                if self.session.backspace() {
+                    self.finish_test();
+                }
+                self.press_key(keyboard::BACKSPACE_LABEL, Instant::now());
             }
-            AppAction::DeleteWord => {
-                self.session.delete_word();
+            AppAction::DeleteWord if self.session.delete_word() => {
+                self.finish_test();
             }
             AppAction::CycleMode => {
-                self.quote_mode = match self.quote_mode {
+                let mode = match self.quote_mode {
                     QuoteMode::Short => QuoteMode::Medium,
                     QuoteMode::Medium => QuoteMode::Long,
-                    QuoteMode::Long => QuoteMode::Short,
+                    QuoteMode::Long => QuoteMode::Zen,
+                    QuoteMode::Zen if self.continuous.is_some() => QuoteMode::Continuous,
+                    QuoteMode::Zen | QuoteMode::Continuous => QuoteMode::Practice,
+                    QuoteMode::Practice => QuoteMode::Code,
+                    QuoteMode::Code => QuoteMode::Drill,
+                    QuoteMode::Drill => QuoteMode::Short,
                 };
-                self.reset();
+                self.change_mode(mode);
+            }
+            AppAction::RestartSource => {
+                self.restart_source();
+            }
+            // A test under way keeps its rules; the new ones start with the next
+            AppAction::CycleDifficulty => {
+                let difficulty = Difficulty::from_config(&self.config.difficulty).next();
+                self.config.difficulty = difficulty.as_str().to_string();
+                self.save_config().ok();
+                let when = if self.session.is_started() {
+                    ", from the next test"
+                } else {
+                    ""
+                };
+                self.set_message(
+                    format!("Difficulty: {}{}", difficulty.as_str(), when),
+                    StatusLevel::Info,
+                );
+                if !self.session.is_started() {
+                    self.reset();
+                }
+            }
+            AppAction::CycleErrorFeedback => {
+                let feedback = ErrorFeedback::from_config(&self.config.error_feedback).next();
+                self.config.error_feedback = feedback.as_str().to_string();
+                self.save_config().ok();
+            }
+            AppAction::CycleQuoteDifficulty => {
+                let difficulty =
+                    QuoteDifficulty::from_config(&self.config.max_quote_difficulty).next();
+                self.config.max_quote_difficulty = difficulty.as_str().to_string();
+                self.save_config().ok();
+                self.set_message(
+                    format!(
+                        "Quote difficulty: {} (up to {} of {})",
+                        difficulty.as_str(),
+                        difficulty.max_level(),
+                        MAX_DIFFICULTY
+                    ),
+                    StatusLevel::Info,
+                );
+                if !self.session.is_started() {
+                    self.reset();
+                }
+                self.next_quote_preview = self.peek_next_quote();
+            }
+            AppAction::CycleDateWindow => {
+                self.date_window = self.date_window.next();
+            }
+            AppAction::ToggleGhost => {
+                self.config.ghost = !self.config.ghost;
+                self.save_config().ok();
+                self.pick_ghost();
+            }
+            AppAction::RateQuote(rating)
+                if !self.quote_hash.is_empty()
+                    && self.db.set_quote_rating(&self.quote_hash, *rating).is_ok() =>
+            {
+                self.quote_manager.set_rating(&self.quote_hash, *rating);
+            }
+            AppAction::ForceSave => {
+                self.force_save();
+            }
+            AppAction::CopyResult => {
+                self.copy_result();
+            }
+            AppAction::WriteReport => match self.write_report() {
+                Ok(path) => self.set_message(
+                    format!("Report written to {}", path.display()),
+                    StatusLevel::Success,
+                ),
+                Err(e) => self.set_message(format!("{:#}", e), StatusLevel::Error),
+            },
+            AppAction::PlayReplay if self.state() == AppState::Results => {
+                self.play_replay(self.session.replay().clone());
+            }
+            AppAction::PlayReplay if self.state() == AppState::Replay => {
+                self.control_replay();
             }
             AppAction::NewQuote => {
                 self.reset();
             }
+            AppAction::Undo => {
+                self.undo_discard();
+            }
             AppAction::Restart => {
                 self.restart();
             }
             AppAction::ToggleKeyboard => {
-                let new_show = !self.typing_view.show_keyboard();
-                self.typing_view = TypingView::new(new_show, self.quote_mode);
+                self.config.show_keyboard = !self.typing_view.show_keyboard();
+                self.typing_view = TypingView::new(self.config.show_keyboard, self.quote_mode);
+                self.save_config().ok();
+            }
+            AppAction::ToggleAutoAdvance => {
+                self.config.auto_advance = !self.config.auto_advance;
+                self.save_config().ok();
+                self.set_message(
+                    format!(
+                        "Auto-advance {}",
+                        if self.config.auto_advance {
+                            "on"
+                        } else {
+                            "off"
+                        }
+                    ),
+                    StatusLevel::Info,
+                );
+            }
+            AppAction::ToggleFocus => {
+                self.config.focus_mode = !self.config.focus_mode;
+                self.save_config().ok();
+            }
+            AppAction::ShowThemePicker => {
+                self.theme_picker = Some(ThemePicker::new(&self.theme));
+                // The test waits while a theme is chosen
+                self.session.pause();
+                self.state_machine.transition(AppState::ThemePicker);
+            }
+            AppAction::NavigateUp | AppAction::NavigateDown
+                if self.state() == AppState::ThemePicker =>
+            {
+                if let Some(picker) = &mut self.theme_picker {
+                    if matches!(action, AppAction::NavigateUp) {
+                        picker.previous();
+                    } else {
+                        picker.next();
+                    }
+                    if let Some(theme) = picker.selected() {
+                        self.theme = theme.clone();
+                    }
+                }
+            }
+            AppAction::Select if self.state() == AppState::ThemePicker => {
+                if self.theme_picker.take().is_some() {
+                    self.config.theme = self.theme.name.clone();
+                    self.save_config().ok();
+                    self.warning = None;
+                }
+                self.session.resume();
+                self.state_machine.go_back();
+            }
+            AppAction::ShowProfilePicker => {
+                self.profile_picker = Some(ProfilePicker::new(
+                    self.paths.profiles(),
+                    &self.paths.profile,
+                ));
+                self.session.pause();
+                self.state_machine.transition(AppState::ProfilePicker);
+            }
+            AppAction::NavigateUp | AppAction::NavigateDown
+                if self.state() == AppState::ProfilePicker =>
+            {
+                if let Some(picker) = &mut self.profile_picker {
+                    if matches!(action, AppAction::NavigateUp) {
+                        picker.previous();
+                    } else {
+                        picker.next();
+                    }
+                }
+            }
+            AppAction::FilterChar(c) if self.state() == AppState::ProfilePicker => {
+                if let Some(picker) = &mut self.profile_picker {
+                    picker.push_char(*c);
+                }
+            }
+            AppAction::FilterBackspace if self.state() == AppState::ProfilePicker => {
+                if let Some(picker) = &mut self.profile_picker {
+                    picker.pop_char();
+                }
+            }
+            AppAction::Select if self.state() == AppState::ProfilePicker => {
+                let chosen = self
+                    .profile_picker
+                    .as_ref()
+                    .and_then(|picker| picker.chosen())
+                    .map(str::to_string);
+                match chosen {
+                    Some(profile) if profile != self.paths.profile => {
+                        if let Err(e) = self.switch_profile(&profile) {
+                            self.set_message(format!("{:#}", e), StatusLevel::Error);
+                        }
+                    }
+                    _ => {
+                        self.profile_picker = None;
+                        self.session.resume();
+                        self.state_machine.go_back();
+                    }
+                }
             }
-            AppAction::CycleTheme => {
-                self.cycle_theme();
+            AppAction::CycleLanguage => {
+                self.cycle_language();
             }
             AppAction::ShowHistory => {
                 self.state_machine.transition(AppState::History);
@@ -138,8 +817,69 @@ impl App {
             AppAction::ShowStats => {
                 self.state_machine.transition(AppState::Stats);
             }
-            AppAction::BackToTesting => {
-                self.state_machine.transition(AppState::Testing);
+            AppAction::ShowHeatmap => {
+                self.state_machine.transition(AppState::Heatmap);
+            }
+            AppAction::ShowSettings => {
+                self.state_machine.transition(AppState::Settings);
+            }
+            AppAction::Back | AppAction::CloseDetails => {
+                if self.state() == AppState::QuitConfirm {
+                    self.session.resume();
+                }
+                if self.state() == AppState::ThemePicker {
+                    if let Some(picker) = self.theme_picker.take() {
+                        self.theme = picker.original().clone();
+                    }
+                    self.session.resume();
+                }
+                if self.state() == AppState::ProfilePicker {
+                    self.profile_picker = None;
+                    self.session.resume();
+                }
+                if self.state() == AppState::Replay {
+                    self.replay_player = None;
+                    self.scheduler.cancel(Task::ReplayFrame);
+                }
+                self.state_machine.go_back();
+                if !self.is_open(AppState::QuotePicker) {
+                    self.quote_picker = None;
+                }
+            }
+            AppAction::ShowQuotePicker => {
+                self.quote_picker = Some(QuotePicker::new(&self.quote_manager));
+                self.state_machine.transition(AppState::QuotePicker);
+            }
+            AppAction::FilterChar(c) => {
+                if let Some(picker) = &mut self.quote_picker {
+                    picker.push_char(*c, &self.quote_manager);
+                }
+            }
+            AppAction::FilterBackspace => {
+                if let Some(picker) = &mut self.quote_picker {
+                    picker.pop_char(&self.quote_manager);
+                }
+            }
+            AppAction::NavigateUp if self.state() == AppState::QuotePicker => {
+                if let Some(picker) = &mut self.quote_picker {
+                    picker.previous();
+                }
+            }
+            AppAction::NavigateDown if self.state() == AppState::QuotePicker => {
+                if let Some(picker) = &mut self.quote_picker {
+                    picker.next();
+                }
+            }
+            AppAction::Select if self.state() == AppState::QuotePicker => {
+                if let Some(id) = self.quote_picker.take().and_then(|p| p.selected_id()) {
+                    self.start_quote(id);
+                } else {
+                    self.state_machine.go_back();
+                }
+            }
+            AppAction::Select if self.state() == AppState::History => {
+                self.marquee.reset();
+                self.state_machine.transition(AppState::HistoryDetail);
             }
             _ => {}
         }
@@ -148,101 +888,1791 @@ impl App {
     }
 
     pub fn on_tick(&mut self) {
-        if self.session.is_complete() {
-            return;
+        for task in self.scheduler.due(Instant::now()) {
+            match task {
+                Task::WpmRecalc => {
+                    self.session.update_metrics();
+                    if !self.scheduler.is_scheduled(Task::WpmAnimation) {
+                        self.scheduler
+                            .every(Task::WpmAnimation, ANIMATION_FRAME, Instant::now());
+                    }
+                }
+                Task::WpmAnimation => {
+                    let target = self.session.wpm();
+                    // Nothing left to ease; the next recalculation starts it again
+                    if self.wpm_animator.update(target) == target {
+                        self.scheduler.cancel(Task::WpmAnimation);
+                    }
+                }
+                Task::CursorBlink => {
+                    self.cursor_visible = !self.cursor_visible;
+                }
+                Task::MarqueeStep => {
+                    self.marquee.advance();
+                }
+                Task::PressedKeyFade => {
+                    self.fade_pressed_keys(Instant::now());
+                }
+                Task::ErrorFlash => {
+                    self.error_flash = false;
+                }
+                Task::AfkCheck => {
+                    let discard = !self.config.afk_behavior.eq_ignore_ascii_case("pause");
+                    self.session.check_afk(
+                        Duration::from_secs(self.config.afk_seconds),
+                        discard,
+                        Instant::now(),
+                    );
+                }
+                Task::Screensaver => {
+                    self.screensaver =
+                        self.state() == AppState::Testing && !self.session.is_complete();
+                }
+                // The last frame stays on screen until Esc
+                Task::ReplayFrame
+                    if self
+                        .replay_player
+                        .as_ref()
+                        .is_none_or(|player| player.is_finished(Instant::now())) =>
+                {
+                    self.scheduler.cancel(Task::ReplayFrame);
+                }
+                Task::ReplayFrame => {}
+                Task::StatusExpiry => {
+                    self.status_line.expire(Instant::now());
+                    self.arm_status_expiry(Instant::now());
+                }
+                Task::AdvanceFlash => {
+                    self.advance_flash = None;
+                }
+            }
         }
+    }
+
+    /// When the event loop next needs to wake up for a timer
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.scheduler.next_deadline()
+    }
 
+    /// Show `text` at the bottom of every screen for a few seconds, after
+    /// any messages already waiting
+    pub fn set_message(&mut self, text: String, level: StatusLevel) {
         let now = Instant::now();
-        if now.duration_since(self.last_tick) >= Duration::from_millis(250) {
-            self.last_tick = now;
-            self.session.update_metrics();
-            self.update_wpm_animation();
+        self.status_line.set_message(text, level, STATUS_TTL, now);
+        self.arm_status_expiry(now);
+    }
+
+    /// Wake up when the message on screen runs out
+    fn arm_status_expiry(&mut self, now: Instant) {
+        if let Some(deadline) = self.status_line.deadline() {
+            self.scheduler.once(
+                Task::StatusExpiry,
+                deadline.saturating_duration_since(now),
+                now,
+            );
         }
+    }
 
-        if let Some(timestamp) = self.pressed_key_timestamp {
-            if now.duration_since(timestamp) >= Duration::from_millis(120) {
-                self.pressed_keys.clear();
-                self.pressed_key_timestamp = None;
+    /// The status line over the bottom row; drawn after every screen
+    pub fn draw_status(&self, frame: &mut Frame) {
+        self.status_line.draw(frame, &self.theme);
+    }
+
+    fn arm_cursor_blink(&mut self, now: Instant) {
+        self.cursor_visible = true;
+        if self.config.cursor_blink {
+            self.scheduler
+                .every(Task::CursorBlink, CURSOR_BLINK_INTERVAL, now);
+        }
+    }
+
+    /// (Re)start the idle countdown; called on every keypress
+    fn arm_screensaver(&mut self, now: Instant) {
+        match self.config.screensaver_minutes {
+            0 => self.scheduler.cancel(Task::Screensaver),
+            minutes => {
+                self.scheduler
+                    .once(Task::Screensaver, Duration::from_secs(minutes * 60), now)
             }
         }
     }
 
-    fn update_wpm_animation(&mut self) {
-        self.animated_wpm = metrics::animate_wpm(
-            self.animated_wpm,
-            self.session.wpm(),
-            &mut self.last_wpm_for_animation,
-        );
+    fn stop_timers(&mut self) {
+        self.scheduler.cancel(Task::WpmRecalc);
+        self.scheduler.cancel(Task::WpmAnimation);
+        self.scheduler.cancel(Task::AfkCheck);
+        self.scheduler.cancel(Task::CursorBlink);
+        self.cursor_visible = true;
     }
 
     pub fn draw(&self, frame: &mut Frame) {
-        match self.state() {
-            AppState::Testing if self.session.is_complete() => {
-                ResultsView::draw(frame, &self.session, &self.quote_source, &self.theme);
+        if draw_if_too_small(frame) {
+            return;
+        }
+        match self.screen() {
+            AppState::Results => self.draw_results(frame),
+            AppState::Testing if self.screensaver => Screensaver::draw(frame, &self.theme),
+            AppState::Testing => self.draw_typing(frame),
+            AppState::QuotePicker => {
+                if let Some(picker) = &self.quote_picker {
+                    picker.draw(frame, frame.area(), &self.quote_manager, &self.theme);
+                }
             }
-            AppState::Results => {
-                ResultsView::draw(frame, &self.session, &self.quote_source, &self.theme);
-            }
-            AppState::Testing => {
-                self.typing_view.draw(
-                    frame,
-                    &self.session,
-                    &self.quote_source,
-                    &self.theme,
-                    self.animated_wpm,
-                );
+            AppState::QuitConfirm => {
+                self.draw_typing(frame);
+                QuitConfirm::draw(frame, &self.theme);
             }
-            _ => {} // History and Stats are handled separately
+            AppState::Replay => self.draw_replay(frame),
+            _ => {} // History, Stats, Heatmap and Settings are handled separately
+        }
+    }
+
+    /// The theme or profile picker over whichever screen is underneath
+    pub fn draw_pickers(&self, frame: &mut Frame) {
+        if let Some(picker) = &self.theme_picker {
+            picker.draw(frame, &self.theme);
+        }
+        if let Some(picker) = &self.profile_picker {
+            picker.draw(frame, &self.theme);
         }
     }
 
+    /// Light `key` on the on-screen keyboard until it fades
+    fn press_key(&mut self, key: char, now: Instant) {
+        if self.pressed_keys.len() >= PRESSED_KEY_CAP {
+            self.pressed_keys.remove(0);
+        }
+        self.pressed_keys.push((key, now));
+        if !self.scheduler.is_scheduled(Task::PressedKeyFade) {
+            self.scheduler
+                .once(Task::PressedKeyFade, PRESSED_KEY_FADE, now);
+        }
+    }
+
+    /// Drop presses older than the fade and wake up again for the next one
+    fn fade_pressed_keys(&mut self, now: Instant) {
+        self.pressed_keys
+            .retain(|&(_, at)| now.duration_since(at) < PRESSED_KEY_FADE);
+        if let Some(&(_, oldest)) = self.pressed_keys.first() {
+            let delay = PRESSED_KEY_FADE.saturating_sub(now.duration_since(oldest));
+            self.scheduler.once(Task::PressedKeyFade, delay, now);
+        }
+    }
+
+    fn draw_typing(&self, frame: &mut Frame) {
+        let pressed_keys: Vec<char> = self.pressed_keys.iter().map(|&(key, _)| key).collect();
+        let goal = self.goal_progress();
+        let pending = self.pending_warning();
+        let quote_size = self.quote_size();
+        let recent = self.recent_speeds();
+        let tally = self
+            .advance_tally
+            .label(self.speed_unit())
+            .filter(|_| self.config.auto_advance);
+        let ctx = TypingViewContext {
+            recent: &recent,
+            advance_flash: self.advance_flash.as_deref(),
+            advance_tally: tally.as_deref(),
+            goal: goal.as_deref(),
+            quote_size: quote_size.as_deref(),
+            quote_difficulty: self.quote_difficulty,
+            quote_source: &self.quote_source,
+            marquee: &self.marquee,
+            animated_wpm: self.wpm_animator.value(),
+            speed_unit: self.speed_unit(),
+            accuracy_thresholds: self.accuracy_thresholds(),
+            wpm_thresholds: self.avg_wpm.map(Thresholds::wpm),
+            cursor_visible: self.cursor_visible,
+            warning: pending
+                .as_deref()
+                .or(self.warning.as_deref())
+                .or(self.db_warning.as_deref()),
+            keyboard_style: self.keyboard_style(),
+            show_progress: self.config.show_progress,
+            focus: self.config.focus_mode,
+            language: match self.quote_mode {
+                QuoteMode::Code => self.code_language().as_str(),
+                QuoteMode::Drill => self.drill_set.as_str(),
+                _ => self.quote_manager.language(),
+            },
+            streak: self.streak,
+            profile: self.show_profile.then_some(self.paths.profile.as_str()),
+            error_flash: self.error_flash,
+            pressed_keys: &pressed_keys,
+            drill_keys: (self.quote_mode == QuoteMode::Drill).then_some(&self.drill_keys[..]),
+            hard_chars: if self.config.highlight_hard_words {
+                &self.hard_chars
+            } else {
+                &[]
+            },
+            next_quote: self.next_quote_preview.as_deref(),
+            ghost: self
+                .ghost_wpm
+                .filter(|_| !self.session.is_complete())
+                .map(|wpm| self.session.ghost_position(wpm)),
+            replay: None,
+        };
+        self.typing_view
+            .draw(frame, &self.session, &self.theme, &ctx);
+    }
+
+    /// The typing screen as it was at the replay's current position, with
+    /// only the stats line above the quote
+    fn draw_replay(&self, frame: &mut Frame) {
+        let Some(player) = &self.replay_player else {
+            return;
+        };
+        let now = Instant::now();
+        let session = TypingSession::replay_frame(player.replay(), player.position(now));
+        let status = if player.is_finished(now) {
+            "REPLAY · P: again · ESC: stop".to_string()
+        } else {
+            format!("REPLAY {} · P: speed · ESC: stop", player.speed_label())
+        };
+        let ctx = TypingViewContext {
+            recent: &[],
+            advance_flash: None,
+            advance_tally: None,
+            goal: None,
+            quote_size: None,
+            quote_difficulty: None,
+            quote_source: &self.quote_source,
+            marquee: &self.marquee,
+            animated_wpm: player.wpm(now),
+            speed_unit: self.speed_unit(),
+            accuracy_thresholds: self.accuracy_thresholds(),
+            wpm_thresholds: None,
+            cursor_visible: true,
+            warning: None,
+            keyboard_style: self.keyboard_style(),
+            show_progress: self.config.show_progress,
+            focus: true,
+            language: self.quote_manager.language(),
+            streak: 0,
+            profile: None,
+            error_flash: false,
+            pressed_keys: &[],
+            drill_keys: None,
+            ghost: None,
+            hard_chars: &[],
+            next_quote: None,
+            replay: Some(&status),
+        };
+        self.typing_view.draw(frame, &session, &self.theme, &ctx);
+    }
+
+    /// Watch `replay` in the quote box; Esc goes back to where it started
+    fn play_replay(&mut self, replay: Replay) {
+        let now = Instant::now();
+        self.replay_player = Some(ReplayPlayer::new(replay, now));
+        self.scheduler
+            .every(Task::ReplayFrame, ANIMATION_FRAME, now);
+        self.state_machine.transition(AppState::Replay);
+    }
+
+    /// `P` during playback: switch between 1x and 2x, or start over once
+    /// the replay has finished
+    fn control_replay(&mut self) {
+        let now = Instant::now();
+        let Some(player) = &mut self.replay_player else {
+            return;
+        };
+        if player.is_finished(now) {
+            *player = ReplayPlayer::new(player.replay().clone(), now);
+        } else {
+            player.toggle_speed(now);
+        }
+        self.scheduler
+            .every(Task::ReplayFrame, ANIMATION_FRAME, now);
+    }
+
+    /// Play the kept replay of a saved result, e.g. from History's detail pane
+    pub fn play_saved_replay(&mut self, result_id: i64) -> anyhow::Result<()> {
+        let data = self
+            .db
+            .get_replay(result_id)?
+            .context("No replay kept for this result")?;
+        self.play_replay(Replay::decode(&data)?);
+        Ok(())
+    }
+
+    fn draw_results(&self, frame: &mut Frame) {
+        let pending = self.pending_warning();
+        let ctx = ResultsViewContext {
+            warning: pending.as_deref(),
+            speed_unit: self.speed_unit(),
+            accuracy_thresholds: self.accuracy_thresholds(),
+            wpm_thresholds: self.avg_wpm.map(Thresholds::wpm),
+            quote_source: &self.quote_source,
+            marquee: &self.marquee,
+            rating: self.quote_manager.rating(&self.quote_hash),
+            personal_best: self.personal_best,
+            ranking: self.ranking.map(|ranking| (ranking, self.mode_name())),
+            recent_form: self.recent_form,
+            suggestions: &self.suggestions,
+            quote_best: self.quote_best,
+            ghost_wpm: self.ghost_wpm,
+            source_finished: self.source_finished.as_ref(),
+            finger_usage: &self.finger_usage,
+            goal_reached: self.goal_reached,
+            achievements: &self.achievements,
+            below_threshold: self.below_threshold,
+            share_status: self
+                .share_status
+                .as_ref()
+                .map(|(message, _)| message.as_str()),
+            share_card: self
+                .share_status
+                .as_ref()
+                .and_then(|(_, card)| card.as_deref()),
+        };
+        ResultsView::draw(frame, &self.session, &self.theme, &ctx);
+    }
+
     fn finish_test(&mut self) {
-        if let Some(result) = self.session.final_result() {
-            self.db.save_result(&result).ok();
+        self.share_status = None;
+        self.below_threshold = false;
+        self.goal_reached = false;
+        self.achievements.clear();
+        self.personal_best = None;
+        self.ranking = None;
+        self.recent_form = None;
+        self.suggestions.clear();
+        self.quote_best = None;
+        self.source_finished = None;
+        self.finger_usage =
+            FingerUsage::from_chars(&KeyboardLayout::new(), self.session.keystroke_chars());
+        // A failed master run keeps its stats on screen but never reaches the
+        // database, and neither does a run abandoned while AFK
+        if self.session.is_failed() || self.session.is_afk_discarded() {
+            self.last_result = None;
+        } else if let Some(mut result) = self.session.final_result(self.mode_name()) {
+            result.source = self
+                .series
+                .as_ref()
+                .map(|series| series.title().to_string());
+            // Ids are only unique within one quote file; packs don't get per-quote bests
+            result.quote_id = self
+                .quote_id
+                .filter(|_| self.quote_manager.is_builtin())
+                .map(|id| id as i64);
+            if let Ok(dist) = self.db.get_wpm_distribution(&result.mode) {
+                result.anomaly = dist.classify(result.wpm);
+            }
+            // Compared with the average before this run joins it
+            if self.config.coaching {
+                let avg = self.db.get_avg_wpm_for_mode(&result.mode).ok().flatten();
+                self.suggestions = coach::suggestions(&result, self.session.key_stats(), avg);
+            }
+            self.below_threshold = self.below_save_threshold(&result);
+            if !self.below_threshold {
+                self.save_run(&result);
+            }
+            if let Some(series) = &mut self.series {
+                self.source_finished = series.record(&result);
+            } else if self.quote_mode == QuoteMode::Continuous {
+                self.advance_bookmark(&result);
+            }
             self.last_result = Some(result);
         }
+        self.stop_timers();
+        // Runs that weren't saved, and a series' closing totals, still get
+        // the results screen
+        if self.config.auto_advance
+            && !self.below_threshold
+            && self.source_finished.is_none()
+            && let Some(result) = &self.last_result
+        {
+            let (wpm, accuracy) = (result.wpm, result.accuracy);
+            self.advance(wpm, accuracy);
+            return;
+        }
+        self.marquee.reset();
         self.state_machine.transition(AppState::Results);
     }
 
+    /// Whether `result` is too slow or inaccurate to save under
+    /// `min_wpm_to_save` and `min_accuracy_to_save`; 0 turns a gate off
+    fn below_save_threshold(&self, result: &TestResult) -> bool {
+        result.wpm < self.config.min_wpm_to_save
+            || result.accuracy < self.config.min_accuracy_to_save
+    }
+
+    /// Results screen `S`: save the finished run the thresholds held back
+    fn force_save(&mut self) {
+        if !self.below_threshold {
+            return;
+        }
+        self.below_threshold = false;
+        if let Some(result) = self.last_result.clone() {
+            self.save_run(&result);
+        }
+    }
+
+    /// Save a finished run with its key, bigram, finger, replay and word
+    /// stats, and work out what the results screen says about it
+    fn save_run(&mut self, result: &TestResult) {
+        // Ties and the first test in a mode count as a new best, unless the
+        // run is suspiciously fast and still needs confirming in History
+        if result.anomaly != Anomaly::High
+            && let Ok(previous) = self.db.get_best_wpm_for_mode(&result.mode)
+            && previous.is_none_or(|best| result.wpm >= best)
+        {
+            self.personal_best = Some(PersonalBest { previous });
+            if self.config.notify_on_pb {
+                crossterm::execute!(io::stdout(), Print('\x07')).ok();
+            }
+        }
+        self.flush_pending_results();
+        // A locked database often frees up a moment later
+        let saved = self
+            .db
+            .save_result(result)
+            .or_else(|_| self.db.save_result(result));
+        match saved {
+            Ok(id) => {
+                let was_met = self.daily_goal().is_met(&self.today);
+                self.refresh_daily();
+                self.load_recent_results();
+                self.goal_reached = !was_met && self.daily_goal().is_met(&self.today);
+                self.unlock_achievements(result);
+                if let Some(quote_id) = result.quote_id {
+                    self.quote_best = self.db.get_best_for_quote(quote_id).ok().flatten();
+                }
+                // Flagged runs aren't counted, so they have no place to show
+                if result.anomaly != Anomaly::High {
+                    self.ranking = self
+                        .db
+                        .get_percentile(&result.mode, result.wpm)
+                        .ok()
+                        .filter(|ranking| ranking.total >= MIN_RANKED_TESTS);
+                }
+                self.recent_form = self
+                    .db
+                    .get_last_n_average(Some(&result.mode), RECENT_FORM_RESULTS)
+                    .ok();
+                self.db.save_key_stats(id, self.session.key_stats()).ok();
+                self.db
+                    .save_bigram_stats(id, &self.session.bigram_latencies())
+                    .ok();
+                self.db.save_finger_stats(id, &self.finger_usage).ok();
+                if self.config.replay_keep > 0
+                    && let Ok(data) = self.session.replay().encode()
+                {
+                    self.db.save_replay(id, &data, self.config.replay_keep).ok();
+                }
+                if self.config.track_word_errors {
+                    self.db
+                        .save_word_errors(id, &self.session.word_tallies())
+                        .ok();
+                }
+            }
+            Err(e) => {
+                self.pending_results.push(result.clone());
+                self.set_message(format!("Result not saved: {}", e), StatusLevel::Error);
+            }
+        }
+    }
+
+    /// Store the achievements the saved results now earn, keeping the new
+    /// ones for the results screen
+    fn unlock_achievements(&mut self, result: &TestResult) {
+        let (Ok(unlocked), Ok(stats), Ok(activity)) = (
+            self.db.get_achievements(),
+            self.db.get_stats(None),
+            self.db.get_daily_activity(Local::now().date_naive()),
+        ) else {
+            return;
+        };
+        let progress = Progress {
+            total_tests: stats.total_tests,
+            best_wpm: stats.best_wpm,
+            longest_streak: activity.longest_streak,
+            last_hour: Some(result.timestamp.with_timezone(&Local).hour()),
+        };
+        for achievement in achievements::newly_unlocked(&progress, &unlocked) {
+            if self
+                .db
+                .unlock_achievement(achievement.id, result.timestamp)
+                .is_ok()
+            {
+                self.achievements.push(achievement);
+            }
+        }
+    }
+
+    /// Start the next test straight after a finished one, keeping its speed
+    /// in the header for a moment and adding it to the tally
+    fn advance(&mut self, wpm: f64, accuracy: f64) {
+        let chars_per_word = self.session.chars_per_word();
+        self.advance_tally.tests += 1;
+        self.advance_tally.wpm_sum += wpm;
+        self.advance_tally.chars_per_word_sum += chars_per_word;
+        let unit = self.speed_unit();
+        self.advance_flash = Some(format!(
+            "✓ {:.1} {} · {:.1}%{}",
+            unit.from_wpm(wpm, chars_per_word),
+            unit.label(),
+            accuracy,
+            if self.personal_best.is_some() {
+                " · new best"
+            } else {
+                ""
+            }
+        ));
+        self.reset();
+        let now = Instant::now();
+        self.scheduler.once(Task::AdvanceFlash, ADVANCE_FLASH, now);
+    }
+
+    /// Try again to save results the database refused earlier. Only the
+    /// results themselves are kept, not their key and word stats. Returns
+    /// whether nothing is left pending.
+    pub fn flush_pending_results(&mut self) -> bool {
+        if self.pending_results.is_empty() {
+            return true;
+        }
+        match self.db.save_results(&self.pending_results) {
+            Ok(()) => {
+                let count = self.pending_results.len();
+                self.pending_results.clear();
+                self.set_message(
+                    format!("Saved {} earlier result(s)", count),
+                    StatusLevel::Success,
+                );
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Save what is still pending before exiting; the warning to print once
+    /// the terminal is restored if some results couldn't be
+    pub fn save_on_exit(&mut self) -> Option<String> {
+        (!self.flush_pending_results()).then(|| {
+            format!(
+                "{} result(s) could not be saved",
+                self.pending_results.len()
+            )
+        })
+    }
+
+    /// Stays up while results are waiting to be saved
+    fn pending_warning(&self) -> Option<String> {
+        match self.pending_results.len() {
+            0 => None,
+            count => Some(format!(
+                "{} result(s) not saved yet; retrying after the next test and on exit",
+                count
+            )),
+        }
+    }
+
+    /// Switch to `mode` and start a new test, saving it as `default_mode`
+    /// when `remember_mode` is on
+    fn change_mode(&mut self, mode: QuoteMode) {
+        self.quote_mode = mode;
+        self.series = None;
+        if self.config.remember_mode && self.config.default_mode != mode.as_str() {
+            self.config.default_mode = mode.as_str().to_string();
+            self.save_config().ok();
+        }
+        self.reset();
+    }
+
     pub fn reset(&mut self) {
-        if let Some(quote_obj) = self.quote_manager.get_random_quote(self.quote_mode) {
+        let abandoned_id = self.record_abandonment();
+        self.keep_for_undo(abandoned_id);
+        self.quote_difficulty = None;
+        // A series goes on to its next chunk; after the last one, back to quotes
+        if let Some(series) = &self.series
+            && !series.is_finished()
+        {
+            self.session.reset(series.current().to_string());
+            self.quote_source = series.progress_label();
+            self.quote_hash.clear();
+            self.quote_id = None;
+            self.begin_test();
+            return;
+        }
+        self.series = None;
+        self.apply_language();
+        // The source may have gone away after a config reload
+        if self.quote_mode == QuoteMode::Continuous && self.continuous.is_none() {
+            self.quote_mode = QuoteMode::Medium;
+        }
+        let max_difficulty = self.max_quote_difficulty();
+
+        if let Some(source) = &self.continuous
+            && self.quote_mode == QuoteMode::Continuous
+        {
+            let index = self.bookmark.chunk_index;
+            self.session
+                .reset(source.chunk(index).unwrap_or_default().to_string());
+            self.quote_source = source.progress_label(index);
+            self.quote_hash.clear();
+            self.quote_id = None;
+        } else if self.quote_mode == QuoteMode::Zen {
+            self.session.reset(String::new());
+            self.quote_source = format!(
+                "Zen mode - type freely, {} to finish",
+                self.config.zen_end_key.to_uppercase()
+            );
+            self.quote_hash.clear();
+            self.quote_id = None;
+        } else if self.quote_mode == QuoteMode::Practice {
+            let worst = self
+                .db
+                .get_worst_words(PRACTICE_WORDS, MIN_WORD_OCCURRENCES)
+                .unwrap_or_default();
+            let (practice_words, source) = if worst.len() >= MIN_PRACTICE_WORDS {
+                let source = format!("Practice - your {} most mistyped words", worst.len());
+                (worst, source)
+            } else {
+                let texts = self.quote_manager.quotes().iter().map(|q| q.text.as_str());
+                (
+                    words::random_words(texts, PRACTICE_WORDS),
+                    "Practice - random words until more mistakes are recorded".to_string(),
+                )
+            };
+            self.session.reset(words::practice_text(
+                &practice_words,
+                words::PRACTICE_LENGTH,
+            ));
+            self.quote_source = source;
+            self.quote_hash.clear();
+            self.quote_id = None;
+        } else if self.quote_mode == QuoteMode::Code {
+            if let Some(snippet) = self.quote_manager.get_random_snippet(self.code_language()) {
+                self.session.reset(snippet.text.clone());
+                self.quote_source = snippet.source.clone();
+                self.quote_hash = snippet.hash.clone();
+            }
+            self.quote_id = None;
+        } else if self.quote_mode == QuoteMode::Drill {
+            let layout = KeyboardLayout::new();
+            self.drill_set = DrillSet::from_config(&self.config.drill_set);
+            self.drill_keys = self.drill_set.keys(&layout, &self.config.drill_keys);
+            // An unusable custom set falls back to the home row, and is saved as such
+            let text = words::drill_text(&self.drill_keys, words::PRACTICE_LENGTH)
+                .or_else(|e| {
+                    self.warning = Some(format!("config.toml: drill_keys: {:#}", e));
+                    self.drill_set = DrillSet::HomeRow;
+                    self.drill_keys = self.drill_set.keys(&layout, "");
+                    words::drill_text(&self.drill_keys, words::PRACTICE_LENGTH)
+                })
+                .unwrap_or_default();
+            self.session.reset(text);
+            self.quote_source = format!(
+                "Drill - {} keys: {}",
+                self.drill_set.as_str(),
+                self.drill_keys.iter().collect::<String>()
+            );
+            self.quote_hash.clear();
+            self.quote_id = None;
+        } else if let Some(quote_obj) = self
+            .quote_manager
+            .next_quote(self.quote_mode, max_difficulty)
+        {
             self.session.reset(quote_obj.text.clone());
             self.quote_source = quote_obj.source.clone();
+            self.quote_hash = quote_obj.hash.clone();
+            self.quote_id = Some(quote_obj.id);
+            self.quote_difficulty = Some(quote_obj.difficulty);
+        }
+        self.begin_test();
+    }
+
+    /// Start a test on a quote chosen in the picker. The mode follows the
+    /// quote's length so the result lands with comparable tests.
+    fn start_quote(&mut self, id: usize) {
+        let Some(quote_obj) = self.quote_manager.get_quote_by_id(id) else {
+            return;
+        };
+        self.record_abandonment();
+        self.series = None;
+        self.quote_mode = QuoteMode::for_length(quote_obj.length);
+        self.session.reset(quote_obj.text.clone());
+        self.quote_source = quote_obj.source.clone();
+        self.quote_hash = quote_obj.hash.clone();
+        self.quote_id = Some(id);
+        self.quote_difficulty = Some(quote_obj.difficulty);
+        self.begin_test();
+    }
+
+    /// Flash the quote border or ring the bell after a wrong key, per `error_feedback`
+    fn error_feedback(&mut self, now: Instant) {
+        match ErrorFeedback::from_config(&self.config.error_feedback) {
+            ErrorFeedback::None => {}
+            // Cleared by the scheduler even if no other key follows
+            ErrorFeedback::Flash => {
+                self.error_flash = true;
+                self.scheduler.once(Task::ErrorFlash, ERROR_FLASH, now);
+            }
+            ErrorFeedback::Bell => {
+                crossterm::execute!(io::stdout(), Print('\x07')).ok();
+            }
+        }
+    }
+
+    /// Find the words of the new quote that are on the worst-words list, to
+    /// mark them and to note the ones typed cleanly
+    fn load_hard_words(&mut self) {
+        let worst: HashSet<String> = if self.config.track_word_errors {
+            self.db
+                .get_worst_words(HARD_WORDS, MIN_WORD_OCCURRENCES)
+                .unwrap_or_default()
+                .into_iter()
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        let quote = self.session.quote();
+        self.hard_words = metrics::quote_words(quote)
+            .into_iter()
+            .map(|(word, _)| word)
+            .filter(|word| worst.contains(word))
+            .collect();
+        self.hard_chars = metrics::hard_word_chars(quote, &self.hard_words);
+    }
+
+    /// Choose the ghost's pace from `ghost_target`; no ghost for zen or when
+    /// there is no history to race yet
+    fn pick_ghost(&mut self) {
+        self.ghost_wpm = None;
+        if !self.config.ghost || self.session.is_zen() {
+            return;
+        }
+        self.ghost_wpm = match self.config.ghost_target.as_str() {
+            "best" => self
+                .db
+                .get_best_wpm_for_mode(self.mode_name())
+                .ok()
+                .flatten(),
+            "average" => self
+                .db
+                .get_avg_wpm_for_mode(self.mode_name())
+                .ok()
+                .flatten(),
+            target => target.parse::<f64>().ok(),
+        }
+        .filter(|wpm| *wpm > 0.0);
+    }
+
+    /// Average WPM for the quote's size estimate: the mode's, else across all
+    /// modes, else none when there is no history
+    fn load_avg_wpm(&mut self) {
+        self.avg_wpm = self
+            .db
+            .get_avg_wpm_for_mode(self.mode_name())
+            .ok()
+            .flatten()
+            .or_else(|| self.db.get_avg_wpm().ok().flatten())
+            .filter(|wpm| *wpm > 0.0);
+    }
+
+    /// Cache the latest results and the overall average for the strip under
+    /// the header, so drawing never touches the database
+    fn load_recent_results(&mut self) {
+        let mut recent = self
+            .db
+            .get_results_filtered(None, None, SortOrder::Date, RECENT_RESULTS, 0)
+            .unwrap_or_default();
+        recent.reverse();
+        self.recent_results = recent;
+        self.overall_avg_wpm = self.db.get_avg_wpm().ok().flatten();
+    }
+
+    /// The recent results strip: each speed and whether it beat the average
+    fn recent_speeds(&self) -> Vec<(f64, bool)> {
+        if !self.config.show_recent {
+            return Vec::new();
+        }
+        let unit = self.speed_unit();
+        let average = self.overall_avg_wpm.unwrap_or(0.0);
+        self.recent_results
+            .iter()
+            .map(|result| {
+                (
+                    unit.from_wpm(result.wpm, result.chars_per_word()),
+                    result.wpm >= average,
+                )
+            })
+            .collect()
+    }
+
+    /// "words: 54 · ~40s at your avg WPM" for the footer; zen has no size
+    fn quote_size(&self) -> Option<String> {
+        if self.session.is_zen() {
+            return None;
         }
-        self.animated_wpm = 0.0;
-        self.last_wpm_for_animation = 0.0;
-        self.last_tick = Instant::now();
+        let words = format!("words: {}", self.session.word_count());
+        let Some(wpm) = self.avg_wpm else {
+            return Some(words);
+        };
+        let chars = self.session.quote().chars().count() as f64;
+        let secs = (chars / metrics::CHARS_PER_WORD / wpm * 60.0).round() as u64;
+        let estimate = if secs < 60 {
+            format!("~{}s", secs)
+        } else {
+            format!("~{}m {}s", secs / 60, secs % 60)
+        };
+        Some(format!("{} · {} at your avg WPM", words, estimate))
+    }
+
+    /// Clear per-test UI state and show the typing screen for the new session
+    fn begin_test(&mut self) {
+        // A test brought back with Ctrl+Z keeps the difficulty it started with
+        if !self.session.is_started() {
+            self.session
+                .set_difficulty(Difficulty::from_config(&self.config.difficulty));
+        }
+        self.pick_ghost();
+        self.load_hard_words();
+        self.load_avg_wpm();
+        self.wpm_animator.reset();
+        self.stop_timers();
+        self.arm_cursor_blink(Instant::now());
+        self.marquee.reset();
+        self.next_quote_preview = self.peek_next_quote();
         self.state_machine = StateMachine::new(AppState::Testing);
         self.typing_view = TypingView::new(self.typing_view.show_keyboard(), self.quote_mode);
     }
 
+    /// The start of the quote the next test will use, reserved now so the
+    /// preview is what comes up. `None` outside the quote modes, during a
+    /// series, and while a language change is waiting for the next quote.
+    fn peek_next_quote(&mut self) -> Option<String> {
+        if !self.config.next_quote_preview
+            || self.series.is_some()
+            || !matches!(
+                self.quote_mode,
+                QuoteMode::Short | QuoteMode::Medium | QuoteMode::Long
+            )
+            || self.quote_manager.language() != self.config.language
+        {
+            return None;
+        }
+        let max_difficulty = self.max_quote_difficulty();
+        let quote = self
+            .quote_manager
+            .peek_next(self.quote_mode, max_difficulty)?;
+        Some(truncate_to_width(&quote.text, NEXT_QUOTE_PREVIEW))
+    }
+
+    /// Back up the database into the backups folder, pruning to `backup_keep`
+    pub fn backup_database(&self) -> anyhow::Result<PathBuf> {
+        backup::create(&self.db, &self.paths.backups_dir(), self.config.backup_keep)
+    }
+
+    /// Write this week's report into the reports folder
+    pub fn write_report(&self) -> anyhow::Result<PathBuf> {
+        report::write(
+            &self.db,
+            &self.paths.reports_dir(),
+            Local::now().date_naive(),
+        )
+    }
+
+    pub fn latest_backup(&self) -> Option<PathBuf> {
+        backup::latest(&self.paths.backups_dir())
+    }
+
+    /// Replace the database with a backup and reload everything derived from it
+    pub fn restore_database(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.db.restore_from(path)?;
+        self.quote_manager
+            .set_ratings(self.db.get_quote_ratings().unwrap_or_default());
+        self.refresh_daily();
+        self.load_recent_results();
+        self.load_continuous_source();
+        Ok(())
+    }
+
+    /// Hold on to the current test if it is about to be thrown away mid-way.
+    /// Only the latest one is kept.
+    fn keep_for_undo(&mut self, abandoned_id: Option<i64>) {
+        if !self.session.is_started() || self.session.is_complete() {
+            return;
+        }
+        let mut session = self.session.clone();
+        session.pause();
+        self.discarded = Some(DiscardedTest {
+            session,
+            quote_source: self.quote_source.clone(),
+            quote_hash: self.quote_hash.clone(),
+            quote_id: self.quote_id,
+            quote_mode: self.quote_mode,
+            abandoned_id,
+            at: Instant::now(),
+        });
+        self.set_message(
+            "Test discarded · Ctrl+Z to bring it back".to_string(),
+            StatusLevel::Info,
+        );
+    }
+
+    /// Ctrl+Z: bring back the test thrown away in the last few seconds, its
+    /// clock picking up where it stopped. Does nothing once the new test has
+    /// been typed in.
+    fn undo_discard(&mut self) {
+        if self.session.is_started() {
+            return;
+        }
+        let Some(discarded) = self
+            .discarded
+            .take()
+            .filter(|discarded| discarded.at.elapsed() < UNDO_WINDOW)
+        else {
+            return;
+        };
+        // It was never abandoned after all
+        if let Some(id) = discarded.abandoned_id {
+            self.db.delete_abandoned(id).ok();
+        }
+        self.session = discarded.session;
+        self.session.resume();
+        self.quote_source = discarded.quote_source;
+        self.quote_hash = discarded.quote_hash;
+        self.quote_id = discarded.quote_id;
+        self.quote_mode = discarded.quote_mode;
+        self.begin_test();
+        self.set_message("Test restored".to_string(), StatusLevel::Success);
+    }
+
+    /// Log the current test if it is about to be thrown away after typing
+    /// started, returning the row written
+    fn record_abandonment(&self) -> Option<i64> {
+        if !self.config.track_abandonment
+            || !self.session.is_started()
+            || self.session.is_complete()
+        {
+            return None;
+        }
+        self.db
+            .save_abandoned(
+                self.mode_name(),
+                self.session.typed().chars().count(),
+                self.session.duration().as_secs_f64(),
+            )
+            .ok()
+    }
+
+    /// Load `config.continuous_source` and its bookmark. If the file changed
+    /// since the bookmark was written, reading continues at about the same
+    /// fraction of the text and the footer offers Ctrl+B to start over.
+    fn load_continuous_source(&mut self) {
+        self.continuous = None;
+        let Some(path) = self.config.continuous_source.clone() else {
+            return;
+        };
+        let source = match ContinuousSource::load(Path::new(&path)) {
+            Ok(source) => source,
+            Err(e) => {
+                self.warning = Some(format!("{:#}", e));
+                return;
+            }
+        };
+
+        let key = source.path.display().to_string();
+        let mut bookmark = self
+            .db
+            .get_bookmark(&key)
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        if bookmark.file_hash.is_empty() {
+            bookmark.file_hash = source.hash.clone();
+        } else if bookmark.file_hash != source.hash {
+            if let Some(index) =
+                (bookmark.chunk_index * source.chunk_count()).checked_div(bookmark.chunk_count)
+            {
+                bookmark.chunk_index = index;
+            }
+            bookmark.file_hash = source.hash.clone();
+            self.warning = Some(format!(
+                "{} changed on disk; continuing at about the same place (Ctrl+B starts over)",
+                source.title
+            ));
+        }
+        bookmark.chunk_count = source.chunk_count();
+        bookmark.chunk_index = bookmark.chunk_index.min(source.chunk_count() - 1);
+        self.db.save_bookmark(&key, &bookmark).ok();
+
+        self.bookmark = bookmark;
+        self.continuous = Some(source);
+    }
+
+    /// Move to the next chunk after a finished test; the last chunk wraps
+    /// around and reports totals for the whole source
+    fn advance_bookmark(&mut self, result: &TestResult) {
+        let Some(source) = &self.continuous else {
+            return;
+        };
+        let bookmark = &mut self.bookmark;
+        bookmark.tests += 1;
+        bookmark.wpm_sum += result.wpm;
+        bookmark.accuracy_sum += result.accuracy;
+        bookmark.duration_seconds += result.duration_seconds;
+        bookmark.chunk_index += 1;
+
+        if bookmark.chunk_index >= source.chunk_count() {
+            self.source_finished = Some(SourceSummary {
+                title: source.title.clone(),
+                tests: bookmark.tests,
+                avg_wpm: bookmark.wpm_sum / bookmark.tests as f64,
+                avg_accuracy: bookmark.accuracy_sum / bookmark.tests as f64,
+                duration_seconds: bookmark.duration_seconds,
+            });
+            *bookmark = Bookmark {
+                file_hash: source.hash.clone(),
+                chunk_count: source.chunk_count(),
+                ..Bookmark::default()
+            };
+        }
+        self.db
+            .save_bookmark(&source.path.display().to_string(), bookmark)
+            .ok();
+    }
+
+    /// Start the continuous source over from its first chunk
+    fn restart_source(&mut self) {
+        let Some(source) = &self.continuous else {
+            return;
+        };
+        self.bookmark = Bookmark {
+            file_hash: source.hash.clone(),
+            chunk_count: source.chunk_count(),
+            ..Bookmark::default()
+        };
+        self.db
+            .save_bookmark(&source.path.display().to_string(), &self.bookmark)
+            .ok();
+        self.warning = None;
+        if self.quote_mode == QuoteMode::Continuous {
+            self.reset();
+        }
+    }
+
+    /// Reload the streak and today's totals for the daily goal
+    fn refresh_daily(&mut self) {
+        let today = Local::now().date_naive();
+        if let Ok(activity) = self.db.get_daily_activity(today) {
+            self.streak = activity.current_streak;
+        }
+        if let Ok(summary) = self.db.get_today_summary(today) {
+            self.today = summary;
+        }
+    }
+
+    fn daily_goal(&self) -> DailyGoal {
+        DailyGoal {
+            tests: self.config.daily_goal_tests,
+            minutes: self.config.daily_goal_minutes,
+        }
+    }
+
+    /// e.g. "Goal: 6/10"; `None` when no daily goal is set
+    pub fn goal_progress(&self) -> Option<String> {
+        self.daily_goal().progress(&self.today)
+    }
+
+    /// Mode recorded with results; `--file`/`--text` content is kept apart
+    /// from quote modes
+    fn mode_name(&self) -> &'static str {
+        if let Some(series) = &self.series {
+            series.mode()
+        } else {
+            match self.quote_mode {
+                QuoteMode::Code => self.code_language().mode_name(),
+                QuoteMode::Drill => self.drill_set.mode_name(),
+                mode => mode.as_str(),
+            }
+        }
+    }
+
+    /// Copy the share card for the finished test; if that fails the card is
+    /// shown on the results screen to copy by hand
+    fn copy_result(&mut self) {
+        let Some(result) = self.session.final_result(self.mode_name()) else {
+            return;
+        };
+        let card = share::share_card(&self.config.share_format, &result);
+        let method = ClipboardMethod::from_config(&self.config.clipboard);
+        self.share_status = Some(match self.clipboard.copy(&card, method) {
+            Ok(()) => ("Copied!".to_string(), None),
+            Err(e) => (format!("{:#}; the card is below", e), Some(card)),
+        });
+    }
+
+    fn max_quote_difficulty(&self) -> u8 {
+        QuoteDifficulty::from_config(&self.config.max_quote_difficulty).max_level()
+    }
+
+    fn code_language(&self) -> CodeLanguage {
+        CodeLanguage::from_config(&self.config.code_language)
+    }
+
     pub fn restart(&mut self) {
+        // Restarting a flubbed first word shouldn't leave a trace
+        if !self
+            .session
+            .is_within(FREE_RESTART_CHARS, FREE_RESTART_TIME)
+        {
+            let abandoned_id = self.record_abandonment();
+            self.keep_for_undo(abandoned_id);
+        }
+        self.restart_session();
+    }
+
+    /// Start the same text over, the clock and every counter with it
+    fn restart_session(&mut self) {
         self.session.restart();
-        self.animated_wpm = 0.0;
-        self.last_wpm_for_animation = 0.0;
-        self.last_tick = Instant::now();
+        self.session
+            .set_difficulty(Difficulty::from_config(&self.config.difficulty));
+        self.wpm_animator.reset();
+        self.stop_timers();
+        self.arm_cursor_blink(Instant::now());
+        self.pick_ghost();
+        // Retrying from the results screen goes straight back to typing
+        self.state_machine = StateMachine::new(AppState::Testing);
     }
 
-    fn cycle_theme(&mut self) {
-        let themes = Theme::available_themes();
-        let current_index = themes
+    /// Switch to the next quote language. A test already under way is
+    /// finished in the old language; the switch happens with the next quote.
+    fn cycle_language(&mut self) {
+        if matches!(self.quote_mode, QuoteMode::Code | QuoteMode::Drill) {
+            if self.quote_mode == QuoteMode::Code {
+                self.config.code_language = self.code_language().next().as_str().to_string();
+            } else {
+                self.config.drill_set = self.drill_set.next().as_str().to_string();
+            }
+            self.save_config().ok();
+            if !self.session.is_started() {
+                self.reset();
+            }
+            return;
+        }
+        let languages = self.quote_manager.available_languages();
+        let current = languages
             .iter()
-            .position(|&t| t == self.theme.name)
+            .position(|l| *l == self.config.language)
             .unwrap_or(0);
-        let next_index = (current_index + 1) % themes.len();
-        self.theme = Theme::from_name(themes[next_index]);
-        self.config.theme = self.theme.name.clone();
+        self.config.language = languages[(current + 1) % languages.len()].clone();
         self.save_config().ok();
+        if !self.session.is_started() {
+            self.reset();
+        }
+        self.next_quote_preview = self.peek_next_quote();
+    }
+
+    /// Load `config.language` if it isn't the active pool yet
+    fn apply_language(&mut self) {
+        if self.quote_manager.language() == self.config.language {
+            return;
+        }
+        match self.quote_manager.load_language(&self.config.language) {
+            Ok(()) => self.warning = None,
+            Err(e) => self.warning = Some(format!("{:#}", e)),
+        }
+    }
+
+    /// Re-read config.toml after it was edited outside the app. If it no longer
+    /// parses or names an unknown theme, the current settings stay in place and
+    /// the problem is shown in the footer.
+    pub fn reload_config(&mut self) {
+        let config = match ConfigManager::for_paths(&self.paths).and_then(|mgr| mgr.load()) {
+            Ok(config) => config,
+            Err(e) => {
+                self.warning = Some(format!("config.toml: {:#}", e));
+                return;
+            }
+        };
+
+        if !Theme::available_themes()
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(&config.theme))
+        {
+            self.warning = Some(format!("config.toml: unknown theme `{}`", config.theme));
+            return;
+        }
+        let theme = match Theme::try_from_name(&config.theme) {
+            Ok(theme) => theme,
+            Err(e) => {
+                self.warning = Some(format!("{:#}", e));
+                return;
+            }
+        };
+
+        self.theme = theme;
+        self.input_handler =
+            InputHandler::new(zen_end_key(&config.zen_end_key), config.shift_uppercase);
+        self.session.set_strict_completion(config.strict_completion);
+        self.session.set_auto_indent(config.code_auto_indent);
+        self.session.set_space_skips_word(config.space_skips_word);
+        self.session
+            .set_difficulty(Difficulty::from_config(&config.difficulty));
+        self.session
+            .set_confidence(Confidence::from_config(&config.confidence));
+        self.quote_manager
+            .set_rating_weight(config.quote_rating_weight);
+        self.quote_manager.set_ascii_only(config.ascii_only);
+        let sound_changed =
+            (&config.sound, config.sound_volume) != (&self.config.sound, self.config.sound_volume);
+        self.typing_view = TypingView::new(config.show_keyboard, self.quote_mode);
+        self.config = config;
+        if sound_changed {
+            self.open_sounds();
+        }
+        if !self.session.is_started() {
+            self.apply_language();
+        }
+        self.arm_screensaver(Instant::now());
+        self.scheduler.cancel(Task::CursorBlink);
+        if !self.session.is_complete() {
+            self.arm_cursor_blink(Instant::now());
+        }
+        self.warning = None;
+        self.load_continuous_source();
+    }
+
+    /// Open the audio device for the configured key sounds. When it can't be
+    /// opened typing carries on silently and the reason is shown once.
+    fn open_sounds(&mut self) {
+        let style = SoundStyle::from_config(&self.config.sound);
+        self.sounds = KeySounds::silent();
+        match KeySounds::open(style, self.config.sound_volume) {
+            Ok(sounds) => self.sounds = sounds,
+            Err(e) => self.set_message(
+                format!("{:#}; typing sounds are off", e),
+                StatusLevel::Error,
+            ),
+        }
+    }
+
+    pub fn speed_unit(&self) -> SpeedUnit {
+        SpeedUnit::from_config(&self.config.speed_unit)
+    }
+
+    pub fn set_warning(&mut self, warning: String) {
+        self.warning = Some(warning);
+    }
+
+    pub fn warning(&self) -> Option<&str> {
+        self.warning.as_deref().or(self.db_warning.as_deref())
+    }
+
+    pub fn paths(&self) -> &Paths {
+        &self.paths
+    }
+
+    /// Start over as `profile`, with its own database and settings and a
+    /// fresh test. Results still waiting to be saved go to the old profile.
+    fn switch_profile(&mut self, profile: &str) -> anyhow::Result<()> {
+        check_profile_name(profile)?;
+        self.record_abandonment();
+        self.flush_pending_results();
+        *self = App::new(StartupOptions {
+            profile: Some(profile.to_string()),
+            ..StartupOptions::default()
+        })?;
+        Ok(())
     }
 
     pub fn save_config(&self) -> anyhow::Result<()> {
-        let config_mgr = ConfigManager::new()?;
+        let config_mgr = ConfigManager::for_paths(&self.paths)?;
         config_mgr.save(&self.config)?;
         Ok(())
     }
 
     // Getters
+    /// Whether the screen for `state` is showing or can be returned to
+    pub fn is_open(&self, state: AppState) -> bool {
+        self.state_machine.is_open(state)
+    }
+
     pub fn state(&self) -> AppState {
         self.state_machine.current()
     }
+
+    /// The screen to draw: the current state, or for an overlay the state
+    /// underneath it
+    pub fn screen(&self) -> AppState {
+        match self.state() {
+            AppState::ThemePicker | AppState::ProfilePicker => {
+                self.state_machine.previous().unwrap_or(AppState::Testing)
+            }
+            state => state,
+        }
+    }
+
+    pub fn accuracy_thresholds(&self) -> Thresholds {
+        Thresholds::accuracy(&self.config)
+    }
+
+    pub fn date_window(&self) -> DateWindow {
+        self.date_window
+    }
+
+    pub fn relative_time(&self) -> bool {
+        self.config.relative_time
+    }
+
+    pub fn keyboard_style(&self) -> KeyboardStyle {
+        KeyboardStyle::from_config(&self.config.keyboard_style)
+    }
+
+    /// The tests saved since launch, for printing on quit; `None` when
+    /// `session_summary` is off or there were none
+    pub fn session_report(&self) -> Option<SessionReport> {
+        if !self.config.session_summary {
+            return None;
+        }
+        let session = self.db.get_stats(Some(self.launched_at)).ok()?;
+        let lifetime = self.db.get_stats(None).ok()?;
+        SessionReport::new(&session, &lifetime)
+    }
+
+    pub fn marquee(&self) -> &Marquee {
+        &self.marquee
+    }
+
+    /// Run the marquee timer only while the frame just drawn had text too
+    /// long for its line, starting over from the beginning once none does
+    pub fn sync_marquee(&mut self, now: Instant) {
+        let scheduled = self.scheduler.is_scheduled(Task::MarqueeStep);
+        if self.marquee.take_scrolling() {
+            if !scheduled {
+                self.scheduler.every(Task::MarqueeStep, MARQUEE_STEP, now);
+            }
+        } else if scheduled {
+            self.scheduler.cancel(Task::MarqueeStep);
+            self.marquee.reset();
+        }
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+}
+
+/// Key that ends a zen test: "enter", otherwise Escape
+fn zen_end_key(name: &str) -> KeyCode {
+    match name.to_lowercase().as_str() {
+        "enter" => KeyCode::Enter,
+        _ => KeyCode::Esc,
+    }
+}
+
+pub(crate) fn open_database(paths: &Paths) -> anyhow::Result<Database> {
+    std::fs::create_dir_all(&paths.data_dir)
+        .with_context(|| format!("Failed to create {}", paths.data_dir.display()))?;
+    let db_path = paths.db_file();
+    Database::open(&db_path.to_string_lossy())
+        .with_context(|| format!("Failed to open database {}", db_path.display()))
+}
+
+/// What happens on a wrong key besides the red character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFeedback {
+    None,
+    Flash,
+    Bell,
+}
+
+impl ErrorFeedback {
+    fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "flash" => ErrorFeedback::Flash,
+            "bell" => ErrorFeedback::Bell,
+            _ => ErrorFeedback::None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ErrorFeedback::None => "none",
+            ErrorFeedback::Flash => "flash",
+            ErrorFeedback::Bell => "bell",
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            ErrorFeedback::None => ErrorFeedback::Flash,
+            ErrorFeedback::Flash => ErrorFeedback::Bell,
+            ErrorFeedback::Bell => ErrorFeedback::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::paths::DirOverrides;
+    use crossterm::event::KeyModifiers;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    /// An app with its own empty config and data dirs under the temp dir
+    pub(crate) fn test_app(name: &str) -> App {
+        let dir = std::env::temp_dir().join(format!("tuitype-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        let paths = Paths::with_overrides(DirOverrides {
+            config_dir: Some(dir.join("config")),
+            data_dir: Some(dir.join("data")),
+        })
+        .unwrap();
+        App::with_paths(StartupOptions::default(), paths).unwrap()
+    }
+
+    fn remove_test_dirs(app: &App) {
+        if let Some(dir) = app.paths().data_dir.parent() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn esc_from_history_returns_to_results() {
+        let mut app = test_app("history-from-results");
+        app.state_machine.transition(AppState::Results);
+        app.handle_input(KeyEvent::new(KeyCode::Char('h'), KeyModifiers::CONTROL));
+        assert_eq!(app.state(), AppState::History);
+        app.handle_input(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(app.state(), AppState::Results);
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn only_started_tests_count_as_abandoned() {
+        let mut app = test_app("abandonment");
+        let abandoned = |app: &App| app.db.get_abandonment(1).unwrap().abandoned;
+        app.reset();
+        assert_eq!(abandoned(&app), 0);
+
+        app.session.type_char('x');
+        app.reset();
+        assert_eq!(abandoned(&app), 1);
+
+        app.config.track_abandonment = false;
+        app.session.type_char('x');
+        app.reset();
+        assert_eq!(abandoned(&app), 1);
+        remove_test_dirs(&app);
+    }
+
+    /// A test of the current quote with its first `count` characters typed
+    fn type_some(app: &mut App, count: usize) -> String {
+        let typed: String = app.session.quote().chars().take(count).collect();
+        for c in typed.chars() {
+            app.session.type_char(c);
+        }
+        typed
+    }
+
+    fn ctrl_z() -> KeyEvent {
+        KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn undo_brings_back_a_discarded_test_unabandoned() {
+        let mut app = test_app("undo");
+        let abandoned = |app: &App| app.db.get_abandonment(1).unwrap().abandoned;
+        let typed = type_some(&mut app, 5);
+        app.reset();
+        assert_eq!(app.session.typed(), "");
+        assert_eq!(abandoned(&app), 1);
+
+        app.handle_input(ctrl_z());
+        assert_eq!(app.session.typed(), typed);
+        assert_eq!(abandoned(&app), 0);
+        // Only once
+        app.handle_input(ctrl_z());
+        assert_eq!(app.session.typed(), typed);
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn undo_does_nothing_after_the_window() {
+        let mut app = test_app("undo-late");
+        type_some(&mut app, 5);
+        app.reset();
+        app.discarded.as_mut().unwrap().at = Instant::now() - UNDO_WINDOW;
+
+        app.handle_input(ctrl_z());
+        assert_eq!(app.session.typed(), "");
+        assert_eq!(app.db.get_abandonment(1).unwrap().abandoned, 1);
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn undo_does_nothing_once_the_new_test_is_typed_in() {
+        let mut app = test_app("undo-typed");
+        type_some(&mut app, 5);
+        app.reset();
+        let typed = type_some(&mut app, 1);
+
+        app.handle_input(ctrl_z());
+        assert_eq!(app.session.typed(), typed);
+        assert_eq!(app.db.get_abandonment(1).unwrap().abandoned, 1);
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn shifted_punctuation_finishes_at_full_accuracy() {
+        let mut app = test_app("shifted-punctuation");
+        for quote in ["Wait! Stop.", "Why? Because.", "She said \"no\" twice."] {
+            app.session.reset(quote.to_string());
+            app.state_machine.transition(AppState::Testing);
+            for c in quote.chars() {
+                // Sent the way most terminals do: the shifted character, with SHIFT
+                let modifiers = if c.is_ascii_uppercase() || "!?\"".contains(c) {
+                    KeyModifiers::SHIFT
+                } else {
+                    KeyModifiers::NONE
+                };
+                app.handle_input(KeyEvent::new(KeyCode::Char(c), modifiers));
+            }
+            assert_eq!(app.state(), AppState::Results, "{}", quote);
+            assert_eq!(app.session.typed(), quote);
+            assert_eq!(app.session.accuracy(), 100.0, "{}", quote);
+        }
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn difficulty_cycles_between_tests() {
+        let mut app = test_app("cycle-difficulty");
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(app.session.difficulty(), Difficulty::Normal);
+        app.handle_input(ctrl_d);
+        assert_eq!(app.session.difficulty(), Difficulty::Stop);
+
+        // Mid-test the run and its rules carry on
+        let typed = type_some(&mut app, 5);
+        app.handle_input(ctrl_d);
+        assert_eq!(app.session.typed(), typed);
+        assert_eq!(app.session.difficulty(), Difficulty::Stop);
+        assert_eq!(app.config.difficulty, "master");
+        assert_eq!(app.db.get_abandonment(1).unwrap().abandoned, 0);
+
+        app.restart();
+        assert_eq!(app.session.difficulty(), Difficulty::Master);
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn changed_mode_is_remembered_for_next_start() {
+        let mut app = test_app("remember-mode");
+        assert_eq!(app.quote_mode, QuoteMode::Medium);
+        app.change_mode(QuoteMode::Long);
+        let reopened = App::with_paths(StartupOptions::default(), app.paths().clone()).unwrap();
+        assert_eq!(reopened.quote_mode, QuoteMode::Long);
+
+        app.config.remember_mode = false;
+        app.change_mode(QuoteMode::Short);
+        let reopened = App::with_paths(StartupOptions::default(), app.paths().clone()).unwrap();
+        assert_eq!(reopened.quote_mode, QuoteMode::Long);
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn unknown_default_mode_falls_back_to_medium() {
+        let mut app = test_app("unknown-mode");
+        app.config.default_mode = "sprint".to_string();
+        app.save_config().unwrap();
+        let reopened = App::with_paths(StartupOptions::default(), app.paths().clone()).unwrap();
+        assert_eq!(reopened.quote_mode, QuoteMode::Medium);
+        assert!(
+            reopened
+                .warning
+                .as_deref()
+                .is_some_and(|w| w.starts_with("config.toml: unknown mode `sprint`")),
+            "{:?}",
+            reopened.warning
+        );
+        remove_test_dirs(&app);
+    }
+
+    /// The screen after drawing `app` at `width` x `height`, one string per row
+    fn screen(
+        terminal: &mut Terminal<TestBackend>,
+        app: &App,
+        width: u16,
+        height: u16,
+    ) -> Vec<String> {
+        terminal.backend_mut().resize(width, height);
+        terminal
+            .draw(|frame| {
+                app.draw(frame);
+                app.draw_status(frame);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn typing_screen_follows_resizes() {
+        let mut app = test_app("resize");
+        app.typing_view = TypingView::new(true, app.quote_mode);
+        let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+        let has = |screen: &[String], text: &str| screen.iter().any(|row| row.contains(text));
+
+        let large = screen(&mut terminal, &app, 100, 40);
+        assert!(has(&large, "═══ QUOTE ═══"));
+        assert!(has(&large, "⇪") && has(&large, "⇥"));
+        assert!(!has(&large, "keyboard hidden"));
+
+        // The same terminal shrunk to the minimum: the keyboard gives way to the quote
+        let small = screen(&mut terminal, &app, 60, 18);
+        assert_eq!(small.len(), 18);
+        assert!(small.iter().all(|row| row.chars().count() == 60));
+        assert!(has(&small, "═══ QUOTE ═══"));
+        assert!(has(&small, "keyboard hidden: terminal too small"));
+        assert!(!has(&small, "⇪"));
+
+        let tiny = screen(&mut terminal, &app, 50, 12);
+        assert!(has(&tiny, "Terminal too small: need 60x18, have 50x12"));
+
+        // And it all comes back
+        assert_eq!(screen(&mut terminal, &app, 100, 40), large);
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn keyboard_toggle_is_saved() {
+        let mut app = test_app("toggle-keyboard");
+        assert!(!app.typing_view.show_keyboard());
+        app.handle_input(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+        assert!(app.typing_view.show_keyboard());
+        let reopened = App::with_paths(StartupOptions::default(), app.paths().clone()).unwrap();
+        assert!(reopened.typing_view.show_keyboard());
+        remove_test_dirs(&app);
+    }
+
+    /// A finished run of `text`
+    fn result(text: &str) -> TestResult {
+        let mut session = TypingSession::new(text.to_string());
+        for c in text.chars() {
+            session.type_char(c);
+        }
+        session.final_result("medium").unwrap()
+    }
+
+    #[test]
+    fn save_gates_hold_back_slow_or_sloppy_runs() {
+        let mut app = test_app("save-gate");
+        let run = |wpm, accuracy| TestResult {
+            wpm,
+            accuracy,
+            ..result("one two three")
+        };
+        // Both gates off by default
+        assert!(!app.below_save_threshold(&run(0.0, 0.0)));
+
+        app.config.min_wpm_to_save = 30.0;
+        app.config.min_accuracy_to_save = 90.0;
+        for (wpm, accuracy, below) in [
+            (30.0, 90.0, false),
+            (80.0, 100.0, false),
+            (29.9, 100.0, true),
+            (80.0, 89.9, true),
+            (10.0, 50.0, true),
+        ] {
+            assert_eq!(
+                app.below_save_threshold(&run(wpm, accuracy)),
+                below,
+                "{} WPM at {}%",
+                wpm,
+                accuracy
+            );
+        }
+        remove_test_dirs(&app);
+    }
+
+    /// Type the current quote with every character wrong, finishing the test
+    fn type_all_wrong(app: &mut App) {
+        let quote: Vec<char> = app.session.quote().chars().collect();
+        for c in quote {
+            app.session.type_char(if c == 'x' { 'y' } else { 'x' });
+        }
+        assert!(app.session.is_complete());
+        app.finish_test();
+    }
+
+    #[test]
+    fn results_keys_start_a_new_quote_or_the_same_one() {
+        let mut app = test_app("results-keys");
+        for (key, new_quote) in [
+            (KeyCode::Char(' '), true),
+            (KeyCode::Enter, true),
+            (KeyCode::Char('n'), true),
+            (KeyCode::Char('r'), false),
+        ] {
+            type_all_wrong(&mut app);
+            assert_eq!(app.state(), AppState::Results);
+            let (id, quote) = (app.quote_id, app.session.quote().to_string());
+
+            app.handle_input(KeyEvent::from(key));
+            assert_eq!(app.state(), AppState::Testing, "{:?}", key);
+            assert_eq!(app.session.typed(), "", "{:?}", key);
+            assert_eq!(app.quote_id != id, new_quote, "{:?}", key);
+            assert_eq!(app.session.quote() != quote, new_quote, "{:?}", key);
+        }
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn gated_run_is_only_saved_when_forced() {
+        let mut app = test_app("force-save");
+        app.config.min_accuracy_to_save = 50.0;
+        type_all_wrong(&mut app);
+        assert_eq!(app.state(), AppState::Results);
+        assert!(app.below_threshold);
+        assert_eq!(app.db.count_results(None).unwrap(), 0);
+
+        app.handle_input(KeyEvent::from(KeyCode::Char('s')));
+        assert!(!app.below_threshold);
+        assert_eq!(app.db.count_results(None).unwrap(), 1);
+        // Once only
+        app.handle_input(KeyEvent::from(KeyCode::Char('s')));
+        assert_eq!(app.db.count_results(None).unwrap(), 1);
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn ungated_run_is_saved_straight_away() {
+        let mut app = test_app("no-gate");
+        type_all_wrong(&mut app);
+        assert!(!app.below_threshold);
+        assert_eq!(app.db.count_results(None).unwrap(), 1);
+        // Nothing held back, so nothing to force
+        app.force_save();
+        assert_eq!(app.db.count_results(None).unwrap(), 1);
+        remove_test_dirs(&app);
+    }
+
+    #[test]
+    fn save_on_exit_warns_when_the_database_is_read_only() {
+        let mut app = test_app("read-only-db");
+        let db_file = app.paths().db_file();
+        app.db = Database::open(&format!("file:{}?mode=ro", db_file.display())).unwrap();
+        app.pending_results.push(result("one two three"));
+        assert_eq!(
+            app.save_on_exit().as_deref(),
+            Some("1 result(s) could not be saved")
+        );
+
+        app.db = Database::open(&db_file.to_string_lossy()).unwrap();
+        assert_eq!(app.save_on_exit(), None);
+        assert!(app.pending_results.is_empty());
+        remove_test_dirs(&app);
+    }
 }
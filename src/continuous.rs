@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::quotes::quote_hash;
+
+// A chunk ends at the first sentence end after MIN_WORDS, or at MAX_WORDS
+const MIN_WORDS: usize = 40;
+const MAX_WORDS: usize = 80;
+
+/// A long text file served one chunk per test, in order
+#[derive(Debug, Clone)]
+pub struct ContinuousSource {
+    pub path: PathBuf,
+    pub title: String,
+    /// Hash of the file contents; a mismatch with the bookmark means the file changed
+    pub hash: String,
+    chunks: Vec<String>,
+    /// Index of the first chunk of each chapter
+    chapter_starts: Vec<usize>,
+}
+
+impl ContinuousSource {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let (chunks, chapter_starts) = split_into_chunks(&text);
+        if chunks.is_empty() {
+            bail!("{} contains no text", path.display());
+        }
+
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().replace(['_', '-'], " "))
+            .unwrap_or_else(|| path.display().to_string());
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            title,
+            hash: quote_hash(&text),
+            chunks,
+            chapter_starts,
+        })
+    }
+
+    pub fn chunk(&self, index: usize) -> Option<&str> {
+        self.chunks.get(index).map(String::as_str)
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Footer text for the chunk at `index`, e.g. "The Hobbit — 34% through chapter 3"
+    pub fn progress_label(&self, index: usize) -> String {
+        let chapter = self
+            .chapter_starts
+            .iter()
+            .rposition(|&start| start <= index);
+
+        match chapter {
+            Some(i) => {
+                let start = self.chapter_starts[i];
+                let end = self
+                    .chapter_starts
+                    .get(i + 1)
+                    .copied()
+                    .unwrap_or(self.chunks.len());
+                format!(
+                    "{} — {}% through chapter {}",
+                    self.title,
+                    percent(index - start, end - start),
+                    i + 1
+                )
+            }
+            None => format!(
+                "{} — {}% through",
+                self.title,
+                percent(index, self.chunks.len())
+            ),
+        }
+    }
+}
+
+fn percent(done: usize, total: usize) -> usize {
+    (done * 100).checked_div(total).unwrap_or(0)
+}
+
+/// Deterministically split `text` into chunks of whole words. Chapter headings
+/// ("Chapter 3", "CHAPTER III: ...") are dropped and always start a new chunk.
+fn split_into_chunks(text: &str) -> (Vec<String>, Vec<usize>) {
+    let mut chunks = Vec::new();
+    let mut chapter_starts = Vec::new();
+    let mut words: Vec<&str> = Vec::new();
+
+    let flush = |words: &mut Vec<&str>, chunks: &mut Vec<String>| {
+        if !words.is_empty() {
+            chunks.push(words.join(" "));
+            words.clear();
+        }
+    };
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if is_chapter_heading(trimmed) {
+            flush(&mut words, &mut chunks);
+            chapter_starts.push(chunks.len());
+            continue;
+        }
+        // Paragraph breaks are a natural place to end a chunk
+        if trimmed.is_empty() && words.len() >= MIN_WORDS {
+            flush(&mut words, &mut chunks);
+            continue;
+        }
+
+        for word in trimmed.split_whitespace() {
+            words.push(word);
+            let sentence_end = word
+                .trim_end_matches(['"', '\'', '”', '’', ')'])
+                .ends_with(['.', '!', '?']);
+            if (words.len() >= MIN_WORDS && sentence_end) || words.len() >= MAX_WORDS {
+                flush(&mut words, &mut chunks);
+            }
+        }
+    }
+    flush(&mut words, &mut chunks);
+
+    // A heading at the very end has no chunks of its own
+    chapter_starts.retain(|&start| start < chunks.len());
+    chapter_starts.dedup();
+    (chunks, chapter_starts)
+}
+
+fn is_chapter_heading(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    line.len() <= 60 && (lower == "chapter" || lower.starts_with("chapter "))
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+/// Runs finished from midnight up to this local hour count as night owl runs
+const NIGHT_OWL_UNTIL_HOUR: u32 = 5;
+
+/// What achievements are judged on, gathered after each saved run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    pub total_tests: i64,
+    /// Fastest run, leaving out unconfirmed anomalies
+    pub best_wpm: f64,
+    pub longest_streak: u32,
+    /// Local hour (0–23) the latest run finished in
+    pub last_hour: Option<u32>,
+}
+
+pub struct Achievement {
+    /// Key in the `achievements` table, so it must never change
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    earned: fn(&Progress) -> bool,
+}
+
+impl Achievement {
+    pub fn is_earned(&self, progress: &Progress) -> bool {
+        (self.earned)(progress)
+    }
+}
+
+/// Every achievement, in the order they are listed
+pub const ACHIEVEMENTS: [Achievement; 5] = [
+    Achievement {
+        id: "first_test",
+        name: "First steps",
+        description: "Finish a test",
+        earned: |p| p.total_tests >= 1,
+    },
+    Achievement {
+        id: "hundred_tests",
+        name: "Centurion",
+        description: "Finish 100 tests",
+        earned: |p| p.total_tests >= 100,
+    },
+    Achievement {
+        id: "wpm_100",
+        name: "100 WPM club",
+        description: "Reach 100 WPM in a test",
+        earned: |p| p.best_wpm >= 100.0,
+    },
+    Achievement {
+        id: "streak_7",
+        name: "Week streak",
+        description: "Type on 7 days in a row",
+        earned: |p| p.longest_streak >= 7,
+    },
+    Achievement {
+        id: "night_owl",
+        name: "Night owl",
+        description: "Finish a test after midnight",
+        earned: |p| p.last_hour.is_some_and(|hour| hour < NIGHT_OWL_UNTIL_HOUR),
+    },
+];
+
+/// Achievements `progress` earns that aren't in `unlocked` yet, keyed by id
+pub fn newly_unlocked(
+    progress: &Progress,
+    unlocked: &HashMap<String, DateTime<Utc>>,
+) -> Vec<&'static Achievement> {
+    ACHIEVEMENTS
+        .iter()
+        .filter(|a| !unlocked.contains_key(a.id) && a.is_earned(progress))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn achievement(id: &str) -> &'static Achievement {
+        ACHIEVEMENTS.iter().find(|a| a.id == id).unwrap()
+    }
+
+    fn ids(achievements: &[&Achievement]) -> Vec<&'static str> {
+        achievements.iter().map(|a| a.id).collect()
+    }
+
+    #[test]
+    fn each_achievement_unlocks_at_its_threshold() {
+        let base = Progress {
+            total_tests: 1,
+            best_wpm: 40.0,
+            longest_streak: 1,
+            last_hour: Some(14),
+        };
+        for (id, below, reached) in [
+            (
+                "first_test",
+                Progress {
+                    total_tests: 0,
+                    ..base.clone()
+                },
+                base.clone(),
+            ),
+            (
+                "hundred_tests",
+                Progress {
+                    total_tests: 99,
+                    ..base.clone()
+                },
+                Progress {
+                    total_tests: 100,
+                    ..base.clone()
+                },
+            ),
+            (
+                "wpm_100",
+                Progress {
+                    best_wpm: 99.9,
+                    ..base.clone()
+                },
+                Progress {
+                    best_wpm: 100.0,
+                    ..base.clone()
+                },
+            ),
+            (
+                "streak_7",
+                Progress {
+                    longest_streak: 6,
+                    ..base.clone()
+                },
+                Progress {
+                    longest_streak: 7,
+                    ..base.clone()
+                },
+            ),
+            (
+                "night_owl",
+                Progress {
+                    last_hour: Some(23),
+                    ..base.clone()
+                },
+                Progress {
+                    last_hour: Some(0),
+                    ..base.clone()
+                },
+            ),
+        ] {
+            assert!(
+                !achievement(id).is_earned(&below),
+                "{} earned by {:?}",
+                id,
+                below
+            );
+            assert!(
+                achievement(id).is_earned(&reached),
+                "{} not earned by {:?}",
+                id,
+                reached
+            );
+        }
+    }
+
+    #[test]
+    fn night_owl_runs_until_five() {
+        let owl = achievement("night_owl");
+        for (hour, earned) in [(0, true), (4, true), (5, false), (12, false), (23, false)] {
+            let progress = Progress {
+                last_hour: Some(hour),
+                ..Progress::default()
+            };
+            assert_eq!(owl.is_earned(&progress), earned, "{}:00", hour);
+        }
+        assert!(!owl.is_earned(&Progress::default()));
+    }
+
+    #[test]
+    fn only_new_achievements_are_announced() {
+        let progress = Progress {
+            total_tests: 150,
+            best_wpm: 120.0,
+            longest_streak: 3,
+            last_hour: Some(10),
+        };
+        assert_eq!(
+            ids(&newly_unlocked(&progress, &HashMap::new())),
+            ["first_test", "hundred_tests", "wpm_100"]
+        );
+        let unlocked = HashMap::from([
+            ("first_test".to_string(), Utc::now()),
+            ("wpm_100".to_string(), Utc::now()),
+        ]);
+        assert_eq!(
+            ids(&newly_unlocked(&progress, &unlocked)),
+            ["hundred_tests"]
+        );
+        assert!(newly_unlocked(&Progress::default(), &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn achievement_ids_are_unique() {
+        let mut ids: Vec<&str> = ACHIEVEMENTS.iter().map(|a| a.id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), ACHIEVEMENTS.len());
+    }
+}
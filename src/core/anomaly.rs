@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+/// Results further than this many standard deviations from the mode's mean are flagged
+const Z_THRESHOLD: f64 = 3.0;
+
+/// Flagging only starts once a mode has this many unflagged results
+const MIN_SAMPLES: i64 = 10;
+
+/// How a result compares to the rest of its mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Anomaly {
+    #[default]
+    None,
+    /// Far above the mean: pasted text, a bug, or a genuinely great run
+    High,
+    /// Far below the mean: most likely interrupted
+    Low,
+}
+
+impl Anomaly {
+    /// Stored in the `anomaly` column of `test_results`
+    pub fn to_db(self) -> i64 {
+        match self {
+            Anomaly::None => 0,
+            Anomaly::High => 1,
+            Anomaly::Low => -1,
+        }
+    }
+
+    pub fn from_db(value: i64) -> Self {
+        match value {
+            1 => Anomaly::High,
+            -1 => Anomaly::Low,
+            _ => Anomaly::None,
+        }
+    }
+}
+
+/// Running mean and variance of WPM for one mode (Welford's algorithm), so
+/// the cached values can be updated one result at a time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WpmDistribution {
+    pub count: i64,
+    pub mean: f64,
+    pub m2: f64,
+}
+
+impl WpmDistribution {
+    pub fn push(&mut self, wpm: f64) {
+        self.count += 1;
+        let delta = wpm - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (wpm - self.mean);
+    }
+
+    /// Sample standard deviation
+    pub fn std_dev(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64).sqrt()
+    }
+
+    pub fn classify(&self, wpm: f64) -> Anomaly {
+        let std_dev = self.std_dev();
+        if self.count < MIN_SAMPLES || std_dev <= f64::EPSILON {
+            return Anomaly::None;
+        }
+        let z = (wpm - self.mean) / std_dev;
+        if z > Z_THRESHOLD {
+            Anomaly::High
+        } else if z < -Z_THRESHOLD {
+            Anomaly::Low
+        } else {
+            Anomaly::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` runs alternating between 58 and 62 WPM: mean 60, std dev about 2
+    fn synthetic(count: usize) -> WpmDistribution {
+        let mut dist = WpmDistribution::default();
+        for i in 0..count {
+            dist.push(if i % 2 == 0 { 58.0 } else { 62.0 });
+        }
+        dist
+    }
+
+    #[test]
+    fn running_stats_match_a_two_pass_computation() {
+        let samples = [41.5, 63.0, 55.2, 70.8, 48.1, 59.9, 66.4];
+        let mut dist = WpmDistribution::default();
+        for wpm in samples {
+            dist.push(wpm);
+        }
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        assert_eq!(dist.count, 7);
+        assert!((dist.mean - mean).abs() < 1e-9);
+        assert!((dist.std_dev() - variance.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn results_beyond_three_std_devs_are_flagged() {
+        let dist = synthetic(50);
+        let std_dev = dist.std_dev();
+        assert!((std_dev - 2.02).abs() < 0.01, "{}", std_dev);
+        for (wpm, expected) in [
+            (60.0, Anomaly::None),
+            (65.9, Anomaly::None),
+            (54.1, Anomaly::None),
+            (60.0 + 3.1 * std_dev, Anomaly::High),
+            (60.0 - 3.1 * std_dev, Anomaly::Low),
+            (250.0, Anomaly::High),
+            (5.0, Anomaly::Low),
+        ] {
+            assert_eq!(dist.classify(wpm), expected, "{} WPM", wpm);
+        }
+    }
+
+    #[test]
+    fn flagging_waits_for_enough_history() {
+        assert_eq!(
+            synthetic(MIN_SAMPLES as usize - 1).classify(250.0),
+            Anomaly::None
+        );
+        assert_eq!(
+            synthetic(MIN_SAMPLES as usize).classify(250.0),
+            Anomaly::High
+        );
+    }
+
+    #[test]
+    fn identical_runs_flag_nothing() {
+        let mut dist = WpmDistribution::default();
+        for _ in 0..20 {
+            dist.push(60.0);
+        }
+        assert_eq!(dist.std_dev(), 0.0);
+        assert_eq!(dist.classify(200.0), Anomaly::None);
+    }
+
+    #[test]
+    fn anomaly_round_trips_through_the_db_value() {
+        for anomaly in [Anomaly::None, Anomaly::High, Anomaly::Low] {
+            assert_eq!(Anomaly::from_db(anomaly.to_db()), anomaly);
+        }
+        assert_eq!(Anomaly::from_db(7), Anomaly::None);
+    }
+}
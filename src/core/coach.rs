@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use crate::models::TestResult;
+
+/// Below this accuracy the run was rushed
+const MIN_ACCURACY: f64 = 92.0;
+/// Below this consistency the pace was uneven
+const MIN_CONSISTENCY: f64 = 60.0;
+/// A key behind more than this share of the mistakes is worth naming
+const KEY_ERROR_SHARE: f64 = 0.25;
+/// Too few mistakes to blame one key on
+const MIN_KEY_ERRORS: u64 = 4;
+/// How far above the mode's average counts as outgrowing it
+const BEAT_AVERAGE_BY: f64 = 1.15;
+
+/// One thing to try next time, worked out from a finished run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Suggestion {
+    SlowDown {
+        accuracy: f64,
+    },
+    SteadyPace {
+        consistency: f64,
+    },
+    /// `share` is the fraction of the run's mistakes made on `key`
+    PracticeKey {
+        key: char,
+        share: f64,
+    },
+    /// The run was well above the average for its mode
+    LongerMode {
+        next: &'static str,
+    },
+}
+
+impl Suggestion {
+    pub fn message(&self) -> String {
+        match self {
+            Suggestion::SlowDown { accuracy } => format!(
+                "Accuracy was {:.0}%; slowing down a little usually pays off",
+                accuracy
+            ),
+            Suggestion::SteadyPace { consistency } => format!(
+                "Consistency was {:.0}%; try a steadier pace over bursts",
+                consistency
+            ),
+            Suggestion::PracticeKey { key, share } => format!(
+                "{:.0}% of your mistakes were on {}",
+                share * 100.0,
+                key_name(*key)
+            ),
+            Suggestion::LongerMode { next } => {
+                format!("Well above your average; try {} quotes", next)
+            }
+        }
+    }
+}
+
+/// Suggestions for a finished run, most pressing first. `key_errors` maps
+/// each expected char to its (hits, misses); `avg_wpm` is the mode's
+/// average before this run.
+pub fn suggestions(
+    result: &TestResult,
+    key_errors: &HashMap<char, (u64, u64)>,
+    avg_wpm: Option<f64>,
+) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+    if result.accuracy < MIN_ACCURACY {
+        suggestions.push(Suggestion::SlowDown {
+            accuracy: result.accuracy,
+        });
+    }
+    if let Some(consistency) = result.consistency
+        && consistency < MIN_CONSISTENCY
+    {
+        suggestions.push(Suggestion::SteadyPace { consistency });
+    }
+    let total: u64 = key_errors.values().map(|(_, misses)| misses).sum();
+    // Ties go to the lower char so the advice doesn't flicker between runs
+    let worst = key_errors
+        .iter()
+        .filter(|(_, (_, misses))| *misses > 0)
+        .max_by(|a, b| a.1 .1.cmp(&b.1 .1).then(b.0.cmp(a.0)));
+    if total >= MIN_KEY_ERRORS
+        && let Some((&key, &(_, misses))) = worst
+    {
+        let share = misses as f64 / total as f64;
+        if share > KEY_ERROR_SHARE {
+            suggestions.push(Suggestion::PracticeKey { key, share });
+        }
+    }
+    let next = match result.mode.as_str() {
+        "short" => Some("medium"),
+        "medium" => Some("long"),
+        _ => None,
+    };
+    if let (Some(next), Some(avg)) = (next, avg_wpm)
+        && avg > 0.0
+        && result.wpm >= avg * BEAT_AVERAGE_BY
+    {
+        suggestions.push(Suggestion::LongerMode { next });
+    }
+    suggestions
+}
+
+fn key_name(key: char) -> String {
+    match key {
+        ' ' => "space".to_string(),
+        '\n' => "enter".to_string(),
+        '\t' => "tab".to_string(),
+        c => format!("'{}'", c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::anomaly::Anomaly;
+    use chrono::Utc;
+
+    /// A clean, steady 60 WPM run in `mode`
+    fn run(mode: &str) -> TestResult {
+        TestResult {
+            id: None,
+            timestamp: Utc::now(),
+            mode: mode.to_string(),
+            wpm: 60.0,
+            raw_wpm: 60.0,
+            accuracy: 98.0,
+            real_accuracy: Some(97.0),
+            consistency: Some(80.0),
+            consistency_version: 2,
+            burst_wpm: None,
+            quote_length: 150,
+            word_count: Some(30),
+            duration_seconds: 30,
+            quote_id: None,
+            source: None,
+            anomaly: Anomaly::None,
+            confirmed: false,
+        }
+    }
+
+    #[test]
+    fn good_run_gets_no_advice() {
+        let keys = HashMap::from([('a', (40, 1)), ('s', (30, 1))]);
+        assert!(suggestions(&run("medium"), &keys, Some(60.0)).is_empty());
+        assert!(suggestions(&run("medium"), &HashMap::new(), None).is_empty());
+    }
+
+    #[test]
+    fn low_accuracy_suggests_slowing_down() {
+        let result = TestResult {
+            accuracy: 91.5,
+            ..run("medium")
+        };
+        assert_eq!(
+            suggestions(&result, &HashMap::new(), None),
+            [Suggestion::SlowDown { accuracy: 91.5 }]
+        );
+        let result = TestResult {
+            accuracy: MIN_ACCURACY,
+            ..run("medium")
+        };
+        assert!(suggestions(&result, &HashMap::new(), None).is_empty());
+    }
+
+    #[test]
+    fn low_consistency_suggests_a_steadier_pace() {
+        let result = TestResult {
+            consistency: Some(45.0),
+            ..run("medium")
+        };
+        assert_eq!(
+            suggestions(&result, &HashMap::new(), None),
+            [Suggestion::SteadyPace { consistency: 45.0 }]
+        );
+        // Too short a run to measure it says nothing
+        let result = TestResult {
+            consistency: None,
+            ..run("medium")
+        };
+        assert!(suggestions(&result, &HashMap::new(), None).is_empty());
+    }
+
+    #[test]
+    fn key_behind_most_mistakes_is_named() {
+        let keys = HashMap::from([
+            ('e', (50, 3)),
+            ('r', (20, 1)),
+            ('t', (30, 1)),
+            (' ', (40, 0)),
+        ]);
+        assert_eq!(
+            suggestions(&run("medium"), &keys, None),
+            [Suggestion::PracticeKey {
+                key: 'e',
+                share: 0.6
+            }]
+        );
+        // Spread evenly, no key stands out
+        let keys = HashMap::from([
+            ('a', (9, 1)),
+            ('b', (9, 1)),
+            ('c', (9, 1)),
+            ('d', (9, 1)),
+            ('e', (9, 1)),
+        ]);
+        assert!(suggestions(&run("medium"), &keys, None).is_empty());
+        // Nor with too few mistakes to judge
+        let keys = HashMap::from([('q', (9, 3))]);
+        assert!(suggestions(&run("medium"), &keys, None).is_empty());
+    }
+
+    #[test]
+    fn ties_between_keys_go_to_the_lower_one() {
+        let keys = HashMap::from([('z', (5, 2)), ('b', (5, 2))]);
+        assert_eq!(
+            suggestions(&run("medium"), &keys, None),
+            [Suggestion::PracticeKey {
+                key: 'b',
+                share: 0.5
+            }]
+        );
+    }
+
+    #[test]
+    fn beating_the_average_suggests_a_longer_mode() {
+        for (mode, next) in [
+            ("short", Some("medium")),
+            ("medium", Some("long")),
+            ("long", None),
+        ] {
+            let advice = suggestions(&run(mode), &HashMap::new(), Some(50.0));
+            let expected: Vec<Suggestion> = next
+                .map(|next| Suggestion::LongerMode { next })
+                .into_iter()
+                .collect();
+            assert_eq!(advice, expected, "{}", mode);
+        }
+        // 60 is only 9% above 55
+        assert!(suggestions(&run("short"), &HashMap::new(), Some(55.0)).is_empty());
+        assert!(suggestions(&run("short"), &HashMap::new(), Some(0.0)).is_empty());
+    }
+
+    #[test]
+    fn rules_stack_in_order() {
+        let result = TestResult {
+            accuracy: 85.0,
+            consistency: Some(50.0),
+            wpm: 90.0,
+            ..run("short")
+        };
+        let keys = HashMap::from([(' ', (40, 5))]);
+        let advice = suggestions(&result, &keys, Some(60.0));
+        assert_eq!(advice.len(), 4);
+        assert_eq!(advice[2].message(), "100% of your mistakes were on space");
+        assert_eq!(
+            advice[3].message(),
+            "Well above your average; try medium quotes"
+        );
+    }
+}
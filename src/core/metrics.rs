@@ -1,4 +1,11 @@
-use std::time::Instant;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::models::BigramLatency;
+
+/// Gaps longer than this are pauses, not typing speed
+const MAX_BIGRAM_GAP: Duration = Duration::from_secs(2);
 
 /// Calculate WPM (Words Per Minute) based on characters typed and elapsed time
 pub fn calculate_wpm(chars_typed: usize, elapsed_secs: f64) -> f64 {
@@ -9,6 +16,60 @@ pub fn calculate_wpm(chars_typed: usize, elapsed_secs: f64) -> f64 {
     words / (elapsed_secs / 60.0)
 }
 
+/// Characters counted as one word by the standard WPM
+pub const CHARS_PER_WORD: f64 = 5.0;
+
+/// How typing speed is shown. Speeds are always stored as WPM at five
+/// characters a word; the others are only for display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedUnit {
+    /// Words per minute, counting every five characters as a word
+    Wpm5,
+    /// Characters per minute
+    Cpm,
+    /// Words per minute counting the words actually in the text
+    RealWords,
+}
+
+impl SpeedUnit {
+    pub fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "cpm" => SpeedUnit::Cpm,
+            "real_words" => SpeedUnit::RealWords,
+            _ => SpeedUnit::Wpm5,
+        }
+    }
+
+    /// Suffix shown after a speed
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpeedUnit::Wpm5 => "WPM",
+            SpeedUnit::Cpm => "CPM",
+            SpeedUnit::RealWords => "rWPM",
+        }
+    }
+
+    /// Show a stored WPM in this unit. `chars_per_word` is the average word
+    /// length, spaces included, of the text it was typed on.
+    pub fn from_wpm(&self, wpm: f64, chars_per_word: f64) -> f64 {
+        match self {
+            SpeedUnit::Wpm5 => wpm,
+            SpeedUnit::Cpm => wpm * CHARS_PER_WORD,
+            SpeedUnit::RealWords if chars_per_word > 0.0 => wpm * CHARS_PER_WORD / chars_per_word,
+            SpeedUnit::RealWords => wpm,
+        }
+    }
+}
+
+/// Average word length of `text`, counting the space after each word; the
+/// standard five for text without words
+pub fn chars_per_word(text: &str) -> f64 {
+    match text.split_whitespace().count() {
+        0 => CHARS_PER_WORD,
+        words => text.chars().count() as f64 / words as f64,
+    }
+}
+
 /// Calculate raw WPM (including mistakes)
 pub fn calculate_raw_wpm(total_chars: usize, elapsed_secs: f64) -> f64 {
     if elapsed_secs < 1.0 / 60.0 {
@@ -26,6 +87,12 @@ pub fn calculate_accuracy(correct: usize, attempted: usize) -> f64 {
     (correct as f64 / attempted as f64) * 100.0
 }
 
+/// Accuracy over every keystroke: typos count even if they were backspaced
+/// away later. Backspaces themselves are not keystrokes here.
+pub fn calculate_real_accuracy(keystrokes: usize, errors: usize) -> f64 {
+    calculate_accuracy(keystrokes.saturating_sub(errors), keystrokes)
+}
+
 /// Count correct characters in typed text against quote
 pub fn count_correct_chars(typed: &str, quote: &str) -> usize {
     typed
@@ -35,40 +102,444 @@ pub fn count_correct_chars(typed: &str, quote: &str) -> usize {
         .count()
 }
 
-/// Calculate WPM consistency from history
-pub fn calculate_consistency(wpm_history: &[(Instant, f64)]) -> f64 {
-    if wpm_history.len() < 2 {
-        return 100.0;
+/// Version of the consistency formula stored with each result. Version 1
+/// was `(mean - std_dev) / mean`, which clamped to 0 for spiky tests and
+/// read 100% for very short ones.
+pub const CONSISTENCY_VERSION: i64 = 2;
+/// WPM samples this early in a test swing wildly and are left out
+const CONSISTENCY_WARMUP: Duration = Duration::from_secs(2);
+/// Fewer samples than this after the warm-up give no consistency at all
+const MIN_CONSISTENCY_SAMPLES: usize = 5;
+
+/// How steady the WPM stayed, as `100 / (1 + cv)` where `cv` is the
+/// coefficient of variation (standard deviation over mean) of the samples.
+/// `wpm_history` holds each sample's time into the test; samples in the
+/// first two seconds are skipped, and `None` means too few were left.
+pub fn calculate_consistency(wpm_history: &[(Duration, f64)]) -> Option<f64> {
+    let wpms: Vec<f64> = wpm_history
+        .iter()
+        .filter(|(at, _)| *at >= CONSISTENCY_WARMUP)
+        .map(|(_, wpm)| *wpm)
+        .collect();
+    if wpms.len() < MIN_CONSISTENCY_SAMPLES {
+        return None;
     }
 
-    let wpms: Vec<f64> = wpm_history.iter().map(|(_, wpm)| *wpm).collect();
     let mean = wpms.iter().sum::<f64>() / wpms.len() as f64;
+    if mean <= 0.0 {
+        return None;
+    }
     let variance = wpms.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / wpms.len() as f64;
-    let std_dev = variance.sqrt();
-
-    // Convert to percentage (lower std_dev = higher consistency)
-    ((mean - std_dev) / mean * 100.0).max(0.0).min(100.0)
+    let cv = variance.sqrt() / mean;
+    Some(100.0 / (1.0 + cv))
 }
 
-/// Animate WPM value towards target
-pub fn animate_wpm(current: f64, target: f64, last_for_animation: &mut f64) -> f64 {
-    if target == 0.0 {
-        *last_for_animation = 0.0;
+/// Highest WPM over any `window` of the test, counting the correct
+/// keystrokes at `times` (in order). Zero when the keystrokes span less
+/// than one window, since a partial window would overstate the pace.
+pub fn burst_wpm(times: &[Instant], window: Duration) -> f64 {
+    let (Some(&first), Some(&last)) = (times.first(), times.last()) else {
         return 0.0;
+    };
+    if last.saturating_duration_since(first) < window {
+        return 0.0;
+    }
+
+    let mut in_window = VecDeque::new();
+    let mut best = 0;
+    for &t in times {
+        in_window.push_back(t);
+        while let Some(&oldest) = in_window.front()
+            && t.saturating_duration_since(oldest) > window
+        {
+            in_window.pop_front();
+        }
+        // Only full windows, which start no earlier than the first keystroke
+        if t.saturating_duration_since(first) >= window {
+            best = best.max(in_window.len());
+        }
+    }
+    calculate_wpm(best, window.as_secs_f64())
+}
+
+/// Least-squares slope of y over x, or `None` with fewer than two distinct x values
+pub fn least_squares_slope(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+
+    if variance == 0.0 {
+        return None;
+    }
+    Some(covariance / variance)
+}
+
+/// Total latency per bigram from a test's keystrokes: bigram -> (count, total ms).
+///
+/// A bigram is two consecutive correct keystrokes; its latency is the time
+/// between them. Pairs with whitespace, gaps over `MAX_BIGRAM_GAP` and the
+/// first keystroke of the test (which has nothing before it) are skipped.
+pub fn bigram_latencies(keystrokes: &[(char, Instant, bool)]) -> HashMap<String, (u64, u64)> {
+    let mut latencies: HashMap<String, (u64, u64)> = HashMap::new();
+    for pair in keystrokes.windows(2) {
+        let (first, first_at, first_ok) = pair[0];
+        let (second, second_at, second_ok) = pair[1];
+        if !first_ok || !second_ok || first.is_whitespace() || second.is_whitespace() {
+            continue;
+        }
+        let gap = second_at.saturating_duration_since(first_at);
+        if gap > MAX_BIGRAM_GAP {
+            continue;
+        }
+        let entry = latencies
+            .entry(format!("{}{}", first, second))
+            .or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += gap.as_millis() as u64;
+    }
+    latencies
+}
+
+/// The `n` bigrams with the highest average latency, slowest first
+pub fn slowest_bigrams(latencies: &HashMap<String, (u64, u64)>, n: usize) -> Vec<BigramLatency> {
+    let mut bigrams: Vec<BigramLatency> = latencies
+        .iter()
+        .filter(|(_, (count, _))| *count > 0)
+        .map(|(bigram, (count, total_ms))| BigramLatency {
+            bigram: bigram.clone(),
+            count: *count as i64,
+            avg_ms: *total_ms as f64 / *count as f64,
+        })
+        .collect();
+    bigrams.sort_by(|a, b| b.avg_ms.total_cmp(&a.avg_ms));
+    bigrams.truncate(n);
+    bigrams
+}
+
+/// Each word of `quote` with the character indices it covers, lowercased.
+/// Punctuation around a word is dropped, so `"through,"` is `through`;
+/// apostrophes and hyphens inside it stay. Bare punctuation isn't a word.
+pub fn quote_words(quote: &str) -> Vec<(String, Range<usize>)> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut start = 0;
+    for (i, c) in quote.chars().chain(std::iter::once(' ')).enumerate() {
+        if !c.is_whitespace() {
+            if word.is_empty() {
+                start = i;
+            }
+            word.push(c);
+            continue;
+        }
+        let trimmed = word
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        if !trimmed.is_empty() {
+            words.push((trimmed, start..i));
+        }
+        word.clear();
+    }
+    words
+}
+
+/// Words of `quote` (see `quote_words`) that had a miss at any of the
+/// `missed` character indices, in quote order without repeats
+pub fn mistyped_words(quote: &str, missed: &BTreeSet<usize>) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for (word, span) in quote_words(quote) {
+        if missed.range(span).next().is_some() && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Each distinct word of `quote` (see `quote_words`) with how many times it
+/// appears and how many of those had a miss at one of the `missed` character
+/// indices, in quote order
+pub fn word_tallies(quote: &str, missed: &BTreeSet<usize>) -> Vec<(String, u32, u32)> {
+    let mut tallies: Vec<(String, u32, u32)> = Vec::new();
+    for (word, span) in quote_words(quote) {
+        let miss = missed.range(span).next().is_some() as u32;
+        match tallies.iter_mut().find(|(seen, ..)| *seen == word) {
+            Some((_, occurrences, misses)) => {
+                *occurrences += 1;
+                *misses += miss;
+            }
+            None => tallies.push((word, 1, miss)),
+        }
+    }
+    tallies
+}
+
+/// For each character of `quote`, whether it is part of a word in `hard`
+pub fn hard_word_chars(quote: &str, hard: &HashSet<String>) -> Vec<bool> {
+    let mut chars = vec![false; quote.chars().count()];
+    for (word, span) in quote_words(quote) {
+        if hard.contains(&word) {
+            chars[span].fill(true);
+        }
+    }
+    chars
+}
+
+/// Where a typist holding `wpm` would be after `elapsed_secs`, in characters
+/// (5 per word), clamped to the quote length
+pub fn ghost_chars(wpm: f64, elapsed_secs: f64, quote_len: usize) -> usize {
+    let chars = wpm * 5.0 / 60.0 * elapsed_secs;
+    (chars.max(0.0) as usize).min(quote_len)
+}
+
+/// The WPM shown while typing, easing towards the live value instead of
+/// jumping with every recalculation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WpmAnimator {
+    value: f64,
+}
+
+impl WpmAnimator {
+    /// Share of the remaining gap closed per frame
+    const SMOOTHING: f64 = 0.15;
+    /// Closer than this and the value lands on the target
+    const SNAP: f64 = 0.1;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Step one frame towards `target` and return the value to show.
+    /// A target of zero (a fresh or restarted test) snaps straight to zero.
+    pub fn update(&mut self, target: f64) -> f64 {
+        let diff = target - self.value;
+        if target == 0.0 || diff.abs() < Self::SNAP {
+            self.value = target;
+        } else {
+            self.value += diff * Self::SMOOTHING;
+        }
+        self.value
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn reset(&mut self) {
+        self.value = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn speed_units_round_trip_through_stored_wpm() {
+        let text = "the quick brown fox jumps over the lazy dog ";
+        let chars = text.chars().count();
+        let words = text.split_whitespace().count();
+        let secs = 12.0;
+        let minutes = secs / 60.0;
+        let wpm = calculate_wpm(chars, secs);
+        let per_word = chars_per_word(text);
+
+        assert_close(SpeedUnit::Wpm5.from_wpm(wpm, per_word), wpm);
+        assert_close(
+            SpeedUnit::Cpm.from_wpm(wpm, per_word),
+            chars as f64 / minutes,
+        );
+        assert_close(
+            SpeedUnit::RealWords.from_wpm(wpm, per_word),
+            words as f64 / minutes,
+        );
+        // Back from characters per minute to the stored WPM
+        assert_close(SpeedUnit::Cpm.from_wpm(wpm, per_word) / CHARS_PER_WORD, wpm);
+    }
+
+    #[test]
+    fn real_words_without_a_word_length_show_wpm() {
+        assert_close(SpeedUnit::RealWords.from_wpm(60.0, 0.0), 60.0);
+        assert_close(chars_per_word("   "), CHARS_PER_WORD);
+    }
+
+    #[test]
+    fn speed_unit_from_config() {
+        assert_eq!(SpeedUnit::from_config("CPM"), SpeedUnit::Cpm);
+        assert_eq!(SpeedUnit::from_config("real_words"), SpeedUnit::RealWords);
+        assert_eq!(SpeedUnit::from_config("wpm"), SpeedUnit::Wpm5);
+        assert_eq!(SpeedUnit::from_config("furlongs"), SpeedUnit::Wpm5);
+    }
+
+    #[test]
+    fn word_tallies_count_each_occurrence() {
+        let quote = "The cat saw the dog, the end.";
+        // The second "the" (index 12) and "dog" (index 17) have a miss
+        let missed = BTreeSet::from([12, 17]);
+        assert_eq!(
+            word_tallies(quote, &missed),
+            [
+                ("the".to_string(), 3, 1),
+                ("cat".to_string(), 1, 0),
+                ("saw".to_string(), 1, 0),
+                ("dog".to_string(), 1, 1),
+                ("end".to_string(), 1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn slope_of_a_line_is_exact() {
+        let rising: Vec<(f64, f64)> = (0..10).map(|x| (x as f64, 2.5 * x as f64 + 40.0)).collect();
+        assert_close(least_squares_slope(&rising).unwrap(), 2.5);
+        let falling = [(1.0, 10.0), (3.0, 6.0)];
+        assert_close(least_squares_slope(&falling).unwrap(), -2.0);
+        let flat = [(0.0, 5.0), (1.0, 5.0), (2.0, 5.0)];
+        assert_close(least_squares_slope(&flat).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn slope_fits_noisy_points() {
+        // Best fit through (0,1) (1,3) (2,2) (3,6): covariance 7 over variance 5
+        let points = [(0.0, 1.0), (1.0, 3.0), (2.0, 2.0), (3.0, 6.0)];
+        assert_close(least_squares_slope(&points).unwrap(), 1.4);
+        // Order doesn't matter
+        let shuffled = [(3.0, 6.0), (0.0, 1.0), (2.0, 2.0), (1.0, 3.0)];
+        assert_close(least_squares_slope(&shuffled).unwrap(), 1.4);
+    }
+
+    #[test]
+    fn slope_needs_two_distinct_x_values() {
+        assert_eq!(least_squares_slope(&[]), None);
+        assert_eq!(least_squares_slope(&[(1.0, 2.0)]), None);
+        assert_eq!(least_squares_slope(&[(4.0, 1.0), (4.0, 9.0)]), None);
+    }
+
+    #[test]
+    fn real_accuracy_counts_every_error_committed() {
+        assert_eq!(calculate_real_accuracy(0, 0), 100.0);
+        assert_eq!(calculate_real_accuracy(10, 0), 100.0);
+        assert_close(calculate_real_accuracy(12, 1), 11.0 / 12.0 * 100.0);
+        assert_eq!(calculate_real_accuracy(4, 4), 0.0);
+        // More errors than keystrokes can't go negative
+        assert_eq!(calculate_real_accuracy(2, 5), 0.0);
+    }
+
+    /// One WPM sample a second, starting at `start` seconds into the test
+    fn samples(start: u64, wpms: &[f64]) -> Vec<(Duration, f64)> {
+        wpms.iter()
+            .enumerate()
+            .map(|(i, &wpm)| (Duration::from_secs(start + i as u64), wpm))
+            .collect()
+    }
+
+    #[test]
+    fn consistency_of_steady_ramping_and_spiky_runs() {
+        assert_close(
+            calculate_consistency(&samples(2, &[70.0; 8])).unwrap(),
+            100.0,
+        );
+
+        // Mean 60, standard deviation √200
+        let ramp = calculate_consistency(&samples(2, &[40.0, 50.0, 60.0, 70.0, 80.0])).unwrap();
+        assert_close(ramp, 100.0 / (1.0 + 200f64.sqrt() / 60.0));
+        assert!(ramp > 75.0 && ramp < 85.0, "{}", ramp);
+
+        // Mean 88, standard deviation 56
+        let spiky = calculate_consistency(&samples(2, &[60.0, 60.0, 200.0, 60.0, 60.0])).unwrap();
+        assert_close(spiky, 100.0 / (1.0 + 56.0 / 88.0));
+        assert!(spiky < ramp - 15.0, "{} vs {}", spiky, ramp);
+    }
+
+    #[test]
+    fn consistency_needs_five_samples_after_the_warm_up() {
+        assert_eq!(calculate_consistency(&[]), None);
+        assert_eq!(calculate_consistency(&samples(2, &[70.0; 4])), None);
+        // Samples at 0s and 1s don't count towards the five
+        assert_eq!(calculate_consistency(&samples(0, &[70.0; 6])), None);
+        assert!(calculate_consistency(&samples(0, &[70.0; 7])).is_some());
+        assert_eq!(calculate_consistency(&samples(2, &[0.0; 5])), None);
+    }
+
+    #[test]
+    fn consistency_ignores_the_warm_up_swings() {
+        let mut history = samples(0, &[5.0, 300.0]);
+        history.extend(samples(2, &[70.0; 5]));
+        assert_close(calculate_consistency(&history).unwrap(), 100.0);
     }
 
-    if (target - *last_for_animation).abs() < 0.5 {
-        return current;
+    /// An animator that has had time to reach `wpm`
+    fn settled_at(wpm: f64) -> WpmAnimator {
+        let mut animator = WpmAnimator::new();
+        for _ in 0..100 {
+            animator.update(wpm);
+        }
+        assert_eq!(animator.value(), wpm);
+        animator
     }
 
-    let diff = target - *last_for_animation;
-    let new_value = current + diff * 0.15;
-    *last_for_animation = new_value;
+    #[test]
+    fn animated_wpm_converges_on_the_target() {
+        let mut animator = WpmAnimator::new();
+        animator.update(10.0);
+        let mut previous = animator.value();
+        let mut frames = 1;
+        while animator.value() != 80.0 {
+            let value = animator.update(80.0);
+            // Eases in from below without overshooting
+            assert!(
+                value > previous && value <= 80.0,
+                "{} after {}",
+                value,
+                previous
+            );
+            previous = value;
+            frames += 1;
+            assert!(frames < 100, "still at {} after {} frames", value, frames);
+        }
+        // Each frame closes 15% of the gap
+        let mut animator = settled_at(50.0);
+        assert_close(animator.update(70.0), 53.0);
+        // And it eases back down too
+        assert_close(animator.update(3.0), 45.5);
+        for _ in 0..100 {
+            animator.update(20.0);
+        }
+        assert_eq!(animator.value(), 20.0);
+    }
 
-    if diff.abs() < 0.1 {
-        *last_for_animation = target;
-        return target;
+    #[test]
+    fn animated_wpm_snaps_when_close() {
+        let mut animator = settled_at(60.0);
+        assert_eq!(animator.update(60.05), 60.05);
     }
 
-    new_value
+    #[test]
+    fn animated_wpm_snaps_to_zero_on_reset() {
+        let mut animator = WpmAnimator::new();
+        assert_eq!(animator.update(0.0), 0.0);
+        animator.update(90.0);
+        assert_eq!(animator.update(0.0), 0.0);
+
+        animator.update(90.0);
+        animator.reset();
+        assert_eq!(animator.value(), 0.0);
+    }
 }
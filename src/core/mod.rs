@@ -1,2 +1,6 @@
+pub mod achievements;
+pub mod anomaly;
+pub mod coach;
 pub mod metrics;
+pub mod replay;
 pub mod typing_session;
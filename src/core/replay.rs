@@ -0,0 +1,174 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::core::metrics;
+
+/// First byte of an encoded replay: how the JSON after it is stored
+const FORMAT_JSON: u8 = 0;
+const FORMAT_ZSTD: u8 = 1;
+
+/// One change to the typed text
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReplayKey {
+    /// The character added; unused for backspaces
+    pub ch: char,
+    /// Time since the first keystroke, AFK pauses left out
+    pub ms: u64,
+    pub backspace: bool,
+}
+
+/// Everything typed during a test, in order, for playing it back afterwards
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub quote: String,
+    pub keys: Vec<ReplayKey>,
+}
+
+impl Replay {
+    pub fn new(quote: String) -> Self {
+        Self {
+            quote,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Log an edit that took the typed text from `before` characters to
+    /// `typed`. Edits only ever append or truncate, so a shorter text is
+    /// that many backspaces and a longer one adds its last characters.
+    pub fn record(&mut self, before: usize, typed: &str, ms: u64) {
+        let after = typed.chars().count();
+        for _ in after..before {
+            self.keys.push(ReplayKey {
+                ch: '\0',
+                ms,
+                backspace: true,
+            });
+        }
+        for ch in typed.chars().skip(before) {
+            self.keys.push(ReplayKey {
+                ch,
+                ms,
+                backspace: false,
+            });
+        }
+    }
+
+    /// Length of the test: the time of the last keystroke
+    pub fn duration_ms(&self) -> u64 {
+        self.keys.last().map_or(0, |key| key.ms)
+    }
+
+    /// The typed text `ms` into the test, with how many characters were
+    /// typed wrong up to then, corrected or not
+    pub fn state_at(&self, ms: u64) -> (String, usize) {
+        let quote: Vec<char> = self.quote.chars().collect();
+        let mut typed: Vec<char> = Vec::new();
+        let mut mistakes = 0;
+        for key in self.keys.iter().take_while(|key| key.ms <= ms) {
+            if key.backspace {
+                typed.pop();
+                continue;
+            }
+            // Zen has no text to be wrong against
+            if !quote.is_empty() && quote.get(typed.len()) != Some(&key.ch) {
+                mistakes += 1;
+            }
+            typed.push(key.ch);
+        }
+        (typed.into_iter().collect(), mistakes)
+    }
+
+    /// Compact form for the database: JSON, compressed when built with the
+    /// `zstd` feature
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let json = serde_json::to_vec(self)?;
+        #[cfg(feature = "zstd")]
+        {
+            let mut data = vec![FORMAT_ZSTD];
+            data.extend(zstd::encode_all(json.as_slice(), 0)?);
+            Ok(data)
+        }
+        #[cfg(not(feature = "zstd"))]
+        {
+            let mut data = vec![FORMAT_JSON];
+            data.extend(json);
+            Ok(data)
+        }
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let Some((&format, body)) = data.split_first() else {
+            bail!("Replay is empty");
+        };
+        match format {
+            FORMAT_JSON => Ok(serde_json::from_slice(body)?),
+            #[cfg(feature = "zstd")]
+            FORMAT_ZSTD => Ok(serde_json::from_slice(&zstd::decode_all(body)?)?),
+            #[cfg(not(feature = "zstd"))]
+            FORMAT_ZSTD => bail!("Replay is compressed; this build lacks the zstd feature"),
+            _ => bail!("Unknown replay format {}", format),
+        }
+    }
+}
+
+/// Plays a replay back against the clock, at normal or double speed
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    /// Replay time reached when the speed last changed
+    offset: Duration,
+    since: Instant,
+    double_speed: bool,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay, now: Instant) -> Self {
+        Self {
+            replay,
+            offset: Duration::ZERO,
+            since: now,
+            double_speed: false,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Milliseconds into the test at `now`, stopping at the end
+    pub fn position(&self, now: Instant) -> u64 {
+        let mut elapsed = now.saturating_duration_since(self.since);
+        if self.double_speed {
+            elapsed *= 2;
+        }
+        ((self.offset + elapsed).as_millis() as u64).min(self.replay.duration_ms())
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        self.position(now) >= self.replay.duration_ms()
+    }
+
+    /// Switch between 1x and 2x, carrying on from the current position
+    pub fn toggle_speed(&mut self, now: Instant) {
+        self.offset = Duration::from_millis(self.position(now));
+        self.since = now;
+        self.double_speed = !self.double_speed;
+    }
+
+    pub fn speed_label(&self) -> &'static str {
+        if self.double_speed {
+            "2x"
+        } else {
+            "1x"
+        }
+    }
+
+    /// WPM of the text on screen at `now`
+    pub fn wpm(&self, now: Instant) -> f64 {
+        let ms = self.position(now);
+        let (typed, _) = self.replay.state_at(ms);
+        metrics::calculate_wpm(typed.chars().count(), ms as f64 / 1000.0)
+    }
+}
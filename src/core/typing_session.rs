@@ -1,39 +1,214 @@
+use std::collections::{BTreeSet, HashMap};
 use std::time::{Duration, Instant};
 
+use crate::core::anomaly::Anomaly;
 use crate::core::metrics;
+use crate::core::replay::Replay;
 use crate::models::TestResult;
 use chrono::Utc;
 
+/// How many characters may be typed past the end of the quote
+const MAX_EXTRA_CHARS: usize = 20;
+
+/// Stands in for the characters of a skipped word; never matches the quote,
+/// so they show and count as mistakes
+const SKIPPED_CHAR: char = '\0';
+
+/// Window for the burst (peak) WPM
+const BURST_WINDOW: Duration = Duration::from_secs(5);
+
+/// Whether `typed` finishes `quote`.
+///
+/// Strict completion needs an exact match, so a wrong final character (e.g. a
+/// swapped `."`) or characters typed past the end must be corrected before the
+/// test ends. Otherwise, the default, the test ends once the quote's length is
+/// reached and any uncorrected errors count against accuracy instead.
+pub fn is_session_complete(typed: &str, quote: &str, strict: bool) -> bool {
+    if strict {
+        typed == quote
+    } else {
+        typed.chars().count() >= quote.chars().count()
+    }
+}
+
+/// How mistakes are handled while typing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    /// Wrong characters are inserted and can be corrected
+    #[default]
+    Normal,
+    /// Wrong characters are rejected; only the right key advances
+    Stop,
+    /// The first mistake fails the test
+    Master,
+}
+
+impl Difficulty {
+    /// Parse the `difficulty` config value, falling back to normal
+    pub fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "stop" => Difficulty::Stop,
+            "master" => Difficulty::Master,
+            _ => Difficulty::Normal,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Normal => "normal",
+            Difficulty::Stop => "stop",
+            Difficulty::Master => "master",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Normal => Difficulty::Stop,
+            Difficulty::Stop => Difficulty::Master,
+            Difficulty::Master => Difficulty::Normal,
+        }
+    }
+}
+
+/// Whether mistakes can be taken back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Confidence {
+    #[default]
+    Off,
+    /// Backspace does nothing, so errors stay
+    On,
+    /// Neither backspace nor word delete work, and wrong characters are
+    /// typed in even in stop difficulty
+    Max,
+}
+
+impl Confidence {
+    /// Parse the `confidence` config value, falling back to off
+    pub fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "on" => Confidence::On,
+            "max" => Confidence::Max,
+            _ => Confidence::Off,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Confidence::Off => "off",
+            Confidence::On => "on",
+            Confidence::Max => "max",
+        }
+    }
+
+    /// Added to the saved mode, so runs without corrections are only
+    /// compared with each other
+    pub fn mode_suffix(&self) -> &'static str {
+        match self {
+            Confidence::Off => "",
+            Confidence::On => "+confidence",
+            Confidence::Max => "+confidence-max",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TypingSession {
     quote: String,
     typed: String,
     started_at: Option<Instant>,
     mistakes: usize,
+    /// Characters typed against the quote, backspaces excluded
+    total_keystrokes: usize,
     is_complete: bool,
     completed_at: Option<Instant>,
-    wpm_history: Vec<(Instant, f64)>,
+    /// WPM samples with the time into the test each was taken
+    wpm_history: Vec<(Duration, f64)>,
     final_wpm: f64,
     final_accuracy: f64,
     final_duration: Duration,
+    key_stats: HashMap<char, (u64, u64)>, // expected char -> (hits, misses)
+    keystrokes: Vec<(char, Instant, bool)>, // typed char, when, correct
+    /// Quote character indices that were ever typed wrong, corrected or not
+    missed_positions: BTreeSet<usize>,
+    strict_completion: bool,
+    /// After a correct newline, type the next line's indentation automatically
+    auto_indent: bool,
+    /// Space in the middle of a word gives up on the rest of it
+    space_skips_word: bool,
+    uncorrected_errors: usize,
+    difficulty: Difficulty,
+    confidence: Confidence,
+    failed: bool,
+    last_key_at: Option<Instant>,
+    /// Set while AFK: the time of the last keystroke, where the clock stops
+    afk_since: Option<Instant>,
+    afk_discarded: bool,
+    /// Set while a prompt is over the test: the clock stops here
+    paused_at: Option<Instant>,
+    /// Every edit to the typed text, for watching the test back
+    replay: Replay,
 }
 
 impl TypingSession {
     pub fn new(quote: String) -> Self {
         Self {
+            replay: Replay::new(quote.clone()),
             quote,
             typed: String::new(),
             started_at: None,
             mistakes: 0,
+            total_keystrokes: 0,
             is_complete: false,
             completed_at: None,
             wpm_history: Vec::new(),
             final_wpm: 0.0,
             final_accuracy: 100.0,
             final_duration: Duration::from_secs(0),
+            key_stats: HashMap::new(),
+            keystrokes: Vec::new(),
+            missed_positions: BTreeSet::new(),
+            strict_completion: false,
+            auto_indent: true,
+            space_skips_word: false,
+            uncorrected_errors: 0,
+            difficulty: Difficulty::Normal,
+            confidence: Confidence::Off,
+            failed: false,
+            last_key_at: None,
+            afk_since: None,
+            afk_discarded: false,
+            paused_at: None,
         }
     }
 
+    /// A still of `replay` at `ms`: the text typed by then and the mistakes
+    /// made on the way, for drawing playback with the typing view
+    pub fn replay_frame(replay: &Replay, ms: u64) -> Self {
+        let mut session = Self::new(replay.quote.clone());
+        (session.typed, session.mistakes) = replay.state_at(ms);
+        session
+    }
+
+    pub fn set_strict_completion(&mut self, strict: bool) {
+        self.strict_completion = strict;
+    }
+
+    pub fn set_auto_indent(&mut self, auto_indent: bool) {
+        self.auto_indent = auto_indent;
+    }
+
+    pub fn set_space_skips_word(&mut self, space_skips_word: bool) {
+        self.space_skips_word = space_skips_word;
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    pub fn set_confidence(&mut self, confidence: Confidence) {
+        self.confidence = confidence;
+    }
+
     pub fn start(&mut self) {
         if self.started_at.is_none() {
             self.started_at = Some(Instant::now());
@@ -41,85 +216,246 @@ impl TypingSession {
     }
 
     pub fn type_char(&mut self, c: char) -> bool {
+        let before = self.typed.chars().count();
+        let is_complete = self.insert_char(c);
+        self.log_edit(before);
+        is_complete
+    }
+
+    /// Add the latest edit to the replay; `before` is the typed length ahead of it
+    fn log_edit(&mut self, before: usize) {
+        let ms = self.elapsed().unwrap_or_default().as_millis() as u64;
+        self.replay.record(before, &self.typed, ms);
+    }
+
+    fn insert_char(&mut self, c: char) -> bool {
         if self.is_complete {
             return false;
         }
 
+        // Coming back from AFK in pause mode: the idle gap doesn't count
+        let now = Instant::now();
+        if let Some(since) = self.afk_since.take()
+            && !self.afk_discarded
+            && let Some(start) = self.started_at.as_mut()
+        {
+            *start += now.saturating_duration_since(since);
+        }
+        self.last_key_at = Some(now);
+
         self.start();
 
-        let expected = self.quote.chars().nth(self.typed.len());
+        // Zen sessions accept everything and only end through `finish`
+        if self.is_zen() {
+            self.keystrokes.push((c, now, true));
+            self.typed.push(c);
+            return false;
+        }
+
+        let mut typed_len = self.typed.chars().count();
+        if typed_len >= self.quote.chars().count() + MAX_EXTRA_CHARS {
+            return false;
+        }
+
+        // Space part-way through a word moves on to the next one
+        if c == ' ' && self.is_mid_word() {
+            if self.skip_word() {
+                return true;
+            }
+            typed_len = self.typed.chars().count();
+        }
+
+        let expected = self.quote.chars().nth(typed_len);
+        self.total_keystrokes += 1;
         if expected != Some(c) {
             self.mistakes += 1;
         }
 
+        if let Some(expected) = expected {
+            let entry = self.key_stats.entry(expected).or_insert((0, 0));
+            if expected == c {
+                entry.0 += 1;
+            } else {
+                entry.1 += 1;
+                self.missed_positions.insert(typed_len);
+            }
+        }
+
+        if expected != Some(c) {
+            match self.difficulty {
+                Difficulty::Normal => {}
+                // The miss is recorded but the character never lands, unless
+                // it could never be taken back anyway
+                Difficulty::Stop if self.confidence != Confidence::Max => return false,
+                Difficulty::Stop => {}
+                Difficulty::Master => {
+                    self.keystrokes.push((c, now, false));
+                    self.typed.push(c);
+                    self.failed = true;
+                    self.complete();
+                    return true;
+                }
+            }
+        }
+
+        self.keystrokes.push((c, now, expected == Some(c)));
         self.typed.push(c);
+        if c == '\n' && expected == Some(c) && self.auto_indent {
+            self.type_indent();
+        }
 
-        // Check for completion
-        if self.typed.len() == self.quote.len() {
-            let last_typed = self.typed.chars().last();
-            let last_quote = self.quote.chars().last();
+        self.complete_if_done()
+    }
 
-            if last_typed == last_quote {
-                self.complete();
-                return true;
-            }
+    /// Some of the current word is typed and some is left, with
+    /// `space_skips_word` on in normal difficulty
+    fn is_mid_word(&self) -> bool {
+        self.space_skips_word
+            && self.difficulty == Difficulty::Normal
+            && self
+                .quote
+                .chars()
+                .nth(self.typed.chars().count())
+                .is_some_and(|c| !c.is_whitespace())
+            && self
+                .typed
+                .chars()
+                .last()
+                .is_some_and(|c| !c.is_whitespace())
+    }
+
+    /// Give up on the rest of the current word: its remaining characters are
+    /// filled in as wrong, counting as attempted keystrokes and mistakes, so
+    /// the space that follows lands on the next word. Returns whether that
+    /// reached the end of the quote and completed the test.
+    fn skip_word(&mut self) -> bool {
+        let start = self.typed.chars().count();
+        let rest = self
+            .quote
+            .chars()
+            .skip(start)
+            .take_while(|c| !c.is_whitespace())
+            .count();
+        for position in start..start + rest {
+            self.typed.push(SKIPPED_CHAR);
+            self.missed_positions.insert(position);
         }
+        self.total_keystrokes += rest;
+        self.mistakes += rest;
+        self.complete_if_done()
+    }
 
-        false
+    /// Fill in the spaces and tabs that start the next line of the quote.
+    /// They count as typed text but not as keystrokes.
+    fn type_indent(&mut self) {
+        let indent: String = self
+            .quote
+            .chars()
+            .skip(self.typed.chars().count())
+            .take_while(|&c| c == ' ' || c == '\t')
+            .collect();
+        self.typed.push_str(&indent);
     }
 
-    pub fn backspace(&mut self) {
-        if !self.is_complete {
-            self.typed.pop();
+    /// End the test if the last edit finished the quote. Checked after every
+    /// change to the typed text; returns whether the test is now complete.
+    fn complete_if_done(&mut self) -> bool {
+        // Without backspace a strict test could never be finished, so
+        // confidence mode ends once the quote's length is reached
+        let strict = self.strict_completion && self.confidence == Confidence::Off;
+        if self.is_zen() || !is_session_complete(&self.typed, &self.quote, strict) {
+            return false;
         }
+        self.complete();
+        true
     }
 
-    pub fn delete_word(&mut self) {
-        if self.is_complete {
-            return;
+    /// End the test early. Used by zen mode, which has no text to complete;
+    /// does nothing if typing never started.
+    pub fn finish(&mut self) -> bool {
+        if self.is_complete || self.started_at.is_none() {
+            return false;
+        }
+        self.complete();
+        true
+    }
+
+    /// Remove the last typed character; returns whether that completed the test.
+    /// Does nothing in confidence mode.
+    pub fn backspace(&mut self) -> bool {
+        if self.is_complete || self.confidence != Confidence::Off {
+            return false;
         }
+        let before = self.typed.chars().count();
+        self.typed.pop();
+        self.log_edit(before);
+        self.complete_if_done()
+    }
 
-        // Find the start of the current word (from right)
-        let mut start = self.typed.len();
+    /// Characters typed beyond the end of the quote
+    pub fn extra_chars(&self) -> &str {
+        match self.typed.char_indices().nth(self.quote.chars().count()) {
+            Some((i, _)) => &self.typed[i..],
+            None => "",
+        }
+    }
 
-        // Move left until we hit a non-word character or beginning
-        while start > 0 {
-            let ch = self.typed.as_bytes()[start - 1];
-            if ch.is_ascii_whitespace() || !ch.is_ascii_alphanumeric() {
-                break;
-            }
-            start -= 1;
+    /// Remove the word before the cursor; returns whether that completed the
+    /// test. Does nothing in max confidence mode.
+    pub fn delete_word(&mut self) -> bool {
+        if self.is_complete || self.confidence == Confidence::Max {
+            return false;
         }
 
-        // Remove characters from start to end
-        self.typed.drain(start..);
+        // Spaces before the cursor go with the word, so repeated presses walk
+        // back a word at a time; a run of punctuation counts as a word
+        let trimmed = self.typed.trim_end();
+        let word_char = match trimmed.chars().next_back() {
+            Some(last) => last.is_alphanumeric(),
+            None => false,
+        };
+        // Byte offset where the trailing run of word (or punctuation) characters starts
+        let start = trimmed
+            .char_indices()
+            .rev()
+            .take_while(|&(_, c)| !c.is_whitespace() && c.is_alphanumeric() == word_char)
+            .last()
+            .map_or(trimmed.len(), |(i, _)| i);
+        let before = self.typed.chars().count();
+        self.typed.truncate(start);
+        self.log_edit(before);
+        self.complete_if_done()
     }
 
     fn complete(&mut self) {
         self.is_complete = true;
         self.completed_at = Some(Instant::now());
 
-        let correct = metrics::count_correct_chars(&self.typed, &self.quote);
-        self.final_accuracy = metrics::calculate_accuracy(correct, self.typed.len());
+        let typed_len = self.typed.chars().count();
+        let correct = if self.is_zen() {
+            typed_len
+        } else {
+            metrics::count_correct_chars(&self.typed, &self.quote)
+        };
+        self.uncorrected_errors = typed_len - correct;
+        self.final_accuracy = metrics::calculate_accuracy(correct, typed_len);
 
-        if let Some(start) = self.started_at {
-            self.final_duration = start.elapsed();
-            self.final_wpm =
-                metrics::calculate_wpm(self.typed.len(), self.final_duration.as_secs_f64());
+        if let Some(elapsed) = self.elapsed() {
+            self.final_duration = elapsed;
+            self.final_wpm = metrics::calculate_wpm(typed_len, self.final_duration.as_secs_f64());
         }
     }
 
     pub fn update_metrics(&mut self) {
-        if self.is_complete {
+        if self.is_complete || self.afk_since.is_some() {
             return;
         }
 
-        if let Some(start) = self.started_at {
-            let elapsed = start.elapsed().as_secs_f64();
-            let wpm = metrics::calculate_wpm(self.typed.len(), elapsed);
+        if let Some(elapsed) = self.elapsed() {
+            let wpm = metrics::calculate_wpm(self.typed.chars().count(), elapsed.as_secs_f64());
 
             if wpm > 0.0 {
-                self.wpm_history.push((Instant::now(), wpm));
+                self.wpm_history.push((elapsed, wpm));
             }
         }
     }
@@ -129,24 +465,46 @@ impl TypingSession {
         self.typed.clear();
         self.started_at = None;
         self.mistakes = 0;
+        self.total_keystrokes = 0;
         self.is_complete = false;
         self.completed_at = None;
         self.wpm_history.clear();
         self.final_wpm = 0.0;
         self.final_accuracy = 100.0;
         self.final_duration = Duration::from_secs(0);
+        self.key_stats.clear();
+        self.keystrokes.clear();
+        self.replay = Replay::new(self.quote.clone());
+        self.missed_positions.clear();
+        self.uncorrected_errors = 0;
+        self.failed = false;
+        self.last_key_at = None;
+        self.afk_since = None;
+        self.afk_discarded = false;
+        self.paused_at = None;
     }
 
     pub fn restart(&mut self) {
         self.typed.clear();
         self.started_at = None;
         self.mistakes = 0;
+        self.total_keystrokes = 0;
         self.is_complete = false;
         self.completed_at = None;
         self.wpm_history.clear();
         self.final_wpm = 0.0;
         self.final_accuracy = 100.0;
         self.final_duration = Duration::from_secs(0);
+        self.key_stats.clear();
+        self.keystrokes.clear();
+        self.replay = Replay::new(self.quote.clone());
+        self.missed_positions.clear();
+        self.uncorrected_errors = 0;
+        self.failed = false;
+        self.last_key_at = None;
+        self.afk_since = None;
+        self.afk_discarded = false;
+        self.paused_at = None;
     }
 
     // Getters
@@ -158,6 +516,129 @@ impl TypingSession {
         &self.typed
     }
 
+    /// The quote character under the cursor; `None` past the end or in zen mode
+    pub fn expected_char(&self) -> Option<char> {
+        self.quote.chars().nth(self.typed.chars().count())
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Share of the quote typed so far, 0.0..=1.0 (0 for zen, which has no end)
+    pub fn progress(&self) -> f64 {
+        let total = self.quote.chars().count();
+        if total == 0 {
+            return 0.0;
+        }
+        (self.typed.chars().count() as f64 / total as f64).min(1.0)
+    }
+
+    /// 1-based index of the word under the cursor: spaces in the quote before it, plus one
+    pub fn word_index(&self) -> usize {
+        if self.is_zen() {
+            return self.typed.split_whitespace().count().max(1);
+        }
+        let spaces = self
+            .quote
+            .chars()
+            .take(self.typed.chars().count())
+            .filter(|&c| c == ' ')
+            .count();
+        (spaces + 1).min(self.word_count().max(1))
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.quote.split_whitespace().count()
+    }
+
+    /// Time since the first keystroke; frozen at the last keystroke while AFK
+    /// and at the pause while paused
+    fn elapsed(&self) -> Option<Duration> {
+        let start = self.started_at?;
+        Some(match self.afk_since.or(self.paused_at) {
+            Some(since) => since.saturating_duration_since(start),
+            None => start.elapsed(),
+        })
+    }
+
+    /// At most `chars` typed, or less than `time` on the clock
+    pub fn is_within(&self, chars: usize, time: Duration) -> bool {
+        self.typed.chars().count() <= chars || self.elapsed().is_none_or(|e| e < time)
+    }
+
+    /// Stop the clock, e.g. while the quit prompt is showing
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+        }
+    }
+
+    /// Restart the clock where `pause` stopped it; the paused time doesn't
+    /// count towards the test or towards going AFK
+    pub fn resume(&mut self) {
+        let Some(paused_at) = self.paused_at.take() else {
+            return;
+        };
+        let paused = paused_at.elapsed();
+        if let Some(start) = self.started_at.as_mut() {
+            *start += paused;
+        }
+        if let Some(last) = self.last_key_at.as_mut() {
+            *last += paused;
+        }
+    }
+
+    /// Stop the clock if nothing has been typed for `threshold`. With `discard`
+    /// the attempt is also marked so its result is never saved; otherwise the
+    /// test pauses and picks up at the next keystroke. Returns whether the
+    /// session went AFK just now.
+    pub fn check_afk(&mut self, threshold: Duration, discard: bool, now: Instant) -> bool {
+        if self.is_complete || self.afk_since.is_some() || self.paused_at.is_some() {
+            return false;
+        }
+        match self.last_key_at {
+            Some(last) if now.saturating_duration_since(last) >= threshold => {
+                self.afk_since = Some(last);
+                self.afk_discarded |= discard;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_afk(&self) -> bool {
+        self.afk_since.is_some()
+    }
+
+    /// Went AFK under the "discard" behavior; the result must not be saved
+    pub fn is_afk_discarded(&self) -> bool {
+        self.afk_discarded
+    }
+
+    /// Whether the first key of this attempt has been typed
+    pub fn is_started(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn confidence(&self) -> Confidence {
+        self.confidence
+    }
+
+    /// A master-difficulty test that ended on a mistake
+    pub fn is_failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Zen sessions have no target text
+    pub fn is_zen(&self) -> bool {
+        self.quote.is_empty()
+    }
+
     pub fn is_complete(&self) -> bool {
         self.is_complete
     }
@@ -166,19 +647,58 @@ impl TypingSession {
         self.mistakes
     }
 
+    pub fn uncorrected_errors(&self) -> usize {
+        self.uncorrected_errors
+    }
+
+    pub fn key_stats(&self) -> &HashMap<char, (u64, u64)> {
+        &self.key_stats
+    }
+
+    /// Per-bigram (count, total ms) for this attempt
+    pub fn bigram_latencies(&self) -> HashMap<String, (u64, u64)> {
+        metrics::bigram_latencies(&self.keystrokes)
+    }
+
+    /// Every character typed against the text, wrong ones included
+    pub fn keystroke_chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.keystrokes.iter().map(|&(c, _, _)| c)
+    }
+
+    /// Words with at least one wrong keystroke, corrected ones included
+    pub fn mistyped_words(&self) -> Vec<String> {
+        metrics::mistyped_words(&self.quote, &self.missed_positions)
+    }
+
+    /// (word, times typed, times with a miss) for each word of the quote
+    pub fn word_tallies(&self) -> Vec<(String, u32, u32)> {
+        metrics::word_tallies(&self.quote, &self.missed_positions)
+    }
+
     pub fn wpm(&self) -> f64 {
         if self.is_complete {
             self.final_wpm
-        } else if let Some(start) = self.started_at {
-            metrics::calculate_wpm(self.typed.len(), start.elapsed().as_secs_f64())
+        } else if let Some(elapsed) = self.elapsed() {
+            metrics::calculate_wpm(self.typed.chars().count(), elapsed.as_secs_f64())
         } else {
             0.0
         }
     }
 
+    /// Best WPM over any 5 seconds of the test; 0 for shorter tests
+    pub fn burst_wpm(&self) -> f64 {
+        let times: Vec<Instant> = self
+            .keystrokes
+            .iter()
+            .filter(|&&(_, _, correct)| correct)
+            .map(|&(_, at, _)| at)
+            .collect();
+        metrics::burst_wpm(&times, BURST_WINDOW)
+    }
+
     pub fn raw_wpm(&self) -> f64 {
-        if let Some(start) = self.started_at {
-            metrics::calculate_raw_wpm(self.typed.len(), start.elapsed().as_secs_f64())
+        if let Some(elapsed) = self.elapsed() {
+            metrics::calculate_raw_wpm(self.typed.chars().count(), elapsed.as_secs_f64())
         } else {
             0.0
         }
@@ -187,27 +707,59 @@ impl TypingSession {
     pub fn accuracy(&self) -> f64 {
         if self.is_complete {
             self.final_accuracy
+        } else if self.is_zen() {
+            100.0
         } else {
             let correct = metrics::count_correct_chars(&self.typed, &self.quote);
-            metrics::calculate_accuracy(correct, self.typed.len().max(1))
+            metrics::calculate_accuracy(correct, self.typed.chars().count().max(1))
         }
     }
 
-    pub fn consistency(&self) -> f64 {
+    /// Characters a ghost typing at `wpm` would have reached by now; 0 before
+    /// the first keystroke and frozen once the test ends or goes AFK
+    pub fn ghost_position(&self, wpm: f64) -> usize {
+        let elapsed = if self.is_complete {
+            self.final_duration
+        } else {
+            self.elapsed().unwrap_or_default()
+        };
+        metrics::ghost_chars(wpm, elapsed.as_secs_f64(), self.quote.chars().count())
+    }
+
+    /// Accuracy over every keystroke, counting typos that were corrected
+    pub fn real_accuracy(&self) -> f64 {
+        if self.is_zen() {
+            return 100.0;
+        }
+        metrics::calculate_real_accuracy(self.total_keystrokes, self.mistakes)
+    }
+
+    /// `None` until there are enough WPM samples to judge
+    pub fn consistency(&self) -> Option<f64> {
         metrics::calculate_consistency(&self.wpm_history)
     }
 
     pub fn duration(&self) -> Duration {
         if self.is_complete {
             self.final_duration
-        } else if let Some(start) = self.started_at {
-            start.elapsed()
+        } else if let Some(elapsed) = self.elapsed() {
+            elapsed
         } else {
             Duration::from_secs(0)
         }
     }
 
-    pub fn final_result(&self) -> Option<TestResult> {
+    /// Average word length of the text, for showing speed in real words;
+    /// zen has only what was typed
+    pub fn chars_per_word(&self) -> f64 {
+        if self.is_zen() {
+            metrics::chars_per_word(&self.typed)
+        } else {
+            metrics::chars_per_word(&self.quote)
+        }
+    }
+
+    pub fn final_result(&self, mode: &str) -> Option<TestResult> {
         if !self.is_complete {
             return None;
         }
@@ -215,13 +767,268 @@ impl TypingSession {
         Some(TestResult {
             id: None,
             timestamp: Utc::now(),
-            mode: "medium".to_string(),
+            mode: format!("{}{}", mode, self.confidence.mode_suffix()),
             wpm: self.final_wpm,
             raw_wpm: self.raw_wpm(),
             accuracy: self.final_accuracy,
+            real_accuracy: Some(self.real_accuracy()),
+            burst_wpm: Some(self.burst_wpm()),
             consistency: self.consistency(),
-            quote_length: self.quote.len() as i64,
+            consistency_version: metrics::CONSISTENCY_VERSION,
+            quote_length: if self.is_zen() {
+                self.typed.chars().count() as i64
+            } else {
+                self.quote.chars().count() as i64
+            },
+            word_count: Some(if self.is_zen() {
+                self.typed.split_whitespace().count() as i64
+            } else {
+                self.quote.split_whitespace().count() as i64
+            }),
             duration_seconds: self.final_duration.as_secs() as i64,
+            quote_id: None,
+            source: None,
+            anomaly: Anomaly::None,
+            confirmed: false,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(text: &str) -> TypingSession {
+        TypingSession::new(text.to_string())
+    }
+
+    fn type_str(session: &mut TypingSession, text: &str) {
+        for c in text.chars() {
+            session.type_char(c);
+        }
+    }
+
+    #[test]
+    fn completion_with_trailing_punctuation() {
+        let quote = "He said \"stop.\"";
+        let swapped = "He said \"stop\".";
+        assert!(is_session_complete(quote, quote, true));
+        assert!(is_session_complete(quote, quote, false));
+        assert!(!is_session_complete(swapped, quote, true));
+        assert!(is_session_complete(swapped, quote, false));
+        // One short is never done
+        let short = &quote[..quote.len() - 1];
+        assert!(!is_session_complete(short, quote, true));
+        assert!(!is_session_complete(short, quote, false));
+    }
+
+    #[test]
+    fn non_strict_finishes_with_swapped_punctuation_and_counts_it() {
+        let mut s = session("Really?!\"");
+        type_str(&mut s, "Really!?\"");
+        assert!(s.is_complete());
+        assert!(s.accuracy() < 100.0);
+    }
+
+    #[test]
+    fn strict_waits_for_the_punctuation_to_be_fixed() {
+        let mut s = session("Wait...\")");
+        s.set_strict_completion(true);
+        // Full length, in the wrong order
+        type_str(&mut s, "Wait..\").");
+        assert!(!s.is_complete());
+        for _ in 0..3 {
+            s.backspace();
+        }
+        assert!(!s.is_complete());
+        type_str(&mut s, ".\")");
+        assert!(s.is_complete());
+        assert_eq!(s.accuracy(), 100.0);
+    }
+
+    #[test]
+    fn expected_char_counts_characters_not_bytes() {
+        let mut s = session("café über naïve");
+        type_str(&mut s, "café");
+        assert_eq!(s.expected_char(), Some(' '));
+        type_str(&mut s, " üb");
+        assert_eq!(s.expected_char(), Some('e'));
+        type_str(&mut s, "er na");
+        assert_eq!(s.expected_char(), Some('ï'));
+    }
+
+    #[test]
+    fn corrected_typos_lower_real_accuracy_only() {
+        let mut s = session("hello world");
+        // "o" for "l", backspaced and fixed: 12 keystrokes, one of them wrong
+        type_str(&mut s, "helo");
+        s.backspace();
+        type_str(&mut s, "lo world");
+        assert!(s.is_complete());
+        assert_eq!(s.mistakes(), 1);
+        assert_eq!(s.accuracy(), 100.0);
+        assert!((s.real_accuracy() - 11.0 / 12.0 * 100.0).abs() < 1e-9);
+
+        let result = s.final_result("short").unwrap();
+        assert_eq!(result.accuracy, 100.0);
+        assert_eq!(result.real_accuracy, Some(s.real_accuracy()));
+    }
+
+    #[test]
+    fn every_retyped_typo_counts_again() {
+        let mut s = session("cats");
+        // Two wrong tries at the third letter before the right one
+        type_str(&mut s, "cax");
+        s.backspace();
+        type_str(&mut s, "y");
+        s.backspace();
+        type_str(&mut s, "ts");
+        assert!(s.is_complete());
+        assert_eq!(s.mistakes(), 2);
+        assert_eq!(s.accuracy(), 100.0);
+        assert!((s.real_accuracy() - 200.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn uncorrected_typos_lower_both_accuracies() {
+        let mut s = session("dog");
+        type_str(&mut s, "dig");
+        assert!(s.is_complete());
+        assert!((s.accuracy() - 200.0 / 3.0).abs() < 1e-9);
+        assert!((s.real_accuracy() - 200.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn never_complete_while_shorter() {
+        for quote in ["a", "hello world", "naïve café", "日本語"] {
+            let chars: Vec<char> = quote.chars().collect();
+            for len in 0..chars.len() {
+                let typed: String = chars[..len].iter().collect();
+                assert!(!is_session_complete(&typed, quote, true), "{:?}", typed);
+                assert!(!is_session_complete(&typed, quote, false), "{:?}", typed);
+            }
+            assert!(is_session_complete(quote, quote, true));
+            assert!(is_session_complete(quote, quote, false));
+        }
+        // Extra characters only finish a lenient test
+        assert!(!is_session_complete("abcd", "abc", true));
+        assert!(is_session_complete("abcd", "abc", false));
+    }
+
+    #[test]
+    fn strict_finishes_after_correcting_the_last_character() {
+        let mut s = session("abc");
+        s.set_strict_completion(true);
+        type_str(&mut s, "abx");
+        assert!(!s.is_complete());
+        assert!(!s.backspace());
+        assert!(s.type_char('c'));
+        assert!(s.is_complete());
+    }
+
+    #[test]
+    fn strict_finishes_after_typing_past_the_end() {
+        let mut s = session("abc");
+        s.set_strict_completion(true);
+        type_str(&mut s, "abxd");
+        assert!(!s.is_complete());
+        assert_eq!(s.extra_chars(), "d");
+        s.backspace();
+        s.backspace();
+        assert!(s.type_char('c'));
+        assert!(s.is_complete());
+    }
+
+    #[test]
+    fn strict_finishes_after_delete_word() {
+        let mut s = session("one two");
+        s.set_strict_completion(true);
+        type_str(&mut s, "one twx");
+        assert!(!s.is_complete());
+        assert!(!s.delete_word());
+        assert_eq!(s.typed(), "one ");
+        type_str(&mut s, "two");
+        assert!(s.is_complete());
+    }
+
+    /// What's left of `typed` after each press of delete-word, until nothing is
+    fn delete_word_steps(typed: &str) -> Vec<String> {
+        let mut s = session(&format!("{} and more", typed));
+        type_str(&mut s, typed);
+        let mut steps = Vec::new();
+        while !s.typed().is_empty() {
+            assert!(!s.delete_word());
+            steps.push(s.typed().to_string());
+        }
+        steps
+    }
+
+    #[test]
+    fn delete_word_takes_trailing_spaces_with_the_word() {
+        assert_eq!(delete_word_steps("foo  bar"), ["foo  ", ""]);
+        assert_eq!(delete_word_steps("foo  "), [""]);
+    }
+
+    #[test]
+    fn delete_word_stops_at_punctuation() {
+        assert_eq!(delete_word_steps("foo.bar"), ["foo.", "foo", ""]);
+        assert_eq!(delete_word_steps("foo, bar"), ["foo, ", "foo", ""]);
+        assert_eq!(delete_word_steps("foo...!"), ["foo", ""]);
+    }
+
+    fn skipping(text: &str) -> TypingSession {
+        let mut s = session(text);
+        s.set_space_skips_word(true);
+        s
+    }
+
+    #[test]
+    fn space_mid_word_fills_the_rest_as_wrong() {
+        let mut s = skipping("hello world");
+        type_str(&mut s, "he ");
+        assert_eq!(s.typed().chars().count(), 6);
+        assert_eq!(s.expected_char(), Some('w'));
+        assert_eq!(s.mistakes(), 3);
+
+        type_str(&mut s, "world");
+        assert!(s.is_complete());
+        // "he" + 3 skipped + " world": 11 attempted, 3 of them wrong
+        assert_eq!(s.uncorrected_errors(), 3);
+        assert!((s.accuracy() - 8.0 / 11.0 * 100.0).abs() < 1e-9);
+        assert!((s.real_accuracy() - 8.0 / 11.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn skipped_characters_count_towards_wpm() {
+        let mut s = skipping("hello world");
+        type_str(&mut s, "h");
+        s.started_at = Some(Instant::now() - Duration::from_secs(12));
+        type_str(&mut s, " world");
+        assert!(s.is_complete());
+        // 11 characters in 12 seconds
+        assert!((s.wpm() - 11.0).abs() < 0.01, "{}", s.wpm());
+    }
+
+    #[test]
+    fn skipping_the_last_word_completes() {
+        let mut s = skipping("hello world");
+        type_str(&mut s, "hello wo ");
+        assert!(s.is_complete());
+        assert_eq!(s.mistakes(), 3);
+        assert_eq!(s.extra_chars(), "");
+    }
+
+    #[test]
+    fn space_mid_word_is_a_typo_without_skipping() {
+        let mut s = session("hello world");
+        type_str(&mut s, "he ");
+        assert_eq!(s.typed(), "he ");
+        assert_eq!(s.mistakes(), 1);
+        assert_eq!(s.expected_char(), Some('l'));
+
+        // Nor between words, where space is simply right
+        let mut s = skipping("hello world");
+        type_str(&mut s, "hello ");
+        assert_eq!(s.mistakes(), 0);
+    }
+}
@@ -1,137 +1,754 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
+use crate::quotes::QuoteMode;
 use crate::state::AppState;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum AppAction {
     Quit,
     ShowHistory,
     ShowStats,
-    BackToTesting,
-    CycleTheme,
+    ShowHeatmap,
+    ShowSettings,
+    ShowQuotePicker,
+    EditConfig,
+    ConfirmResult,
+    Back,
+    CloseDetails,
+    ShowThemePicker,
+    ShowProfilePicker,
+    CycleLanguage,
     CycleMode,
+    CycleDifficulty,
+    CycleQuoteDifficulty,
+    ToggleGhost,
+    CycleErrorFeedback,
+    RestartSource,
+    DeleteResult,
+    ClearHistory,
+    ConfirmDelete,
+    BackupDatabase,
+    RestoreDatabase,
+    CycleHistoryMode,
+    CycleHistorySort,
+    /// Stats and History: all time -> 7 -> 30 -> 90 days
+    CycleDateWindow,
+    /// Write this week's Markdown report from the Stats screen
+    WriteReport,
+    CancelDelete,
     NewQuote,
+    /// Bring back a test just thrown away by a new quote or restart
+    Undo,
     Restart,
+    RateQuote(u8),
+    CopyResult,
+    /// Save a run held back by the save thresholds
+    ForceSave,
+    /// Play the test back; during playback, switch between 1x and 2x
+    PlayReplay,
     ToggleKeyboard,
+    ToggleFocus,
+    ToggleAutoAdvance,
     TypeChar(char),
+    FilterChar(char),
+    FilterBackspace,
     Backspace,
     DeleteWord,
+    EndTest,
     NavigateUp,
     NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    PageUp,
+    PageDown,
     Select,
     None,
 }
 
-pub struct InputHandler;
+/// What the app is doing when a key arrives, which decides what the key means
+#[derive(Debug, Clone, Copy)]
+pub struct InputContext {
+    pub state: AppState,
+    /// Whether the test on the typing screen has finished
+    pub is_complete: bool,
+    /// The next character of the text being typed, if any
+    pub expected: Option<char>,
+    /// Mode of the test on the typing screen
+    pub mode: QuoteMode,
+}
 
-impl InputHandler {
-    pub fn new() -> Self {
-        Self
+impl InputContext {
+    /// A picker or prompt is open over another screen and takes every key
+    pub fn overlay_open(&self) -> bool {
+        matches!(
+            self.state,
+            AppState::QuitConfirm | AppState::ThemePicker | AppState::ProfilePicker
+        )
     }
 
-    pub fn handle(&self, key: KeyEvent, state: AppState, is_complete: bool) -> AppAction {
-        match (key.code, key.modifiers, state) {
-            // Global quit
-            (KeyCode::Char('`'), _, _) => AppAction::Quit,
+    /// Whether keys type into the running test
+    fn typing(&self) -> bool {
+        self.state == AppState::Testing && !self.is_complete
+    }
+}
 
-            // Mode switching - always available
-            (KeyCode::Tab, _, _) => AppAction::CycleMode,
+pub struct InputHandler {
+    zen_end_key: KeyCode,
+    /// Uppercase letters typed with SHIFT, for terminals that report the
+    /// unshifted letter plus the modifier
+    shift_uppercase: bool,
+}
 
-            // Theme cycling
-            (KeyCode::Char('t'), mods, _) if mods.contains(KeyModifiers::CONTROL) => {
-                AppAction::CycleTheme
-            }
+impl InputHandler {
+    pub fn new(zen_end_key: KeyCode, shift_uppercase: bool) -> Self {
+        Self {
+            zen_end_key,
+            shift_uppercase,
+        }
+    }
 
-            // Toggle keyboard
-            (KeyCode::Char('f'), mods, _) if mods.contains(KeyModifiers::CONTROL) => {
-                AppAction::ToggleKeyboard
-            }
+    pub fn handle(&self, key: KeyEvent, ctx: &InputContext) -> AppAction {
+        // Global quit; in the confirmation prompt it also means "yes". Like
+        // Tab, it types itself where the text has one (JS template literals).
+        if key.code == KeyCode::Char('`') {
+            return if ctx.typing() && ctx.expected == Some('`') {
+                AppAction::TypeChar('`')
+            } else {
+                AppAction::Quit
+            };
+        }
+        if ctx.overlay_open() {
+            return overlay_action(key, ctx.state);
+        }
+        // Tab types a tab where the text has one (code snippets) and
+        // switches mode everywhere else
+        if key.code == KeyCode::Tab {
+            return if ctx.typing() && ctx.expected == Some('\t') {
+                AppAction::TypeChar('\t')
+            } else {
+                AppAction::CycleMode
+            };
+        }
+        // History's own letters win over the Ctrl shortcuts
+        if ctx.state == AppState::History
+            && let Some(action) = history_action(key)
+        {
+            return action;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL)
+            && let KeyCode::Char(c) = key.code
+            && let Some(action) = shortcut_action(c, ctx)
+        {
+            return action;
+        }
 
-            // New quote / restart
-            (KeyCode::Char('n'), mods, _) if mods.contains(KeyModifiers::CONTROL) => {
-                AppAction::NewQuote
+        match ctx.state {
+            AppState::Testing => self.testing_action(key, ctx),
+            AppState::Results => results_action(key),
+            AppState::History | AppState::HistoryDetail | AppState::Stats => {
+                browse_action(key, ctx.state)
             }
-            (KeyCode::Char('r'), mods, _) if mods.contains(KeyModifiers::CONTROL) => {
-                AppAction::Restart
+            AppState::QuotePicker => quote_picker_action(key),
+            AppState::Heatmap | AppState::Settings | AppState::Replay => match key.code {
+                KeyCode::Char('e') if ctx.state == AppState::Settings => AppAction::EditConfig,
+                KeyCode::Char('p') if ctx.state == AppState::Replay => AppAction::PlayReplay,
+                KeyCode::Esc => AppAction::Back,
+                KeyCode::Enter => AppAction::Select,
+                _ => AppAction::None,
+            },
+            AppState::QuitConfirm | AppState::ThemePicker | AppState::ProfilePicker => {
+                overlay_action(key, ctx.state)
             }
+        }
+    }
 
-            // History view
-            (KeyCode::Char('h'), mods, _) if mods.contains(KeyModifiers::CONTROL) => {
-                AppAction::ShowHistory
+    fn testing_action(&self, key: KeyEvent, ctx: &InputContext) -> AppAction {
+        if !ctx.typing() {
+            return match key.code {
+                KeyCode::Enter => AppAction::Select,
+                _ => AppAction::None,
+            };
+        }
+        match key.code {
+            // End a running zen test; in other modes the key keeps its usual
+            // meaning, so Enter still breaks lines in code snippets
+            code if code == self.zen_end_key && ctx.mode == QuoteMode::Zen => AppAction::EndTest,
+            // Line breaks in code snippets; ignored for text without any
+            KeyCode::Enter => AppAction::TypeChar('\n'),
+            // Opt-in for terminals that send Shift+letter as the lowercase
+            // letter; Ctrl+Shift keys are left to the arms below
+            KeyCode::Char(c)
+                if self.shift_uppercase
+                    && key.modifiers.contains(KeyModifiers::SHIFT)
+                    && !key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                AppAction::TypeChar(uppercase(c))
             }
-
-            // Stats view
-            (KeyCode::Char('s'), mods, _) if mods.contains(KeyModifiers::CONTROL) => {
-                AppAction::ShowStats
+            // Many terminals send Ctrl+Backspace as 0x08, which arrives as
+            // Ctrl+H
+            KeyCode::Char('h') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                AppAction::DeleteWord
+            }
+            // The character is taken as the terminal sent it, since most
+            // already apply SHIFT (Shift+1 is '!', not '1')
+            KeyCode::Char(c) => AppAction::TypeChar(c),
+            // Ctrl and Alt delete a word like in an editor
+            KeyCode::Backspace
+                if key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                AppAction::DeleteWord
             }
+            KeyCode::Backspace => AppAction::Backspace,
+            _ => AppAction::None,
+        }
+    }
+}
 
-            // Escape to go back
-            (KeyCode::Esc, _, AppState::History | AppState::Stats) => AppAction::BackToTesting,
+/// `c` as a capital, unless it has no single-character one (e.g. 'ß')
+fn uppercase(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => c,
+    }
+}
 
-            // Navigation in history/stats
-            (KeyCode::Up, _, AppState::History | AppState::Stats) => AppAction::NavigateUp,
-            (KeyCode::Down, _, AppState::History | AppState::Stats) => AppAction::NavigateDown,
+/// The quit prompt and the pickers, which take every key while open
+fn overlay_action(key: KeyEvent, state: AppState) -> AppAction {
+    match (key.code, state) {
+        (KeyCode::Char('y'), AppState::QuitConfirm) => AppAction::Quit,
+        // Any other key means "no" and goes back to the test
+        (_, AppState::QuitConfirm) => AppAction::Back,
+        (KeyCode::Esc, _) => AppAction::Back,
+        (KeyCode::Enter, _) => AppAction::Select,
+        (KeyCode::Up, _) => AppAction::NavigateUp,
+        (KeyCode::Down, _) => AppAction::NavigateDown,
+        // Typing in the profile picker names a new profile
+        (KeyCode::Backspace, AppState::ProfilePicker) => AppAction::FilterBackspace,
+        (KeyCode::Char(c), AppState::ProfilePicker)
+            if !key.modifiers.contains(KeyModifiers::CONTROL) =>
+        {
+            AppAction::FilterChar(c)
+        }
+        _ => AppAction::None,
+    }
+}
 
-            // Select/Enter
-            (KeyCode::Enter, _, _) => {
-                if is_complete && (state == AppState::Testing || state == AppState::Results) {
-                    AppAction::NewQuote
-                } else {
-                    AppAction::Select
-                }
-            }
+/// Ctrl+`c`, available from every screen unless noted
+fn shortcut_action(c: char, ctx: &InputContext) -> Option<AppAction> {
+    let action = match c {
+        't' => AppAction::ShowThemePicker,
+        'a' => AppAction::ShowProfilePicker,
+        // Quote language; applies from the next quote
+        'u' => AppAction::CycleLanguage,
+        'd' => AppAction::CycleDifficulty,
+        // Hardest quotes offered: easy -> normal -> hard
+        'q' => AppAction::CycleQuoteDifficulty,
+        'g' => AppAction::ToggleGhost,
+        // Wrong-key feedback: none -> flash -> bell
+        'k' => AppAction::CycleErrorFeedback,
+        // Start the continuous source over
+        'b' => AppAction::RestartSource,
+        'f' => AppAction::ToggleKeyboard,
+        // Focus mode: only the stats line and the quote
+        'l' => AppAction::ToggleFocus,
+        // Skip the results screen between tests
+        'y' => AppAction::ToggleAutoAdvance,
+        'n' => AppAction::NewQuote,
+        'r' => AppAction::Restart,
+        'z' if ctx.state == AppState::Testing => AppAction::Undo,
+        // Delete a word, as in a shell
+        'w' if ctx.typing() => AppAction::DeleteWord,
+        'w' if ctx.state == AppState::Testing => AppAction::None,
+        // Left to the test, where it is Ctrl+Backspace
+        'h' if ctx.typing() => return None,
+        'h' => AppAction::ShowHistory,
+        's' => AppAction::ShowStats,
+        'e' => AppAction::ShowHeatmap,
+        'o' => AppAction::ShowQuotePicker,
+        'p' => AppAction::ShowSettings,
+        _ => return None,
+    };
+    Some(action)
+}
 
-            // Space handling - NewQuote for Results, Restart for Testing
-            (KeyCode::Char(' '), _, _) => {
-                if is_complete && state == AppState::Testing {
-                    AppAction::Restart
-                } else if is_complete && state == AppState::Results {
-                    AppAction::NewQuote
-                } else {
-                    AppAction::TypeChar(' ')
-                }
-            }
+/// Deleting and backing up history; Ctrl+D means "clear all" here rather
+/// than difficulty
+fn history_action(key: KeyEvent) -> Option<AppAction> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let action = match key.code {
+        KeyCode::Char('d') if ctrl => AppAction::ClearHistory,
+        KeyCode::Char('d') => AppAction::DeleteResult,
+        KeyCode::Char('y') => AppAction::ConfirmDelete,
+        KeyCode::Char('m') => AppAction::CycleHistoryMode,
+        KeyCode::Char('b') => AppAction::BackupDatabase,
+        KeyCode::Char('r') => AppAction::RestoreDatabase,
+        KeyCode::Char('s') if !ctrl => AppAction::CycleHistorySort,
+        KeyCode::Char('n') if !ctrl => AppAction::CancelDelete,
+        _ => return None,
+    };
+    Some(action)
+}
 
-            // Character input during testing
-            (KeyCode::Char(c), mods, AppState::Testing) if mods.contains(KeyModifiers::SHIFT) => {
-                if !is_complete {
-                    AppAction::TypeChar(c.to_ascii_uppercase())
-                } else {
-                    AppAction::None
-                }
-            }
+/// History, its detail pane and Stats
+fn browse_action(key: KeyEvent, state: AppState) -> AppAction {
+    let detail = state == AppState::HistoryDetail;
+    let history = detail || state == AppState::History;
+    match key.code {
+        // Vouch for a flagged result
+        KeyCode::Char('c') if history => AppAction::ConfirmResult,
+        KeyCode::Char('w') if !detail && !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            AppAction::CycleDateWindow
+        }
+        KeyCode::Char('p') if detail => AppAction::PlayReplay,
+        KeyCode::Char('r') if !history => AppAction::WriteReport,
+        // The detail pane closes back to the list
+        KeyCode::Esc if detail => AppAction::CloseDetails,
+        KeyCode::Esc => AppAction::Back,
+        KeyCode::Up => AppAction::NavigateUp,
+        KeyCode::Down => AppAction::NavigateDown,
+        KeyCode::PageUp if history => AppAction::PageUp,
+        KeyCode::PageDown if history => AppAction::PageDown,
+        KeyCode::Left if !history => AppAction::NavigateLeft,
+        KeyCode::Right if !history => AppAction::NavigateRight,
+        KeyCode::Enter => AppAction::Select,
+        _ => AppAction::None,
+    }
+}
 
-            (KeyCode::Char(c), _, AppState::Testing) => {
-                if !is_complete {
-                    AppAction::TypeChar(c)
-                } else {
-                    AppAction::None
-                }
-            }
+/// Space, Enter or N move on to a new quote, R retries the same text
+fn results_action(key: KeyEvent) -> AppAction {
+    match key.code {
+        KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Char('n') => AppAction::NewQuote,
+        KeyCode::Char('r') => AppAction::Restart,
+        KeyCode::Char('c') => AppAction::CopyResult,
+        KeyCode::Char('s') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            AppAction::ForceSave
+        }
+        KeyCode::Char('p') => AppAction::PlayReplay,
+        // Quote rating
+        KeyCode::Char(c @ '1'..='5') => AppAction::RateQuote(c as u8 - b'0'),
+        _ => AppAction::None,
+    }
+}
 
-            // Ctrl+Backspace / Ctrl+H shows history (must be before general backspace arms)
-            (KeyCode::Backspace, mods, _) if mods.contains(KeyModifiers::CONTROL) => {
-                AppAction::ShowHistory
-            }
+/// Searching the quote list
+fn quote_picker_action(key: KeyEvent) -> AppAction {
+    match key.code {
+        KeyCode::Esc => AppAction::Back,
+        KeyCode::Enter => AppAction::Select,
+        KeyCode::Up => AppAction::NavigateUp,
+        KeyCode::Down => AppAction::NavigateDown,
+        KeyCode::Backspace => AppAction::FilterBackspace,
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            AppAction::FilterChar(c)
+        }
+        _ => AppAction::None,
+    }
+}
 
-            // Backspace handling
-            (KeyCode::Backspace, mods, AppState::Testing) if mods.contains(KeyModifiers::ALT) => {
-                if !is_complete {
-                    AppAction::DeleteWord
-                } else {
-                    AppAction::None
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::typing_session::TypingSession;
+
+    fn typing(expected: Option<char>) -> InputContext {
+        InputContext {
+            state: AppState::Testing,
+            is_complete: false,
+            expected,
+            mode: QuoteMode::Code,
+        }
+    }
+
+    /// The key a terminal sends for `c`
+    fn key_for(c: char) -> KeyEvent {
+        let code = match c {
+            '\n' => KeyCode::Enter,
+            '\t' => KeyCode::Tab,
+            c => KeyCode::Char(c),
+        };
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn backtick_types_where_expected_and_quits_elsewhere() {
+        let handler = InputHandler::new(KeyCode::Esc, false);
+        let key = key_for('`');
+        assert_eq!(
+            handler.handle(key, &typing(Some('`'))),
+            AppAction::TypeChar('`')
+        );
+        assert_eq!(handler.handle(key, &typing(Some('a'))), AppAction::Quit);
+        let done = InputContext {
+            is_complete: true,
+            ..typing(Some('`'))
+        };
+        assert_eq!(handler.handle(key, &done), AppAction::Quit);
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
 
-            (KeyCode::Backspace, _, AppState::Testing) => {
-                if !is_complete {
-                    AppAction::Backspace
-                } else {
-                    AppAction::None
+    fn ctx(state: AppState) -> InputContext {
+        InputContext {
+            state,
+            is_complete: false,
+            expected: Some('a'),
+            mode: QuoteMode::Medium,
+        }
+    }
+
+    #[test]
+    fn handle_maps_keys_by_state() {
+        let handler = InputHandler::new(KeyCode::Esc, false);
+        let none = KeyModifiers::NONE;
+        let ctrl = KeyModifiers::CONTROL;
+        let alt = KeyModifiers::ALT;
+        let done = InputContext {
+            is_complete: true,
+            ..ctx(AppState::Testing)
+        };
+        let zen = InputContext {
+            mode: QuoteMode::Zen,
+            ..ctx(AppState::Testing)
+        };
+        let cases = [
+            // Tab types a tab only where the text has one
+            (
+                key(KeyCode::Tab, none),
+                typing(Some('\t')),
+                AppAction::TypeChar('\t'),
+            ),
+            (
+                key(KeyCode::Tab, none),
+                typing(Some('a')),
+                AppAction::CycleMode,
+            ),
+            (key(KeyCode::Tab, none), done, AppAction::CycleMode),
+            (
+                key(KeyCode::Tab, none),
+                ctx(AppState::Results),
+                AppAction::CycleMode,
+            ),
+            // Backtick likewise, and quits everywhere else
+            (
+                key(KeyCode::Char('`'), none),
+                typing(Some('`')),
+                AppAction::TypeChar('`'),
+            ),
+            (
+                key(KeyCode::Char('`'), none),
+                typing(Some('a')),
+                AppAction::Quit,
+            ),
+            (
+                key(KeyCode::Char('`'), none),
+                ctx(AppState::Stats),
+                AppAction::Quit,
+            ),
+            (
+                key(KeyCode::Char('`'), none),
+                ctx(AppState::QuitConfirm),
+                AppAction::Quit,
+            ),
+            // Deleting while typing
+            (
+                key(KeyCode::Backspace, none),
+                typing(None),
+                AppAction::Backspace,
+            ),
+            (
+                key(KeyCode::Backspace, ctrl),
+                typing(None),
+                AppAction::DeleteWord,
+            ),
+            (
+                key(KeyCode::Backspace, alt),
+                typing(None),
+                AppAction::DeleteWord,
+            ),
+            (
+                key(KeyCode::Char('w'), ctrl),
+                typing(None),
+                AppAction::DeleteWord,
+            ),
+            (key(KeyCode::Char('w'), ctrl), done, AppAction::None),
+            (
+                key(KeyCode::Char('h'), ctrl),
+                typing(None),
+                AppAction::DeleteWord,
+            ),
+            (key(KeyCode::Char('h'), ctrl), done, AppAction::ShowHistory),
+            // Typing, and the typing screen once the test is over
+            (
+                key(KeyCode::Char('x'), none),
+                typing(None),
+                AppAction::TypeChar('x'),
+            ),
+            (
+                key(KeyCode::Enter, none),
+                typing(None),
+                AppAction::TypeChar('\n'),
+            ),
+            (key(KeyCode::Enter, none), done, AppAction::Select),
+            (key(KeyCode::Char('x'), none), done, AppAction::None),
+            (key(KeyCode::Esc, none), zen, AppAction::EndTest),
+            (
+                key(KeyCode::Esc, none),
+                ctx(AppState::Testing),
+                AppAction::None,
+            ),
+            // Shortcuts
+            (
+                key(KeyCode::Char('s'), ctrl),
+                typing(None),
+                AppAction::ShowStats,
+            ),
+            (
+                key(KeyCode::Char('z'), ctrl),
+                ctx(AppState::Testing),
+                AppAction::Undo,
+            ),
+            (
+                key(KeyCode::Char('z'), ctrl),
+                ctx(AppState::Results),
+                AppAction::None,
+            ),
+            (
+                key(KeyCode::Char('h'), ctrl),
+                ctx(AppState::Results),
+                AppAction::ShowHistory,
+            ),
+            // History's letters win over the shortcuts
+            (
+                key(KeyCode::Char('d'), ctrl),
+                ctx(AppState::History),
+                AppAction::ClearHistory,
+            ),
+            (
+                key(KeyCode::Char('d'), ctrl),
+                ctx(AppState::Stats),
+                AppAction::CycleDifficulty,
+            ),
+            (
+                key(KeyCode::Char('d'), none),
+                ctx(AppState::History),
+                AppAction::DeleteResult,
+            ),
+            // Other screens
+            (
+                key(KeyCode::Char('r'), none),
+                ctx(AppState::Results),
+                AppAction::Restart,
+            ),
+            (
+                key(KeyCode::Char('3'), none),
+                ctx(AppState::Results),
+                AppAction::RateQuote(3),
+            ),
+            (
+                key(KeyCode::Char('r'), none),
+                ctx(AppState::Stats),
+                AppAction::WriteReport,
+            ),
+            (
+                key(KeyCode::Esc, none),
+                ctx(AppState::HistoryDetail),
+                AppAction::CloseDetails,
+            ),
+            (
+                key(KeyCode::Char('w'), none),
+                ctx(AppState::HistoryDetail),
+                AppAction::None,
+            ),
+            (
+                key(KeyCode::Char('q'), none),
+                ctx(AppState::QuotePicker),
+                AppAction::FilterChar('q'),
+            ),
+            (
+                key(KeyCode::Char('e'), none),
+                ctx(AppState::Settings),
+                AppAction::EditConfig,
+            ),
+            (
+                key(KeyCode::Char('p'), none),
+                ctx(AppState::Replay),
+                AppAction::PlayReplay,
+            ),
+            // Overlays take every key
+            (
+                key(KeyCode::Char('y'), none),
+                ctx(AppState::QuitConfirm),
+                AppAction::Quit,
+            ),
+            (
+                key(KeyCode::Char('s'), ctrl),
+                ctx(AppState::QuitConfirm),
+                AppAction::Back,
+            ),
+            (
+                key(KeyCode::Char('x'), none),
+                ctx(AppState::ProfilePicker),
+                AppAction::FilterChar('x'),
+            ),
+            (
+                key(KeyCode::Char('x'), none),
+                ctx(AppState::ThemePicker),
+                AppAction::None,
+            ),
+        ];
+        for (key, ctx, expected) in cases {
+            assert_eq!(
+                handler.handle(key, &ctx),
+                expected,
+                "{:?} in {:?}",
+                key,
+                ctx
+            );
+        }
+    }
+
+    #[test]
+    fn testing_action_types_and_deletes() {
+        let handler = InputHandler::new(KeyCode::Enter, true);
+        let zen = InputContext {
+            mode: QuoteMode::Zen,
+            ..typing(None)
+        };
+        let cases = [
+            (
+                key(KeyCode::Enter, KeyModifiers::NONE),
+                zen,
+                AppAction::EndTest,
+            ),
+            (
+                key(KeyCode::Enter, KeyModifiers::NONE),
+                typing(None),
+                AppAction::TypeChar('\n'),
+            ),
+            (
+                key(KeyCode::Char('a'), KeyModifiers::SHIFT),
+                typing(None),
+                AppAction::TypeChar('A'),
+            ),
+            (
+                key(KeyCode::Char('ß'), KeyModifiers::SHIFT),
+                typing(None),
+                AppAction::TypeChar('ß'),
+            ),
+            (
+                key(KeyCode::Char('!'), KeyModifiers::SHIFT),
+                typing(None),
+                AppAction::TypeChar('!'),
+            ),
+            (
+                key(
+                    KeyCode::Char('h'),
+                    KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+                ),
+                typing(None),
+                AppAction::DeleteWord,
+            ),
+            (
+                key(KeyCode::Backspace, KeyModifiers::ALT),
+                typing(None),
+                AppAction::DeleteWord,
+            ),
+            (
+                key(KeyCode::Left, KeyModifiers::NONE),
+                typing(None),
+                AppAction::None,
+            ),
+        ];
+        for (key, ctx, expected) in cases {
+            assert_eq!(handler.testing_action(key, &ctx), expected, "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn shifted_characters_are_typed_as_sent_by_default() {
+        let handler = InputHandler::new(KeyCode::Enter, false);
+        for (c, expected) in [('!', '!'), ('A', 'A'), ('a', 'a'), ('"', '"')] {
+            assert_eq!(
+                handler.testing_action(key(KeyCode::Char(c), KeyModifiers::SHIFT), &typing(None)),
+                AppAction::TypeChar(expected),
+                "Shift+{:?}",
+                c
+            );
+        }
+    }
+
+    #[test]
+    fn shortcut_action_depends_on_state() {
+        let done = InputContext {
+            is_complete: true,
+            ..typing(None)
+        };
+        let cases = [
+            ('w', typing(None), Some(AppAction::DeleteWord)),
+            ('w', done, Some(AppAction::None)),
+            ('w', ctx(AppState::Results), None),
+            ('z', done, Some(AppAction::Undo)),
+            ('z', ctx(AppState::History), None),
+            ('r', ctx(AppState::Results), Some(AppAction::Restart)),
+            ('p', typing(None), Some(AppAction::ShowSettings)),
+            ('x', typing(None), None),
+        ];
+        for (c, ctx, expected) in cases {
+            assert_eq!(
+                shortcut_action(c, &ctx),
+                expected,
+                "Ctrl+{} in {:?}",
+                c,
+                ctx
+            );
+        }
+    }
+
+    #[test]
+    fn ctrl_h_deletes_a_word_while_typing() {
+        let handler = InputHandler::new(KeyCode::Esc, false);
+        let ctrl_h = key(KeyCode::Char('h'), KeyModifiers::CONTROL);
+        assert_eq!(
+            handler.handle(ctrl_h, &typing(Some('h'))),
+            AppAction::DeleteWord
+        );
+        assert_eq!(shortcut_action('h', &typing(None)), None);
+        assert_eq!(
+            handler.handle(ctrl_h, &ctx(AppState::Stats)),
+            AppAction::ShowHistory
+        );
+    }
+
+    #[test]
+    fn every_javascript_snippet_can_be_typed() {
+        let handler = InputHandler::new(KeyCode::Esc, false);
+        let file: serde_json::Value =
+            serde_json::from_str(include_str!("../../data/code_javascript.json")).unwrap();
+        for quote in file["quotes"].as_array().unwrap() {
+            let text = quote["text"].as_str().unwrap();
+            let mut session = TypingSession::new(text.to_string());
+            session.set_auto_indent(false);
+            for c in text.chars() {
+                let ctx = InputContext {
+                    expected: session.expected_char(),
+                    is_complete: session.is_complete(),
+                    ..typing(None)
+                };
+                match handler.handle(key_for(c), &ctx) {
+                    AppAction::TypeChar(typed) => {
+                        session.type_char(typed);
+                    }
+                    action => panic!("{:?} in {:?} gave {:?}", c, text, action),
                 }
             }
-
-            _ => AppAction::None,
+            assert!(session.is_complete(), "{:?} wasn't finished", text);
+            assert_eq!(session.accuracy(), 100.0);
         }
     }
 }
@@ -1,13 +1,186 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Finger {
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+impl Hand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hand::Left => "Left",
+            Hand::Right => "Right",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FingerKind {
     Pinky,
     Ring,
     Middle,
-    IndexLeft,
-    IndexRight,
+    Index,
     Thumb,
 }
 
+impl FingerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FingerKind::Pinky => "pinky",
+            FingerKind::Ring => "ring",
+            FingerKind::Middle => "middle",
+            FingerKind::Index => "index",
+            FingerKind::Thumb => "thumb",
+        }
+    }
+}
+
+/// The finger that presses a key in standard touch typing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Finger {
+    pub hand: Hand,
+    pub kind: FingerKind,
+}
+
+impl Finger {
+    pub const LEFT_PINKY: Finger = Finger::new(Hand::Left, FingerKind::Pinky);
+    pub const LEFT_RING: Finger = Finger::new(Hand::Left, FingerKind::Ring);
+    pub const LEFT_MIDDLE: Finger = Finger::new(Hand::Left, FingerKind::Middle);
+    pub const LEFT_INDEX: Finger = Finger::new(Hand::Left, FingerKind::Index);
+    pub const RIGHT_INDEX: Finger = Finger::new(Hand::Right, FingerKind::Index);
+    pub const RIGHT_MIDDLE: Finger = Finger::new(Hand::Right, FingerKind::Middle);
+    pub const RIGHT_RING: Finger = Finger::new(Hand::Right, FingerKind::Ring);
+    pub const RIGHT_PINKY: Finger = Finger::new(Hand::Right, FingerKind::Pinky);
+    /// The spacebar is one key, so it is filed under the right thumb
+    /// whichever thumb presses it
+    pub const RIGHT_THUMB: Finger = Finger::new(Hand::Right, FingerKind::Thumb);
+
+    /// Left pinky to right thumb, the order breakdowns are listed in
+    pub const ALL: [Finger; 9] = [
+        Finger::LEFT_PINKY,
+        Finger::LEFT_RING,
+        Finger::LEFT_MIDDLE,
+        Finger::LEFT_INDEX,
+        Finger::RIGHT_INDEX,
+        Finger::RIGHT_MIDDLE,
+        Finger::RIGHT_RING,
+        Finger::RIGHT_PINKY,
+        Finger::RIGHT_THUMB,
+    ];
+
+    pub const fn new(hand: Hand, kind: FingerKind) -> Self {
+        Self { hand, kind }
+    }
+
+    /// Short name, e.g. "L-index"; also the key in the `finger_stats` table
+    pub fn name(&self) -> &'static str {
+        match (self.hand, self.kind) {
+            (Hand::Left, FingerKind::Pinky) => "L-pinky",
+            (Hand::Left, FingerKind::Ring) => "L-ring",
+            (Hand::Left, FingerKind::Middle) => "L-middle",
+            (Hand::Left, FingerKind::Index) => "L-index",
+            (Hand::Left, FingerKind::Thumb) => "L-thumb",
+            (Hand::Right, FingerKind::Pinky) => "R-pinky",
+            (Hand::Right, FingerKind::Ring) => "R-ring",
+            (Hand::Right, FingerKind::Middle) => "R-middle",
+            (Hand::Right, FingerKind::Index) => "R-index",
+            (Hand::Right, FingerKind::Thumb) => "R-thumb",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|finger| finger.name() == name)
+    }
+}
+
+/// Keystrokes per finger. Characters the layout has no key for (typographic
+/// punctuation, accents) are counted in `other` instead of being dropped.
+#[derive(Debug, Clone, Default)]
+pub struct FingerUsage {
+    pub counts: HashMap<Finger, u64>,
+    pub other: u64,
+}
+
+impl FingerUsage {
+    pub fn from_chars(layout: &KeyboardLayout, chars: impl IntoIterator<Item = char>) -> Self {
+        let mut usage = Self::default();
+        for c in chars {
+            match layout.finger_for(c) {
+                Some(finger) => *usage.counts.entry(finger).or_insert(0) += 1,
+                None => usage.other += 1,
+            }
+        }
+        usage
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum::<u64>() + self.other
+    }
+
+    pub fn count(&self, finger: Finger) -> u64 {
+        self.counts.get(&finger).copied().unwrap_or(0)
+    }
+
+    pub fn hand_count(&self, hand: Hand) -> u64 {
+        self.counts
+            .iter()
+            .filter(|(finger, _)| finger.hand == hand)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Share of all keystrokes in percent
+    pub fn percent(&self, count: u64) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => count as f64 / total as f64 * 100.0,
+        }
+    }
+}
+
+/// Physical shape of the on-screen keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyboardStyle {
+    /// Standard row-staggered keyboard
+    #[default]
+    Ansi,
+    /// ANSI keys in two halves with a gap between the hands
+    Split,
+    /// Uniform keys in straight columns without stagger
+    Ortho,
+}
+
+impl KeyboardStyle {
+    /// Parse the `keyboard_style` config value, falling back to ANSI
+    pub fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "split" => KeyboardStyle::Split,
+            "ortho" => KeyboardStyle::Ortho,
+            _ => KeyboardStyle::Ansi,
+        }
+    }
+}
+
+// Horizontal gap between keys, and between the halves of a split keyboard
+const KEY_GAP: i32 = 1;
+pub const SPLIT_GAP: i32 = 6;
+// Keys typed by the left hand at the start of each staggered row
+const LEFT_HAND_KEYS: usize = 6;
+const ORTHO_KEY_WIDTH: i32 = 3;
+const THUMB_KEY_WIDTH: i32 = 7;
+const COMPACT_KEY_WIDTH: i32 = 2;
+const COMPACT_SPACE_WIDTH: i32 = 10;
+
+/// A key positioned by a geometry generator; `x` is relative to the left edge
+/// of the keyboard
+pub struct KeyPlacement<'a> {
+    pub key: &'a KeyDef,
+    pub row: usize,
+    pub x: i32,
+    pub width: i32,
+}
+
 #[derive(Clone)]
 pub struct KeyDef {
     pub label: &'static str,
@@ -29,85 +202,85 @@ impl KeyboardLayout {
                 KeyDef {
                     label: "`",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::LEFT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "1",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::LEFT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "2",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::LEFT_RING,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "3",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::LEFT_MIDDLE,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "4",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::LEFT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "5",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::LEFT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "6",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::RIGHT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "7",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::RIGHT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "8",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::RIGHT_MIDDLE,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "9",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::RIGHT_RING,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "0",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "-",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "=",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "←",
                     width: 4,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: Some(1),
                 },
             ],
@@ -116,85 +289,85 @@ impl KeyboardLayout {
                 KeyDef {
                     label: "⇥",
                     width: 4,
-                    finger: Finger::Pinky,
+                    finger: Finger::LEFT_PINKY,
                     visual_width: Some(1),
                 },
                 KeyDef {
                     label: "q",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::LEFT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "w",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::LEFT_RING,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "e",
                     width: 3,
-                    finger: Finger::Middle,
+                    finger: Finger::LEFT_MIDDLE,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "r",
                     width: 3,
-                    finger: Finger::IndexLeft,
+                    finger: Finger::LEFT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "t",
                     width: 3,
-                    finger: Finger::IndexLeft,
+                    finger: Finger::LEFT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "y",
                     width: 3,
-                    finger: Finger::IndexRight,
+                    finger: Finger::RIGHT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "u",
                     width: 3,
-                    finger: Finger::IndexRight,
+                    finger: Finger::RIGHT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "i",
                     width: 3,
-                    finger: Finger::Middle,
+                    finger: Finger::RIGHT_MIDDLE,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "o",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::RIGHT_RING,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "p",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "[",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "]",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "\\",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
             ],
@@ -203,79 +376,79 @@ impl KeyboardLayout {
                 KeyDef {
                     label: "⇪",
                     width: 6,
-                    finger: Finger::Pinky,
+                    finger: Finger::LEFT_PINKY,
                     visual_width: Some(1),
                 },
                 KeyDef {
                     label: "a",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::LEFT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "s",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::LEFT_RING,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "d",
                     width: 3,
-                    finger: Finger::Middle,
+                    finger: Finger::LEFT_MIDDLE,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "f",
                     width: 3,
-                    finger: Finger::IndexLeft,
+                    finger: Finger::LEFT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "g",
                     width: 3,
-                    finger: Finger::IndexLeft,
+                    finger: Finger::LEFT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "h",
                     width: 3,
-                    finger: Finger::IndexRight,
+                    finger: Finger::RIGHT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "j",
                     width: 3,
-                    finger: Finger::IndexRight,
+                    finger: Finger::RIGHT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "k",
                     width: 3,
-                    finger: Finger::Middle,
+                    finger: Finger::RIGHT_MIDDLE,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "l",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::RIGHT_RING,
                     visual_width: None,
                 },
                 KeyDef {
                     label: ";",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "'",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "↵",
                     width: 5,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: Some(1),
                 },
             ],
@@ -284,73 +457,73 @@ impl KeyboardLayout {
                 KeyDef {
                     label: "⇧",
                     width: 7,
-                    finger: Finger::Pinky,
+                    finger: Finger::LEFT_PINKY,
                     visual_width: Some(1),
                 },
                 KeyDef {
                     label: "z",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::LEFT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "x",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::LEFT_RING,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "c",
                     width: 3,
-                    finger: Finger::Middle,
+                    finger: Finger::LEFT_MIDDLE,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "v",
                     width: 3,
-                    finger: Finger::IndexLeft,
+                    finger: Finger::LEFT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "b",
                     width: 3,
-                    finger: Finger::IndexLeft,
+                    finger: Finger::LEFT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "n",
                     width: 3,
-                    finger: Finger::IndexRight,
+                    finger: Finger::RIGHT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "m",
                     width: 3,
-                    finger: Finger::IndexRight,
+                    finger: Finger::RIGHT_INDEX,
                     visual_width: None,
                 },
                 KeyDef {
                     label: ",",
                     width: 3,
-                    finger: Finger::Middle,
+                    finger: Finger::RIGHT_MIDDLE,
                     visual_width: None,
                 },
                 KeyDef {
                     label: ".",
                     width: 3,
-                    finger: Finger::Ring,
+                    finger: Finger::RIGHT_RING,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "/",
                     width: 3,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: None,
                 },
                 KeyDef {
                     label: "⇧",
                     width: 8,
-                    finger: Finger::Pinky,
+                    finger: Finger::RIGHT_PINKY,
                     visual_width: Some(1),
                 },
             ],
@@ -358,7 +531,7 @@ impl KeyboardLayout {
             vec![KeyDef {
                 label: " ",
                 width: 15,
-                finger: Finger::Thumb,
+                finger: Finger::RIGHT_THUMB,
                 visual_width: None,
             }],
         ];
@@ -367,26 +540,173 @@ impl KeyboardLayout {
 
         Self { rows, home_row }
     }
-    // TODO: Add functionality to shift keys
-    #[allow(dead_code)]
-    pub fn get_finger(&self, key: char) -> Option<Finger> {
-        let key_lower = key.to_ascii_lowercase();
-        for row in &self.rows {
-            for key_def in row {
-                if key_def.label.chars().next().map(|c| c.to_ascii_lowercase()) == Some(key_lower) {
-                    return Some(key_def.finger);
-                }
-            }
-        }
-        None
+
+    /// The finger that types `c`, shifted characters included ('A' and '!'
+    /// resolve to the fingers for 'a' and '1'). `None` for characters that
+    /// aren't on the keyboard.
+    pub fn finger_for(&self, c: char) -> Option<Finger> {
+        let label = match c {
+            '\t' => TAB_LABEL,
+            '\n' => ENTER_LABEL,
+            c => base_key(c).0,
+        };
+        self.rows
+            .iter()
+            .flatten()
+            .find(|key_def| key_def.label.starts_with(label))
+            .map(|key_def| key_def.finger)
     }
 
     pub fn is_home_row(&self, key: char) -> bool {
         self.home_row.contains(&key.to_ascii_lowercase())
     }
 
-    pub fn get_rows(&self) -> &Vec<Vec<KeyDef>> {
-        &self.rows
+    /// The keys the fingers rest on, e.g. "asdfjkl;"
+    pub fn home_row(&self) -> &[char] {
+        &self.home_row
+    }
+
+    /// Letter keys on `row`, where 1 is the top letter row and 3 the bottom
+    pub fn row_letters(&self, row: usize) -> Vec<char> {
+        self.rows
+            .get(row)
+            .into_iter()
+            .flatten()
+            .filter_map(letter)
+            .collect()
+    }
+
+    /// Letter keys typed by `hand`
+    pub fn hand_letters(&self, hand: Hand) -> Vec<char> {
+        self.rows
+            .iter()
+            .flatten()
+            .filter(|key_def| key_def.finger.hand == hand)
+            .filter_map(letter)
+            .collect()
+    }
+
+    /// Where every key goes for `style`. Each key appears once, except the
+    /// spacebar, which becomes one thumb key per half on a split keyboard.
+    pub fn geometry(&self, style: KeyboardStyle) -> Vec<KeyPlacement<'_>> {
+        let mut placements = Vec::new();
+        let space_row = self.rows.len() - 1;
+        let key_width = |key: &KeyDef| match style {
+            KeyboardStyle::Ortho => ORTHO_KEY_WIDTH,
+            _ => key.width as i32,
+        };
+
+        // Both halves of a split keyboard line up: the right half starts one
+        // gap after the widest left half
+        let right_half_x = self.rows[..space_row]
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .take(LEFT_HAND_KEYS)
+                    .map(|key| key_width(key) + KEY_GAP)
+                    .sum::<i32>()
+                    - KEY_GAP
+            })
+            .max()
+            .unwrap_or(0)
+            + SPLIT_GAP;
+
+        for (row_idx, row) in self.rows[..space_row].iter().enumerate() {
+            let mut x = 0;
+            for (col, key) in row.iter().enumerate() {
+                let width = key_width(key);
+                if style == KeyboardStyle::Split && col == LEFT_HAND_KEYS {
+                    x = right_half_x;
+                }
+                placements.push(KeyPlacement {
+                    key,
+                    row: row_idx,
+                    x,
+                    width,
+                });
+                x += width + KEY_GAP;
+            }
+        }
+
+        // Spacebar: centered under the home row, or a thumb key each side of the gap
+        let home_row = space_row / 2;
+        let row_extent = |row: usize| {
+            let keys: Vec<&KeyPlacement> = placements.iter().filter(|p| p.row == row).collect();
+            let start = keys.first().map(|p| p.x).unwrap_or(0);
+            let end = keys.last().map(|p| p.x + p.width).unwrap_or(0);
+            (start, end)
+        };
+        let space = &self.rows[space_row][0];
+        match style {
+            KeyboardStyle::Split => {
+                placements.push(KeyPlacement {
+                    key: space,
+                    row: space_row,
+                    x: right_half_x - SPLIT_GAP - THUMB_KEY_WIDTH,
+                    width: THUMB_KEY_WIDTH,
+                });
+                placements.push(KeyPlacement {
+                    key: space,
+                    row: space_row,
+                    x: right_half_x,
+                    width: THUMB_KEY_WIDTH,
+                });
+            }
+            _ => {
+                let (start, end) = row_extent(home_row);
+                let width = space.width as i32;
+                placements.push(KeyPlacement {
+                    key: space,
+                    row: space_row,
+                    x: start + (end - start - width) / 2,
+                    width,
+                });
+            }
+        }
+
+        placements
+    }
+
+    /// Where every key goes on the compact keyboard for narrow terminals:
+    /// two-column keys with no gaps and no modifier keys. Rows keep the
+    /// stagger of the full keyboard, scaled down to the smaller keys.
+    pub fn compact_geometry(&self) -> Vec<KeyPlacement<'_>> {
+        let mut placements = Vec::new();
+        let space_row = self.rows.len() - 1;
+        let pitch = self.rows[0][0].width as i32 + KEY_GAP;
+        // Modifiers are the keys drawn with a symbol narrower than the key
+        let is_modifier = |key: &KeyDef| key.visual_width.is_some();
+        for (row_idx, row) in self.rows[..space_row].iter().enumerate() {
+            // How far the leading modifiers push the row in on the full keyboard
+            let lead: i32 = row
+                .iter()
+                .take_while(|key| is_modifier(key))
+                .map(|key| key.width as i32 + KEY_GAP)
+                .sum();
+            let mut x = lead * COMPACT_KEY_WIDTH / pitch;
+            for key in row.iter().filter(|key| !is_modifier(key)) {
+                placements.push(KeyPlacement {
+                    key,
+                    row: row_idx,
+                    x,
+                    width: COMPACT_KEY_WIDTH,
+                });
+                x += COMPACT_KEY_WIDTH;
+            }
+        }
+
+        let home_row = space_row / 2;
+        let home_keys = placements.iter().filter(|p| p.row == home_row);
+        let start = home_keys.clone().map(|p| p.x).min().unwrap_or(0);
+        let end = home_keys.map(|p| p.x + p.width).max().unwrap_or(0);
+        placements.push(KeyPlacement {
+            key: &self.rows[space_row][0],
+            row: space_row,
+            x: start + (end - start - COMPACT_SPACE_WIDTH) / 2,
+            width: COMPACT_SPACE_WIDTH,
+        });
+
+        placements
     }
 }
 
@@ -395,3 +715,155 @@ impl Default for KeyboardLayout {
         Self::new()
     }
 }
+
+/// Label of the shift keys
+pub const SHIFT_LABEL: char = '⇧';
+/// Label of the backspace key
+pub const BACKSPACE_LABEL: char = '←';
+const TAB_LABEL: char = '⇥';
+const ENTER_LABEL: char = '↵';
+
+/// The letter a key types, if it types one
+fn letter(key_def: &KeyDef) -> Option<char> {
+    let mut chars = key_def.label.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_alphabetic() => Some(c),
+        _ => None,
+    }
+}
+
+/// The key that types `c` on a US layout and whether shift is held for it,
+/// e.g. 'A' -> ('a', true), '!' -> ('1', true), ' ' -> (' ', false)
+pub fn base_key(c: char) -> (char, bool) {
+    const SHIFTED: [(char, char); 21] = [
+        ('~', '`'),
+        ('!', '1'),
+        ('@', '2'),
+        ('#', '3'),
+        ('$', '4'),
+        ('%', '5'),
+        ('^', '6'),
+        ('&', '7'),
+        ('*', '8'),
+        ('(', '9'),
+        (')', '0'),
+        ('_', '-'),
+        ('+', '='),
+        ('{', '['),
+        ('}', ']'),
+        ('|', '\\'),
+        (':', ';'),
+        ('"', '\''),
+        ('<', ','),
+        ('>', '.'),
+        ('?', '/'),
+    ];
+    if c.is_ascii_uppercase() {
+        return (c.to_ascii_lowercase(), true);
+    }
+    SHIFTED
+        .iter()
+        .find(|&&(shifted, _)| shifted == c)
+        .map_or((c, false), |&(_, base)| (base, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every printable ASCII character by the finger that types it
+    const FINGERS: [(Finger, &str); 9] = [
+        (Finger::LEFT_PINKY, "`~1!qQaAzZ"),
+        (Finger::LEFT_RING, "2@wWsSxX"),
+        (Finger::LEFT_MIDDLE, "3#eEdDcC"),
+        (Finger::LEFT_INDEX, "4$5%rRtTfFgGvVbB"),
+        (Finger::RIGHT_INDEX, "6^7&yYuUhHjJnNmM"),
+        (Finger::RIGHT_MIDDLE, "8*iIkK,<"),
+        (Finger::RIGHT_RING, "9(oOlL.>"),
+        (Finger::RIGHT_PINKY, "0)-_=+pP[{]}\\|;:'\"/?"),
+        (Finger::RIGHT_THUMB, " "),
+    ];
+
+    #[test]
+    fn finger_for_covers_printable_ascii() {
+        let layout = KeyboardLayout::new();
+        for c in (0x20..=0x7E_u8).map(char::from) {
+            let expected: Vec<Finger> = FINGERS
+                .iter()
+                .filter(|(_, chars)| chars.contains(c))
+                .map(|&(finger, _)| finger)
+                .collect();
+            assert_eq!(expected.len(), 1, "{:?} listed {} times", c, expected.len());
+            assert_eq!(layout.finger_for(c), Some(expected[0]), "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn finger_for_whitespace_and_unknown_characters() {
+        let layout = KeyboardLayout::new();
+        assert_eq!(layout.finger_for('\t'), Some(Finger::LEFT_PINKY));
+        assert_eq!(layout.finger_for('\n'), Some(Finger::RIGHT_PINKY));
+        assert_eq!(layout.finger_for('é'), None);
+        assert_eq!(layout.finger_for('—'), None);
+    }
+
+    /// x of the key labelled `label`, and the row it is on
+    fn position(placements: &[KeyPlacement], label: &str) -> (usize, i32) {
+        let placement = placements.iter().find(|p| p.key.label == label).unwrap();
+        (placement.row, placement.x)
+    }
+
+    #[test]
+    fn ansi_rows_are_staggered_by_their_modifiers() {
+        let layout = KeyboardLayout::new();
+        let ansi = layout.geometry(KeyboardStyle::Ansi);
+        // Tab, caps lock and shift push each row further right of the digits
+        assert_eq!(position(&ansi, "1"), (0, 4));
+        assert_eq!(position(&ansi, "q"), (1, 5));
+        assert_eq!(position(&ansi, "a"), (2, 7));
+        assert_eq!(position(&ansi, "z"), (3, 8));
+        // Letters in a row are evenly spaced
+        for (row, letters) in ["qwertyuiop", "asdfghjkl", "zxcvbnm"].iter().enumerate() {
+            let xs: Vec<i32> = letters
+                .chars()
+                .map(|c| position(&ansi, &c.to_string()).1)
+                .collect();
+            assert!(
+                xs.windows(2).all(|pair| pair[1] - pair[0] == 4),
+                "row {}: {:?}",
+                row + 1,
+                xs
+            );
+        }
+        // The spacebar sits centered under the home row, which ends with enter
+        let home_end = position(&ansi, "↵").1 + 5;
+        let (space_row, space_x) = position(&ansi, " ");
+        assert_eq!(space_row, 4);
+        assert_eq!(space_x, (home_end - 15) / 2);
+    }
+
+    #[test]
+    fn ortho_rows_line_up() {
+        let layout = KeyboardLayout::new();
+        let ortho = layout.geometry(KeyboardStyle::Ortho);
+        for label in ["1", "q", "a", "z"] {
+            assert_eq!(position(&ortho, label).1, 4, "{}", label);
+        }
+        assert!(ortho
+            .iter()
+            .all(|p| p.width == ORTHO_KEY_WIDTH || p.key.label == " "));
+    }
+
+    #[test]
+    fn compact_rows_keep_the_stagger() {
+        let layout = KeyboardLayout::new();
+        let compact = layout.compact_geometry();
+        let xs: Vec<i32> = ["1", "q", "a", "z"]
+            .iter()
+            .map(|label| position(&compact, label).1)
+            .collect();
+        assert!(xs.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", xs);
+        assert!(xs[3] > xs[0], "{:?}", xs);
+        assert!(compact.iter().all(|p| p.key.visual_width.is_none()));
+    }
+}
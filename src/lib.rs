@@ -0,0 +1,29 @@
+//! The typing engine behind TUItype, for tools that want to drive tests or
+//! read the results database without the terminal UI.
+//!
+//! - [`core::typing_session`] runs a single test and scores it
+//! - [`core::metrics`] has the WPM, accuracy and consistency formulas
+//! - [`models`] holds the result and config types stored on disk
+//! - [`quotes`] loads and picks quotes and code snippets
+//! - [`storage`] reads and writes the SQLite database, config and backups
+//!
+//! Driving a test from code:
+//!
+//! ```
+//! use tuitype::core::typing_session::TypingSession;
+//!
+//! let mut session = TypingSession::new("hello world".to_string());
+//! for c in "hello world".chars() {
+//!     session.type_char(c);
+//! }
+//! assert!(session.is_complete());
+//!
+//! let result = session.final_result("custom").unwrap();
+//! assert_eq!(result.accuracy, 100.0);
+//! ```
+
+pub mod core;
+pub mod keyboard;
+pub mod models;
+pub mod quotes;
+pub mod storage;
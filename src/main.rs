@@ -1,141 +1,881 @@
 use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
 
+use chrono::Local;
+use clap::{Arg, ArgAction, ArgMatches};
 use crossterm::{
     event::{self, Event, KeyEventKind},
-    terminal::{disable_raw_mode, enable_raw_mode},
+    style::Print,
+    terminal::{disable_raw_mode, enable_raw_mode, SetTitle},
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
+use ratatui::{backend::CrosstermBackend, layout::Rect, Terminal};
 
 mod app;
-mod core;
+mod continuous;
 mod input;
-mod keyboard;
-mod models;
-mod quotes;
+mod scheduler;
+mod series;
+mod share;
+mod sound;
 mod state;
-mod storage;
 mod theme;
 mod ui;
+mod words;
 
-use crate::app::App;
+use tuitype::{core, keyboard, models, quotes, storage};
+
+use crate::app::{App, CustomText, StartupOptions, RECENT_FORM_RESULTS};
+use crate::core::anomaly::Anomaly;
 use crate::input::handler::AppAction;
+use crate::models::{AggregatePeriod, DateWindow, FilteredStats, SortOrder, TestResult};
+use crate::quotes::QuoteMode;
 use crate::state::AppState;
-use crate::ui::history::HistoryView;
+use crate::storage::db::Database;
+use crate::storage::paths::{check_profile_name, set_cli_overrides, DirOverrides, Paths};
+use crate::storage::report;
+use crate::theme::Theme;
+use crate::ui::heatmap::HeatmapView;
+use crate::ui::history::{HistoryView, PendingDelete, HISTORY_PAGE, PAGE_ROWS};
+use crate::ui::settings::SettingsView;
 use crate::ui::stats::StatsView;
+use crate::ui::status_line::StatusLevel;
+use crate::ui::too_small::draw_if_too_small;
 
 fn main() -> io::Result<()> {
-    // 1. Setup terminal
+    // 1. Handle command-line flags that don't need the TUI
+    let matches = clap::Command::new("TUItype")
+        .arg(
+            Arg::new("paths")
+                .long("paths")
+                .action(ArgAction::SetTrue)
+                .help("Print config, database, themes and quotes locations and exit"),
+        )
+        .arg(
+            Arg::new("list-themes")
+                .long("list-themes")
+                .action(ArgAction::SetTrue)
+                .help("Print the available themes and exit"),
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .action(ArgAction::SetTrue)
+                .help("Write this week's Markdown report, print its path and exit"),
+        )
+        .arg(
+            Arg::new("config-dir")
+                .long("config-dir")
+                .value_name("DIR")
+                .help("Keep config.toml, profiles and themes here (overrides TUITYPE_CONFIG_DIR)"),
+        )
+        .arg(
+            Arg::new("data-dir")
+                .long("data-dir")
+                .value_name("DIR")
+                .help("Keep the database, backups and quotes here (overrides TUITYPE_DATA_DIR)"),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("NAME")
+                .help("Use this profile's results and settings (not saved to the config)"),
+        )
+        .arg(
+            Arg::new("mode")
+                .long("mode")
+                .value_name("MODE")
+                .value_parser([
+                    "short",
+                    "medium",
+                    "long",
+                    "zen",
+                    "continuous",
+                    "practice",
+                    "code",
+                    "drill",
+                ])
+                .help("Start in this mode (not saved to the config)"),
+        )
+        .arg(
+            Arg::new("theme")
+                .long("theme")
+                .value_name("NAME")
+                .help("Use this theme for this run (not saved to the config)"),
+        )
+        .arg(
+            Arg::new("file")
+                .long("file")
+                .value_name("PATH")
+                .conflicts_with("text")
+                .help("Type the contents of a file instead of a random quote"),
+        )
+        .arg(
+            Arg::new("text")
+                .long("text")
+                .value_name("TEXT")
+                .help("Type this text instead of a random quote"),
+        )
+        .get_matches();
+
+    set_cli_overrides(DirOverrides {
+        config_dir: matches.get_one::<String>("config-dir").map(PathBuf::from),
+        data_dir: matches.get_one::<String>("data-dir").map(PathBuf::from),
+    });
+
+    if matches.get_flag("paths") {
+        let mut paths = Paths::resolve().map_err(io::Error::other)?;
+        if let Some(profile) = matches.get_one::<String>("profile") {
+            paths = paths.with_profile(profile);
+        }
+        println!("Config file:      {}", paths.config_file().display());
+        if let Some(file) = paths.profile_config_file() {
+            println!("Profile config:   {}", file.display());
+        }
+        println!("Database:         {}", paths.db_file().display());
+        println!("Themes directory: {}", paths.themes_dir().display());
+        println!("Quotes directory: {}", paths.quotes_dir().display());
+        return Ok(());
+    }
+
+    if matches.get_flag("report") {
+        let mut paths = Paths::resolve().map_err(io::Error::other)?;
+        if let Some(profile) = matches.get_one::<String>("profile") {
+            paths = paths.with_profile(profile);
+        }
+        match write_report(&paths) {
+            Ok(path) => println!("{}", path.display()),
+            Err(e) => {
+                eprintln!("error: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    if matches.get_flag("list-themes") {
+        for name in Theme::available_themes() {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+
+    // Bad values are reported before the terminal is touched
+    let options = match startup_options(&matches) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    // Load everything before entering the alternate screen, so a startup
+    // failure prints a readable message to an untouched terminal
+    let mut app = match App::new(options) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // 2. Setup terminal. The shell's window title is saved on the terminal's
+    // title stack so it can be put back on exit. A panic from here on puts
+    // the terminal back before reporting.
+    install_panic_hook();
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    crossterm::execute!(terminal.backend_mut(), Print(PUSH_TITLE))?;
+    enter_tui(&mut terminal, app.mouse())?;
+
+    // 3. Run app
+    let res = run_app(&mut terminal, &mut app);
+
+    // 4. Restore terminal and window title, the same way a panic does. The
+    // session report and any warning go after, so they stay in the
+    // scrollback.
+    restore_terminal();
+    crossterm::execute!(terminal.backend_mut(), Print(POP_TITLE))?;
+    if let Some(report) = app.session_report() {
+        println!("{}", report);
+    }
+
+    // 5. Propagate any error after restoring terminal
+    if let Some(warning) = res? {
+        eprintln!("{}", warning);
+    }
+    Ok(())
+}
+
+/// This week's report for `--report`, from the database as it is on disk
+fn write_report(paths: &Paths) -> anyhow::Result<PathBuf> {
+    let db = app::open_database(paths)?;
+    report::write(&db, &paths.reports_dir(), Local::now().date_naive())
+}
+
+/// Turn the parsed `--profile`, `--mode`, `--theme`, `--file` and `--text`
+/// flags into overrides for this run
+fn startup_options(matches: &ArgMatches) -> Result<StartupOptions, String> {
+    let profile = matches.get_one::<String>("profile").cloned();
+    if let Some(name) = &profile {
+        check_profile_name(name).map_err(|e| e.to_string())?;
+    }
+
+    let mode = matches
+        .get_one::<String>("mode")
+        .map(|name| name.parse::<QuoteMode>())
+        .transpose()?;
+
+    let theme = matches.get_one::<String>("theme").cloned();
+    if let Some(name) = &theme {
+        let themes = Theme::available_themes();
+        if !themes.iter().any(|t| t.eq_ignore_ascii_case(name)) {
+            return Err(format!(
+                "unknown theme `{}` (available: {})",
+                name,
+                themes.join(", ")
+            ));
+        }
+    }
+
+    let custom_text = if let Some(path) = matches.get_one::<String>("file") {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+        let source = Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        Some(CustomText::file(&text, source)?)
+    } else if let Some(text) = matches.get_one::<String>("text") {
+        Some(CustomText::new(text, "Custom text".to_string())?)
+    } else {
+        None
+    };
+
+    Ok(StartupOptions {
+        profile,
+        mode,
+        theme,
+        custom_text,
+    })
+}
+
+type Tui = Terminal<CrosstermBackend<io::Stdout>>;
+
+/// xterm save/restore window title; ignored by terminals without a title stack
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+fn enter_tui(terminal: &mut Tui, mouse: bool) -> io::Result<()> {
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
     crossterm::execute!(
-        stdout,
-        crossterm::terminal::EnterAlternateScreen,
-        crossterm::event::EnableMouseCapture
+        terminal.backend_mut(),
+        crossterm::terminal::EnterAlternateScreen
     )?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    set_mouse_capture(terminal, mouse)?;
+    terminal.clear()
+}
+
+/// Capturing the mouse gives up the terminal's own selection and right-click
+fn set_mouse_capture(terminal: &mut Tui, on: bool) -> io::Result<()> {
+    if on {
+        crossterm::execute!(terminal.backend_mut(), crossterm::event::EnableMouseCapture)
+    } else {
+        crossterm::execute!(
+            terminal.backend_mut(),
+            crossterm::event::DisableMouseCapture
+        )
+    }
+}
 
-    // 2. Run app
-    let res = run_app(&mut terminal);
+/// Put the terminal back for the shell: raw mode off, mouse released, main
+/// screen and cursor back. Errors are ignored since there is nowhere left to
+/// report them, and calling it again changes nothing.
+fn restore_terminal() {
+    disable_raw_mode().ok();
+    restore_screen(&mut io::stdout()).ok();
+}
 
-    // 3. Restore terminal
+/// The escape sequences half of `restore_terminal`, written to `out`
+fn restore_screen(out: &mut impl io::Write) -> io::Result<()> {
+    crossterm::execute!(
+        out,
+        crossterm::event::DisableMouseCapture,
+        crossterm::terminal::LeaveAlternateScreen,
+        crossterm::cursor::Show
+    )
+}
+
+/// Restore the terminal before a panic is reported, so the message lands on
+/// the main screen and the shell stays usable afterwards
+fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        restore_terminal();
+        crossterm::execute!(io::stdout(), Print(POP_TITLE)).ok();
+        eprintln!("{}\n\n{}", info, std::backtrace::Backtrace::force_capture());
+    }));
+}
+
+fn leave_tui(terminal: &mut Tui) -> io::Result<()> {
     disable_raw_mode()?;
     crossterm::execute!(
         terminal.backend_mut(),
         crossterm::event::DisableMouseCapture,
         crossterm::terminal::LeaveAlternateScreen
     )?;
-    terminal.show_cursor()?;
+    terminal.show_cursor()
+}
 
-    // 4. Propagate any error after restoring terminal
-    res
+/// Suspend the TUI, run `$EDITOR` on `path` and bring the TUI back. The
+/// terminal is restored whether or not the editor starts or exits cleanly;
+/// the editor's failure is returned as a message afterwards.
+fn edit_file(terminal: &mut Tui, path: &Path, mouse: bool) -> io::Result<Result<(), String>> {
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| {
+        if cfg!(windows) {
+            "notepad".to_string()
+        } else {
+            "vi".to_string()
+        }
+    });
+    // $EDITOR may carry arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    leave_tui(terminal)?;
+    let status = Command::new(program).args(parts).arg(path).status();
+    enter_tui(terminal, mouse)?;
+
+    Ok(match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} exited with {}", program, status)),
+        Err(e) => Err(format!("Failed to launch {}: {}", program, e)),
+    })
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
-    let mut app = App::new().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+/// First `limit` History results for a filter, with aggregates over all of them
+fn history_page(
+    db: &Database,
+    mode: Option<&str>,
+    window: DateWindow,
+    sort: SortOrder,
+    limit: usize,
+) -> rusqlite::Result<(Vec<TestResult>, FilteredStats)> {
+    let since = window.since(Local::now());
+    Ok((
+        db.get_results_filtered(mode, since, sort, limit, 0)?,
+        db.get_filtered_stats(mode, since)?,
+    ))
+}
+
+/// Fetch the next History page when moving `rows` down would run past what is loaded
+fn load_more_history(app: &mut App, view: &mut HistoryView, rows: usize) {
+    if !view.needs_more(rows) {
+        return;
+    }
+    match app.db.get_results_filtered(
+        view.mode_filter,
+        view.window.since(Local::now()),
+        view.sort,
+        HISTORY_PAGE,
+        view.results.len(),
+    ) {
+        Ok(results) => view.append_results(results),
+        Err(e) => app.set_message(format!("Failed to load history: {}", e), StatusLevel::Error),
+    }
+}
+
+/// Everything the Stats screen shows, with the totals, trend and leaderboard
+/// limited to the app's date window
+fn load_stats(app: &App) -> rusqlite::Result<StatsView> {
+    let window = app.date_window();
+    let since = window.since(Local::now());
+    let mut recent = app
+        .db
+        .get_results_filtered(None, since, SortOrder::Date, TREND_RESULTS, 0)?;
+    recent.reverse();
+    let mut view = StatsView::new(
+        app.db.get_stats(since)?,
+        app.db.get_wpm_by_length()?,
+        app.db.get_abandonment(ABANDONMENT_WEEKS)?,
+        app.db
+            .get_slowest_bigrams(SLOWEST_BIGRAMS, MIN_BIGRAM_SAMPLES)?,
+        app.db.get_daily_activity(Local::now().date_naive())?,
+        recent,
+        app.db.get_finger_stats()?,
+    );
+    view.set_window(window);
+    view.set_goal(app.goal_progress());
+    view.set_top_results(
+        app.db
+            .get_top_results(None, since, LEADERBOARD_RESULTS)
+            .unwrap_or_default(),
+    );
+    view.set_speed_unit(app.speed_unit());
+    view.set_achievements(app.db.get_achievements().unwrap_or_default());
+    view.set_recent_form(
+        app.db
+            .get_last_n_average(None, RECENT_FORM_RESULTS)
+            .unwrap_or_default(),
+    );
+    view.set_aggregates(
+        app.db
+            .get_aggregates(AggregatePeriod::Day, AGGREGATE_DAYS)
+            .unwrap_or_default(),
+        app.db
+            .get_aggregates(AggregatePeriod::Week, AGGREGATE_WEEKS)
+            .unwrap_or_default(),
+    );
+    Ok(view)
+}
+
+// Upper bound on how long the loop sleeps when no timer is pending
+const IDLE_POLL: Duration = Duration::from_secs(1);
+
+// Weeks shown in the abandonment chart on the Stats screen
+const ABANDONMENT_WEEKS: usize = 8;
+
+// Lifetime slowest bigrams on the Stats screen, and how often a bigram must
+// have been typed to be listed
+const SLOWEST_BIGRAMS: usize = 10;
+const MIN_BIGRAM_SAMPLES: i64 = 5;
+// Latest results plotted on the Stats screen's trend chart
+const TREND_RESULTS: usize = 100;
+// Fastest runs on the Stats screen's personal leaderboard
+const LEADERBOARD_RESULTS: usize = 10;
+// Days and weeks listed in the Stats screen's daily and weekly tables
+const AGGREGATE_DAYS: usize = 90;
+const AGGREGATE_WEEKS: usize = 52;
+
+/// Runs until the app quits, returning the warning to print once the
+/// terminal is restored, if any
+fn run_app(terminal: &mut Tui, app: &mut App) -> io::Result<Option<String>> {
     let mut history_view: Option<HistoryView> = None;
     let mut stats_view: Option<StatsView> = None;
+    let mut heatmap_view: Option<HeatmapView> = None;
+    let mut settings_view: Option<SettingsView> = None;
 
+    let mut title = String::new();
     loop {
+        let next_title = app.window_title();
+        if next_title != title {
+            crossterm::execute!(terminal.backend_mut(), SetTitle(&next_title))?;
+            title = next_title;
+        }
+
         // Draw UI based on state
-        terminal.draw(|frame| match app.state() {
-            AppState::Testing | AppState::Results => {
-                app.draw(frame);
+        terminal.draw(|frame| {
+            // Debug builds can crash on purpose to check the terminal comes back
+            if cfg!(debug_assertions) && std::env::var_os("TUITYPE_DEBUG_PANIC").is_some() {
+                panic!("TUITYPE_DEBUG_PANIC is set");
             }
-            AppState::History => {
-                if let Some(ref view) = history_view {
-                    view.draw(frame, frame.area());
-                } else {
-                    // Draw placeholder if view hasn't been created yet
-                    let placeholder = ratatui::widgets::Paragraph::new("Loading history...").block(
-                        ratatui::widgets::Block::default()
-                            .borders(ratatui::widgets::Borders::ALL)
-                            .title(" History "),
-                    );
-                    frame.render_widget(placeholder, frame.area());
-                }
+            if draw_if_too_small(frame) {
+                return;
             }
-            AppState::Stats => {
-                if let Some(ref view) = stats_view {
-                    view.draw(frame, frame.area());
-                } else {
-                    // Draw placeholder if view hasn't been created yet
-                    let placeholder = ratatui::widgets::Paragraph::new("Loading stats...").block(
-                        ratatui::widgets::Block::default()
-                            .borders(ratatui::widgets::Borders::ALL)
-                            .title(" Stats "),
-                    );
-                    frame.render_widget(placeholder, frame.area());
+            match app.screen() {
+                AppState::Testing
+                | AppState::Results
+                | AppState::QuitConfirm
+                | AppState::QuotePicker
+                | AppState::Replay => {
+                    app.draw(frame);
                 }
+                state @ (AppState::History | AppState::HistoryDetail) => {
+                    if let Some(ref view) = history_view {
+                        view.draw(
+                            frame,
+                            frame.area(),
+                            state == AppState::HistoryDetail,
+                            app.marquee(),
+                            app.theme(),
+                        );
+                    } else {
+                        // Draw placeholder if view hasn't been created yet
+                        let placeholder = ratatui::widgets::Paragraph::new("Loading history...")
+                            .block(
+                                ratatui::widgets::Block::default()
+                                    .borders(ratatui::widgets::Borders::ALL)
+                                    .title(" History "),
+                            );
+                        frame.render_widget(placeholder, frame.area());
+                    }
+                }
+                AppState::Stats => {
+                    if let Some(ref view) = stats_view {
+                        view.draw(frame, frame.area());
+                    } else {
+                        // Draw placeholder if view hasn't been created yet
+                        let placeholder = ratatui::widgets::Paragraph::new("Loading stats...")
+                            .block(
+                                ratatui::widgets::Block::default()
+                                    .borders(ratatui::widgets::Borders::ALL)
+                                    .title(" Stats "),
+                            );
+                        frame.render_widget(placeholder, frame.area());
+                    }
+                }
+                AppState::Heatmap => {
+                    if let Some(ref view) = heatmap_view {
+                        view.draw(frame, frame.area(), app.theme());
+                    } else {
+                        // Draw placeholder if view hasn't been created yet
+                        let placeholder = ratatui::widgets::Paragraph::new("Loading heatmap...")
+                            .block(
+                                ratatui::widgets::Block::default()
+                                    .borders(ratatui::widgets::Borders::ALL)
+                                    .title(" Heatmap "),
+                            );
+                        frame.render_widget(placeholder, frame.area());
+                    }
+                }
+                AppState::Settings => {
+                    if let Some(ref view) = settings_view {
+                        view.draw(frame, frame.area(), app.theme());
+                    }
+                }
+                AppState::ThemePicker | AppState::ProfilePicker => {}
             }
+            app.draw_pickers(frame);
+            app.draw_status(frame);
         })?;
+        app.sync_marquee(Instant::now());
+
+        // Sleep until the next timer is due or input arrives
+        let timeout = app
+            .next_deadline()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or(IDLE_POLL)
+            .min(IDLE_POLL);
+
+        let key = if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => Some(key),
+                Event::Mouse(mouse) => {
+                    let size = terminal.size()?;
+                    app.mouse_key(mouse, Rect::new(0, 0, size.width, size.height))
+                }
+                // Pick up the new size now; the next pass redraws for it straight
+                // away, and every layout is worked out from the frame it draws
+                Event::Resize(_, height) => {
+                    terminal.autoresize()?;
+                    if let Some(ref mut view) = history_view {
+                        // The list sits inside a border, below the summary line
+                        view.fit_rows(height.saturating_sub(3) as usize);
+                    }
+                    None
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
 
         // Handle input
-        if event::poll(std::time::Duration::from_millis(16))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if let Some(action) = app.handle_input(key) {
-                        match action {
-                            AppAction::Quit => break,
-                            AppAction::ShowHistory => match app.db.get_recent_results(50) {
-                                Ok(results) => {
-                                    history_view = Some(HistoryView::new(results));
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to load history: {}", e);
-                                }
-                            },
-                            AppAction::ShowStats => match app.db.get_stats() {
-                                Ok(stats) => {
-                                    stats_view = Some(StatsView::new(stats));
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to load stats: {}", e);
+        if let Some(key) = key
+            && let Some(action) = app.handle_input(key)
+        {
+            match action {
+                AppAction::Quit => return Ok(app.save_on_exit()),
+                AppAction::ShowHistory => {
+                    let window = app.date_window();
+                    match history_page(&app.db, None, window, SortOrder::Date, HISTORY_PAGE) {
+                        Ok((results, summary)) => {
+                            let mut view = HistoryView::new(results, summary, app.relative_time());
+                            view.window = window;
+                            view.set_speed_unit(app.speed_unit());
+                            view.set_accuracy_thresholds(app.accuracy_thresholds());
+                            history_view = Some(view);
+                        }
+                        Err(e) => {
+                            app.set_message(
+                                format!("Failed to load history: {}", e),
+                                StatusLevel::Error,
+                            );
+                        }
+                    }
+                }
+                AppAction::ShowStats => match load_stats(app) {
+                    Ok(view) => stats_view = Some(view),
+                    Err(e) => {
+                        app.set_message(format!("Failed to load stats: {}", e), StatusLevel::Error);
+                    }
+                },
+                AppAction::ShowHeatmap => match app.db.get_key_stats() {
+                    Ok(key_stats) => {
+                        heatmap_view = Some(HeatmapView::new(key_stats, app.keyboard_style()));
+                    }
+                    Err(e) => {
+                        app.set_message(
+                            format!("Failed to load key stats: {}", e),
+                            StatusLevel::Error,
+                        );
+                    }
+                },
+                AppAction::ShowSettings => {
+                    settings_view = Some(SettingsView::new(
+                        app.paths().clone(),
+                        app.warning().map(str::to_string),
+                    ));
+                }
+                AppAction::EditConfig => {
+                    let config_file = app.paths().config_file();
+                    let edited = edit_file(terminal, &config_file, app.mouse())?;
+                    // The editor may have set its own title
+                    title.clear();
+                    app.reload_config();
+                    set_mouse_capture(terminal, app.mouse())?;
+                    if let Err(e) = edited {
+                        app.set_warning(e);
+                    }
+                    settings_view = Some(SettingsView::new(
+                        app.paths().clone(),
+                        app.warning().map(str::to_string),
+                    ));
+                }
+                // Keep the views that can still be returned to; switching
+                // profile leaves none
+                AppAction::Back | AppAction::Select => {
+                    if !app.is_open(AppState::History) {
+                        history_view = None;
+                    }
+                    if !app.is_open(AppState::Stats) {
+                        stats_view = None;
+                    }
+                    if !app.is_open(AppState::Heatmap) {
+                        heatmap_view = None;
+                    }
+                    if !app.is_open(AppState::Settings) {
+                        settings_view = None;
+                    }
+                }
+                AppAction::ConfirmResult => {
+                    if let Some(ref mut view) = history_view
+                        && let Some(result) = view.selected_result_mut()
+                        && result.anomaly == Anomaly::High
+                        && !result.confirmed
+                        && let Some(id) = result.id
+                    {
+                        match app.db.confirm_result(id) {
+                            Ok(()) => result.confirmed = true,
+                            Err(e) => app.set_message(
+                                format!("Failed to confirm result: {}", e),
+                                StatusLevel::Error,
+                            ),
+                        }
+                    }
+                }
+                AppAction::PlayReplay if app.state() == AppState::HistoryDetail => {
+                    if let Some(ref mut view) = history_view
+                        && let Some(id) = view.selected_result_mut().and_then(|result| result.id)
+                        && let Err(e) = app.play_saved_replay(id)
+                    {
+                        view.set_status(format!("{:#}", e), false);
+                    }
+                }
+                AppAction::DeleteResult => {
+                    if let Some(ref mut view) = history_view
+                        && !view.results.is_empty()
+                    {
+                        view.pending_delete = Some(PendingDelete::Selected);
+                    }
+                }
+                AppAction::ClearHistory => {
+                    if let Some(ref mut view) = history_view
+                        && !view.results.is_empty()
+                    {
+                        view.pending_delete = Some(PendingDelete::All);
+                    }
+                }
+                AppAction::BackupDatabase => {
+                    if let Some(ref mut view) = history_view {
+                        match app.backup_database() {
+                            Ok(path) => {
+                                view.set_status(format!("Backed up to {}", path.display()), true)
+                            }
+                            Err(e) => view.set_status(format!("{:#}", e), false),
+                        }
+                    }
+                }
+                AppAction::RestoreDatabase => {
+                    if let Some(ref mut view) = history_view {
+                        match app.latest_backup() {
+                            Some(path) => view.pending_delete = Some(PendingDelete::Restore(path)),
+                            None => view.set_status("No backups to restore yet".to_string(), false),
+                        }
+                    }
+                }
+                AppAction::CancelDelete => {
+                    if let Some(ref mut view) = history_view {
+                        view.pending_delete = None;
+                    }
+                }
+                AppAction::ConfirmDelete => {
+                    if let Some(ref mut view) = history_view
+                        && let Some(pending) = view.pending_delete.take()
+                    {
+                        let deleted = match pending {
+                            // Results read back from the database always have an id
+                            PendingDelete::Selected => {
+                                match view.results.get(view.selected).and_then(|result| result.id) {
+                                    Some(id) => app.db.delete_result(id),
+                                    None => Ok(()),
                                 }
-                            },
-                            AppAction::BackToTesting => {
-                                history_view = None;
-                                stats_view = None;
                             }
-                            AppAction::NavigateUp => {
-                                if let Some(ref mut view) = history_view {
-                                    view.previous();
+                            PendingDelete::All => app.db.delete_all_results(),
+                            PendingDelete::Restore(path) => {
+                                match app.restore_database(&path) {
+                                    Ok(()) => view
+                                        .set_status(format!("Restored {}", path.display()), true),
+                                    Err(e) => view.set_status(format!("{:#}", e), false),
                                 }
+                                Ok(())
                             }
-                            AppAction::NavigateDown => {
-                                if let Some(ref mut view) = history_view {
-                                    view.next();
+                        };
+                        // Reload as many rows as were showing so the selection stays put
+                        let limit = view.results.len().max(HISTORY_PAGE);
+                        match deleted.and_then(|()| {
+                            history_page(&app.db, view.mode_filter, view.window, view.sort, limit)
+                        }) {
+                            Ok((results, summary)) => view.set_results(results, summary),
+                            Err(e) => app.set_message(
+                                format!("Failed to delete results: {}", e),
+                                StatusLevel::Error,
+                            ),
+                        }
+                    }
+                }
+                AppAction::CycleHistoryMode | AppAction::CycleHistorySort => {
+                    if let Some(ref mut view) = history_view {
+                        if matches!(action, AppAction::CycleHistoryMode) {
+                            view.cycle_mode_filter();
+                        } else {
+                            view.cycle_sort();
+                        }
+                        match history_page(
+                            &app.db,
+                            view.mode_filter,
+                            view.window,
+                            view.sort,
+                            HISTORY_PAGE,
+                        ) {
+                            Ok((results, summary)) => view.replace_results(results, summary),
+                            Err(e) => app.set_message(
+                                format!("Failed to load history: {}", e),
+                                StatusLevel::Error,
+                            ),
+                        }
+                    }
+                }
+                // Only the open screen reloads; the other picks the window up when opened
+                AppAction::CycleDateWindow => {
+                    if app.state() == AppState::Stats {
+                        if let Some(tab) = stats_view.as_ref().map(StatsView::tab) {
+                            match load_stats(app) {
+                                Ok(mut view) => {
+                                    view.set_tab(tab);
+                                    stats_view = Some(view);
                                 }
+                                Err(e) => app.set_message(
+                                    format!("Failed to load stats: {}", e),
+                                    StatusLevel::Error,
+                                ),
                             }
-                            _ => {}
                         }
+                    } else if let Some(ref mut view) = history_view {
+                        view.window = app.date_window();
+                        match history_page(
+                            &app.db,
+                            view.mode_filter,
+                            view.window,
+                            view.sort,
+                            HISTORY_PAGE,
+                        ) {
+                            Ok((results, summary)) => view.replace_results(results, summary),
+                            Err(e) => app.set_message(
+                                format!("Failed to load history: {}", e),
+                                StatusLevel::Error,
+                            ),
+                        }
+                    }
+                }
+                // The quote picker handles its own navigation
+                AppAction::NavigateUp if app.state() == AppState::Stats => {
+                    if let Some(ref mut view) = stats_view {
+                        view.scroll_up();
                     }
                 }
+                AppAction::NavigateDown if app.state() == AppState::Stats => {
+                    if let Some(ref mut view) = stats_view {
+                        view.scroll_down();
+                    }
+                }
+                AppAction::NavigateUp
+                    if !matches!(
+                        app.state(),
+                        AppState::QuotePicker | AppState::ThemePicker | AppState::ProfilePicker
+                    ) =>
+                {
+                    if let Some(ref mut view) = history_view {
+                        view.previous();
+                    }
+                }
+                AppAction::NavigateDown
+                    if !matches!(
+                        app.state(),
+                        AppState::QuotePicker | AppState::ThemePicker | AppState::ProfilePicker
+                    ) =>
+                {
+                    if let Some(ref mut view) = history_view {
+                        load_more_history(app, view, 1);
+                        view.next();
+                    }
+                }
+                AppAction::PageUp => {
+                    if let Some(ref mut view) = history_view {
+                        view.previous_page();
+                    }
+                }
+                AppAction::PageDown => {
+                    if let Some(ref mut view) = history_view {
+                        load_more_history(app, view, PAGE_ROWS);
+                        view.next_page();
+                    }
+                }
+                AppAction::NavigateLeft => {
+                    if let Some(ref mut view) = stats_view {
+                        view.previous_tab();
+                    }
+                }
+                AppAction::NavigateRight => {
+                    if let Some(ref mut view) = stats_view {
+                        view.next_tab();
+                    }
+                }
+                _ => {}
             }
         }
 
         app.on_tick();
     }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restoring_twice_changes_nothing() {
+        let mut first = Vec::new();
+        let mut second = Vec::new();
+        restore_screen(&mut first).unwrap();
+        restore_screen(&mut second).unwrap();
+        assert_eq!(first, second);
+        let sequences = String::from_utf8(first).unwrap();
+        // Main screen and cursor back
+        assert!(sequences.contains("\x1b[?1049l"), "{:?}", sequences);
+        assert!(sequences.contains("\x1b[?25h"), "{:?}", sequences);
+
+        // Raw mode was never on here; turning it off is harmless, twice over
+        disable_raw_mode().unwrap();
+        disable_raw_mode().unwrap();
+    }
 }
@@ -1,6 +1,12 @@
-use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Days, Local, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::core::anomaly::Anomaly;
+use crate::core::metrics::CHARS_PER_WORD;
+use crate::storage::paths::DEFAULT_PROFILE;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestResult {
     pub id: Option<i64>,
@@ -8,32 +14,748 @@ pub struct TestResult {
     pub mode: String, // "short", "medium", "long"
     pub wpm: f64,
     pub raw_wpm: f64,
+    /// Share of the final text that matches the quote; corrected typos don't count
     pub accuracy: f64,
-    pub consistency: f64,
+    /// Share of all keystrokes that were right, corrected typos included.
+    /// `None` for results saved before this was recorded.
+    pub real_accuracy: Option<f64>,
+    /// `None` when the test was too short to measure it
+    pub consistency: Option<f64>,
+    /// Formula the consistency was worked out with; 1 is the legacy one
+    pub consistency_version: i64,
+    /// Best WPM over any 5 seconds. `None` for results saved before this was recorded.
+    pub burst_wpm: Option<f64>,
     pub quote_length: i64,
+    /// Words in the text typed, for showing speed in real words.
+    /// `None` for results saved before this was recorded.
+    pub word_count: Option<i64>,
     pub duration_seconds: i64,
+    /// Id of the quote typed; `None` for zen, continuous and custom text
+    pub quote_id: Option<i64>,
+    /// File or text a `--file`/`--text` result was typed from
+    pub source: Option<String>,
+    pub anomaly: Anomaly,
+    /// A high anomaly the user has vouched for; it then counts towards bests
+    pub confirmed: bool,
+}
+
+impl TestResult {
+    /// Average word length of the text typed; the standard five when the
+    /// word count wasn't recorded
+    pub fn chars_per_word(&self) -> f64 {
+        match self.word_count {
+            Some(words) if words > 0 => self.quote_length as f64 / words as f64,
+            _ => CHARS_PER_WORD,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-#[allow(dead_code)]
 pub struct UserStats {
     pub total_tests: i64,
     pub best_wpm: f64,
+    /// Highest burst WPM of any result; 0 before any were recorded
+    pub best_burst_wpm: f64,
     pub avg_wpm: f64,
     pub avg_accuracy: f64,
     pub total_time_seconds: i64,
+    pub best_wpm_by_mode: Vec<(String, f64)>,
+}
+
+/// Malformed results found by `Database::validate`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Results whose timestamp isn't a date; they are skipped when read
+    pub bad_timestamps: usize,
+    /// Results moved to `malformed_results` when timestamps became numbers
+    pub set_aside: usize,
+    /// Results with a negative speed or duration, or accuracy outside 0–100%
+    pub out_of_range: usize,
+}
+
+impl ValidationReport {
+    /// e.g. "Database: 1 result with an unreadable date hidden"; `None`
+    /// when nothing is wrong
+    pub fn message(&self) -> Option<String> {
+        let plural = |n: usize| if n == 1 { "result" } else { "results" };
+        let problems: Vec<String> = [
+            (self.bad_timestamps, "with an unreadable date hidden"),
+            (self.set_aside, "set aside in malformed_results"),
+            (self.out_of_range, "with impossible values"),
+        ]
+        .into_iter()
+        .filter(|&(n, _)| n > 0)
+        .map(|(n, what)| format!("{} {} {}", n, plural(n), what))
+        .collect();
+        (!problems.is_empty()).then(|| format!("Database: {}", problems.join(", ")))
+    }
+}
+
+/// Aggregates over the results matching a History filter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilteredStats {
+    pub count: usize,
+    pub avg_wpm: f64,
+    /// Leaves out unconfirmed anomalies, like the mode bests
+    pub best_wpm: f64,
+    pub avg_accuracy: f64,
+}
+
+/// Tests grouped by quote length in steps of 100 characters; the last bucket is open-ended
+#[derive(Debug, Clone)]
+pub struct LengthBucket {
+    pub min_length: i64,
+    pub tests: i64,
+    pub avg_wpm: f64,
+}
+
+impl LengthBucket {
+    pub const WIDTH: i64 = 100;
+    pub const LAST_MIN: i64 = 500;
+
+    pub fn is_last(&self) -> bool {
+        self.min_length >= Self::LAST_MIN
+    }
+}
+
+/// Started-but-discarded tests compared with all started tests
+#[derive(Debug, Clone, Default)]
+pub struct AbandonmentStats {
+    pub started: i64,
+    pub abandoned: i64,
+    /// Median characters typed before giving up
+    pub median_chars: Option<i64>,
+    /// Oldest first, one entry per ISO week with any tests
+    pub weekly: Vec<WeeklyAbandonment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WeeklyAbandonment {
+    /// e.g. "W42"
+    pub label: String,
+    pub started: i64,
+    pub abandoned: i64,
+}
+
+/// Share of started tests that were abandoned, in percent
+pub fn abandonment_rate(started: i64, abandoned: i64) -> f64 {
+    if started == 0 {
+        0.0
+    } else {
+        abandoned as f64 / started as f64 * 100.0
+    }
+}
+
+/// Order of the History list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// Newest first
+    #[default]
+    Date,
+    /// Fastest first
+    Wpm,
+    /// Most accurate first
+    Accuracy,
+}
+
+impl SortOrder {
+    pub fn next(&self) -> Self {
+        match self {
+            SortOrder::Date => SortOrder::Wpm,
+            SortOrder::Wpm => SortOrder::Accuracy,
+            SortOrder::Accuracy => SortOrder::Date,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortOrder::Date => "date",
+            SortOrder::Wpm => "WPM",
+            SortOrder::Accuracy => "accuracy",
+        }
+    }
+}
+
+/// How far back Stats and History look
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateWindow {
+    Week,
+    Month,
+    Quarter,
+    #[default]
+    All,
+}
+
+impl DateWindow {
+    /// all -> 7 days -> 30 days -> 90 days -> all
+    pub fn next(&self) -> Self {
+        match self {
+            DateWindow::All => DateWindow::Week,
+            DateWindow::Week => DateWindow::Month,
+            DateWindow::Month => DateWindow::Quarter,
+            DateWindow::Quarter => DateWindow::All,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DateWindow::Week => "last 7 days",
+            DateWindow::Month => "last 30 days",
+            DateWindow::Quarter => "last 90 days",
+            DateWindow::All => "all time",
+        }
+    }
+
+    fn days(&self) -> Option<u64> {
+        match self {
+            DateWindow::Week => Some(7),
+            DateWindow::Month => Some(30),
+            DateWindow::Quarter => Some(90),
+            DateWindow::All => None,
+        }
+    }
+
+    /// Local midnight starting the window, so "last 7 days" is today and the
+    /// six days before it; `None` for all time
+    pub fn since(&self, now: DateTime<Local>) -> Option<DateTime<Utc>> {
+        let first_day = now
+            .date_naive()
+            .checked_sub_days(Days::new(self.days()? - 1))?;
+        local_midnight(first_day)
+    }
+}
+
+/// The moment `date` starts in local time
+pub fn local_midnight(date: NaiveDate) -> Option<DateTime<Utc>> {
+    // Midnight can be skipped or repeated by a DST change
+    let midnight = date
+        .and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+        .or_else(|| {
+            date.and_hms_opt(1, 0, 0)?
+                .and_local_timezone(Local)
+                .earliest()
+        })?;
+    Some(midnight.with_timezone(&Utc))
+}
+
+/// Completed tests per local calendar day, with the streaks they form
+#[derive(Debug, Clone, Default)]
+pub struct DailyActivity {
+    pub tests_by_day: BTreeMap<NaiveDate, i64>,
+    /// Consecutive days up to today, or up to yesterday if nothing was typed today yet
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+impl DailyActivity {
+    pub fn new(tests_by_day: BTreeMap<NaiveDate, i64>, today: NaiveDate) -> Self {
+        let (current_streak, longest_streak) = streaks(tests_by_day.keys().copied(), today);
+        Self {
+            tests_by_day,
+            current_streak,
+            longest_streak,
+        }
+    }
+}
+
+/// (current, longest) runs of consecutive days in `days`, which must be sorted.
+/// A streak that ended yesterday is still current, since today isn't over.
+pub fn streaks(days: impl IntoIterator<Item = NaiveDate>, today: NaiveDate) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut last: Option<NaiveDate> = None;
+    for day in days {
+        if day > today {
+            break;
+        }
+        run = match last {
+            Some(prev) if prev.succ_opt() == Some(day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        last = Some(day);
+    }
+
+    let current = match last {
+        Some(day) if day == today || day.succ_opt() == Some(today) => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+/// Calendar span that results are grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregatePeriod {
+    Day,
+    /// Monday to Sunday
+    Week,
+}
+
+/// Totals for the results in one day or week
+#[derive(Debug, Clone)]
+pub struct PeriodAggregate {
+    /// Local date the day or week starts on
+    pub start: NaiveDate,
+    pub tests: i64,
+    pub avg_wpm: f64,
+    pub best_wpm: f64,
+    pub avg_accuracy: f64,
+    pub minutes: f64,
+}
+
+/// Completed tests and typing time since local midnight
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TodaySummary {
+    pub tests: i64,
+    pub seconds: i64,
+}
+
+/// What was typed between launch and quit, printed once the TUI has closed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionReport {
+    pub tests: i64,
+    pub avg_wpm: f64,
+    pub best_wpm: f64,
+    pub seconds: i64,
+    /// Average over every run before this session; `None` on the first one
+    pub previous_avg_wpm: Option<f64>,
+}
+
+impl SessionReport {
+    /// From the stats since launch and over all time; `None` when no test
+    /// was saved this session
+    pub fn new(session: &UserStats, lifetime: &UserStats) -> Option<Self> {
+        if session.total_tests == 0 {
+            return None;
+        }
+        let earlier = lifetime.total_tests - session.total_tests;
+        let previous_avg_wpm = (earlier > 0).then(|| {
+            (lifetime.avg_wpm * lifetime.total_tests as f64
+                - session.avg_wpm * session.total_tests as f64)
+                / earlier as f64
+        });
+        Some(Self {
+            tests: session.total_tests,
+            avg_wpm: session.avg_wpm,
+            best_wpm: session.best_wpm,
+            seconds: session.total_time_seconds,
+            previous_avg_wpm,
+        })
+    }
+}
+
+impl std::fmt::Display for SessionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "TUItype session: {} {} in {}",
+            self.tests,
+            if self.tests == 1 { "test" } else { "tests" },
+            format_duration(self.seconds)
+        )?;
+        write!(
+            f,
+            "  Average {:.1} WPM · best {:.1} WPM",
+            self.avg_wpm, self.best_wpm
+        )?;
+        if let Some(previous) = self.previous_avg_wpm {
+            write!(
+                f,
+                " · {:+.1} vs your average of {:.1}",
+                self.avg_wpm - previous,
+                previous
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// e.g. "1h 4m 12s", "4m 12s" or "12s"
+pub fn format_duration(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    let seconds = seconds % 60;
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Tests and/or minutes to type each day; a zero target is not a goal
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DailyGoal {
+    pub tests: u32,
+    pub minutes: u32,
+}
+
+impl DailyGoal {
+    pub fn is_set(&self) -> bool {
+        self.tests > 0 || self.minutes > 0
+    }
+
+    /// Every target that is set has been reached
+    pub fn is_met(&self, today: &TodaySummary) -> bool {
+        self.is_set()
+            && today.tests >= self.tests as i64
+            && today.seconds / 60 >= self.minutes as i64
+    }
+
+    /// "Goal: 6/10", "Goal: 7/15 min" or both; `None` without a goal
+    pub fn progress(&self, today: &TodaySummary) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.tests > 0 {
+            parts.push(format!("{}/{}", today.tests, self.tests));
+        }
+        if self.minutes > 0 {
+            parts.push(format!("{}/{} min", today.seconds / 60, self.minutes));
+        }
+        if parts.is_empty() {
+            return None;
+        }
+        let check = if self.is_met(today) { " ✓" } else { "" };
+        Some(format!("Goal: {}{}", parts.join(" · "), check))
+    }
+}
+
+/// Average time between the two keys of a bigram
+#[derive(Debug, Clone)]
+pub struct BigramLatency {
+    pub bigram: String,
+    pub count: i64,
+    pub avg_ms: f64,
+}
+
+/// A finished test that matched or beat the best WPM for its mode
+#[derive(Debug, Clone, Copy)]
+pub struct PersonalBest {
+    /// Record before this run, `None` for the first test in a mode
+    pub previous: Option<f64>,
+}
+
+/// Where a run places among every result in its mode
+#[derive(Debug, Clone, Copy)]
+pub struct Ranking {
+    /// 1 for the fastest; runs of equal speed share the better place
+    pub rank: i64,
+    pub total: i64,
+}
+
+impl Ranking {
+    /// Share of results at or behind this one's place, e.g. 12 for "top 12%"
+    pub fn top_percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        self.rank as f64 / self.total as f64 * 100.0
+    }
+}
+
+/// Mean WPM of the latest `n` results next to the `n` before them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecentForm {
+    pub n: usize,
+    /// `None` before the first result
+    pub last: Option<f64>,
+    /// `None` until there are `2 * n` results, so the trend never compares
+    /// against a partial window
+    pub prior: Option<f64>,
+}
+
+impl RecentForm {
+    /// How many WPM the latest results are ahead of the ones before them
+    pub fn trend(&self) -> Option<f64> {
+        Some(self.last? - self.prior?)
+    }
+}
+
+/// Reading position in a continuous source, plus totals over the chunks
+/// finished since it was last started from the beginning
+#[derive(Debug, Clone, Default)]
+pub struct Bookmark {
+    pub file_hash: String,
+    pub chunk_index: usize,
+    pub chunk_count: usize,
+    pub tests: i64,
+    pub wpm_sum: f64,
+    pub accuracy_sum: f64,
+    pub duration_seconds: i64,
+}
+
+/// Shown on the results screen after the last chunk of a continuous source
+#[derive(Debug, Clone)]
+pub struct SourceSummary {
+    pub title: String,
+    pub tests: i64,
+    pub avg_wpm: f64,
+    pub avg_accuracy: f64,
+    pub duration_seconds: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
+    /// Whose results to use when `--profile` isn't given. Only read from
+    /// config.toml; each profile keeps its own database.
+    #[serde(default = "default_profile")]
+    pub profile: String,
+
     #[serde(default = "default_theme")]
     pub theme: String,
 
+    /// Mode a new session starts in
     #[serde(default = "default_mode")]
     pub default_mode: String,
 
+    /// Save the mode to `default_mode` whenever it changes, so the next
+    /// session starts where this one left off
+    #[serde(default = "default_true")]
+    pub remember_mode: bool,
+
+    /// Snippet language in code mode: "rust", "python" or "javascript"
+    #[serde(default = "default_code_language")]
+    pub code_language: String,
+
+    /// Keys drill mode makes words from: "homerow", "lefthand", "righthand",
+    /// "toprow", or "custom" for the keys in `drill_keys`
+    #[serde(default = "default_drill_set")]
+    pub drill_set: String,
+
+    /// Keys for `drill_set = "custom"`, e.g. "qwerasdf"; needs at least four
+    #[serde(default)]
+    pub drill_keys: String,
+
+    /// In code mode, indentation after a newline is typed for you
+    #[serde(default = "default_true")]
+    pub code_auto_indent: bool,
+
+    /// Quote language: "english" (built in) or the name of a `.json` pack in
+    /// the quotes directory
+    #[serde(default = "default_language")]
+    pub language: String,
+
     #[serde(default = "default_time")]
     pub default_time: u64,
+
+    /// Require the typed text to match the quote exactly before a test ends.
+    /// Off by default so a typo in the final character can't leave the test stuck.
+    #[serde(default)]
+    pub strict_completion: bool,
+
+    #[serde(default)]
+    pub cursor_blink: bool,
+
+    /// Selection bias from quote ratings: each star above/below 3 multiplies or
+    /// divides a quote's odds by this factor. 1.0 ignores ratings.
+    #[serde(default = "default_rating_weight")]
+    pub quote_rating_weight: f64,
+
+    /// Key that ends a zen-mode test: "esc" or "enter"
+    #[serde(default = "default_zen_end_key")]
+    pub zen_end_key: String,
+
+    /// Capitalize letters typed with SHIFT held. Only needed on terminals
+    /// that send the lowercase letter plus SHIFT; others already send the
+    /// shifted character.
+    #[serde(default)]
+    pub shift_uppercase: bool,
+
+    /// Minutes without a keypress on the typing screen before the quote is
+    /// hidden behind a clock. 0 disables the screensaver.
+    #[serde(default = "default_screensaver_minutes")]
+    pub screensaver_minutes: u64,
+
+    /// Space in the middle of a word skips to the next one; the rest of the
+    /// word counts as mistakes. Only in normal difficulty.
+    #[serde(default)]
+    pub space_skips_word: bool,
+
+    /// How mistakes are treated: "normal", "stop" (wrong keys are rejected)
+    /// or "master" (the first mistake fails the test)
+    #[serde(default = "default_difficulty")]
+    pub difficulty: String,
+
+    /// Taking mistakes back: "off", "on" (backspace does nothing) or "max"
+    /// (word delete doesn't work either, and wrong keys are typed in even in
+    /// stop difficulty). Saved runs get "+confidence" or "+confidence-max"
+    /// added to their mode.
+    #[serde(default = "default_confidence")]
+    pub confidence: String,
+
+    /// Text file typed chunk by chunk in continuous mode, e.g. a book
+    #[serde(default)]
+    pub continuous_source: Option<String>,
+
+    /// On-screen keyboard shape: "ansi", "split" or "ortho"
+    #[serde(default = "default_keyboard_style")]
+    pub keyboard_style: String,
+
+    /// Record tests that are started and then thrown away, for the Stats screen
+    #[serde(default = "default_true")]
+    pub track_abandonment: bool,
+
+    /// Database backups kept in the backups folder; older ones are deleted
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: usize,
+
+    /// Replays of the most recent tests kept for watching back; older ones
+    /// are deleted. 0 saves none.
+    #[serde(default = "default_replay_keep")]
+    pub replay_keep: usize,
+
+    /// Record the words mistyped in each test
+    #[serde(default = "default_true")]
+    pub track_word_errors: bool,
+
+    /// Underline the words of a quote that are on the worst-words list
+    #[serde(default = "default_true")]
+    pub highlight_hard_words: bool,
+
+    /// Show the opening of the next quote under the quote box, so the next
+    /// test is no surprise
+    #[serde(default = "default_true")]
+    pub next_quote_preview: bool,
+
+    /// Capture the mouse for the scroll wheel and clickable header hints.
+    /// Off leaves the terminal's own text selection and right-click alone.
+    #[serde(default = "default_true")]
+    pub mouse: bool,
+
+    /// Show recent results in History as "2h ago" or "yesterday 21:14"
+    /// instead of a full date. Times are always local.
+    #[serde(default = "default_true")]
+    pub relative_time: bool,
+
+    /// Progress bar and word counter under the typing header
+    #[serde(default = "default_true")]
+    pub show_progress: bool,
+
+    /// Speeds of the last five results under the typing header, green above
+    /// your average and red below; hidden in focus mode
+    #[serde(default = "default_true")]
+    pub show_recent: bool,
+
+    /// Up to two tips on the results screen, e.g. slowing down when
+    /// accuracy dropped or the key behind most mistakes
+    #[serde(default = "default_true")]
+    pub coaching: bool,
+
+    /// On-screen keyboard under the quote; Ctrl+F flips it and saves it here
+    #[serde(default)]
+    pub show_keyboard: bool,
+
+    /// Accuracy at or above this is shown in green in the typing header, on
+    /// the results screen and in History
+    #[serde(default = "default_accuracy_good")]
+    pub accuracy_good: f64,
+
+    /// Accuracy at or above this, but below `accuracy_good`, is yellow;
+    /// lower is red
+    #[serde(default = "default_accuracy_fair")]
+    pub accuracy_fair: f64,
+
+    /// Runs slower than this WPM are shown but not saved unless S is pressed
+    /// on the results screen; 0 saves every run
+    #[serde(default)]
+    pub min_wpm_to_save: f64,
+
+    /// Same for accuracy, in percent
+    #[serde(default)]
+    pub min_accuracy_to_save: f64,
+
+    /// Start the same text over on the first wrong key, for practicing
+    /// without mistakes
+    #[serde(default)]
+    pub auto_restart_on_first_error: bool,
+
+    /// Print the tests, speed and time of the session to the terminal on quit
+    #[serde(default)]
+    pub session_summary: bool,
+
+    /// Go straight to the next quote after a test, skipping the results
+    /// screen; the header shows the run and a tally instead. Ctrl+Y flips it.
+    #[serde(default)]
+    pub auto_advance: bool,
+
+    /// Collapse the typing screen to the stats line and the quote: no keybind
+    /// hints, attribution or keyboard
+    #[serde(default)]
+    pub focus_mode: bool,
+
+    /// Seconds without a keystroke before a running test counts as AFK; 0 disables
+    #[serde(default = "default_afk_seconds")]
+    pub afk_seconds: u64,
+
+    /// What AFK does to the test: "discard" (never saved) or "pause"
+    #[serde(default = "default_afk_behavior")]
+    pub afk_behavior: String,
+
+    /// Extra signal on a wrong key: "none", "flash" (the quote border blinks)
+    /// or "bell" (terminal bell)
+    #[serde(default = "default_error_feedback")]
+    pub error_feedback: String,
+
+    /// How speeds are shown: "wpm5" (five characters a word), "cpm"
+    /// (characters per minute) or "real_words" (the words in the text).
+    /// Results are always stored as five-character WPM.
+    #[serde(default = "default_speed_unit")]
+    pub speed_unit: String,
+
+    /// Key clicks while typing: "off", "click" or "typewriter". Needs a build
+    /// with the `sound` feature.
+    #[serde(default = "default_sound")]
+    pub sound: String,
+
+    /// Loudness of the key clicks, from 0.0 to 1.0
+    #[serde(default = "default_sound_volume")]
+    pub sound_volume: f64,
+
+    /// Hardest quotes offered, by their 1–5 difficulty: "easy" (up to 2),
+    /// "normal" (up to 3) or "hard" (all of them)
+    #[serde(default = "default_max_quote_difficulty")]
+    pub max_quote_difficulty: String,
+
+    /// Straighten curly quotes and dashes in quotes, and skip quotes that
+    /// still contain non-ASCII characters
+    #[serde(default)]
+    pub ascii_only: bool,
+
+    /// Ring the terminal bell on a new personal best
+    #[serde(default)]
+    pub notify_on_pb: bool,
+
+    /// Text copied with `C` on the results screen. Placeholders: {wpm}, {raw},
+    /// {acc}, {consistency}, {mode} and {time} (seconds)
+    #[serde(default = "default_share_format")]
+    pub share_format: String,
+
+    /// Where `C` copies to: "system" (desktop clipboard) or "osc52" (through
+    /// the terminal, which also works over SSH)
+    #[serde(default = "default_clipboard")]
+    pub clipboard: String,
+
+    /// Completed tests to aim for each day; 0 for no test goal
+    #[serde(default)]
+    pub daily_goal_tests: u32,
+
+    /// Minutes of typing to aim for each day; 0 for no time goal
+    #[serde(default)]
+    pub daily_goal_minutes: u32,
+
+    /// Race a ghost cursor moving at a fixed pace
+    #[serde(default)]
+    pub ghost: bool,
+
+    /// The ghost's pace: "best" (personal best for the mode), "average", or a WPM number
+    #[serde(default = "default_ghost_target")]
+    pub ghost_target: String,
+}
+
+fn default_profile() -> String {
+    DEFAULT_PROFILE.to_string()
 }
 
 fn default_theme() -> String {
@@ -42,16 +764,221 @@ fn default_theme() -> String {
 fn default_mode() -> String {
     "medium".to_string()
 }
+fn default_code_language() -> String {
+    "rust".to_string()
+}
+fn default_max_quote_difficulty() -> String {
+    "hard".to_string()
+}
+fn default_drill_set() -> String {
+    "homerow".to_string()
+}
+fn default_language() -> String {
+    "english".to_string()
+}
 fn default_time() -> u64 {
     60
 }
+fn default_rating_weight() -> f64 {
+    1.0
+}
+fn default_zen_end_key() -> String {
+    "esc".to_string()
+}
+fn default_screensaver_minutes() -> u64 {
+    5
+}
+fn default_confidence() -> String {
+    "off".to_string()
+}
+fn default_difficulty() -> String {
+    "normal".to_string()
+}
+fn default_keyboard_style() -> String {
+    "ansi".to_string()
+}
+fn default_accuracy_good() -> f64 {
+    97.0
+}
+fn default_accuracy_fair() -> f64 {
+    92.0
+}
+fn default_true() -> bool {
+    true
+}
+fn default_backup_keep() -> usize {
+    10
+}
+fn default_speed_unit() -> String {
+    "wpm5".to_string()
+}
+fn default_sound() -> String {
+    "off".to_string()
+}
+fn default_sound_volume() -> f64 {
+    0.5
+}
+fn default_replay_keep() -> usize {
+    50
+}
+fn default_afk_seconds() -> u64 {
+    30
+}
+fn default_afk_behavior() -> String {
+    "discard".to_string()
+}
+fn default_error_feedback() -> String {
+    "none".to_string()
+}
+fn default_share_format() -> String {
+    "TUItype · {wpm} WPM · {acc}% acc · {mode} · {time}s".to_string()
+}
+fn default_clipboard() -> String {
+    "system".to_string()
+}
+fn default_ghost_target() -> String {
+    "best".to_string()
+}
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            profile: default_profile(),
             theme: default_theme(),
             default_mode: default_mode(),
+            remember_mode: true,
+            language: default_language(),
+            code_language: default_code_language(),
+            drill_set: default_drill_set(),
+            drill_keys: String::new(),
+            code_auto_indent: true,
             default_time: default_time(),
+            strict_completion: false,
+            cursor_blink: false,
+            quote_rating_weight: default_rating_weight(),
+            max_quote_difficulty: default_max_quote_difficulty(),
+            zen_end_key: default_zen_end_key(),
+            shift_uppercase: false,
+            screensaver_minutes: default_screensaver_minutes(),
+            space_skips_word: false,
+            difficulty: default_difficulty(),
+            confidence: default_confidence(),
+            continuous_source: None,
+            keyboard_style: default_keyboard_style(),
+            track_abandonment: true,
+            track_word_errors: true,
+            highlight_hard_words: true,
+            next_quote_preview: true,
+            backup_keep: default_backup_keep(),
+            replay_keep: default_replay_keep(),
+            mouse: true,
+            relative_time: true,
+            show_progress: true,
+            show_recent: true,
+            coaching: true,
+            show_keyboard: false,
+            accuracy_good: default_accuracy_good(),
+            accuracy_fair: default_accuracy_fair(),
+            min_wpm_to_save: 0.0,
+            min_accuracy_to_save: 0.0,
+            auto_restart_on_first_error: false,
+            session_summary: false,
+            auto_advance: false,
+            focus_mode: false,
+            afk_seconds: default_afk_seconds(),
+            afk_behavior: default_afk_behavior(),
+            error_feedback: default_error_feedback(),
+            speed_unit: default_speed_unit(),
+            sound: default_sound(),
+            sound_volume: default_sound_volume(),
+            ascii_only: false,
+            notify_on_pb: false,
+            daily_goal_tests: 0,
+            daily_goal_minutes: 0,
+            share_format: default_share_format(),
+            clipboard: default_clipboard(),
+            ghost: false,
+            ghost_target: default_ghost_target(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abandonment_rate_is_a_percentage_of_started_tests() {
+        assert_eq!(abandonment_rate(0, 0), 0.0);
+        assert_eq!(abandonment_rate(4, 0), 0.0);
+        assert_eq!(abandonment_rate(4, 1), 25.0);
+        assert_eq!(abandonment_rate(3, 3), 100.0);
+    }
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    fn days(days: &[u32]) -> Vec<NaiveDate> {
+        days.iter().map(|&d| day(d)).collect()
+    }
+
+    #[test]
+    fn streaks_run_up_to_today_or_yesterday() {
+        let today = day(10);
+        for (typed, current, longest) in [
+            (&[][..], 0, 0),
+            (&[10][..], 1, 1),
+            (&[8, 9, 10][..], 3, 3),
+            // Nothing typed today yet: yesterday's streak still counts
+            (&[7, 8, 9][..], 3, 3),
+            // A missed day breaks it
+            (&[6, 7, 8][..], 0, 3),
+            (&[1, 2, 3, 4, 6, 9, 10][..], 2, 4),
+            (&[1, 3, 5, 7, 9][..], 1, 1),
+        ] {
+            assert_eq!(
+                streaks(days(typed), today),
+                (current, longest),
+                "{:?}",
+                typed
+            );
         }
     }
+
+    #[test]
+    fn streaks_ignore_days_after_today() {
+        assert_eq!(streaks(days(&[9, 10, 11, 12, 13]), day(10)), (2, 2));
+    }
+
+    #[test]
+    fn streaks_cross_month_ends() {
+        let feb = |d| NaiveDate::from_ymd_opt(2026, 2, d).unwrap();
+        let typed = [feb(27), feb(28), day(1), day(2)];
+        assert_eq!(streaks(typed, day(2)), (4, 4));
+    }
+
+    #[test]
+    fn daily_activity_counts_streaks_over_days_with_tests() {
+        let tests_by_day = BTreeMap::from([(day(4), 2), (day(5), 1), (day(9), 5)]);
+        let activity = DailyActivity::new(tests_by_day, day(10));
+        assert_eq!(activity.current_streak, 1);
+        assert_eq!(activity.longest_streak, 2);
+        assert_eq!(activity.tests_by_day[&day(9)], 5);
+    }
+
+    #[test]
+    fn trend_needs_both_windows() {
+        let form = |last, prior| RecentForm { n: 10, last, prior };
+        assert_eq!(
+            form(Some(86.4), Some(84.3))
+                .trend()
+                .map(|t| (t * 10.0).round()),
+            Some(21.0)
+        );
+        assert_eq!(form(Some(70.0), Some(75.5)).trend(), Some(-5.5));
+        assert_eq!(form(Some(60.0), Some(60.0)).trend(), Some(0.0));
+        assert_eq!(form(Some(60.0), None).trend(), None);
+        assert_eq!(form(None, None).trend(), None);
+    }
 }
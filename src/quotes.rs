@@ -1,14 +1,31 @@
-use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
 use rand::prelude::*;
 use serde::Deserialize;
 
+use crate::keyboard::{Hand, KeyboardLayout};
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Quote {
     pub text: String,
     pub source: String,
+    /// Optional in language packs; counted from `text` when missing
+    #[serde(default)]
     pub length: usize,
-    #[allow(dead_code)]
+    /// Optional in language packs; numbered in file order when missing
+    #[serde(default)]
     pub id: usize,
+    /// Stable hash of `text`, used to key per-quote data in the database
+    #[serde(skip)]
+    pub hash: String,
+    /// 1 (plain lowercase prose) to `MAX_DIFFICULTY`, see `difficulty_level`
+    #[serde(skip)]
+    pub difficulty: u8,
 }
 
 // MonkeyType's actual JSON structure
@@ -22,56 +39,623 @@ pub enum QuoteMode {
     Short,
     Medium,
     Long,
+    /// Free typing without a quote
+    Zen,
+    /// Sequential chunks of the configured `continuous_source` file
+    Continuous,
+    /// Generated from the words mistyped most often
+    Practice,
+    /// Multi-line code snippets in the configured `code_language`
+    Code,
+    /// Pseudo-words using only the keys of the configured `drill_set`
+    Drill,
 }
 
 impl QuoteMode {
+    /// The length groups quotes are indexed by
+    const LENGTH_GROUPS: [QuoteMode; 3] = [QuoteMode::Short, QuoteMode::Medium, QuoteMode::Long];
+
     pub fn length_range(&self) -> (usize, usize) {
         match self {
             QuoteMode::Short => (0, 100),
             QuoteMode::Medium => (101, 300), // Match MonkeyType's groups
             QuoteMode::Long => (301, usize::MAX),
+            QuoteMode::Zen
+            | QuoteMode::Continuous
+            | QuoteMode::Practice
+            | QuoteMode::Code
+            | QuoteMode::Drill => (0, 0),
+        }
+    }
+
+    /// The length group a quote of `length` characters belongs to
+    pub fn for_length(length: usize) -> Self {
+        [QuoteMode::Short, QuoteMode::Medium]
+            .into_iter()
+            .find(|mode| length <= mode.length_range().1)
+            .unwrap_or(QuoteMode::Long)
+    }
+
+    /// Whether a quote of `length` characters is offered in this mode
+    fn contains(&self, length: usize) -> bool {
+        let (min, max) = self.length_range();
+        length >= min && length <= max
+    }
+
+    /// Name stored in `TestResult.mode`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuoteMode::Short => "short",
+            QuoteMode::Medium => "medium",
+            QuoteMode::Long => "long",
+            QuoteMode::Zen => "zen",
+            QuoteMode::Continuous => "continuous",
+            QuoteMode::Practice => "practice",
+            // Results are saved per language, see `CodeLanguage::mode_name`
+            QuoteMode::Code => "code",
+            // Saved per key set, see `DrillSet::mode_name`
+            QuoteMode::Drill => "drill",
+        }
+    }
+}
+
+impl FromStr for QuoteMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "short" => Ok(QuoteMode::Short),
+            "medium" => Ok(QuoteMode::Medium),
+            "long" => Ok(QuoteMode::Long),
+            "zen" => Ok(QuoteMode::Zen),
+            "continuous" => Ok(QuoteMode::Continuous),
+            "practice" => Ok(QuoteMode::Practice),
+            "code" => Ok(QuoteMode::Code),
+            "drill" => Ok(QuoteMode::Drill),
+            _ => Err(format!(
+                "unknown mode `{}` (expected short, medium, long, zen, continuous, practice, code or drill)",
+                s
+            )),
+        }
+    }
+}
+
+/// Language of the snippets in code mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodeLanguage {
+    Rust,
+    Python,
+    JavaScript,
+}
+
+impl CodeLanguage {
+    const ALL: [CodeLanguage; 3] = [
+        CodeLanguage::Rust,
+        CodeLanguage::Python,
+        CodeLanguage::JavaScript,
+    ];
+
+    /// Parse the `code_language` config value, falling back to Rust
+    pub fn from_config(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.as_str().eq_ignore_ascii_case(value))
+            .unwrap_or(CodeLanguage::Rust)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CodeLanguage::Rust => "rust",
+            CodeLanguage::Python => "python",
+            CodeLanguage::JavaScript => "javascript",
+        }
+    }
+
+    /// Name stored in `TestResult.mode`, e.g. "code_rust"
+    pub fn mode_name(&self) -> &'static str {
+        match self {
+            CodeLanguage::Rust => "code_rust",
+            CodeLanguage::Python => "code_python",
+            CodeLanguage::JavaScript => "code_javascript",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            CodeLanguage::Rust => CodeLanguage::Python,
+            CodeLanguage::Python => CodeLanguage::JavaScript,
+            CodeLanguage::JavaScript => CodeLanguage::Rust,
+        }
+    }
+
+    fn snippets_json(&self) -> &'static str {
+        match self {
+            CodeLanguage::Rust => include_str!("../data/code_rust.json"),
+            CodeLanguage::Python => include_str!("../data/code_python.json"),
+            CodeLanguage::JavaScript => include_str!("../data/code_javascript.json"),
+        }
+    }
+}
+
+/// Highest quote difficulty level
+pub const MAX_DIFFICULTY: u8 = 5;
+/// Scores at which levels 2 to 5 start, set so the built-in quotes spread
+/// over all five levels
+const DIFFICULTY_STEPS: [f64; 4] = [0.35, 0.5, 0.65, 0.85];
+
+/// Which quotes are offered, by their difficulty level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteDifficulty {
+    /// Levels 1 and 2
+    Easy,
+    /// Up to level 3
+    Normal,
+    /// Every quote
+    Hard,
+}
+
+impl QuoteDifficulty {
+    /// Parse the `max_quote_difficulty` config value, falling back to every quote
+    pub fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "easy" => QuoteDifficulty::Easy,
+            "normal" => QuoteDifficulty::Normal,
+            _ => QuoteDifficulty::Hard,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuoteDifficulty::Easy => "easy",
+            QuoteDifficulty::Normal => "normal",
+            QuoteDifficulty::Hard => "hard",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            QuoteDifficulty::Easy => QuoteDifficulty::Normal,
+            QuoteDifficulty::Normal => QuoteDifficulty::Hard,
+            QuoteDifficulty::Hard => QuoteDifficulty::Easy,
+        }
+    }
+
+    /// Highest difficulty level offered
+    pub fn max_level(&self) -> u8 {
+        match self {
+            QuoteDifficulty::Easy => 2,
+            QuoteDifficulty::Normal => 3,
+            QuoteDifficulty::Hard => MAX_DIFFICULTY,
         }
     }
 }
 
+/// How awkward `text` is to type; about 0.5 for everyday prose. Punctuation
+/// and capitals count most since each one is a reach or a shift, then long
+/// words, then characters outside plain letters, spaces, commas, periods
+/// and apostrophes.
+pub fn difficulty_score(text: &str) -> f64 {
+    let non_space = text.chars().filter(|c| !c.is_whitespace()).count();
+    if non_space == 0 {
+        return 0.0;
+    }
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count().max(1);
+    let punctuation = text
+        .chars()
+        .filter(|c| !c.is_alphanumeric() && !c.is_whitespace())
+        .count();
+    let capitals = text.chars().filter(|c| c.is_uppercase()).count();
+    let rare = text
+        .chars()
+        .filter(|c| !(c.is_ascii_alphabetic() || matches!(c, ' ' | ',' | '.' | '\'')))
+        .count();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let avg_word_len = words
+        .iter()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .chars()
+                .count()
+        })
+        .sum::<usize>() as f64
+        / words.len() as f64;
+
+    punctuation as f64 / non_space as f64 * 6.0
+        + capitals as f64 / letters as f64 * 4.0
+        + (avg_word_len - 4.0).max(0.0) * 0.5
+        + rare as f64 / text.chars().count() as f64 * 10.0
+}
+
+/// `difficulty_score` as a level from 1 to `MAX_DIFFICULTY`
+pub fn difficulty_level(text: &str) -> u8 {
+    let score = difficulty_score(text);
+    1 + DIFFICULTY_STEPS
+        .iter()
+        .filter(|&&step| score >= step)
+        .count() as u8
+}
+
+/// Keys a drill makes its words from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrillSet {
+    /// Where the fingers rest, e.g. "asdf jkl;"
+    HomeRow,
+    LeftHand,
+    RightHand,
+    TopRow,
+    /// The keys in the `drill_keys` config value
+    Custom,
+}
+
+impl DrillSet {
+    const ALL: [DrillSet; 5] = [
+        DrillSet::HomeRow,
+        DrillSet::LeftHand,
+        DrillSet::RightHand,
+        DrillSet::TopRow,
+        DrillSet::Custom,
+    ];
+
+    /// Parse the `drill_set` config value, falling back to the home row
+    pub fn from_config(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|set| set.as_str().eq_ignore_ascii_case(value))
+            .unwrap_or(DrillSet::HomeRow)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DrillSet::HomeRow => "homerow",
+            DrillSet::LeftHand => "lefthand",
+            DrillSet::RightHand => "righthand",
+            DrillSet::TopRow => "toprow",
+            DrillSet::Custom => "custom",
+        }
+    }
+
+    /// Name stored in `TestResult.mode`, e.g. "drill_homerow"
+    pub fn mode_name(&self) -> &'static str {
+        match self {
+            DrillSet::HomeRow => "drill_homerow",
+            DrillSet::LeftHand => "drill_lefthand",
+            DrillSet::RightHand => "drill_righthand",
+            DrillSet::TopRow => "drill_toprow",
+            DrillSet::Custom => "drill_custom",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|set| set == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// The distinct keys in the set on `layout`, lowercase. `custom` is the
+    /// `drill_keys` value; whitespace in it is ignored.
+    pub fn keys(&self, layout: &KeyboardLayout, custom: &str) -> Vec<char> {
+        let mut keys = match self {
+            DrillSet::HomeRow => layout.home_row().to_vec(),
+            DrillSet::LeftHand => layout.hand_letters(Hand::Left),
+            DrillSet::RightHand => layout.hand_letters(Hand::Right),
+            DrillSet::TopRow => layout.row_letters(1),
+            DrillSet::Custom => custom
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .flat_map(char::to_lowercase)
+                .collect(),
+        };
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+}
+
 const QUOTES_JSON: &str = include_str!("../data/english.json");
 
+/// Name of the quotes compiled into the binary
+pub const BUILTIN_LANGUAGE: &str = "english";
+
+/// Quotes served this recently aren't picked again while others are left
+const REPEAT_WINDOW: usize = 10;
+
+/// Random draws made for a quote that is easy enough and not served lately
+/// before relaxing the rules
+const PICK_ATTEMPTS: usize = 32;
+
 pub struct QuoteManager {
-    quotes: Vec<Quote>,
+    /// Quotes offered for tests; an ASCII-only view of `all_quotes` when
+    /// enabled, otherwise the same allocation
+    quotes: Arc<[Quote]>,
+    all_quotes: Arc<[Quote]>,
+    /// Positions in `quotes` for each of `QuoteMode::LENGTH_GROUPS`, so a
+    /// random pick doesn't scan the whole pool
+    by_length: [Vec<usize>; 3],
+    ascii_only: bool,
+    language: String,
+    /// Where `<language>.json` packs beyond the built-in one are found
+    languages_dir: Option<PathBuf>,
+    ratings: HashMap<String, u8>,
+    rating_weight: f64,
+    snippets: HashMap<CodeLanguage, Vec<Quote>>,
+    /// Ids of the latest quotes served by `next_quote`, oldest first
+    recent: VecDeque<usize>,
+    /// Position in `quotes` picked ahead by `peek_next`, with its mode
+    reserved: Option<(QuoteMode, usize)>,
 }
 
 impl QuoteManager {
     pub fn new() -> Result<Self> {
-        let file: MonkeyTypeFile = serde_json::from_str(QUOTES_JSON)?;
-        Ok(Self {
-            quotes: file.quotes,
-        })
+        Self::with_language(None, BUILTIN_LANGUAGE)
+    }
+
+    /// Start on `language`, read from `languages_dir` unless it's the
+    /// built-in one. Only that language is parsed; other packs are read
+    /// when `load_language` selects them.
+    pub fn with_language(languages_dir: Option<PathBuf>, language: &str) -> Result<Self> {
+        let quotes: Arc<[Quote]> = read_language(languages_dir.as_deref(), language)?.into();
+        let mut manager = Self {
+            quotes: quotes.clone(),
+            all_quotes: quotes,
+            by_length: Default::default(),
+            ascii_only: false,
+            language: language.to_string(),
+            languages_dir,
+            ratings: HashMap::new(),
+            rating_weight: 1.0,
+            snippets: CodeLanguage::ALL
+                .into_iter()
+                .map(|lang| {
+                    let snippets = parse_snippets(lang.snippets_json()).with_context(|| {
+                        format!("Failed to parse the built-in {} snippets", lang.as_str())
+                    })?;
+                    Ok((lang, snippets))
+                })
+                .collect::<Result<_>>()?,
+            recent: VecDeque::new(),
+            reserved: None,
+        };
+        manager.index_lengths();
+        Ok(manager)
+    }
+
+    /// A random code snippet; newlines and indentation are part of the text
+    pub fn get_random_snippet(&self, language: CodeLanguage) -> Option<&Quote> {
+        self.snippets.get(&language)?.choose(&mut rand::rng())
+    }
+
+    /// Look for language packs in `dir`, in MonkeyType's quote file format
+    pub fn set_languages_dir(&mut self, dir: PathBuf) {
+        self.languages_dir = Some(dir);
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn is_builtin(&self) -> bool {
+        self.language == BUILTIN_LANGUAGE
+    }
+
+    /// The built-in language followed by the packs in the languages dir, by name
+    pub fn available_languages(&self) -> Vec<String> {
+        let mut packs: Vec<String> = self
+            .languages_dir
+            .as_ref()
+            .and_then(|dir| fs::read_dir(dir).ok())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .filter(|name| name != BUILTIN_LANGUAGE)
+            .collect();
+        packs.sort();
+        packs.insert(0, BUILTIN_LANGUAGE.to_string());
+        packs
+    }
+
+    /// Switch the quote pool to `name`. On error the current language stays.
+    pub fn load_language(&mut self, name: &str) -> Result<()> {
+        self.all_quotes = read_language(self.languages_dir.as_deref(), name)?.into();
+        self.language = name.to_string();
+        self.set_ascii_only(self.ascii_only);
+        Ok(())
+    }
+
+    /// Restrict quotes to plain ASCII. Typographic punctuation is straightened
+    /// first; quotes with anything else left (accents, CJK, ...) are skipped.
+    /// Hashes stay those of the original text so ratings still apply.
+    pub fn set_ascii_only(&mut self, ascii_only: bool) {
+        self.ascii_only = ascii_only;
+        self.quotes = if ascii_only {
+            self.all_quotes
+                .iter()
+                .filter_map(|quote| {
+                    let text = to_ascii(&quote.text);
+                    text.is_ascii().then(|| Quote {
+                        length: text.chars().count(),
+                        difficulty: difficulty_level(&text),
+                        text,
+                        source: to_ascii(&quote.source),
+                        ..quote.clone()
+                    })
+                })
+                .collect()
+        } else {
+            self.all_quotes.clone()
+        };
+        self.index_lengths();
+    }
+
+    /// Rebuild `by_length` after `quotes` changed
+    fn index_lengths(&mut self) {
+        self.reserved = None;
+        self.by_length = QuoteMode::LENGTH_GROUPS.map(|mode| {
+            self.quotes
+                .iter()
+                .enumerate()
+                .filter(|(_, q)| mode.contains(q.length))
+                .map(|(i, _)| i)
+                .collect()
+        });
+    }
+
+    /// Positions of the quotes offered in `mode`; empty for modes that
+    /// don't draw from the quote pool
+    fn indices(&self, mode: QuoteMode) -> &[usize] {
+        QuoteMode::LENGTH_GROUPS
+            .iter()
+            .position(|group| *group == mode)
+            .map_or(&[], |group| &self.by_length[group])
+    }
+
+    /// Replace the known ratings (quote hash -> 1..=5 stars)
+    pub fn set_ratings(&mut self, ratings: HashMap<String, u8>) {
+        self.ratings = ratings;
+    }
+
+    pub fn set_rating(&mut self, hash: &str, rating: u8) {
+        self.ratings.insert(hash.to_string(), rating);
+    }
+
+    pub fn rating(&self, hash: &str) -> Option<u8> {
+        self.ratings.get(hash).copied()
+    }
+
+    /// How strongly ratings bias selection. Each star above or below 3 multiplies
+    /// or divides a quote's weight by this factor; 1.0 ignores ratings.
+    pub fn set_rating_weight(&mut self, weight: f64) {
+        self.rating_weight = weight.max(1.0);
     }
 
     pub fn get_random_quote(&self, mode: QuoteMode) -> Option<&Quote> {
-        let (min, max) = mode.length_range();
+        self.pick(self.indices(mode))
+    }
 
-        let filtered: Vec<&Quote> = self
-            .quotes
+    /// A random quote for `mode` no harder than `max_difficulty`
+    pub fn get_random_quote_filtered(&self, mode: QuoteMode, max_difficulty: u8) -> Option<&Quote> {
+        if max_difficulty >= MAX_DIFFICULTY {
+            return self.get_random_quote(mode);
+        }
+        let indices: Vec<usize> = self
+            .indices(mode)
             .iter()
-            .filter(|q| q.length >= min && q.length < max)
+            .copied()
+            .filter(|&i| self.quotes[i].difficulty <= max_difficulty)
             .collect();
+        self.pick(&indices)
+    }
+
+    /// The quote `next_quote` will serve for `mode`, picked now and held
+    /// until then so it can be previewed
+    pub fn peek_next(&mut self, mode: QuoteMode, max_difficulty: u8) -> Option<&Quote> {
+        let index = match self.reserved {
+            Some((reserved_mode, index))
+                if reserved_mode == mode && self.quotes[index].difficulty <= max_difficulty =>
+            {
+                index
+            }
+            _ => {
+                let index = self.choose(mode, max_difficulty)?;
+                self.reserved = Some((mode, index));
+                index
+            }
+        };
+        self.quotes.get(index)
+    }
+
+    /// A random quote for `mode` like `get_random_quote_filtered`, falling
+    /// back to any length-matched quote when none is easy enough. Takes the
+    /// quote `peek_next` reserved if it still fits, and otherwise avoids
+    /// the last few served.
+    pub fn next_quote(&mut self, mode: QuoteMode, max_difficulty: u8) -> Option<&Quote> {
+        let index = match self.reserved.take() {
+            Some((reserved_mode, index))
+                if reserved_mode == mode && self.quotes[index].difficulty <= max_difficulty =>
+            {
+                index
+            }
+            _ => self.choose(mode, max_difficulty)?,
+        };
+        let id = self.quotes[index].id;
+        self.recent.retain(|&recent| recent != id);
+        self.recent.push_back(id);
+        if self.recent.len() > REPEAT_WINDOW {
+            self.recent.pop_front();
+        }
+        self.quotes.get(index)
+    }
+
+    /// Position of a random quote for `mode`, drawn from the whole group and
+    /// redrawn while it is harder than `max_difficulty` or served lately.
+    /// After `PICK_ATTEMPTS` tries a repeat is allowed, then a harder quote.
+    fn choose(&self, mode: QuoteMode, max_difficulty: u8) -> Option<usize> {
+        let all = self.indices(mode);
+        for allow_recent in [false, true] {
+            for _ in 0..PICK_ATTEMPTS {
+                let index = self.pick_index(all)?;
+                let quote = &self.quotes[index];
+                if quote.difficulty <= max_difficulty
+                    && (allow_recent || !self.recent.contains(&quote.id))
+                {
+                    return Some(index);
+                }
+            }
+        }
+        self.pick_index(all)
+    }
 
-        filtered.choose(&mut rand::rng()).copied()
+    /// One of the quotes at `indices`, weighted by rating when that's on
+    fn pick(&self, indices: &[usize]) -> Option<&Quote> {
+        self.quotes.get(self.pick_index(indices)?)
+    }
+
+    fn pick_index(&self, indices: &[usize]) -> Option<usize> {
+        let mut rng = rand::rng();
+        let index = if self.rating_weight > 1.0 && !self.ratings.is_empty() {
+            indices
+                .choose_weighted(&mut rng, |&i| self.selection_weight(&self.quotes[i]))
+                .ok()?
+        } else {
+            indices.choose(&mut rng)?
+        };
+        Some(*index)
+    }
+
+    fn selection_weight(&self, quote: &Quote) -> f64 {
+        let stars = self.rating(&quote.hash).unwrap_or(3) as i32;
+        self.rating_weight.powi(stars - 3)
     }
 
-    #[allow(dead_code)]
     pub fn get_quote_by_id(&self, id: usize) -> Option<&Quote> {
         self.quotes.iter().find(|q| q.id == id)
     }
 
-    #[allow(dead_code)]
-    pub fn count_by_mode(&self, mode: QuoteMode) -> usize {
-        let (min, max) = mode.length_range();
+    /// Every quote offered for tests
+    pub fn quotes(&self) -> &[Quote] {
+        &self.quotes
+    }
+
+    /// Quotes whose text or source contains `query`, ignoring case; all
+    /// quotes for an empty query
+    pub fn search(&self, query: &str) -> Vec<&Quote> {
+        let query = query.to_lowercase();
         self.quotes
             .iter()
-            .filter(|q| q.length >= min && q.length < max)
-            .count()
+            .filter(|q| {
+                query.is_empty()
+                    || q.text.to_lowercase().contains(&query)
+                    || q.source.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    pub fn count_by_mode(&self, mode: QuoteMode) -> usize {
+        self.indices(mode).len()
     }
 }
 
@@ -80,3 +664,386 @@ impl Default for QuoteManager {
         Self::new().expect("Failed to load quotes")
     }
 }
+
+/// The quotes of language `name`: the built-in ones, or `<name>.json` in
+/// `languages_dir`
+fn read_language(languages_dir: Option<&Path>, name: &str) -> Result<Vec<Quote>> {
+    if name == BUILTIN_LANGUAGE {
+        return parse_quotes(QUOTES_JSON)
+            .context("Failed to parse the built-in quotes (data/english.json)");
+    }
+    let dir = languages_dir.context("No language pack directory")?;
+    let path = dir.join(format!("{}.json", name));
+    let json =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let quotes =
+        parse_quotes(&json).with_context(|| format!("Failed to parse {}", path.display()))?;
+    if quotes.is_empty() {
+        anyhow::bail!("{} contains no quotes", path.display());
+    }
+    Ok(quotes)
+}
+
+/// Quotes from a MonkeyType quote file, with their hashes filled in. Tabs,
+/// line breaks and repeated spaces become single spaces, since prose modes
+/// have no way to type them.
+fn parse_quotes(json: &str) -> Result<Vec<Quote>> {
+    parse_file(json, collapse_whitespace)
+}
+
+/// Code snippets, in the same format; their layout is part of the text
+fn parse_snippets(json: &str) -> Result<Vec<Quote>> {
+    parse_file(json, str::to_string)
+}
+
+fn parse_file(json: &str, normalize: fn(&str) -> String) -> Result<Vec<Quote>> {
+    let mut file: MonkeyTypeFile = serde_json::from_str(json)?;
+    for (i, quote) in file.quotes.iter_mut().enumerate() {
+        let text = normalize(&quote.text);
+        // A length given in the file counts the text before normalizing
+        if text != quote.text {
+            quote.text = text;
+            quote.length = 0;
+        }
+        quote.hash = quote_hash(&quote.text);
+        quote.difficulty = difficulty_level(&quote.text);
+        if quote.length == 0 {
+            quote.length = quote.text.chars().count();
+        }
+        if quote.id == 0 {
+            quote.id = i + 1;
+        }
+    }
+    Ok(file.quotes)
+}
+
+/// `text` with each run of whitespace turned into one space and none at
+/// either end
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Replace typographic punctuation with its ASCII equivalent
+fn to_ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '‘' | '’' | '‚' | '′' => out.push('\''),
+            '“' | '”' | '„' | '″' => out.push('"'),
+            '—' | '–' | '‒' | '−' => out.push('-'),
+            '…' => out.push_str("..."),
+            '\u{00a0}' | '\u{2009}' | '\u{202f}' => out.push(' '),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// FNV-1a hash of the quote text, stable across builds and platforms
+pub fn quote_hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Longest chunk a `--file` or `--text` text is split into
+pub const FILE_CHUNK_CHARS: usize = 300;
+
+/// Splits a long text into quote-sized chunks to type one after another
+#[derive(Debug, Clone, Copy)]
+pub struct TextChunker {
+    max_chars: usize,
+}
+
+impl TextChunker {
+    pub fn new(max_chars: usize) -> Self {
+        Self { max_chars }
+    }
+
+    /// Whole sentences packed into chunks of at most `max_chars`. Line
+    /// breaks (Windows ones too) and blank lines become single spaces. A
+    /// sentence longer than a chunk is split between words; words are never
+    /// split, so a single word longer than a chunk gets one to itself.
+    pub fn chunks(&self, text: &str) -> Vec<String> {
+        let mut sentences: Vec<Vec<&str>> = Vec::new();
+        let mut sentence = Vec::new();
+        for word in text.split_whitespace() {
+            sentence.push(word);
+            if ends_sentence(word) {
+                sentences.push(std::mem::take(&mut sentence));
+            }
+        }
+        if !sentence.is_empty() {
+            sentences.push(sentence);
+        }
+
+        let mut chunks = Vec::new();
+        let mut chunk = String::new();
+        let mut push = |chunk: &mut String, text: &str, max_chars: usize| {
+            let len = text.chars().count();
+            if !chunk.is_empty() && chunk.chars().count() + 1 + len > max_chars {
+                chunks.push(std::mem::take(chunk));
+            }
+            if !chunk.is_empty() {
+                chunk.push(' ');
+            }
+            chunk.push_str(text);
+        };
+        for sentence in &sentences {
+            let joined = sentence.join(" ");
+            if joined.chars().count() <= self.max_chars {
+                push(&mut chunk, &joined, self.max_chars);
+            } else {
+                for word in sentence {
+                    push(&mut chunk, word, self.max_chars);
+                }
+            }
+        }
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+}
+
+/// A word that ends a sentence, closing quotes and brackets aside
+fn ends_sentence(word: &str) -> bool {
+    word.trim_end_matches(['"', '\'', '”', '’', ')'])
+        .ends_with(['.', '!', '?'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Positions of the quotes in `mode`'s length group, found the slow way
+    fn scan(manager: &QuoteManager, mode: QuoteMode) -> Vec<usize> {
+        manager
+            .quotes
+            .iter()
+            .enumerate()
+            .filter(|(_, quote)| QuoteMode::for_length(quote.length) == mode)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[test]
+    fn length_index_matches_a_linear_scan() {
+        let mut manager = QuoteManager::new().unwrap();
+        for ascii_only in [false, true] {
+            manager.set_ascii_only(ascii_only);
+            let mut indexed = 0;
+            for mode in QuoteMode::LENGTH_GROUPS {
+                assert_eq!(manager.indices(mode), scan(&manager, mode), "{:?}", mode);
+                indexed += manager.indices(mode).len();
+            }
+            // Every quote is in exactly one group
+            assert_eq!(indexed, manager.quotes.len());
+        }
+        for mode in [QuoteMode::Zen, QuoteMode::Code, QuoteMode::Drill] {
+            assert!(manager.indices(mode).is_empty());
+        }
+    }
+
+    #[test]
+    fn length_groups_meet_without_gaps() {
+        for (length, mode) in [
+            (0, QuoteMode::Short),
+            (100, QuoteMode::Short),
+            (101, QuoteMode::Medium),
+            (300, QuoteMode::Medium),
+            (301, QuoteMode::Long),
+        ] {
+            assert_eq!(QuoteMode::for_length(length), mode, "{}", length);
+            assert!(mode.contains(length), "{} not in {:?}", length, mode);
+        }
+    }
+
+    #[test]
+    fn difficulty_scores_are_pinned() {
+        for (text, score, level) in [
+            ("the cat sat on the mat", 0.0, 1),
+            ("The quick brown fox jumps over the lazy dog.", 0.2810, 1),
+            (
+                "Life is what happens when you are busy making other plans, they say.",
+                0.3653,
+                2,
+            ),
+            ("It is what it is, and that is that.", 0.6044, 3),
+            (
+                "If you want to go fast, go alone; if you want to go far, go together.",
+                0.6694,
+                4,
+            ),
+            ("Hello, World.", 2.3, 5),
+            ("It's 9:45; \"Why?\" she asked.", 5.1786, 5),
+            ("fn main() { x += 1; }", 6.6095, 5),
+        ] {
+            let actual = difficulty_score(text);
+            assert!(
+                (actual - score).abs() < 1e-4,
+                "{:?} scored {}",
+                text,
+                actual
+            );
+            assert_eq!(difficulty_level(text), level, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn blank_text_is_easiest() {
+        assert_eq!(difficulty_score(""), 0.0);
+        assert_eq!(difficulty_score("   \n"), 0.0);
+        assert_eq!(difficulty_level(""), 1);
+    }
+
+    #[test]
+    fn filtered_quotes_stay_under_the_limit() {
+        let manager = QuoteManager::new().unwrap();
+        for max in 1..=MAX_DIFFICULTY {
+            for _ in 0..50 {
+                if let Some(quote) = manager.get_random_quote_filtered(QuoteMode::Medium, max) {
+                    assert!(quote.difficulty <= max, "{} over {}", quote.difficulty, max);
+                    assert_eq!(quote.difficulty, difficulty_level(&quote.text));
+                }
+            }
+        }
+        assert!(manager
+            .get_random_quote_filtered(QuoteMode::Medium, MAX_DIFFICULTY)
+            .is_some());
+    }
+
+    #[test]
+    fn whitespace_collapses_to_single_spaces() {
+        for (text, expected) in [
+            ("plain words", "plain words"),
+            ("two  spaces", "two spaces"),
+            ("a\ttab", "a tab"),
+            ("line\nbreak\r\nand crlf", "line break and crlf"),
+            ("  padded \t ", "padded"),
+            ("mixed \t\n  run", "mixed run"),
+            ("no\u{00a0}break", "no break"),
+            ("", ""),
+            (" \t\n", ""),
+        ] {
+            assert_eq!(collapse_whitespace(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn prose_is_normalized_on_load_but_code_is_not() {
+        let json = r#"{"quotes": [
+            {"text": "Tabs\tand  doubled spaces", "source": "pack", "length": 25, "id": 7},
+            {"text": "Already clean", "source": "pack", "length": 13}
+        ]}"#;
+        let quotes = parse_quotes(json).unwrap();
+        assert_eq!(quotes[0].text, "Tabs and doubled spaces");
+        // The given length described the text before normalizing
+        assert_eq!(quotes[0].length, 23);
+        assert_eq!(quotes[0].hash, quote_hash("Tabs and doubled spaces"));
+        assert_eq!(quotes[0].id, 7);
+        assert_eq!(quotes[1].text, "Already clean");
+        assert_eq!(quotes[1].id, 2);
+
+        let snippets = parse_snippets(json).unwrap();
+        assert_eq!(snippets[0].text, "Tabs\tand  doubled spaces");
+        assert_eq!(snippets[0].length, 25);
+    }
+
+    const MODES: [QuoteMode; 8] = [
+        QuoteMode::Short,
+        QuoteMode::Medium,
+        QuoteMode::Long,
+        QuoteMode::Zen,
+        QuoteMode::Continuous,
+        QuoteMode::Practice,
+        QuoteMode::Code,
+        QuoteMode::Drill,
+    ];
+
+    #[test]
+    fn mode_names_round_trip() {
+        for mode in MODES {
+            assert_eq!(mode.as_str().parse::<QuoteMode>(), Ok(mode));
+            assert_eq!(mode.as_str().to_uppercase().parse::<QuoteMode>(), Ok(mode));
+        }
+        let names: HashSet<&str> = MODES.iter().map(|mode| mode.as_str()).collect();
+        assert_eq!(names.len(), MODES.len());
+    }
+
+    #[test]
+    fn unknown_mode_names_are_errors() {
+        for name in ["", "quote", "short ", "code_rust"] {
+            let err = name.parse::<QuoteMode>().unwrap_err();
+            assert!(
+                err.starts_with(&format!("unknown mode `{}`", name)),
+                "{}",
+                err
+            );
+        }
+    }
+
+    /// How many of `draws` random short quotes are `hash`
+    fn draws_of(manager: &QuoteManager, hash: &str, draws: usize) -> usize {
+        (0..draws)
+            .filter(|_| manager.get_random_quote(QuoteMode::Short).unwrap().hash == hash)
+            .count()
+    }
+
+    #[test]
+    fn rating_weight_scales_by_stars_from_three() {
+        let mut manager = QuoteManager::new().unwrap();
+        let quote = manager.quotes[manager.indices(QuoteMode::Short)[0]].clone();
+        manager.set_rating_weight(2.0);
+        assert_eq!(manager.selection_weight(&quote), 1.0);
+        for (stars, weight) in [(1, 0.25), (2, 0.5), (3, 1.0), (4, 2.0), (5, 4.0)] {
+            manager.set_ratings(HashMap::from([(quote.hash.clone(), stars)]));
+            assert_eq!(manager.selection_weight(&quote), weight, "{} stars", stars);
+        }
+        // Weights below 1 would invert the ratings
+        manager.set_rating_weight(0.1);
+        assert_eq!(manager.selection_weight(&quote), 1.0);
+    }
+
+    #[test]
+    fn extreme_rating_weights_shift_the_selection() {
+        let mut manager = QuoteManager::new().unwrap();
+        let short = manager.indices(QuoteMode::Short).to_vec();
+        assert!(short.len() > 20);
+        let loved = manager.quotes[short[0]].hash.clone();
+        let hated = manager.quotes[short[1]].hash.clone();
+        manager.set_ratings(HashMap::from([(loved.clone(), 5), (hated.clone(), 1)]));
+
+        // Ignored at 1.0: the loved quote comes up about as often as any other
+        manager.set_rating_weight(1.0);
+        assert!(draws_of(&manager, &loved, 1000) < 200);
+
+        // 1000^2 times as likely as an unrated quote, and the hated one
+        // 1000^2 times less
+        manager.set_rating_weight(1000.0);
+        assert!(draws_of(&manager, &loved, 1000) > 900);
+        assert_eq!(draws_of(&manager, &hated, 1000), 0);
+    }
+
+    #[test]
+    fn next_quote_skips_recent_and_hard_quotes() {
+        let mut manager = QuoteManager::new().unwrap();
+        let mut seen = HashSet::new();
+        for _ in 0..REPEAT_WINDOW {
+            let quote = manager.next_quote(QuoteMode::Medium, 2).unwrap();
+            assert!(quote.difficulty <= 2);
+            assert!(seen.insert(quote.id), "quote {} repeated", quote.id);
+        }
+    }
+
+    #[test]
+    fn next_quote_falls_back_when_every_quote_is_harder() {
+        // Difficulty starts at 1, so no quote is easy enough
+        let mut manager = QuoteManager::new().unwrap();
+        assert!(manager.next_quote(QuoteMode::Short, 0).is_some());
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::{Duration, Instant};
+
+/// Timed jobs driven by the event loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    WpmRecalc,
+    /// One frame of the WPM counter easing towards the live value
+    WpmAnimation,
+    CursorBlink,
+    PressedKeyFade,
+    Screensaver,
+    AfkCheck,
+    ErrorFlash,
+    /// One frame of a replay being played back
+    ReplayFrame,
+    /// The status line message on screen runs out
+    StatusExpiry,
+    /// The last auto-advanced run leaves the header
+    AdvanceFlash,
+    /// Long attributions scroll one cell further
+    MarqueeStep,
+}
+
+#[derive(Debug, Clone)]
+struct Timer {
+    task: Task,
+    deadline: Instant,
+    period: Option<Duration>,
+}
+
+/// Tracks periodic and one-shot timers so the event loop can sleep exactly
+/// until the next one is due. Every method takes `now` explicitly, which keeps
+/// the scheduler independent of the wall clock.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    timers: Vec<Timer>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self { timers: Vec::new() }
+    }
+
+    /// Run `task` every `period`, starting one period from `now`.
+    /// Replaces any existing timer for the same task.
+    pub fn every(&mut self, task: Task, period: Duration, now: Instant) {
+        self.insert(Timer {
+            task,
+            deadline: now + period,
+            period: Some(period),
+        });
+    }
+
+    /// Run `task` once after `delay`. Replaces any existing timer for the same task.
+    pub fn once(&mut self, task: Task, delay: Duration, now: Instant) {
+        self.insert(Timer {
+            task,
+            deadline: now + delay,
+            period: None,
+        });
+    }
+
+    pub fn cancel(&mut self, task: Task) {
+        self.timers.retain(|t| t.task != task);
+    }
+
+    pub fn is_scheduled(&self, task: Task) -> bool {
+        self.timers.iter().any(|t| t.task == task)
+    }
+
+    /// Collect every task due at `now`. One-shots are removed, periodic timers
+    /// are re-armed; a timer that fell behind fires once rather than catching up.
+    pub fn due(&mut self, now: Instant) -> Vec<Task> {
+        let mut fired = Vec::new();
+        self.timers.retain_mut(|timer| {
+            if timer.deadline > now {
+                return true;
+            }
+            fired.push(timer.task);
+            match timer.period {
+                Some(period) => {
+                    timer.deadline += period;
+                    if timer.deadline <= now {
+                        timer.deadline = now + period;
+                    }
+                    true
+                }
+                None => false,
+            }
+        });
+        fired
+    }
+
+    /// Earliest pending deadline, or `None` when nothing is scheduled
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.timers.iter().map(|t| t.deadline).min()
+    }
+
+    fn insert(&mut self, timer: Timer) {
+        self.cancel(timer.task);
+        self.timers.push(timer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn periodic_and_one_shot_timers_fire_on_a_mock_clock() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        assert_eq!(scheduler.next_deadline(), None);
+        scheduler.every(Task::WpmRecalc, ms(250), start);
+        scheduler.every(Task::CursorBlink, ms(500), start);
+        scheduler.once(Task::ErrorFlash, ms(100), start);
+        assert_eq!(scheduler.next_deadline(), Some(start + ms(100)));
+
+        assert!(scheduler.due(start + ms(99)).is_empty());
+        assert_eq!(scheduler.due(start + ms(100)), [Task::ErrorFlash]);
+        assert!(!scheduler.is_scheduled(Task::ErrorFlash));
+        assert_eq!(scheduler.next_deadline(), Some(start + ms(250)));
+
+        assert_eq!(scheduler.due(start + ms(250)), [Task::WpmRecalc]);
+        assert_eq!(
+            scheduler.due(start + ms(500)),
+            [Task::WpmRecalc, Task::CursorBlink]
+        );
+        assert_eq!(scheduler.next_deadline(), Some(start + ms(750)));
+    }
+
+    #[test]
+    fn a_late_periodic_timer_fires_once_and_rearms_from_now() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.every(Task::WpmRecalc, ms(250), start);
+        let late = start + ms(1_100);
+        assert_eq!(scheduler.due(late), [Task::WpmRecalc]);
+        assert_eq!(scheduler.next_deadline(), Some(late + ms(250)));
+    }
+
+    #[test]
+    fn rescheduling_replaces_and_cancel_removes() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.once(Task::StatusExpiry, ms(100), start);
+        scheduler.once(Task::StatusExpiry, ms(300), start);
+        assert!(scheduler.due(start + ms(100)).is_empty());
+        assert_eq!(scheduler.due(start + ms(300)), [Task::StatusExpiry]);
+
+        scheduler.every(Task::MarqueeStep, ms(300), start);
+        assert!(scheduler.is_scheduled(Task::MarqueeStep));
+        scheduler.cancel(Task::MarqueeStep);
+        assert!(!scheduler.is_scheduled(Task::MarqueeStep));
+        assert_eq!(scheduler.next_deadline(), None);
+    }
+}
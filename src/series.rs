@@ -0,0 +1,82 @@
+use crate::models::{SourceSummary, TestResult};
+
+/// Chunks of a `--file` or `--text` text typed one after another, with
+/// totals carried across them for a summary after the last one
+#[derive(Debug, Clone)]
+pub struct SessionSeries {
+    /// Shown as the quote source and saved with each result
+    title: String,
+    /// Mode the results are saved under
+    mode: &'static str,
+    chunks: Vec<String>,
+    index: usize,
+    tests: i64,
+    wpm_sum: f64,
+    accuracy_sum: f64,
+    duration_seconds: i64,
+}
+
+impl SessionSeries {
+    /// `chunks` must not be empty
+    pub fn new(title: String, mode: &'static str, chunks: Vec<String>) -> Self {
+        Self {
+            title,
+            mode,
+            chunks,
+            index: 0,
+            tests: 0,
+            wpm_sum: 0.0,
+            accuracy_sum: 0.0,
+            duration_seconds: 0,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn mode(&self) -> &'static str {
+        self.mode
+    }
+
+    /// The chunk to type next; empty once every chunk is done
+    pub fn current(&self) -> &str {
+        self.chunks.get(self.index).map_or("", String::as_str)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.chunks.len()
+    }
+
+    /// Footer text, e.g. "article.txt — chunk 3/12"; just the title for a
+    /// text that fits in one chunk
+    pub fn progress_label(&self) -> String {
+        if self.chunks.len() == 1 {
+            return self.title.clone();
+        }
+        format!(
+            "{} — chunk {}/{}",
+            self.title,
+            self.index + 1,
+            self.chunks.len()
+        )
+    }
+
+    /// Add a finished chunk's result to the totals and move to the next
+    /// chunk. After the last one, returns the totals for the whole text.
+    pub fn record(&mut self, result: &TestResult) -> Option<SourceSummary> {
+        self.tests += 1;
+        self.wpm_sum += result.wpm;
+        self.accuracy_sum += result.accuracy;
+        self.duration_seconds += result.duration_seconds;
+        self.index += 1;
+
+        (self.is_finished() && self.chunks.len() > 1).then(|| SourceSummary {
+            title: self.title.clone(),
+            tests: self.tests,
+            avg_wpm: self.wpm_sum / self.tests as f64,
+            avg_accuracy: self.accuracy_sum / self.tests as f64,
+            duration_seconds: self.duration_seconds,
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use std::io;
+
+use anyhow::Context;
+use base64::Engine;
+use crossterm::style::Print;
+
+use crate::models::TestResult;
+
+/// How a copied result reaches the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardMethod {
+    /// The desktop clipboard
+    System,
+    /// An OSC 52 escape sequence, which the terminal puts on its host's
+    /// clipboard; works over SSH in terminals that support it
+    Osc52,
+}
+
+impl ClipboardMethod {
+    pub fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "osc52" => ClipboardMethod::Osc52,
+            _ => ClipboardMethod::System,
+        }
+    }
+}
+
+/// Fill the `share_format` placeholders from `result`: {wpm}, {raw}, {acc},
+/// {consistency}, {mode} and {time} (seconds)
+pub fn share_card(format: &str, result: &TestResult) -> String {
+    format
+        .replace("{wpm}", &format!("{:.1}", result.wpm))
+        .replace("{raw}", &format!("{:.1}", result.raw_wpm))
+        .replace("{acc}", &format!("{:.1}", result.accuracy))
+        .replace(
+            "{consistency}",
+            &result
+                .consistency
+                .map_or("—".to_string(), |c| format!("{:.1}", c)),
+        )
+        .replace("{mode}", &result.mode)
+        .replace("{time}", &result.duration_seconds.to_string())
+}
+
+/// Copies text with the configured method. The system clipboard is opened
+/// once and kept, since on X11 the text is only served while it's alive.
+#[derive(Default)]
+pub struct Clipboard {
+    system: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    pub fn copy(&mut self, text: &str, method: ClipboardMethod) -> anyhow::Result<()> {
+        match method {
+            ClipboardMethod::System => {
+                let clipboard = match &mut self.system {
+                    Some(clipboard) => clipboard,
+                    None => self
+                        .system
+                        .insert(arboard::Clipboard::new().context("No clipboard available")?),
+                };
+                clipboard
+                    .set_text(text)
+                    .context("Failed to copy to the clipboard")
+            }
+            ClipboardMethod::Osc52 => {
+                let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+                crossterm::execute!(io::stdout(), Print(format!("\x1b]52;c;{}\x07", encoded)))
+                    .context("Failed to write to the terminal")
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Key clicks while typing. The audio itself only exists in builds with the
+//! `sound` feature; without it `KeySounds` is silent and costs nothing.
+
+/// Which set of samples plays on each keystroke
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundStyle {
+    Off,
+    /// A short, light tick
+    Click,
+    /// A heavier thud with a bit of ring
+    Typewriter,
+}
+
+impl SoundStyle {
+    pub fn from_config(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "click" => SoundStyle::Click,
+            "typewriter" => SoundStyle::Typewriter,
+            _ => SoundStyle::Off,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SoundStyle::Off => "off",
+            SoundStyle::Click => "click",
+            SoundStyle::Typewriter => "typewriter",
+        }
+    }
+}
+
+/// Plays a sample for each accepted key and a different one for a mistake.
+/// Samples are generated once when the device opens; playing one only hands
+/// it to the mixer on the audio thread, so typing never waits on it.
+#[derive(Default)]
+pub struct KeySounds {
+    #[cfg(feature = "sound")]
+    player: Option<player::Player>,
+}
+
+impl KeySounds {
+    pub fn silent() -> Self {
+        Self::default()
+    }
+
+    /// Open the default output device for `style`. `Off` never touches the
+    /// device; any other style fails in builds without the `sound` feature.
+    #[cfg_attr(not(feature = "sound"), allow(unused_variables))]
+    pub fn open(style: SoundStyle, volume: f64) -> anyhow::Result<Self> {
+        if style == SoundStyle::Off {
+            return Ok(Self::silent());
+        }
+        #[cfg(feature = "sound")]
+        {
+            Ok(Self {
+                player: Some(player::Player::open(style, volume.clamp(0.0, 1.0) as f32)?),
+            })
+        }
+        #[cfg(not(feature = "sound"))]
+        {
+            anyhow::bail!(
+                "sound = \"{}\" needs a build with the `sound` feature",
+                style.as_str()
+            )
+        }
+    }
+
+    pub fn key(&self) {
+        #[cfg(feature = "sound")]
+        if let Some(player) = &self.player {
+            player.play(&player.key);
+        }
+    }
+
+    pub fn error(&self) {
+        #[cfg(feature = "sound")]
+        if let Some(player) = &self.player {
+            player.play(&player.error);
+        }
+    }
+}
+
+#[cfg(feature = "sound")]
+mod player {
+    use anyhow::Context;
+    use rodio::buffer::SamplesBuffer;
+    use rodio::{OutputStream, OutputStreamBuilder, Source};
+
+    use super::SoundStyle;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    pub struct Player {
+        /// Owns the audio thread; the mixer stops when this is dropped
+        stream: OutputStream,
+        pub key: SamplesBuffer,
+        pub error: SamplesBuffer,
+        volume: f32,
+    }
+
+    impl Player {
+        pub fn open(style: SoundStyle, volume: f32) -> anyhow::Result<Self> {
+            let mut stream = OutputStreamBuilder::open_default_stream().with_context(|| {
+                format!(
+                    "Couldn't open the audio device for {} sounds",
+                    style.as_str()
+                )
+            })?;
+            // It would print over the terminal UI on the way out
+            stream.log_on_drop(false);
+            let key = match style {
+                SoundStyle::Typewriter => tone(0.04, 140.0, 0.6, 60.0),
+                _ => tone(0.012, 2_000.0, 0.8, 400.0),
+            };
+            Ok(Self {
+                stream,
+                key,
+                error: tone(0.08, 220.0, 0.0, 30.0),
+                volume,
+            })
+        }
+
+        pub fn play(&self, sample: &SamplesBuffer) {
+            self.stream.mixer().add(sample.clone().amplify(self.volume));
+        }
+    }
+
+    /// A decaying sine at `pitch` Hz mixed with `noise` parts of white
+    /// noise, `seconds` long. `decay` is how fast it dies away.
+    fn tone(seconds: f32, pitch: f32, noise: f32, decay: f32) -> SamplesBuffer {
+        let len = (seconds * SAMPLE_RATE as f32) as usize;
+        // A fixed xorshift keeps every click the same without a RNG per sample
+        let mut state: u32 = 0x9e37_79b9;
+        let samples = (0..len)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let white = state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                let sine = (t * pitch * std::f32::consts::TAU).sin();
+                (sine * (1.0 - noise) + white * noise) * (-t * decay).exp()
+            })
+            .collect();
+        SamplesBuffer::new(1, SAMPLE_RATE, samples)
+    }
+}
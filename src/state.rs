@@ -3,19 +3,34 @@ pub enum AppState {
     Testing,
     Results,
     History,
+    /// History with the selected result's detail pane open
+    HistoryDetail,
     Stats,
+    Heatmap,
+    Settings,
+    /// Searching the quote list for the next test
+    QuotePicker,
+    /// Asking before quitting in the middle of a test
+    QuitConfirm,
+    /// Playing back a finished test's keystrokes
+    Replay,
+    /// Choosing a theme over whatever screen was open, which previews it
+    ThemePicker,
+    /// Switching to another profile, or creating one, over the current screen
+    ProfilePicker,
 }
 
 pub struct StateMachine {
     current: AppState,
-    previous: Option<AppState>,
+    /// States to return to, most recent last
+    previous: Vec<AppState>,
 }
 
 impl StateMachine {
     pub fn new(initial: AppState) -> Self {
         Self {
             current: initial,
-            previous: None,
+            previous: Vec::new(),
         }
     }
 
@@ -23,8 +38,103 @@ impl StateMachine {
         self.current
     }
 
+    /// Move to `new_state`, remembering where we came from. Going to a state
+    /// that is already further back unwinds to it instead, so hopping between
+    /// screens doesn't grow the history.
     pub fn transition(&mut self, new_state: AppState) {
-        self.previous = Some(self.current);
+        if new_state == self.current {
+            return;
+        }
+        if let Some(i) = self.previous.iter().position(|&s| s == new_state) {
+            self.previous.truncate(i);
+        } else {
+            self.previous.push(self.current);
+        }
         self.current = new_state;
     }
+
+    /// Return to the previous state; does nothing at the bottom of the history
+    pub fn go_back(&mut self) -> bool {
+        match self.previous.pop() {
+            Some(state) => {
+                self.current = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The state the current one returns to
+    pub fn previous(&self) -> Option<AppState> {
+        self.previous.last().copied()
+    }
+
+    /// Whether `state` is current or somewhere in the history
+    pub fn is_open(&self, state: AppState) -> bool {
+        self.current == state || self.previous.contains(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn go_back_returns_where_the_user_came_from() {
+        let mut states = StateMachine::new(AppState::Testing);
+        states.transition(AppState::Results);
+        states.transition(AppState::History);
+        assert_eq!(states.previous(), Some(AppState::Results));
+
+        // Esc from History
+        assert!(states.go_back());
+        assert_eq!(states.current(), AppState::Results);
+        assert!(states.go_back());
+        assert_eq!(states.current(), AppState::Testing);
+        // Nothing further back
+        assert!(!states.go_back());
+        assert_eq!(states.current(), AppState::Testing);
+    }
+
+    #[test]
+    fn transition_to_the_current_state_does_nothing() {
+        let mut states = StateMachine::new(AppState::Testing);
+        states.transition(AppState::Stats);
+        states.transition(AppState::Stats);
+        assert_eq!(states.previous(), Some(AppState::Testing));
+        assert!(states.go_back());
+        assert!(!states.go_back());
+    }
+
+    #[test]
+    fn revisiting_a_state_unwinds_to_it() {
+        let mut states = StateMachine::new(AppState::Testing);
+        states.transition(AppState::Results);
+        states.transition(AppState::History);
+        states.transition(AppState::Stats);
+        states.transition(AppState::History);
+        // Stats is gone; History still goes back to Results
+        assert!(!states.is_open(AppState::Stats));
+        assert_eq!(states.previous(), Some(AppState::Results));
+
+        states.transition(AppState::Testing);
+        assert_eq!(states.current(), AppState::Testing);
+        assert_eq!(states.previous(), None);
+        assert!(!states.is_open(AppState::Results));
+    }
+
+    #[test]
+    fn overlays_close_back_to_the_screen_below() {
+        let mut states = StateMachine::new(AppState::Testing);
+        states.transition(AppState::History);
+        states.transition(AppState::HistoryDetail);
+        states.transition(AppState::ThemePicker);
+        assert!(states.is_open(AppState::History));
+        assert!(states.is_open(AppState::HistoryDetail));
+
+        assert!(states.go_back());
+        assert_eq!(states.current(), AppState::HistoryDetail);
+        assert!(states.go_back());
+        assert_eq!(states.current(), AppState::History);
+    }
 }
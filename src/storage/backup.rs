@@ -0,0 +1,57 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Local;
+
+use crate::storage::db::Database;
+
+const PREFIX: &str = "typing-";
+const EXTENSION: &str = "db";
+
+/// Copy the live database to `dir/typing-YYYYMMDD-HHMMSS.db`, then delete
+/// the oldest backups so at most `keep` remain
+pub fn create(db: &Database, dir: &Path, keep: usize) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!(
+        "{}{}.{}",
+        PREFIX,
+        Local::now().format("%Y%m%d-%H%M%S"),
+        EXTENSION
+    ));
+    db.backup_to(&path)
+        .with_context(|| format!("Failed to back up to {}", path.display()))?;
+    prune(dir, keep.max(1))?;
+    Ok(path)
+}
+
+/// The most recent backup in `dir`, if any
+pub fn latest(dir: &Path) -> Option<PathBuf> {
+    list(dir).ok()?.pop()
+}
+
+/// Backups in `dir`, oldest first. The timestamped names sort chronologically.
+fn list(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == EXTENSION)
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(PREFIX))
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+fn prune(dir: &Path, keep: usize) -> Result<()> {
+    let backups = list(dir)?;
+    let excess = backups.len().saturating_sub(keep);
+    for path in &backups[..excess] {
+        fs::remove_file(path).with_context(|| format!("Failed to delete {}", path.display()))?;
+    }
+    Ok(())
+}
@@ -1,47 +1,154 @@
 use crate::models::AppConfig;
-use anyhow::Result;
-use directories::ProjectDirs;
+use crate::storage::paths::Paths;
+use anyhow::{Context, Result};
 use std::fs;
 use std::path::PathBuf;
 
 pub struct ConfigManager {
     config_path: PathBuf,
+    /// Per-profile overrides layered over `config_path`; saving writes only
+    /// the settings that differ from it
+    profile_path: Option<PathBuf>,
 }
 
 impl ConfigManager {
     pub fn new() -> Result<Self> {
-        let proj_dirs = ProjectDirs::from("", "", "TypingTUI")
-            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
-
-        let config_dir = proj_dirs.config_dir();
-        fs::create_dir_all(config_dir)?;
+        Self::for_paths(&Paths::resolve()?)
+    }
 
-        let config_path = config_dir.join("config.toml");
+    /// The config of `paths.profile`
+    pub fn for_paths(paths: &Paths) -> Result<Self> {
+        fs::create_dir_all(&paths.config_dir)
+            .with_context(|| format!("Failed to create {}", paths.config_dir.display()))?;
 
-        Ok(Self { config_path })
+        Ok(Self {
+            config_path: paths.config_file(),
+            profile_path: paths.profile_config_file(),
+        })
     }
 
     pub fn load(&self) -> Result<AppConfig> {
-        if !self.config_path.exists() {
-            // Create default config
-            let default = AppConfig::default();
-            self.save(&default)?;
-            return Ok(default);
+        let mut table = self.load_shared()?;
+        if let Some(path) = &self.profile_path
+            && path.exists()
+        {
+            table.extend(read_table(path)?);
         }
-
-        let content = fs::read_to_string(&self.config_path)?;
-        let config: AppConfig = toml::from_str(&content)?;
+        let config: AppConfig = table.try_into().with_context(|| {
+            format!(
+                "Failed to parse {}",
+                self.profile_path
+                    .as_ref()
+                    .unwrap_or(&self.config_path)
+                    .display()
+            )
+        })?;
         Ok(config)
     }
 
     pub fn save(&self, config: &AppConfig) -> Result<()> {
-        let toml_str = toml::to_string_pretty(config)?;
-        fs::write(&self.config_path, toml_str)?;
-        Ok(())
+        let Some(path) = &self.profile_path else {
+            return write(&self.config_path, config);
+        };
+        let shared = self.load_shared()?;
+        let mut overrides = toml::Table::try_from(config)?;
+        overrides.retain(|key, value| shared.get(key) != Some(value));
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        write(path, &overrides)
     }
 
-    #[allow(dead_code)]
     pub fn path(&self) -> &PathBuf {
         &self.config_path
     }
+
+    /// `config.toml` with every default filled in, so overrides only hold
+    /// real differences
+    fn load_shared(&self) -> Result<toml::Table> {
+        if !self.config_path.exists() {
+            // Create default config
+            let default = AppConfig::default();
+            write(&self.config_path, &default)?;
+            return Ok(toml::Table::try_from(default)?);
+        }
+
+        let config: AppConfig = toml::from_str(&read(&self.config_path)?)
+            .with_context(|| format!("Failed to parse {}", self.config_path.display()))?;
+        Ok(toml::Table::try_from(config)?)
+    }
+}
+
+fn read(path: &PathBuf) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn read_table(path: &PathBuf) -> Result<toml::Table> {
+    toml::from_str(&read(path)?).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn write(path: &PathBuf, value: &impl serde::Serialize) -> Result<()> {
+    let toml_str = toml::to_string_pretty(value)?;
+    fs::write(path, toml_str).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::paths::DirOverrides;
+
+    fn manager(name: &str) -> (ConfigManager, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("tuitype-config-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        let paths = Paths::with_overrides(DirOverrides {
+            config_dir: Some(dir.join("config")),
+            data_dir: Some(dir.join("data")),
+        })
+        .unwrap();
+        (ConfigManager::for_paths(&paths).unwrap(), dir)
+    }
+
+    #[test]
+    fn show_keyboard_round_trips() {
+        let (manager, dir) = manager("show-keyboard");
+        let mut config = manager.load().unwrap();
+        assert!(!config.show_keyboard);
+        assert!(manager.path().exists());
+
+        config.show_keyboard = true;
+        manager.save(&config).unwrap();
+        assert!(manager.load().unwrap().show_keyboard);
+        assert!(read(manager.path())
+            .unwrap()
+            .contains("show_keyboard = true"));
+
+        config.show_keyboard = false;
+        manager.save(&config).unwrap();
+        assert!(!manager.load().unwrap().show_keyboard);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn old_configs_without_show_keyboard_still_load() {
+        let (manager, dir) = manager("old-config");
+        fs::write(
+            manager.path(),
+            "theme = \"nord\"\ndefault_mode = \"long\"\n",
+        )
+        .unwrap();
+        let config = manager.load().unwrap();
+        assert!(!config.show_keyboard);
+        assert_eq!(config.theme, "nord");
+        assert_eq!(config.default_mode, "long");
+
+        // Saving writes the new field out alongside the old ones
+        manager.save(&config).unwrap();
+        let saved = read(manager.path()).unwrap();
+        assert!(saved.contains("show_keyboard = false"), "{}", saved);
+        assert!(saved.contains("theme = \"nord\""), "{}", saved);
+        let _ = fs::remove_dir_all(dir);
+    }
 }
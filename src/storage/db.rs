@@ -1,6 +1,52 @@
-use crate::models::{TestResult, UserStats};
-use chrono::Utc;
-use rusqlite::{params, Connection, Result};
+use crate::core::anomaly::{Anomaly, WpmDistribution};
+use crate::keyboard::{Finger, FingerUsage};
+use crate::models::{
+    AbandonmentStats, AggregatePeriod, BigramLatency, Bookmark, DailyActivity, FilteredStats,
+    LengthBucket, PeriodAggregate, Ranking, RecentForm, SortOrder, TestResult, TodaySummary,
+    UserStats, ValidationReport, WeeklyAbandonment,
+};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Result, MAIN_DB};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Stored in `PRAGMA user_version`; bump when a change can't be read by older builds.
+/// Version 2 stores result timestamps as epoch milliseconds instead of text.
+pub const SCHEMA_VERSION: i64 = 2;
+
+/// Stored in the NOT NULL `consistency` column for tests too short to have one
+const NO_CONSISTENCY: f64 = -1.0;
+
+/// Results from `?1` on, or all of them when it is NULL
+const SINCE: &str = "(?1 IS NULL OR timestamp >= ?1)";
+
+/// Columns read by `result_from_row`, in its order
+const RESULT_COLUMNS: &str = "id, timestamp, mode, wpm, raw_wpm, accuracy, consistency,
+    quote_length, duration_seconds, anomaly, confirmed, real_accuracy, quote_id,
+    burst_wpm, source, word_count, consistency_version";
+
+fn result_from_row(row: &rusqlite::Row) -> Result<TestResult> {
+    Ok(TestResult {
+        id: Some(row.get(0)?),
+        timestamp: timestamp_from_millis(row.get(1)?)?,
+        mode: row.get(2)?,
+        wpm: row.get(3)?,
+        raw_wpm: row.get(4)?,
+        accuracy: row.get(5)?,
+        real_accuracy: row.get(11)?,
+        burst_wpm: row.get(13)?,
+        consistency: Some(row.get::<_, f64>(6)?)
+            .filter(|&consistency| consistency != NO_CONSISTENCY),
+        consistency_version: row.get(16)?,
+        quote_length: row.get(7)?,
+        word_count: row.get(15)?,
+        duration_seconds: row.get(8)?,
+        quote_id: row.get(12)?,
+        source: row.get(14)?,
+        anomaly: Anomaly::from_db(row.get(9)?),
+        confirmed: row.get(10)?,
+    })
+}
 
 pub struct Database {
     conn: Connection,
@@ -14,11 +60,49 @@ impl Database {
         Ok(db)
     }
 
+    /// A database that lives only as long as the process; used when the file
+    /// can't be opened so the app still runs, just without saving anything
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        let db = Self { conn };
+        db.init_tables()?;
+        Ok(db)
+    }
+
+    /// Write a consistent copy of the database to `path`, even mid-write
+    pub fn backup_to(&self, path: &Path) -> Result<()> {
+        self.conn
+            .backup(MAIN_DB, path, None::<fn(rusqlite::backup::Progress)>)
+    }
+
+    /// Replace every table with the contents of the backup at `path`. The
+    /// file must be a TUItype database no newer than this build; anything
+    /// else is rejected before the live data is touched.
+    pub fn restore_from(&mut self, path: &Path) -> anyhow::Result<()> {
+        let backup = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let version: i64 = backup.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        let has_results: bool = backup.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'test_results'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_results || !(1..=SCHEMA_VERSION).contains(&version) {
+            anyhow::bail!("{} is not a TUItype database backup", path.display());
+        }
+        drop(backup);
+
+        self.conn
+            .restore(MAIN_DB, path, None::<fn(rusqlite::backup::Progress)>)?;
+        // Bring an older backup up to the current schema
+        self.init_tables()?;
+        Ok(())
+    }
+
     fn init_tables(&self) -> Result<()> {
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS test_results (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
-                timestamp TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
                 mode TEXT NOT NULL,
                 wpm REAL NOT NULL,
                 raw_wpm REAL NOT NULL,
@@ -29,92 +113,1568 @@ impl Database {
             )",
             [],
         )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS quote_ratings (
+                quote_hash TEXT PRIMARY KEY,
+                rating INTEGER NOT NULL,
+                rated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS key_stats (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                result_id INTEGER NOT NULL,
+                key TEXT NOT NULL,
+                hits INTEGER NOT NULL,
+                misses INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS bookmarks (
+                path TEXT PRIMARY KEY,
+                file_hash TEXT NOT NULL,
+                chunk_index INTEGER NOT NULL,
+                chunk_count INTEGER NOT NULL,
+                tests INTEGER NOT NULL,
+                wpm_sum REAL NOT NULL,
+                accuracy_sum REAL NOT NULL,
+                duration_seconds INTEGER NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS abandoned_tests (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                mode TEXT NOT NULL,
+                chars_typed INTEGER NOT NULL,
+                elapsed_seconds REAL NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS keystroke_stats (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                result_id INTEGER NOT NULL,
+                bigram TEXT NOT NULL,
+                count INTEGER NOT NULL,
+                total_ms INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS finger_stats (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                result_id INTEGER NOT NULL,
+                finger TEXT NOT NULL,
+                count INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS word_errors (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                result_id INTEGER NOT NULL,
+                word TEXT NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS replays (
+                result_id INTEGER PRIMARY KEY,
+                data BLOB NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS achievements (
+                name TEXT PRIMARY KEY,
+                unlocked_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        self.add_column_if_missing("test_results", "real_accuracy", "REAL")?;
+        // Times the word was mistyped in the test, of `occurrences`; rows from
+        // before `occurrences` stand for one, mistyped or not
+        self.add_column_if_missing("word_errors", "missed", "INTEGER NOT NULL DEFAULT 1")?;
+        self.add_column_if_missing("word_errors", "occurrences", "INTEGER NOT NULL DEFAULT 1")?;
+        self.add_column_if_missing("test_results", "quote_id", "INTEGER")?;
+        self.add_column_if_missing("test_results", "anomaly", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("test_results", "confirmed", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("test_results", "burst_wpm", "REAL")?;
+        self.add_column_if_missing("test_results", "source", "TEXT")?;
+        self.add_column_if_missing("test_results", "word_count", "INTEGER")?;
+        // Rows from before the column existed used the legacy formula
+        self.add_column_if_missing(
+            "test_results",
+            "consistency_version",
+            "INTEGER NOT NULL DEFAULT 1",
+        )?;
+        self.migrate_timestamps()?;
+
+        let has_mode_stats: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'mode_stats'",
+            [],
+            |row| row.get(0),
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS mode_stats (
+                mode TEXT PRIMARY KEY,
+                count INTEGER NOT NULL,
+                mean REAL NOT NULL,
+                m2 REAL NOT NULL
+            )",
+            [],
+        )?;
+        if !has_mode_stats {
+            self.rebuild_mode_stats()?;
+        }
+        // Only written when it changes, so a read-only database still opens
+        // and just fails to save
+        let version: i64 = self
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            self.conn
+                .pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        Ok(())
+    }
+
+    fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .any(|name| name == column);
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Recompute the cached per-mode distributions from unflagged results
+    fn rebuild_mode_stats(&self) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT mode, wpm FROM test_results WHERE anomaly = 0 ORDER BY id")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut distributions: HashMap<String, WpmDistribution> = HashMap::new();
+        for (mode, wpm) in rows {
+            distributions.entry(mode).or_default().push(wpm);
+        }
+
+        self.conn.execute("DELETE FROM mode_stats", [])?;
+        for (mode, dist) in &distributions {
+            self.store_distribution(mode, dist)?;
+        }
+        Ok(())
+    }
+
+    fn store_distribution(&self, mode: &str, dist: &WpmDistribution) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO mode_stats (mode, count, mean, m2) VALUES (?1, ?2, ?3, ?4)",
+            params![mode, dist.count, dist.mean, dist.m2],
+        )?;
         Ok(())
     }
 
+    /// Cached WPM distribution of unflagged results in `mode`
+    pub fn get_wpm_distribution(&self, mode: &str) -> Result<WpmDistribution> {
+        let dist = self
+            .conn
+            .query_row(
+                "SELECT count, mean, m2 FROM mode_stats WHERE mode = ?1",
+                [mode],
+                |row| {
+                    Ok(WpmDistribution {
+                        count: row.get(0)?,
+                        mean: row.get(1)?,
+                        m2: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(dist.unwrap_or_default())
+    }
+
+    /// Remove one result with its key stats; the cached distributions are rebuilt
+    pub fn delete_result(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM key_stats WHERE result_id = ?1", [id])?;
+        self.conn
+            .execute("DELETE FROM keystroke_stats WHERE result_id = ?1", [id])?;
+        self.conn
+            .execute("DELETE FROM word_errors WHERE result_id = ?1", [id])?;
+        self.conn
+            .execute("DELETE FROM finger_stats WHERE result_id = ?1", [id])?;
+        self.conn
+            .execute("DELETE FROM replays WHERE result_id = ?1", [id])?;
+        self.conn
+            .execute("DELETE FROM test_results WHERE id = ?1", [id])?;
+        self.rebuild_mode_stats()
+    }
+
+    pub fn delete_all_results(&self) -> Result<()> {
+        self.conn.execute("DELETE FROM key_stats", [])?;
+        self.conn.execute("DELETE FROM keystroke_stats", [])?;
+        self.conn.execute("DELETE FROM word_errors", [])?;
+        self.conn.execute("DELETE FROM finger_stats", [])?;
+        self.conn.execute("DELETE FROM replays", [])?;
+        self.conn.execute("DELETE FROM test_results", [])?;
+        self.conn.execute("DELETE FROM mode_stats", [])?;
+        Ok(())
+    }
+
+    /// Vouch for a flagged-high result so it counts towards personal bests
+    pub fn confirm_result(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("UPDATE test_results SET confirmed = 1 WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Insert a result; unflagged results also update the mode's cached distribution
     pub fn save_result(&self, result: &TestResult) -> Result<i64> {
         self.conn.execute(
             "INSERT INTO test_results 
-             (timestamp, mode, wpm, raw_wpm, accuracy, consistency, quote_length, duration_seconds)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+             (timestamp, mode, wpm, raw_wpm, accuracy, consistency, quote_length, duration_seconds,
+              anomaly, confirmed, real_accuracy, quote_id, burst_wpm, source, word_count,
+              consistency_version)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
             params![
-                result.timestamp.to_rfc3339(),
+                result.timestamp.timestamp_millis(),
                 result.mode,
                 result.wpm,
                 result.raw_wpm,
                 result.accuracy,
-                result.consistency,
+                result.consistency.unwrap_or(NO_CONSISTENCY),
                 result.quote_length,
                 result.duration_seconds,
+                result.anomaly.to_db(),
+                result.confirmed,
+                result.real_accuracy,
+                result.quote_id,
+                result.burst_wpm,
+                result.source,
+                result.word_count,
+                result.consistency_version,
+            ],
+        )?;
+        let id = self.conn.last_insert_rowid();
+
+        if result.anomaly == Anomaly::None {
+            let mut dist = self.get_wpm_distribution(&result.mode)?;
+            dist.push(result.wpm);
+            self.store_distribution(&result.mode, &dist)?;
+        }
+        Ok(id)
+    }
+
+    /// Insert results that couldn't be saved earlier, all or none of them
+    pub fn save_results(&self, results: &[TestResult]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for result in results {
+            self.save_result(result)?;
+        }
+        tx.commit()
+    }
+
+    pub fn save_key_stats(&self, result_id: i64, stats: &HashMap<char, (u64, u64)>) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO key_stats (result_id, key, hits, misses) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (key, (hits, misses)) in stats {
+            stmt.execute(params![
+                result_id,
+                key.to_string(),
+                *hits as i64,
+                *misses as i64
+            ])?;
+        }
+        Ok(())
+    }
+
+    pub fn save_bigram_stats(
+        &self,
+        result_id: i64,
+        latencies: &HashMap<String, (u64, u64)>,
+    ) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO keystroke_stats (result_id, bigram, count, total_ms) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (bigram, (count, total_ms)) in latencies {
+            stmt.execute(params![result_id, bigram, *count as i64, *total_ms as i64])?;
+        }
+        Ok(())
+    }
+
+    /// Keystrokes per finger of a test; keys off the layout are stored as "other"
+    pub fn save_finger_stats(&self, result_id: i64, usage: &FingerUsage) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("INSERT INTO finger_stats (result_id, finger, count) VALUES (?1, ?2, ?3)")?;
+        for (finger, count) in &usage.counts {
+            stmt.execute(params![result_id, finger.name(), *count as i64])?;
+        }
+        if usage.other > 0 {
+            stmt.execute(params![result_id, "other", usage.other as i64])?;
+        }
+        Ok(())
+    }
+
+    /// Lifetime keystrokes per finger, summed over all tests
+    pub fn get_finger_stats(&self) -> Result<FingerUsage> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT finger, SUM(count) FROM finger_stats GROUP BY finger")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        let mut usage = FingerUsage::default();
+        for row in rows {
+            let (name, count) = row?;
+            match Finger::from_name(&name) {
+                Some(finger) => {
+                    usage.counts.insert(finger, count as u64);
+                }
+                None => usage.other += count as u64,
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Store a test's encoded replay, keeping only the `keep` most recent
+    pub fn save_replay(&self, result_id: i64, data: &[u8], keep: usize) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO replays (result_id, data) VALUES (?1, ?2)",
+            params![result_id, data],
+        )?;
+        self.conn.execute(
+            "DELETE FROM replays WHERE result_id NOT IN
+             (SELECT result_id FROM replays ORDER BY result_id DESC LIMIT ?1)",
+            [keep as i64],
+        )?;
+        Ok(())
+    }
+
+    /// The encoded replay of a result, if one was kept
+    pub fn get_replay(&self, result_id: i64) -> Result<Option<Vec<u8>>> {
+        self.conn
+            .query_row(
+                "SELECT data FROM replays WHERE result_id = ?1",
+                [result_id],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    /// (word, times typed, times with a miss) for every word of a test
+    pub fn save_word_errors(&self, result_id: i64, tallies: &[(String, u32, u32)]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO word_errors (result_id, word, occurrences, missed)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (word, occurrences, missed) in tallies {
+            stmt.execute(params![result_id, word, occurrences, missed])?;
+        }
+        Ok(())
+    }
+
+    /// The `limit` words with the highest error rate among those typed at
+    /// least `min_occurrences` times, worst first; words never mistyped
+    /// aren't listed
+    pub fn get_worst_words(&self, limit: usize, min_occurrences: u32) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT word FROM word_errors
+             GROUP BY word
+             HAVING SUM(missed) > 0 AND SUM(occurrences) >= ?2
+             ORDER BY SUM(missed) * 1.0 / SUM(occurrences) DESC, SUM(missed) DESC, MAX(id) DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64, min_occurrences], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Lifetime slowest bigrams by average latency, ignoring ones seen fewer
+    /// than `min_count` times
+    pub fn get_slowest_bigrams(&self, limit: usize, min_count: i64) -> Result<Vec<BigramLatency>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT bigram, SUM(count), CAST(SUM(total_ms) AS REAL) / SUM(count) AS avg_ms
+             FROM keystroke_stats
+             GROUP BY bigram
+             HAVING SUM(count) >= ?1
+             ORDER BY avg_ms DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![min_count, limit as i64], |row| {
+            Ok(BigramLatency {
+                bigram: row.get(0)?,
+                count: row.get(1)?,
+                avg_ms: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Lifetime (hits, misses) per expected character, summed over all tests
+    pub fn get_key_stats(&self) -> Result<HashMap<char, (u64, u64)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT key, SUM(hits), SUM(misses) FROM key_stats GROUP BY key")?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+
+        let mut stats = HashMap::new();
+        for row in rows {
+            let (key, hits, misses) = row?;
+            if let Some(c) = key.chars().next() {
+                stats.insert(c, (hits as u64, misses as u64));
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Log a test thrown away mid-way, returning its row id
+    pub fn save_abandoned(
+        &self,
+        mode: &str,
+        chars_typed: usize,
+        elapsed_seconds: f64,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO abandoned_tests (timestamp, mode, chars_typed, elapsed_seconds)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                Utc::now().to_rfc3339(),
+                mode,
+                chars_typed as i64,
+                elapsed_seconds
             ],
         )?;
         Ok(self.conn.last_insert_rowid())
     }
 
-    pub fn get_recent_results(&self, limit: usize) -> Result<Vec<TestResult>> {
+    /// Take back an abandonment logged by `save_abandoned`, for a test brought back
+    pub fn delete_abandoned(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM abandoned_tests WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Local calendar day of every result with a readable timestamp
+    fn result_dates(&self) -> Result<Vec<NaiveDate>> {
+        let mut stmt = self.conn.prepare("SELECT timestamp FROM test_results")?;
+        let timestamps = stmt
+            .query_map([], |row| Ok(row.get::<_, i64>(0).ok()))?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(timestamps
+            .into_iter()
+            .flatten()
+            .filter_map(local_date_from_millis)
+            .collect())
+    }
+
+    /// Completed tests per local day and the streaks they make up to `today`.
+    /// Timestamps are stored in UTC, so they are shifted to the local offset
+    /// before bucketing.
+    pub fn get_daily_activity(&self, today: NaiveDate) -> Result<DailyActivity> {
+        let mut tests_by_day = BTreeMap::new();
+        for day in self.result_dates()? {
+            *tests_by_day.entry(day).or_insert(0) += 1;
+        }
+        Ok(DailyActivity::new(tests_by_day, today))
+    }
+
+    /// Tests and seconds typed on the local calendar day `today`
+    pub fn get_today_summary(&self, today: NaiveDate) -> Result<TodaySummary> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT timestamp, duration_seconds FROM test_results")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0).ok(), row.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut summary = TodaySummary::default();
+        for (timestamp, seconds) in rows {
+            if timestamp.and_then(local_date_from_millis) == Some(today) {
+                summary.tests += 1;
+                summary.seconds += seconds;
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Turn version 1's RFC 3339 result timestamps into epoch milliseconds.
+    /// Rows whose text isn't a date are moved to `malformed_results` rather
+    /// than given a made-up one, and keep their ids reserved.
+    fn migrate_timestamps(&self) -> Result<()> {
+        let column_type: String = self.conn.query_row(
+            "SELECT type FROM pragma_table_info('test_results') WHERE name = 'timestamp'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !column_type.eq_ignore_ascii_case("TEXT") {
+            return Ok(());
+        }
+
+        // Same columns, with every one added by `add_column_if_missing`
         let mut stmt = self.conn.prepare(
-            "SELECT id, timestamp, mode, wpm, raw_wpm, accuracy, consistency,
-                    quote_length, duration_seconds
+            "SELECT name, type, \"notnull\", dflt_value FROM pragma_table_info('test_results')",
+        )?;
+        let columns = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        let definitions: Vec<String> = columns
+            .iter()
+            .map(|(name, kind, not_null, default)| match name.as_str() {
+                "id" => "id INTEGER PRIMARY KEY AUTOINCREMENT".to_string(),
+                "timestamp" => "timestamp INTEGER NOT NULL".to_string(),
+                _ => format!(
+                    "{} {}{}{}",
+                    name,
+                    kind,
+                    if *not_null { " NOT NULL" } else { "" },
+                    default
+                        .as_ref()
+                        .map_or(String::new(), |d| format!(" DEFAULT {}", d))
+                ),
+            })
+            .collect();
+        let names: Vec<&str> = columns.iter().map(|(name, ..)| name.as_str()).collect();
+        let values: Vec<&str> = names
+            .iter()
+            .map(|&name| match name {
+                "timestamp" => {
+                    "COALESCE(CAST(round((julianday(timestamp) - 2440587.5) * 86400000) AS INTEGER), 0)"
+                }
+                name => name,
+            })
+            .collect();
+
+        // Inserting every row and deleting the malformed ones afterwards keeps
+        // AUTOINCREMENT from handing their ids, and stats keyed on them, out again
+        self.conn.execute_batch(&format!(
+            "BEGIN;
+             CREATE TABLE IF NOT EXISTS malformed_results AS
+                 SELECT * FROM test_results WHERE 0;
+             INSERT INTO malformed_results
+                 SELECT * FROM test_results WHERE julianday(timestamp) IS NULL;
+             CREATE TABLE test_results_v2 ({});
+             INSERT INTO test_results_v2 ({}) SELECT {} FROM test_results;
+             DELETE FROM test_results_v2
+                 WHERE id IN (SELECT id FROM test_results WHERE julianday(timestamp) IS NULL);
+             DROP TABLE test_results;
+             ALTER TABLE test_results_v2 RENAME TO test_results;
+             COMMIT;",
+            definitions.join(", "),
+            names.join(", "),
+            values.join(", "),
+        ))
+    }
+
+    /// Problems with the stored results that reading them works around
+    pub fn validate(&self) -> Result<ValidationReport> {
+        let count = |sql: &str| -> Result<usize> {
+            self.conn
+                .query_row(sql, [], |row| row.get::<_, i64>(0))
+                .map(|n| n as usize)
+        };
+        let has_set_aside: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master
+             WHERE type = 'table' AND name = 'malformed_results'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(ValidationReport {
+            bad_timestamps: count(&format!(
+                "SELECT COUNT(*) FROM test_results
+                 WHERE typeof(timestamp) != 'integer'
+                    OR timestamp NOT BETWEEN {} AND {}",
+                DateTime::<Utc>::MIN_UTC.timestamp_millis(),
+                DateTime::<Utc>::MAX_UTC.timestamp_millis()
+            ))?,
+            set_aside: if has_set_aside {
+                count("SELECT COUNT(*) FROM malformed_results")?
+            } else {
+                0
+            },
+            out_of_range: count(
+                "SELECT COUNT(*) FROM test_results
+                 WHERE wpm < 0 OR raw_wpm < 0 OR duration_seconds < 0
+                    OR accuracy NOT BETWEEN 0 AND 100",
+            )?,
+        })
+    }
+
+    /// Totals for the latest `last_n` days or weeks that have results, newest
+    /// first. Periods follow the local calendar; weeks start on Monday.
+    pub fn get_aggregates(
+        &self,
+        period: AggregatePeriod,
+        last_n: usize,
+    ) -> Result<Vec<PeriodAggregate>> {
+        let start = match period {
+            AggregatePeriod::Day => "date(timestamp / 1000, 'unixepoch', 'localtime')",
+            AggregatePeriod::Week => {
+                "date(timestamp / 1000, 'unixepoch', 'localtime', '-6 days', 'weekday 1')"
+            }
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} AS start, COUNT(*), AVG(wpm), MAX(wpm), AVG(accuracy),
+                    SUM(duration_seconds) / 60.0
              FROM test_results
-             ORDER BY timestamp DESC
+             WHERE start IS NOT NULL
+             GROUP BY start
+             ORDER BY start DESC
              LIMIT ?1",
+            start
+        ))?;
+        let rows = stmt
+            .query_map([last_n as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    PeriodAggregate {
+                        start: NaiveDate::MIN,
+                        tests: row.get(1)?,
+                        avg_wpm: row.get(2)?,
+                        best_wpm: row.get(3)?,
+                        avg_accuracy: row.get(4)?,
+                        minutes: row.get(5)?,
+                    },
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(start, aggregate)| {
+                Some(PeriodAggregate {
+                    start: NaiveDate::parse_from_str(&start, "%Y-%m-%d").ok()?,
+                    ..aggregate
+                })
+            })
+            .collect())
+    }
+
+    /// How often started tests are abandoned, overall and for the last `weeks` weeks
+    pub fn get_abandonment(&self, weeks: usize) -> Result<AbandonmentStats> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT chars_typed FROM abandoned_tests ORDER BY chars_typed")?;
+        let chars = stmt
+            .query_map([], |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<_>>>()?;
+
+        // Both tables store UTC timestamps, abandoned tests still as text;
+        // weeks follow the local calendar
+        let mut stmt = self.conn.prepare("SELECT timestamp FROM abandoned_tests")?;
+        let abandoned_dates: Vec<NaiveDate> = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .filter_map(|t| local_date(t))
+            .collect();
+
+        let mut by_week: BTreeMap<(i32, u32), (i64, i64)> = BTreeMap::new();
+        for (dates, abandoned) in [(self.result_dates()?, false), (abandoned_dates, true)] {
+            for date in dates {
+                let week = date.iso_week();
+                let entry = by_week.entry((week.year(), week.week())).or_default();
+                entry.0 += 1;
+                if abandoned {
+                    entry.1 += 1;
+                }
+            }
+        }
+
+        let weekly: Vec<WeeklyAbandonment> = by_week
+            .iter()
+            .rev()
+            .take(weeks)
+            .rev()
+            .map(|(&(_, week), &(started, abandoned))| WeeklyAbandonment {
+                label: format!("W{:02}", week),
+                started,
+                abandoned,
+            })
+            .collect();
+
+        let (started, abandoned) = by_week
+            .values()
+            .fold((0, 0), |(s, a), &(started, abandoned)| {
+                (s + started, a + abandoned)
+            });
+
+        Ok(AbandonmentStats {
+            started,
+            abandoned,
+            median_chars: chars.get(chars.len() / 2).copied(),
+            weekly,
+        })
+    }
+
+    /// Reading position for the continuous source at `path`
+    pub fn get_bookmark(&self, path: &str) -> Result<Option<Bookmark>> {
+        self.conn
+            .query_row(
+                "SELECT file_hash, chunk_index, chunk_count, tests, wpm_sum, accuracy_sum,
+                        duration_seconds
+                 FROM bookmarks WHERE path = ?1",
+                [path],
+                |row| {
+                    Ok(Bookmark {
+                        file_hash: row.get(0)?,
+                        chunk_index: row.get::<_, i64>(1)? as usize,
+                        chunk_count: row.get::<_, i64>(2)? as usize,
+                        tests: row.get(3)?,
+                        wpm_sum: row.get(4)?,
+                        accuracy_sum: row.get(5)?,
+                        duration_seconds: row.get(6)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub fn save_bookmark(&self, path: &str, bookmark: &Bookmark) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO bookmarks
+             (path, file_hash, chunk_index, chunk_count, tests, wpm_sum, accuracy_sum,
+              duration_seconds, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                path,
+                bookmark.file_hash,
+                bookmark.chunk_index as i64,
+                bookmark.chunk_count as i64,
+                bookmark.tests,
+                bookmark.wpm_sum,
+                bookmark.accuracy_sum,
+                bookmark.duration_seconds,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_quote_rating(&self, quote_hash: &str, rating: u8) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO quote_ratings (quote_hash, rating, rated_at)
+             VALUES (?1, ?2, ?3)",
+            params![quote_hash, rating, Utc::now().to_rfc3339()],
         )?;
+        Ok(())
+    }
+
+    pub fn get_quote_ratings(&self) -> Result<HashMap<String, u8>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT quote_hash, rating FROM quote_ratings")?;
+        let ratings = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(ratings)
+    }
 
-        let results = stmt
-            .query_map([limit as i64], |row| {
-                Ok(TestResult {
-                    id: Some(row.get(0)?),
-                    timestamp: row.get::<_, String>(1)?.parse().unwrap_or(Utc::now()),
-                    mode: row.get(2)?,
-                    wpm: row.get(3)?,
-                    raw_wpm: row.get(4)?,
-                    accuracy: row.get(5)?,
-                    consistency: row.get(6)?,
-                    quote_length: row.get(7)?,
-                    duration_seconds: row.get(8)?,
+    /// Record `name` as unlocked at `at`; unlocking it again keeps the first date
+    pub fn unlock_achievement(&self, name: &str, at: DateTime<Utc>) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO achievements (name, unlocked_at) VALUES (?1, ?2)",
+            params![name, at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// When each unlocked achievement was unlocked, by name
+    pub fn get_achievements(&self) -> Result<HashMap<String, DateTime<Utc>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, unlocked_at FROM achievements")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(name, at)| Some((name, at.parse().ok()?)))
+            .collect())
+    }
+
+    /// Best WPM in `mode`, ignoring unconfirmed high anomalies
+    pub fn get_best_wpm_for_mode(&self, mode: &str) -> Result<Option<f64>> {
+        self.conn.query_row(
+            "SELECT MAX(wpm) FROM test_results
+             WHERE mode = ?1 AND (anomaly != 1 OR confirmed = 1)",
+            [mode],
+            |row| row.get(0),
+        )
+    }
+
+    /// Mean WPM for a mode, `None` before its first test
+    pub fn get_avg_wpm_for_mode(&self, mode: &str) -> Result<Option<f64>> {
+        self.conn.query_row(
+            "SELECT AVG(wpm) FROM test_results WHERE mode = ?1",
+            [mode],
+            |row| row.get(0),
+        )
+    }
+
+    /// Where a run of `wpm` ranks among the results in `mode`, counting
+    /// results of the same speed as behind it. Flagged runs are left out,
+    /// as for personal bests.
+    pub fn get_percentile(&self, mode: &str, wpm: f64) -> Result<Ranking> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FILTER (WHERE wpm > ?2), COUNT(*) FROM test_results
+             WHERE mode = ?1 AND (anomaly != 1 OR confirmed = 1)",
+            params![mode, wpm],
+            |row| {
+                Ok(Ranking {
+                    rank: row.get::<_, i64>(0)? + 1,
+                    total: row.get(1)?,
+                })
+            },
+        )
+    }
+
+    /// Keys missed most in results since `since`, as (key, hits, misses)
+    pub fn get_missed_keys(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<(char, u64, u64)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT key, SUM(hits), SUM(misses) FROM key_stats
+             JOIN test_results ON test_results.id = key_stats.result_id
+             WHERE {}
+             GROUP BY key
+             HAVING SUM(misses) > 0
+             ORDER BY SUM(misses) DESC, key
+             LIMIT ?2",
+            SINCE
+        ))?;
+        let rows = stmt.query_map(params![since_param(since), limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+        let mut keys = Vec::new();
+        for row in rows {
+            let (key, hits, misses) = row?;
+            if let Some(c) = key.chars().next() {
+                keys.push((c, hits as u64, misses as u64));
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Mean WPM of the latest `n` results and of the `n` before them, in one
+    /// mode or across all of them
+    pub fn get_last_n_average(&self, mode: Option<&str>, n: usize) -> Result<RecentForm> {
+        let window = |offset: usize| {
+            self.conn.query_row(
+                "SELECT COUNT(*), AVG(wpm) FROM (
+                     SELECT wpm FROM test_results
+                     WHERE ?1 IS NULL OR mode = ?1
+                     ORDER BY timestamp DESC, id DESC
+                     LIMIT ?2 OFFSET ?3
+                 )",
+                params![mode, n as i64, offset as i64],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)? as usize,
+                        row.get::<_, Option<f64>>(1)?,
+                    ))
+                },
+            )
+        };
+        let (_, last) = window(0)?;
+        let (prior_count, prior) = window(n)?;
+        Ok(RecentForm {
+            n,
+            last,
+            prior: prior.filter(|_| prior_count == n),
+        })
+    }
+
+    /// The `n` fastest results since `since`, in one mode or across all of
+    /// them, leaving out flagged runs
+    pub fn get_top_results(
+        &self,
+        mode: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        n: usize,
+    ) -> Result<Vec<TestResult>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM test_results
+             WHERE {} AND (?2 IS NULL OR mode = ?2) AND (anomaly != 1 OR confirmed = 1)
+             ORDER BY wpm DESC, timestamp DESC
+             LIMIT ?3",
+            RESULT_COLUMNS, SINCE
+        ))?;
+        readable(stmt.query_map(params![since_param(since), mode, n as i64], result_from_row)?)
+    }
+
+    /// Mean WPM over every mode, `None` before the first test
+    pub fn get_avg_wpm(&self) -> Result<Option<f64>> {
+        self.conn
+            .query_row("SELECT AVG(wpm) FROM test_results", [], |row| row.get(0))
+    }
+
+    /// Best WPM ever typed on one quote, with the same anomaly rule as mode bests
+    pub fn get_best_for_quote(&self, quote_id: i64) -> Result<Option<f64>> {
+        self.conn.query_row(
+            "SELECT MAX(wpm) FROM test_results
+             WHERE quote_id = ?1 AND (anomaly != 1 OR confirmed = 1)",
+            [quote_id],
+            |row| row.get(0),
+        )
+    }
+
+    /// Average WPM per quote-length bucket (0-99, 100-199, ..., 500+)
+    pub fn get_wpm_by_length(&self) -> Result<Vec<LengthBucket>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT MIN(quote_length / ?1, ?2) AS bucket, COUNT(*), AVG(wpm)
+             FROM test_results
+             GROUP BY bucket
+             ORDER BY bucket",
+        )?;
+
+        let last_bucket = LengthBucket::LAST_MIN / LengthBucket::WIDTH;
+        let buckets = stmt
+            .query_map([LengthBucket::WIDTH, last_bucket], |row| {
+                Ok(LengthBucket {
+                    min_length: row.get::<_, i64>(0)? * LengthBucket::WIDTH,
+                    tests: row.get(1)?,
+                    avg_wpm: row.get(2)?,
                 })
             })?
             .collect::<Result<Vec<_>>>()?;
 
+        Ok(buckets)
+    }
+
+    /// Number of results, optionally in one mode only
+    pub fn count_results(&self, mode: Option<&str>) -> Result<usize> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM test_results WHERE ?1 IS NULL OR mode = ?1",
+            [mode],
+            |row| row.get::<_, i64>(0).map(|count| count as usize),
+        )
+    }
+
+    /// Count, averages and best over every result in `mode` (or all modes)
+    /// since `since`, not just the loaded page
+    pub fn get_filtered_stats(
+        &self,
+        mode: Option<&str>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<FilteredStats> {
+        self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*),
+                        COALESCE(AVG(wpm), 0.0),
+                        COALESCE(MAX(CASE WHEN anomaly != 1 OR confirmed = 1 THEN wpm END), 0.0),
+                        COALESCE(AVG(accuracy), 0.0)
+                 FROM test_results
+                 WHERE {} AND (?2 IS NULL OR mode = ?2)",
+                SINCE
+            ),
+            params![since_param(since), mode],
+            |row| {
+                Ok(FilteredStats {
+                    count: row.get::<_, i64>(0)? as usize,
+                    avg_wpm: row.get(1)?,
+                    best_wpm: row.get(2)?,
+                    avg_accuracy: row.get(3)?,
+                })
+            },
+        )
+    }
+
+    /// One page of results, optionally limited to one mode and to those since
+    /// `since`. Ties in WPM or accuracy fall back to newest first.
+    pub fn get_results_filtered(
+        &self,
+        mode: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        order: SortOrder,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<TestResult>> {
+        let order_by = match order {
+            SortOrder::Date => "timestamp DESC",
+            SortOrder::Wpm => "wpm DESC, timestamp DESC",
+            SortOrder::Accuracy => "accuracy DESC, timestamp DESC",
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM test_results
+             WHERE {} AND (?2 IS NULL OR mode = ?2)
+             ORDER BY {}
+             LIMIT ?3 OFFSET ?4",
+            RESULT_COLUMNS, SINCE, order_by
+        ))?;
+
+        let results = readable(stmt.query_map(
+            params![since_param(since), mode, limit as i64, offset as i64],
+            result_from_row,
+        )?)?;
+
         Ok(results)
     }
 
-    #[allow(dead_code)]
-    pub fn get_stats(&self) -> Result<UserStats> {
-        let total_tests: i64 =
-            self.conn
-                .query_row("SELECT COUNT(*) FROM test_results", [], |row| row.get(0))?;
+    /// Totals over the results since `since`; `None` covers every result
+    pub fn get_stats(&self, since: Option<DateTime<Utc>>) -> Result<UserStats> {
+        let since = since_param(since);
+        let total_tests: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM test_results WHERE {}", SINCE),
+            [&since],
+            |row| row.get(0),
+        )?;
 
         let best_wpm: f64 = self.conn.query_row(
-            "SELECT COALESCE(MAX(wpm), 0.0) FROM test_results",
-            [],
+            &format!(
+                "SELECT COALESCE(MAX(wpm), 0.0) FROM test_results
+                 WHERE {} AND (anomaly != 1 OR confirmed = 1)",
+                SINCE
+            ),
+            [&since],
+            |row| row.get(0),
+        )?;
+
+        let best_burst_wpm: f64 = self.conn.query_row(
+            &format!(
+                "SELECT COALESCE(MAX(burst_wpm), 0.0) FROM test_results
+                 WHERE {} AND (anomaly != 1 OR confirmed = 1)",
+                SINCE
+            ),
+            [&since],
             |row| row.get(0),
         )?;
 
         let avg_wpm: f64 = self.conn.query_row(
-            "SELECT COALESCE(AVG(wpm), 0.0) FROM test_results",
-            [],
+            &format!(
+                "SELECT COALESCE(AVG(wpm), 0.0) FROM test_results WHERE {}",
+                SINCE
+            ),
+            [&since],
             |row| row.get(0),
         )?;
 
         let avg_accuracy: f64 = self.conn.query_row(
-            "SELECT COALESCE(AVG(accuracy), 0.0) FROM test_results",
-            [],
+            &format!(
+                "SELECT COALESCE(AVG(accuracy), 0.0) FROM test_results WHERE {}",
+                SINCE
+            ),
+            [&since],
             |row| row.get(0),
         )?;
 
         let total_time: i64 = self.conn.query_row(
-            "SELECT COALESCE(SUM(duration_seconds), 0) FROM test_results",
-            [],
+            &format!(
+                "SELECT COALESCE(SUM(duration_seconds), 0) FROM test_results WHERE {}",
+                SINCE
+            ),
+            [&since],
             |row| row.get(0),
         )?;
 
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT mode, MAX(wpm) FROM test_results
+                 WHERE {} AND (anomaly != 1 OR confirmed = 1)
+                 GROUP BY mode ORDER BY mode",
+            SINCE
+        ))?;
+        let best_wpm_by_mode = stmt
+            .query_map([&since], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+
         Ok(UserStats {
             total_tests,
             best_wpm,
+            best_burst_wpm,
             avg_wpm,
             avg_accuracy,
             total_time_seconds: total_time,
+            best_wpm_by_mode,
         })
     }
 }
+
+/// The results in `rows` that could be read. A row with a malformed value is
+/// left out rather than failing the whole list; `validate` reports it.
+fn readable(rows: impl Iterator<Item = Result<TestResult>>) -> Result<Vec<TestResult>> {
+    rows.filter(|row| {
+        !matches!(
+            row,
+            Err(rusqlite::Error::IntegralValueOutOfRange(..)
+                | rusqlite::Error::InvalidColumnType(..)
+                | rusqlite::Error::FromSqlConversionFailure(..))
+        )
+    })
+    .collect()
+}
+
+/// `since` written the way timestamps are stored, for `SINCE`
+fn since_param(since: Option<DateTime<Utc>>) -> Option<i64> {
+    since.map(|since| since.timestamp_millis())
+}
+
+/// A stored result timestamp; one no date can have is a conversion error
+fn timestamp_from_millis(millis: i64) -> Result<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(millis)
+        .ok_or(rusqlite::Error::IntegralValueOutOfRange(1, millis))
+}
+
+/// Local calendar day of a stored result timestamp
+fn local_date_from_millis(millis: i64) -> Option<NaiveDate> {
+    DateTime::from_timestamp_millis(millis).map(|utc| utc.with_timezone(&Local).date_naive())
+}
+
+/// Local calendar day of a stored RFC 3339 timestamp, as abandoned tests have
+fn local_date(timestamp: &str) -> Option<NaiveDate> {
+    timestamp
+        .parse::<DateTime<Utc>>()
+        .ok()
+        .map(|utc| utc.with_timezone(&Local).date_naive())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::abandonment_rate;
+
+    fn tally(word: &str, occurrences: u32, missed: u32) -> (String, u32, u32) {
+        (word.to_string(), occurrences, missed)
+    }
+
+    fn result(mode: &str, wpm: f64, anomaly: Anomaly) -> TestResult {
+        TestResult {
+            id: None,
+            timestamp: Utc::now(),
+            mode: mode.to_string(),
+            wpm,
+            raw_wpm: wpm,
+            accuracy: 100.0,
+            real_accuracy: Some(100.0),
+            consistency: None,
+            consistency_version: 2,
+            burst_wpm: None,
+            quote_length: 100,
+            word_count: Some(20),
+            duration_seconds: 20,
+            quote_id: None,
+            source: None,
+            anomaly,
+            confirmed: false,
+        }
+    }
+
+    #[test]
+    fn worst_words_rank_by_error_rate() {
+        let db = Database::open_in_memory().unwrap();
+        // "the" is missed most often but typed far more; "rhythm" and
+        // "queue" fail more often than not
+        db.save_word_errors(1, &[tally("the", 20, 4), tally("rhythm", 2, 2)])
+            .unwrap();
+        db.save_word_errors(2, &[tally("the", 20, 2), tally("rhythm", 1, 0)])
+            .unwrap();
+        db.save_word_errors(3, &[tally("queue", 4, 2), tally("clean", 5, 0)])
+            .unwrap();
+        assert_eq!(
+            db.get_worst_words(10, 3).unwrap(),
+            ["rhythm", "queue", "the"]
+        );
+        assert_eq!(db.get_worst_words(1, 3).unwrap(), ["rhythm"]);
+    }
+
+    #[test]
+    fn worst_words_skip_words_typed_too_rarely() {
+        let db = Database::open_in_memory().unwrap();
+        db.save_word_errors(1, &[tally("once", 1, 1), tally("often", 6, 1)])
+            .unwrap();
+        assert_eq!(db.get_worst_words(10, 3).unwrap(), ["often"]);
+        assert_eq!(db.get_worst_words(10, 1).unwrap(), ["once", "often"]);
+    }
+
+    #[test]
+    fn flagged_results_stay_out_of_the_distribution() {
+        let db = Database::open_in_memory().unwrap();
+        for wpm in [58.0, 62.0, 58.0, 62.0] {
+            db.save_result(&result("short", wpm, Anomaly::None))
+                .unwrap();
+        }
+        db.save_result(&result("short", 400.0, Anomaly::High))
+            .unwrap();
+        db.save_result(&result("long", 30.0, Anomaly::None))
+            .unwrap();
+        let dist = db.get_wpm_distribution("short").unwrap();
+        assert_eq!(dist.count, 4);
+        assert_eq!(dist.mean, 60.0);
+        // The cache rebuilt from scratch agrees with the incremental one
+        db.rebuild_mode_stats().unwrap();
+        let rebuilt = db.get_wpm_distribution("short").unwrap();
+        assert_eq!(rebuilt.count, 4);
+        assert!((rebuilt.std_dev() - dist.std_dev()).abs() < 1e-9);
+        assert_eq!(db.get_wpm_distribution("medium").unwrap().count, 0);
+    }
+
+    #[test]
+    fn high_anomalies_count_for_bests_once_confirmed() {
+        let db = Database::open_in_memory().unwrap();
+        db.save_result(&result("short", 60.0, Anomaly::None))
+            .unwrap();
+        db.save_result(&result("short", 20.0, Anomaly::Low))
+            .unwrap();
+        let id = db
+            .save_result(&result("short", 400.0, Anomaly::High))
+            .unwrap();
+        assert_eq!(db.get_best_wpm_for_mode("short").unwrap(), Some(60.0));
+
+        db.confirm_result(id).unwrap();
+        let flagged = db
+            .get_results_filtered(Some("short"), None, SortOrder::Wpm, 10, 0)
+            .unwrap()
+            .into_iter()
+            .find(|r| r.id == Some(id))
+            .unwrap();
+        assert_eq!(flagged.anomaly, Anomaly::High);
+        assert!(flagged.confirmed);
+        assert_eq!(db.get_best_wpm_for_mode("short").unwrap(), Some(400.0));
+    }
+
+    #[test]
+    fn abandonment_counts_finished_and_abandoned_tests() {
+        let db = Database::open_in_memory().unwrap();
+        let empty = db.get_abandonment(8).unwrap();
+        assert_eq!((empty.started, empty.abandoned), (0, 0));
+        assert_eq!(empty.median_chars, None);
+        assert!(empty.weekly.is_empty());
+
+        for wpm in [50.0, 60.0, 70.0] {
+            db.save_result(&result("short", wpm, Anomaly::None))
+                .unwrap();
+        }
+        db.save_abandoned("short", 40, 10.0).unwrap();
+        db.save_abandoned("short", 4, 2.0).unwrap();
+        db.save_abandoned("medium", 7, 3.0).unwrap();
+
+        let stats = db.get_abandonment(8).unwrap();
+        assert_eq!((stats.started, stats.abandoned), (6, 3));
+        assert_eq!(stats.median_chars, Some(7));
+        assert_eq!(stats.weekly.len(), 1);
+        assert_eq!((stats.weekly[0].started, stats.weekly[0].abandoned), (6, 3));
+        assert_eq!(abandonment_rate(stats.started, stats.abandoned), 50.0);
+    }
+
+    #[test]
+    fn abandonment_is_charted_by_week() {
+        let db = Database::open_in_memory().unwrap();
+        let earlier = Utc::now() - chrono::Days::new(21);
+        db.save_result(&TestResult {
+            timestamp: earlier,
+            ..result("short", 60.0, Anomaly::None)
+        })
+        .unwrap();
+        db.conn
+            .execute(
+                "INSERT INTO abandoned_tests (timestamp, mode, chars_typed, elapsed_seconds)
+                 VALUES (?1, 'short', 3, 1.0)",
+                [earlier.to_rfc3339()],
+            )
+            .unwrap();
+        db.save_result(&result("short", 60.0, Anomaly::None))
+            .unwrap();
+
+        let stats = db.get_abandonment(8).unwrap();
+        let weekly: Vec<(i64, i64)> = stats
+            .weekly
+            .iter()
+            .map(|week| (week.started, week.abandoned))
+            .collect();
+        assert_eq!(weekly, [(2, 1), (1, 0)]);
+        let week = earlier.with_timezone(&Local).iso_week().week();
+        assert_eq!(stats.weekly[0].label, format!("W{:02}", week));
+
+        // Only the latest weeks are charted, but all of them count
+        let latest = db.get_abandonment(1).unwrap();
+        assert_eq!(latest.weekly.len(), 1);
+        assert_eq!(latest.weekly[0].abandoned, 0);
+        assert_eq!((latest.started, latest.abandoned), (3, 1));
+    }
+
+    #[test]
+    fn daily_activity_buckets_by_local_date() {
+        let db = Database::open_in_memory().unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        // Just after local midnight and just before the next one, whatever
+        // the UTC offset
+        let at = |day: NaiveDate, h: u32, m: u32| {
+            day.and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_local_timezone(Local)
+                .earliest()
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let yesterday = today.pred_opt().unwrap();
+        for timestamp in [at(yesterday, 0, 5), at(yesterday, 23, 55), at(today, 0, 5)] {
+            db.save_result(&TestResult {
+                timestamp,
+                ..result("short", 60.0, Anomaly::None)
+            })
+            .unwrap();
+        }
+
+        let activity = db.get_daily_activity(today).unwrap();
+        assert_eq!(
+            activity.tests_by_day.into_iter().collect::<Vec<_>>(),
+            [(yesterday, 2), (today, 1)]
+        );
+        assert_eq!(activity.current_streak, 2);
+        assert_eq!(activity.longest_streak, 2);
+        // Two days later the streak is over
+        let later = db.get_daily_activity(today + chrono::Days::new(2)).unwrap();
+        assert_eq!((later.current_streak, later.longest_streak), (0, 2));
+    }
+
+    #[test]
+    fn achievements_unlock_once() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(db.get_achievements().unwrap().is_empty());
+        let first = Utc::now() - chrono::Days::new(3);
+        db.unlock_achievement("first_test", first).unwrap();
+        db.unlock_achievement("first_test", Utc::now()).unwrap();
+        let unlocked = db.get_achievements().unwrap();
+        assert_eq!(unlocked.len(), 1);
+        assert_eq!(unlocked["first_test"].timestamp(), first.timestamp());
+    }
+
+    /// A version 1 `test_results` table, timestamps as RFC 3339 text, with
+    /// one row whose timestamp isn't a date
+    fn create_v1_results(conn: &Connection) {
+        conn.execute_batch(
+            "CREATE TABLE test_results (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 timestamp TEXT NOT NULL,
+                 mode TEXT NOT NULL,
+                 wpm REAL NOT NULL,
+                 raw_wpm REAL NOT NULL,
+                 accuracy REAL NOT NULL,
+                 consistency REAL NOT NULL,
+                 quote_length INTEGER NOT NULL,
+                 duration_seconds INTEGER NOT NULL
+             );
+             INSERT INTO test_results VALUES
+                 (1, '2026-03-02T10:00:00+00:00', 'short', 50.0, 52.0, 96.0, 80.0, 60, 15),
+                 (2, 'half-written', 'short', 70.0, 72.0, 98.0, 85.0, 60, 12),
+                 (3, '2026-03-03T09:30:00.500+01:00', 'medium', 60.0, 61.0, 97.0, 82.0, 180, 40);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn migration_turns_text_timestamps_into_epoch_ms() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_v1_results(&conn);
+        let db = Database { conn };
+        db.init_tables().unwrap();
+
+        let column_type: String = db
+            .conn
+            .query_row(
+                "SELECT type FROM pragma_table_info('test_results') WHERE name = 'timestamp'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(column_type, "INTEGER");
+        let stored: Vec<(i64, i64)> = db
+            .conn
+            .prepare("SELECT id, timestamp FROM test_results ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        let millis = |rfc3339: &str| {
+            DateTime::parse_from_rfc3339(rfc3339)
+                .unwrap()
+                .timestamp_millis()
+        };
+        assert_eq!(
+            stored,
+            [
+                (1, millis("2026-03-02T10:00:00+00:00")),
+                (3, millis("2026-03-03T09:30:00.500+01:00"))
+            ]
+        );
+
+        // Running it again is a no-op
+        db.migrate_timestamps().unwrap();
+        assert_eq!(db.count_results(None).unwrap(), 2);
+    }
+
+    #[test]
+    fn fixture_db_with_a_bad_row_sets_it_aside() {
+        let dir = std::env::temp_dir().join(format!("tuitype-db-fixture-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("v1.db");
+        create_v1_results(&Connection::open(&path).unwrap());
+
+        let db = Database::open(&path.to_string_lossy()).unwrap();
+        let results = db
+            .get_results_filtered(None, None, SortOrder::Date, 10, 0)
+            .unwrap();
+        let ids: Vec<Option<i64>> = results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, [Some(3), Some(1)]);
+        assert_eq!(
+            results[1].timestamp,
+            DateTime::parse_from_rfc3339("2026-03-02T10:00:00Z").unwrap()
+        );
+        assert_eq!(
+            db.validate().unwrap(),
+            ValidationReport {
+                set_aside: 1,
+                ..ValidationReport::default()
+            }
+        );
+        let kept: String = db
+            .conn
+            .query_row(
+                "SELECT timestamp FROM malformed_results WHERE id = 2",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(kept, "half-written");
+        // The bad row's id isn't handed out again
+        let id = db
+            .save_result(&result("short", 55.0, Anomaly::None))
+            .unwrap();
+        assert_eq!(id, 4);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn unreadable_timestamps_are_skipped_and_reported() {
+        let db = Database::open_in_memory().unwrap();
+        db.save_result(&result("short", 50.0, Anomaly::None))
+            .unwrap();
+        let bad = db
+            .save_result(&result("short", 60.0, Anomaly::None))
+            .unwrap();
+        // A manual edit after the migration
+        db.conn
+            .execute(
+                "UPDATE test_results SET timestamp = 'yesterday' WHERE id = ?1",
+                [bad],
+            )
+            .unwrap();
+        db.save_result(&TestResult {
+            accuracy: 140.0,
+            ..result("short", 70.0, Anomaly::None)
+        })
+        .unwrap();
+
+        let results = db
+            .get_results_filtered(None, None, SortOrder::Date, 10, 0)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.id != Some(bad)));
+        let report = db.validate().unwrap();
+        assert_eq!(
+            report,
+            ValidationReport {
+                bad_timestamps: 1,
+                set_aside: 0,
+                out_of_range: 1,
+            }
+        );
+        assert_eq!(
+            report.message().as_deref(),
+            Some("Database: 1 result with an unreadable date hidden, 1 result with impossible values")
+        );
+    }
+
+    /// Results at 1, 2, 3... WPM in `mode`, a minute apart, oldest first
+    fn save_rising(db: &Database, mode: &str, count: usize) {
+        let start = Utc::now() - chrono::Duration::days(1);
+        for i in 0..count {
+            db.save_result(&TestResult {
+                timestamp: start + chrono::Duration::minutes(i as i64),
+                ..result(mode, (i + 1) as f64, Anomaly::None)
+            })
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn last_n_average_compares_two_windows() {
+        let db = Database::open_in_memory().unwrap();
+        let empty = db.get_last_n_average(None, 10).unwrap();
+        assert_eq!((empty.last, empty.prior), (None, None));
+
+        save_rising(&db, "short", 25);
+        let form = db.get_last_n_average(None, 10).unwrap();
+        // 16..=25 against 6..=15
+        assert_eq!(form.last, Some(20.5));
+        assert_eq!(form.prior, Some(10.5));
+        assert_eq!(form.trend(), Some(10.0));
+    }
+
+    #[test]
+    fn last_n_average_hides_a_partial_prior_window() {
+        let db = Database::open_in_memory().unwrap();
+        save_rising(&db, "short", 15);
+        let form = db.get_last_n_average(None, 10).unwrap();
+        assert_eq!(form.last, Some(10.5));
+        assert_eq!(form.prior, None);
+        assert_eq!(form.trend(), None);
+    }
+
+    #[test]
+    fn last_n_average_by_mode() {
+        let db = Database::open_in_memory().unwrap();
+        save_rising(&db, "short", 20);
+        save_rising(&db, "long", 5);
+        let short = db.get_last_n_average(Some("short"), 10).unwrap();
+        assert_eq!((short.last, short.prior), (Some(15.5), Some(5.5)));
+        let long = db.get_last_n_average(Some("long"), 10).unwrap();
+        assert_eq!((long.last, long.prior), (Some(3.0), None));
+    }
+}
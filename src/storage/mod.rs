@@ -1,2 +1,5 @@
+pub mod backup;
 pub mod config;
 pub mod db;
+pub mod paths;
+pub mod report;
@@ -0,0 +1,293 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Profile whose files keep the names from before profiles existed
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Environment variables that move the config and data dirs
+pub const CONFIG_DIR_VAR: &str = "TUITYPE_CONFIG_DIR";
+pub const DATA_DIR_VAR: &str = "TUITYPE_DATA_DIR";
+
+/// `--config-dir` and `--data-dir`, set once at startup
+static CLI_DIRS: OnceLock<DirOverrides> = OnceLock::new();
+
+/// Config and data dirs to use instead of the platform's
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DirOverrides {
+    pub config_dir: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+}
+
+impl DirOverrides {
+    /// `TUITYPE_CONFIG_DIR` and `TUITYPE_DATA_DIR`; empty values are ignored
+    pub fn from_env() -> Self {
+        let var = |name| {
+            env::var_os(name)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        };
+        Self {
+            config_dir: var(CONFIG_DIR_VAR),
+            data_dir: var(DATA_DIR_VAR),
+        }
+    }
+
+    /// Each dir from `self`, or else from `fallback`
+    pub fn or(self, fallback: DirOverrides) -> Self {
+        Self {
+            config_dir: self.config_dir.or(fallback.config_dir),
+            data_dir: self.data_dir.or(fallback.data_dir),
+        }
+    }
+}
+
+/// Make the `--config-dir` and `--data-dir` flags apply to every later
+/// `Paths::resolve`; only the first call has any effect
+pub fn set_cli_overrides(overrides: DirOverrides) {
+    CLI_DIRS.set(overrides).ok();
+}
+
+/// Locations of everything TUItype reads or writes on disk
+#[derive(Debug, Clone)]
+pub struct Paths {
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+    /// Whose results and config overrides are in use
+    pub profile: String,
+}
+
+impl Paths {
+    /// The platform's dirs, unless moved by a flag or, failing that, an
+    /// environment variable
+    pub fn resolve() -> Result<Self> {
+        let cli = CLI_DIRS.get().cloned().unwrap_or_default();
+        Self::resolve_from(cli, DirOverrides::from_env(), platform_dirs)
+    }
+
+    /// The dirs in `overrides`, and the platform's for any left unset
+    pub fn with_overrides(overrides: DirOverrides) -> Result<Self> {
+        Self::resolve_from(overrides, DirOverrides::default(), platform_dirs)
+    }
+
+    /// Each dir from `cli`, else from `env`, else from `platform`, which is
+    /// only called when one of them is set by neither
+    pub fn resolve_from(
+        cli: DirOverrides,
+        env: DirOverrides,
+        platform: impl FnOnce() -> Result<(PathBuf, PathBuf)>,
+    ) -> Result<Self> {
+        let overrides = cli.or(env);
+        let (config_dir, data_dir) = match (overrides.config_dir, overrides.data_dir) {
+            (Some(config), Some(data)) => (absolute(&config)?, absolute(&data)?),
+            (config, data) => {
+                let (platform_config, platform_data) = platform()?;
+                (
+                    config.map_or(Ok(platform_config), |dir| absolute(&dir))?,
+                    data.map_or(Ok(platform_data), |dir| absolute(&dir))?,
+                )
+            }
+        };
+
+        Ok(Self {
+            config_dir,
+            data_dir,
+            profile: DEFAULT_PROFILE.to_string(),
+        })
+    }
+
+    /// The same locations for `profile`; see `check_profile_name`
+    pub fn with_profile(mut self, profile: &str) -> Self {
+        self.profile = profile.to_string();
+        self
+    }
+
+    pub fn is_default_profile(&self) -> bool {
+        self.profile == DEFAULT_PROFILE
+    }
+
+    /// Shared by every profile; a profile's own settings go in
+    /// `profile_config_file`
+    pub fn config_file(&self) -> PathBuf {
+        self.config_dir.join("config.toml")
+    }
+
+    /// Settings a profile changes from `config.toml`; `None` for the
+    /// default profile, which writes `config.toml` itself
+    pub fn profile_config_file(&self) -> Option<PathBuf> {
+        (!self.is_default_profile()).then(|| {
+            self.config_dir
+                .join("profiles")
+                .join(format!("{}.toml", self.profile))
+        })
+    }
+
+    pub fn db_file(&self) -> PathBuf {
+        if self.is_default_profile() {
+            self.data_dir.join("typing.db")
+        } else {
+            self.data_dir.join(format!("typing-{}.db", self.profile))
+        }
+    }
+
+    /// Database backups made from the History screen
+    pub fn backups_dir(&self) -> PathBuf {
+        let dir = self.data_dir.join("backups");
+        if self.is_default_profile() {
+            dir
+        } else {
+            dir.join(&self.profile)
+        }
+    }
+
+    /// Weekly reports written from the Stats screen or `--report`
+    pub fn reports_dir(&self) -> PathBuf {
+        let dir = self.data_dir.join("reports");
+        if self.is_default_profile() {
+            dir
+        } else {
+            dir.join(&self.profile)
+        }
+    }
+
+    /// User theme files (`*.toml`)
+    pub fn themes_dir(&self) -> PathBuf {
+        self.config_dir.join("themes")
+    }
+
+    /// User quote files
+    pub fn quotes_dir(&self) -> PathBuf {
+        self.data_dir.join("quotes")
+    }
+
+    /// The default profile followed by every profile with a database in the
+    /// data dir, by name
+    pub fn profiles(&self) -> Vec<String> {
+        let mut profiles: Vec<String> = fs::read_dir(&self.data_dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "db" {
+                    return None;
+                }
+                let name = path.file_stem()?.to_str()?.strip_prefix("typing-")?;
+                check_profile_name(name).ok()?;
+                Some(name.to_string())
+            })
+            .filter(|name| name != DEFAULT_PROFILE)
+            .collect();
+        profiles.sort();
+        profiles.insert(0, DEFAULT_PROFILE.to_string());
+        profiles
+    }
+}
+
+/// The platform's config and data dirs for TUItype
+fn platform_dirs() -> Result<(PathBuf, PathBuf)> {
+    let dirs =
+        ProjectDirs::from("", "", "TypingTUI").ok_or_else(|| anyhow::anyhow!("No home dir"))?;
+    Ok((
+        dirs.config_dir().to_path_buf(),
+        dirs.data_dir().to_path_buf(),
+    ))
+}
+
+/// `dir` relative to the working directory, so it stays put if that changes
+fn absolute(dir: &Path) -> Result<PathBuf> {
+    std::path::absolute(dir).with_context(|| format!("Invalid directory {}", dir.display()))
+}
+
+/// Profile names become file names, so they are kept to letters, digits,
+/// `-` and `_`
+pub fn check_profile_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > 32 {
+        anyhow::bail!("profile names are 1 to 32 characters");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "profile `{}` may only use letters, digits, `-` and `_`",
+            name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dirs(config: Option<&str>, data: Option<&str>) -> DirOverrides {
+        DirOverrides {
+            config_dir: config.map(PathBuf::from),
+            data_dir: data.map(PathBuf::from),
+        }
+    }
+
+    fn platform() -> Result<(PathBuf, PathBuf)> {
+        Ok((
+            PathBuf::from("/platform/config"),
+            PathBuf::from("/platform/data"),
+        ))
+    }
+
+    #[test]
+    fn flags_win_over_env_over_platform() {
+        // Every combination of the four overrides being set
+        for set in 0..16 {
+            let given = |bit: u8, dir: &'static str| (set & bit != 0).then_some(dir);
+            let cli = dirs(given(1, "/cli/config"), given(2, "/cli/data"));
+            let env = dirs(given(4, "/env/config"), given(8, "/env/data"));
+            let paths = Paths::resolve_from(cli, env, platform).unwrap();
+
+            let config_dir = match set {
+                s if s & 1 != 0 => "/cli/config",
+                s if s & 4 != 0 => "/env/config",
+                _ => "/platform/config",
+            };
+            let data_dir = match set {
+                s if s & 2 != 0 => "/cli/data",
+                s if s & 8 != 0 => "/env/data",
+                _ => "/platform/data",
+            };
+            assert_eq!(paths.config_dir, PathBuf::from(config_dir), "{:04b}", set);
+            assert_eq!(paths.data_dir, PathBuf::from(data_dir), "{:04b}", set);
+            assert_eq!(paths.profile, DEFAULT_PROFILE);
+        }
+    }
+
+    #[test]
+    fn platform_dirs_are_only_needed_for_unset_dirs() {
+        let no_home = || -> Result<(PathBuf, PathBuf)> { anyhow::bail!("No home dir") };
+        let paths = Paths::resolve_from(
+            dirs(Some("/cli/config"), None),
+            dirs(None, Some("/env/data")),
+            no_home,
+        )
+        .unwrap();
+        assert_eq!(paths.config_dir, PathBuf::from("/cli/config"));
+        assert_eq!(paths.data_dir, PathBuf::from("/env/data"));
+        assert!(
+            Paths::resolve_from(dirs(Some("/cli/config"), None), dirs(None, None), no_home)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn relative_dirs_become_absolute() {
+        let paths = Paths::resolve_from(
+            dirs(Some("config"), Some("data")),
+            dirs(None, None),
+            platform,
+        )
+        .unwrap();
+        assert!(paths.config_dir.is_absolute());
+        assert!(paths.data_dir.ends_with("data"));
+    }
+}
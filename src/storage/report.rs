@@ -0,0 +1,270 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, Days, NaiveDate};
+
+use crate::models::{format_duration, local_midnight, AggregatePeriod, PeriodAggregate};
+use crate::storage::db::Database;
+
+/// Keys listed under "Most-missed keys"
+const MISSED_KEYS: usize = 5;
+
+/// Sparkline bars, lowest to highest
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Everything a weekly report is written from
+#[derive(Debug, Clone)]
+pub struct WeekData {
+    /// Monday the week starts on
+    pub start: NaiveDate,
+    /// Totals for the week; `None` when nothing was typed
+    pub week: Option<PeriodAggregate>,
+    /// Totals for the week before, to show the trend against
+    pub previous: Option<PeriodAggregate>,
+    /// The week's days that have tests
+    pub days: Vec<PeriodAggregate>,
+    /// (key, hits, misses) for the week, most missed first
+    pub missed_keys: Vec<(char, u64, u64)>,
+}
+
+/// The Monday of the week `date` falls in
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday() as u64)
+}
+
+/// Gather the week containing `today` from the database
+pub fn fetch(db: &Database, today: NaiveDate) -> Result<WeekData> {
+    let start = week_start(today);
+    let previous_start = start - Days::new(7);
+    // The two latest weeks with results include this one and the one
+    // before whenever either has any
+    let weeks = db.get_aggregates(AggregatePeriod::Week, 2)?;
+    let find_week = |monday: NaiveDate| weeks.iter().find(|w| w.start == monday).cloned();
+    let days = db
+        .get_aggregates(AggregatePeriod::Day, 7)?
+        .into_iter()
+        .filter(|day| day.start >= start)
+        .collect();
+    Ok(WeekData {
+        start,
+        week: find_week(start),
+        previous: find_week(previous_start),
+        days,
+        missed_keys: db.get_missed_keys(local_midnight(start), MISSED_KEYS)?,
+    })
+}
+
+/// Write the report for the week containing `today` to
+/// `dir/week-YYYY-WW.md`, replacing an earlier one for the same week
+pub fn write(db: &Database, dir: &Path, today: NaiveDate) -> Result<PathBuf> {
+    let data = fetch(db, today)?;
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let week = data.start.iso_week();
+    let path = dir.join(format!("week-{}-{:02}.md", week.year(), week.week()));
+    fs::write(&path, render(&data))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// The report as Markdown
+pub fn render(data: &WeekData) -> String {
+    let end = data.start + Days::new(6);
+    let mut out = format!(
+        "# Typing report: week {} of {}\n\n{} to {}\n\n",
+        data.start.iso_week().week(),
+        data.start.iso_week().year(),
+        data.start.format("%a %-d %b"),
+        end.format("%a %-d %b %Y"),
+    );
+
+    let Some(week) = &data.week else {
+        out.push_str("No tests this week.\n");
+        return out;
+    };
+
+    let _ = writeln!(out, "## Summary\n");
+    let _ = writeln!(out, "- Tests: {}", week.tests);
+    let _ = writeln!(
+        out,
+        "- Time typing: {}",
+        format_duration((week.minutes * 60.0).round() as i64)
+    );
+    let _ = writeln!(
+        out,
+        "- Average WPM: {:.1}{}",
+        week.avg_wpm,
+        versus(week.avg_wpm, data.previous.as_ref().map(|p| p.avg_wpm))
+    );
+    let _ = writeln!(out, "- Best WPM: {:.1}", week.best_wpm);
+    let _ = writeln!(
+        out,
+        "- Accuracy: {:.1}%{}",
+        week.avg_accuracy,
+        versus(
+            week.avg_accuracy,
+            data.previous.as_ref().map(|p| p.avg_accuracy)
+        )
+    );
+
+    let _ = writeln!(out, "\n## Days\n");
+    let _ = writeln!(out, "`{}` Mon to Sun, by average WPM\n", sparkline(data));
+    let _ = writeln!(out, "| Day | Tests | Avg WPM | Accuracy |");
+    let _ = writeln!(out, "| --- | ---: | ---: | ---: |");
+    let mut days: Vec<&PeriodAggregate> = data.days.iter().collect();
+    days.sort_by_key(|day| day.start);
+    for day in days {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {:.1} | {:.1}% |",
+            day.start.format("%a %-d"),
+            day.tests,
+            day.avg_wpm,
+            day.avg_accuracy
+        );
+    }
+
+    if !data.missed_keys.is_empty() {
+        let _ = writeln!(out, "\n## Most-missed keys\n");
+        let _ = writeln!(out, "| Key | Misses | Miss rate |");
+        let _ = writeln!(out, "| --- | ---: | ---: |");
+        for &(key, hits, misses) in &data.missed_keys {
+            let rate = misses as f64 / (hits + misses).max(1) as f64 * 100.0;
+            let _ = writeln!(out, "| {} | {} | {:.1}% |", key_label(key), misses, rate);
+        }
+    }
+    out
+}
+
+/// " (▲ 2.1 vs last week)", or nothing without an earlier week
+fn versus(value: f64, previous: Option<f64>) -> String {
+    match previous {
+        Some(previous) => {
+            let change = value - previous;
+            let arrow = if change >= 0.0 { '▲' } else { '▼' };
+            format!(" ({} {:.1} vs last week)", arrow, change.abs())
+        }
+        None => String::new(),
+    }
+}
+
+/// One bar per day from Monday, scaled to the week's fastest day; `·` for
+/// days without tests
+fn sparkline(data: &WeekData) -> String {
+    let max = data.days.iter().map(|d| d.avg_wpm).fold(0.0, f64::max);
+    (0..7)
+        .map(|offset| {
+            let date = data.start + Days::new(offset);
+            match data.days.iter().find(|d| d.start == date) {
+                Some(day) if max > 0.0 => {
+                    let level = (day.avg_wpm / max * (BARS.len() - 1) as f64).round();
+                    BARS[level as usize]
+                }
+                Some(_) => BARS[0],
+                None => '·',
+            }
+        })
+        .collect()
+}
+
+/// Keys that would vanish or break the table, named instead
+fn key_label(key: char) -> String {
+    match key {
+        ' ' => "space".to_string(),
+        '\n' => "enter".to_string(),
+        '\t' => "tab".to_string(),
+        '|' => "`\\|`".to_string(),
+        '`' => "backtick".to_string(),
+        c => format!("`{}`", c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    fn aggregate(start: NaiveDate, tests: i64, avg_wpm: f64, avg_accuracy: f64) -> PeriodAggregate {
+        PeriodAggregate {
+            start,
+            tests,
+            avg_wpm,
+            best_wpm: avg_wpm + 10.0,
+            avg_accuracy,
+            minutes: tests as f64 * 1.5,
+        }
+    }
+
+    /// Monday 2 March 2026 to Sunday 8 March
+    fn empty_week() -> WeekData {
+        WeekData {
+            start: date(2),
+            week: None,
+            previous: None,
+            days: Vec::new(),
+            missed_keys: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn week_starts_on_monday() {
+        assert_eq!(week_start(date(2)), date(2));
+        assert_eq!(week_start(date(5)), date(2));
+        assert_eq!(week_start(date(8)), date(2));
+        assert_eq!(week_start(date(9)), date(9));
+    }
+
+    #[test]
+    fn zero_test_week_says_so() {
+        assert_eq!(
+            render(&empty_week()),
+            "# Typing report: week 10 of 2026\n\n\
+             Mon 2 Mar to Sun 8 Mar 2026\n\n\
+             No tests this week.\n"
+        );
+    }
+
+    #[test]
+    fn week_lists_totals_days_and_missed_keys() {
+        let data = WeekData {
+            week: Some(aggregate(date(2), 4, 60.0, 95.0)),
+            previous: Some(aggregate(date(2) - Days::new(7), 2, 62.5, 94.0)),
+            // Out of order, as the database may return them
+            days: vec![
+                aggregate(date(4), 1, 40.0, 90.0),
+                aggregate(date(2), 3, 80.0, 97.0),
+            ],
+            missed_keys: vec![('|', 1, 3), (' ', 99, 1)],
+            ..empty_week()
+        };
+        let report = render(&data);
+        assert!(report.contains("- Tests: 4\n"));
+        assert!(report.contains("- Time typing: 6m 0s\n"));
+        assert!(report.contains("- Average WPM: 60.0 (▼ 2.5 vs last week)\n"));
+        assert!(report.contains("- Best WPM: 70.0\n"));
+        assert!(report.contains("- Accuracy: 95.0% (▲ 1.0 vs last week)\n"));
+        assert!(report.contains("`█·▅····` Mon to Sun"));
+        let monday = report.find("| Mon 2 | 3 | 80.0 | 97.0% |").unwrap();
+        let wednesday = report.find("| Wed 4 | 1 | 40.0 | 90.0% |").unwrap();
+        assert!(monday < wednesday);
+        assert!(report.contains("| `\\|` | 3 | 75.0% |"));
+        assert!(report.contains("| space | 1 | 1.0% |"));
+    }
+
+    #[test]
+    fn first_week_has_no_trend_or_missed_keys() {
+        let data = WeekData {
+            week: Some(aggregate(date(2), 1, 50.0, 100.0)),
+            days: vec![aggregate(date(8), 1, 50.0, 100.0)],
+            ..empty_week()
+        };
+        let report = render(&data);
+        assert!(report.contains("- Average WPM: 50.0\n"));
+        assert!(report.contains("`······█`"));
+        assert!(!report.contains("Most-missed keys"));
+    }
+}
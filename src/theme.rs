@@ -1,11 +1,30 @@
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
 use ratatui::style::Color;
 
+use crate::keyboard::FingerKind;
+use crate::storage::paths::Paths;
+
+const BUILTIN_THEMES: [&str; 6] = [
+    "dark",
+    "light",
+    "nord",
+    "dracula",
+    "solarized",
+    "catppuccin-mocha",
+];
+
 #[derive(Debug, Clone)]
 pub struct Theme {
     pub name: String,
     pub correct_char: Color,
     pub incorrect_char: Color,
     pub untyped_char: Color,
+    /// Underline of untyped words that are often mistyped
+    pub caution_char: Color,
     pub cursor_fg: Color,
     pub cursor_bg: Color,
     pub wpm_color: Color,
@@ -27,7 +46,97 @@ pub struct Theme {
 }
 
 impl Theme {
+    /// Both hands share a color per finger, mirrored around the middle
+    pub fn finger_color(&self, kind: FingerKind) -> Color {
+        match kind {
+            FingerKind::Pinky => self.finger_pinky,
+            FingerKind::Ring => self.finger_ring,
+            FingerKind::Middle => self.finger_middle,
+            FingerKind::Index => self.finger_index,
+            FingerKind::Thumb => self.finger_thumb,
+        }
+    }
+
+    /// Resolve a theme by name, falling back to dark if a user theme fails to load
+    #[allow(dead_code)]
     pub fn from_name(name: &str) -> Self {
+        Self::try_from_name(name).unwrap_or_else(|_| Self::dark())
+    }
+
+    /// Resolve a theme by name, checking user themes before the builtins.
+    /// Errors only when a matching user theme file exists but can't be parsed.
+    pub fn try_from_name(name: &str) -> Result<Self> {
+        if let Some((theme_name, path)) = user_theme_files()
+            .into_iter()
+            .find(|(theme_name, _)| theme_name.eq_ignore_ascii_case(name))
+        {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read theme {}", path.display()))?;
+            let mut theme = Self::from_toml_str(&content)
+                .with_context(|| format!("Invalid theme {}", path.display()))?;
+            theme.name = theme_name;
+            return Ok(theme);
+        }
+
+        Ok(Self::builtin(name))
+    }
+
+    /// Parse a theme file. Colors are hex strings (`"#a6e3a1"`) or color names
+    /// (`"darkgray"`); any field left out keeps the dark theme's color.
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let table: toml::Table = toml::from_str(content)?;
+        let mut theme = Self::dark();
+
+        for (key, value) in &table {
+            if key == "name" {
+                let name = value
+                    .as_str()
+                    .ok_or_else(|| anyhow!("`name` must be a string"))?;
+                theme.name = name.to_string();
+                continue;
+            }
+
+            let slot = theme
+                .color_mut(key)
+                .ok_or_else(|| anyhow!("Unknown theme field `{}`", key))?;
+            let raw = value
+                .as_str()
+                .ok_or_else(|| anyhow!("`{}` must be a color string", key))?;
+            *slot = parse_color(raw).with_context(|| format!("Bad value for `{}`", key))?;
+        }
+
+        Ok(theme)
+    }
+
+    fn color_mut(&mut self, field: &str) -> Option<&mut Color> {
+        let color = match field {
+            "correct_char" => &mut self.correct_char,
+            "incorrect_char" => &mut self.incorrect_char,
+            "untyped_char" => &mut self.untyped_char,
+            "caution_char" => &mut self.caution_char,
+            "cursor_fg" => &mut self.cursor_fg,
+            "cursor_bg" => &mut self.cursor_bg,
+            "wpm_color" => &mut self.wpm_color,
+            "accuracy_color" => &mut self.accuracy_color,
+            "error_color" => &mut self.error_color,
+            "mode_color" => &mut self.mode_color,
+            "border_color" => &mut self.border_color,
+            "title_color" => &mut self.title_color,
+            "success_color" => &mut self.success_color,
+            "keyboard_key" => &mut self.keyboard_key,
+            "keyboard_key_text" => &mut self.keyboard_key_text,
+            "current_key_highlight" => &mut self.current_key_highlight,
+            "finger_pinky" => &mut self.finger_pinky,
+            "finger_ring" => &mut self.finger_ring,
+            "finger_middle" => &mut self.finger_middle,
+            "finger_index" => &mut self.finger_index,
+            "finger_thumb" => &mut self.finger_thumb,
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    fn builtin(name: &str) -> Self {
         match name.to_lowercase().as_str() {
             "dark" => Self::dark(),
             "light" => Self::light(),
@@ -45,6 +154,7 @@ impl Theme {
             correct_char: Color::Green,
             incorrect_char: Color::Red,
             untyped_char: Color::DarkGray,
+            caution_char: Color::Rgb(200, 150, 60),
             cursor_fg: Color::White,
             cursor_bg: Color::DarkGray,
             wpm_color: Color::Cyan,
@@ -71,6 +181,7 @@ impl Theme {
             correct_char: Color::Green,
             incorrect_char: Color::Red,
             untyped_char: Color::Gray,
+            caution_char: Color::Rgb(210, 120, 0),
             cursor_fg: Color::Black,
             cursor_bg: Color::Gray,
             wpm_color: Color::Blue,
@@ -97,6 +208,7 @@ impl Theme {
             correct_char: Color::Rgb(163, 190, 140), // Nord14 - green
             incorrect_char: Color::Rgb(191, 97, 106), // Nord11 - red
             untyped_char: Color::Rgb(76, 86, 106),   // Nord3 - dark gray
+            caution_char: Color::Rgb(208, 135, 112), // Nord12 - orange
             cursor_fg: Color::Rgb(236, 239, 244),    // Nord6 - white
             cursor_bg: Color::Rgb(76, 86, 106),      // Nord3
             wpm_color: Color::Rgb(136, 192, 208),    // Nord8 - cyan
@@ -123,6 +235,7 @@ impl Theme {
             correct_char: Color::Rgb(80, 250, 123),    // Green
             incorrect_char: Color::Rgb(255, 85, 85),   // Red
             untyped_char: Color::Rgb(98, 114, 164),    // Comment gray
+            caution_char: Color::Rgb(255, 184, 108),   // Orange
             cursor_fg: Color::Rgb(248, 248, 242),      // Foreground
             cursor_bg: Color::Rgb(68, 71, 90),         // Current line
             wpm_color: Color::Rgb(139, 233, 253),      // Cyan
@@ -149,6 +262,7 @@ impl Theme {
             correct_char: Color::Rgb(133, 153, 0),   // Green
             incorrect_char: Color::Rgb(220, 50, 47), // Red
             untyped_char: Color::Rgb(88, 110, 117),  // Base01
+            caution_char: Color::Rgb(203, 75, 22),   // Orange
             cursor_fg: Color::Rgb(253, 246, 227),    // Base3
             cursor_bg: Color::Rgb(88, 110, 117),     // Base01
             wpm_color: Color::Rgb(42, 161, 152),     // Cyan
@@ -175,6 +289,7 @@ impl Theme {
             correct_char: Color::Rgb(166, 227, 161), // green  #a6e3a1
             incorrect_char: Color::Rgb(243, 139, 168), // red    #f38ba8
             untyped_char: Color::Rgb(88, 91, 112),   // surface2 #585b70
+            caution_char: Color::Rgb(250, 179, 135), // peach  #fab387
             cursor_fg: Color::Rgb(205, 214, 244),    // text   #cdd6f4
             cursor_bg: Color::Rgb(49, 50, 68),       // surface0 #313244
             wpm_color: Color::Rgb(148, 226, 213),    // teal   #94e2d5
@@ -195,14 +310,105 @@ impl Theme {
         }
     }
 
-    pub fn available_themes() -> Vec<&'static str> {
-        vec![
-            "dark",
-            "light",
-            "nord",
-            "dracula",
-            "solarized",
-            "catppuccin-mocha",
-        ]
+    /// Builtin theme names followed by any user themes that don't shadow them
+    pub fn available_themes() -> Vec<String> {
+        let mut themes: Vec<String> = BUILTIN_THEMES.iter().map(|t| t.to_string()).collect();
+        for (name, _) in user_theme_files() {
+            if !themes.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+                themes.push(name);
+            }
+        }
+        themes
+    }
+}
+
+fn parse_color(raw: &str) -> Result<Color> {
+    let raw = raw.trim();
+    if let Some(hex) = raw.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("`{}` is not a #rrggbb hex color", raw);
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+        return Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    Color::from_str(raw).map_err(|_| anyhow!("`{}` is not a known color", raw))
+}
+
+/// User theme files as (name, path), sorted by name. The name is the file stem.
+fn user_theme_files() -> Vec<(String, PathBuf)> {
+    let Some(dir) = Paths::resolve().ok().map(|paths| paths.themes_dir()) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some((name, path))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_and_named_colors_parse() {
+        assert_eq!(parse_color("#ff8000").unwrap(), Color::Rgb(255, 128, 0));
+        assert_eq!(parse_color(" #0A0b0C ").unwrap(), Color::Rgb(10, 11, 12));
+        assert_eq!(parse_color("red").unwrap(), Color::Red);
+        assert_eq!(parse_color("darkgray").unwrap(), Color::DarkGray);
+    }
+
+    #[test]
+    fn bad_colors_are_rejected() {
+        for raw in [
+            "#fff",
+            "#ff80001",
+            "#gg0000",
+            "ff8000",
+            "#",
+            "not-a-color",
+            "",
+        ] {
+            assert!(parse_color(raw).is_err(), "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn missing_fields_default_to_dark() {
+        let theme = Theme::from_toml_str(
+            "name = \"mine\"\n\
+             correct_char = \"#00ff00\"\n\
+             cursor_bg = \"blue\"\n",
+        )
+        .unwrap();
+        let dark = Theme::dark();
+        assert_eq!(theme.name, "mine");
+        assert_eq!(theme.correct_char, Color::Rgb(0, 255, 0));
+        assert_eq!(theme.cursor_bg, Color::Blue);
+        assert_eq!(theme.incorrect_char, dark.incorrect_char);
+        assert_eq!(theme.finger_thumb, dark.finger_thumb);
+
+        let empty = Theme::from_toml_str("").unwrap();
+        assert_eq!(empty.name, "dark");
+        assert_eq!(empty.wpm_color, dark.wpm_color);
+    }
+
+    #[test]
+    fn bad_theme_files_name_the_problem() {
+        let error = |toml: &str| format!("{:#}", Theme::from_toml_str(toml).unwrap_err());
+        assert!(error("wpm_color = \"#12345\"").contains("wpm_color"));
+        assert!(error("sparkle = \"red\"").contains("Unknown theme field `sparkle`"));
+        assert!(error("wpm_color = 3").contains("must be a color string"));
+        assert!(error("name = 3").contains("`name` must be a string"));
+        assert!(Theme::from_toml_str("wpm_color = ").is_err());
     }
 }
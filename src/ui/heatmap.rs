@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::keyboard::KeyboardStyle;
+use crate::theme::Theme;
+use crate::ui::keyboard::{render_keyboard, KeyShading};
+
+// Error rate at which a key is drawn fully red
+const MAX_ERROR_RATE: f64 = 0.25;
+
+pub struct HeatmapView {
+    key_colors: HashMap<char, Color>,
+    worst_keys: Vec<(char, f64)>,
+    keyboard_style: KeyboardStyle,
+}
+
+impl HeatmapView {
+    pub fn new(key_stats: HashMap<char, (u64, u64)>, keyboard_style: KeyboardStyle) -> Self {
+        // Fold uppercase into the base key so 'T' and 't' share one key on the board
+        let mut folded: HashMap<char, (u64, u64)> = HashMap::new();
+        for (key, (hits, misses)) in key_stats {
+            let entry = folded.entry(key.to_ascii_lowercase()).or_insert((0, 0));
+            entry.0 += hits;
+            entry.1 += misses;
+        }
+
+        let mut key_colors = HashMap::new();
+        let mut worst_keys = Vec::new();
+        for (key, (hits, misses)) in folded {
+            let total = hits + misses;
+            if total == 0 {
+                continue;
+            }
+            let rate = misses as f64 / total as f64;
+            key_colors.insert(key, heat_color(rate));
+            if misses > 0 {
+                worst_keys.push((key, rate));
+            }
+        }
+        worst_keys.sort_by(|a, b| b.1.total_cmp(&a.1));
+        worst_keys.truncate(5);
+
+        Self {
+            key_colors,
+            worst_keys,
+            keyboard_style,
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border_color))
+            .title(" ═══ KEY ERROR HEATMAP ═══ ")
+            .title_style(Style::default().fg(theme.title_color))
+            .title_alignment(Alignment::Center);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(2),  // summary
+                Constraint::Length(11), // keyboard
+                Constraint::Length(2),  // legend
+                Constraint::Min(0),
+                Constraint::Length(1), // help
+            ])
+            .split(inner);
+
+        let summary = if self.key_colors.is_empty() {
+            Line::from(Span::styled(
+                "No key data yet - finish a test to start collecting",
+                Style::default().fg(Color::DarkGray),
+            ))
+        } else if self.worst_keys.is_empty() {
+            Line::from(Span::styled(
+                "No missed keys so far!",
+                Style::default().fg(theme.success_color),
+            ))
+        } else {
+            let mut spans = vec![Span::styled(
+                "Most missed: ",
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )];
+            for (i, (key, rate)) in self.worst_keys.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw("  "));
+                }
+                let label = if *key == ' ' {
+                    "space".to_string()
+                } else {
+                    key.to_string()
+                };
+                spans.push(Span::styled(
+                    format!("{} {:.1}%", label, rate * 100.0),
+                    Style::default()
+                        .fg(heat_color(*rate))
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
+            Line::from(spans)
+        };
+        frame.render_widget(
+            Paragraph::new(summary).alignment(Alignment::Center),
+            chunks[0],
+        );
+
+        render_keyboard(
+            chunks[1],
+            frame.buffer_mut(),
+            None,
+            &[],
+            KeyShading::Heat(&self.key_colors),
+            self.keyboard_style,
+            theme,
+        );
+
+        // Legend: gradient swatches from 0% to MAX_ERROR_RATE, plus the no-data color
+        let mut legend = vec![Span::styled("0% ", Style::default().fg(Color::DarkGray))];
+        for step in 0..=5 {
+            let rate = MAX_ERROR_RATE * step as f64 / 5.0;
+            legend.push(Span::styled("██", Style::default().fg(heat_color(rate))));
+        }
+        legend.push(Span::styled(
+            format!(" {:.0}%+   ", MAX_ERROR_RATE * 100.0),
+            Style::default().fg(Color::DarkGray),
+        ));
+        legend.push(Span::styled("██", Style::default().fg(theme.keyboard_key)));
+        legend.push(Span::styled(
+            " no data",
+            Style::default().fg(Color::DarkGray),
+        ));
+        frame.render_widget(
+            Paragraph::new(Line::from(legend)).alignment(Alignment::Center),
+            chunks[2],
+        );
+
+        let help = Line::from(vec![
+            Span::styled("Press ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                "ESC",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(" to go back", Style::default().fg(Color::DarkGray)),
+        ]);
+        frame.render_widget(Paragraph::new(help).alignment(Alignment::Center), chunks[4]);
+    }
+}
+
+/// Map an error rate onto a green → yellow → red gradient
+fn heat_color(rate: f64) -> Color {
+    let t = (rate / MAX_ERROR_RATE).clamp(0.0, 1.0);
+    let (green, yellow, red) = (
+        (80.0, 200.0, 80.0),
+        (230.0, 200.0, 60.0),
+        (220.0, 50.0, 50.0),
+    );
+    let (from, to, t) = if t < 0.5 {
+        (green, yellow, t * 2.0)
+    } else {
+        (yellow, red, (t - 0.5) * 2.0)
+    };
+    let lerp = |a: f64, b: f64| (a + (b - a) * t).round() as u8;
+    Color::Rgb(lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2))
+}
@@ -1,36 +1,186 @@
-use crate::models::TestResult;
+use crate::core::anomaly::Anomaly;
+use crate::core::metrics::{SpeedUnit, CHARS_PER_WORD, CONSISTENCY_VERSION};
+use crate::models::{AppConfig, DateWindow, FilteredStats, SortOrder, TestResult};
+use crate::theme::Theme;
+use crate::ui::marquee::Marquee;
+use crate::ui::metric_style::{style_for_metric, Thresholds};
+use chrono::{DateTime, Local, Utc};
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
     Frame,
 };
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Results fetched per query; more are loaded when scrolling past the end
+pub const HISTORY_PAGE: usize = 50;
+
+/// Rows the selection moves for PageUp/PageDown, matching the scroll window
+pub const PAGE_ROWS: usize = 10;
+
+/// Modes the `m` key cycles through after "all"
+const MODE_FILTERS: [&str; 16] = [
+    "short",
+    "medium",
+    "long",
+    "zen",
+    "continuous",
+    "practice",
+    "code_rust",
+    "code_python",
+    "code_javascript",
+    "drill_homerow",
+    "drill_lefthand",
+    "drill_righthand",
+    "drill_toprow",
+    "drill_custom",
+    "custom",
+    "file",
+];
+
+/// How long a backup or restore message stays in the footer
+const STATUS_DURATION: Duration = Duration::from_secs(4);
+
+/// An action that throws away results, waiting for y/n
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingDelete {
+    Selected,
+    All,
+    /// Replace everything with this backup
+    Restore(PathBuf),
+}
 
 pub struct HistoryView {
     pub results: Vec<TestResult>,
     pub selected: usize,
     pub scroll_offset: usize,
+    pub pending_delete: Option<PendingDelete>,
+    /// Only results in this mode; `None` shows all
+    pub mode_filter: Option<&'static str>,
+    pub sort: SortOrder,
+    /// Only results this recent
+    pub window: DateWindow,
+    /// Every result matching the filter in the database, loaded or not
+    pub summary: FilteredStats,
+    /// Outcome of the last backup or restore: message, success, when
+    status: Option<(String, bool, Instant)>,
+    /// "2h ago" rather than a full date for recent results
+    relative_time: bool,
+    speed_unit: SpeedUnit,
+    accuracy_thresholds: Thresholds,
 }
 
 impl HistoryView {
-    pub fn new(results: Vec<TestResult>) -> Self {
+    pub fn new(results: Vec<TestResult>, summary: FilteredStats, relative_time: bool) -> Self {
         Self {
+            relative_time,
+            summary,
             results,
             selected: 0,
             scroll_offset: 0,
+            pending_delete: None,
+            mode_filter: None,
+            sort: SortOrder::Date,
+            window: DateWindow::All,
+            status: None,
+            speed_unit: SpeedUnit::Wpm5,
+            accuracy_thresholds: Thresholds::accuracy(&AppConfig::default()),
+        }
+    }
+
+    pub fn set_speed_unit(&mut self, unit: SpeedUnit) {
+        self.speed_unit = unit;
+    }
+
+    /// Colors each row's accuracy
+    pub fn set_accuracy_thresholds(&mut self, thresholds: Thresholds) {
+        self.accuracy_thresholds = thresholds;
+    }
+
+    /// Show `message` in the footer for a few seconds
+    pub fn set_status(&mut self, message: String, ok: bool) {
+        self.status = Some((message, ok, Instant::now()));
+    }
+
+    /// all -> short -> medium -> ... -> all
+    pub fn cycle_mode_filter(&mut self) {
+        self.mode_filter = match self.mode_filter {
+            None => Some(MODE_FILTERS[0]),
+            Some(mode) => MODE_FILTERS
+                .iter()
+                .position(|&m| m == mode)
+                .and_then(|i| MODE_FILTERS.get(i + 1))
+                .copied(),
+        };
+    }
+
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+    }
+
+    /// Show the first page of a new filter or sort from the top
+    pub fn replace_results(&mut self, results: Vec<TestResult>, summary: FilteredStats) {
+        self.summary = summary;
+        self.results = results;
+        self.selected = 0;
+        self.scroll_offset = 0;
+        self.pending_delete = None;
+    }
+
+    /// Add the next page below the loaded results
+    pub fn append_results(&mut self, results: Vec<TestResult>) {
+        self.results.extend(results);
+    }
+
+    /// Moving `rows` down would pass the loaded results while the database has more.
+    /// The app loop answers by fetching the next page with `append_results`.
+    pub fn needs_more(&self, rows: usize) -> bool {
+        self.results.len() < self.summary.count && self.selected + rows >= self.results.len()
+    }
+
+    /// Swap in a refreshed list, keeping the selection in range
+    pub fn set_results(&mut self, results: Vec<TestResult>, summary: FilteredStats) {
+        self.summary = summary;
+        self.results = results;
+        self.selected = self.selected.min(self.results.len().saturating_sub(1));
+        self.scroll_offset = self.scroll_offset.min(self.selected);
+        self.pending_delete = None;
+    }
+
+    /// Keep the selection on screen after the list shrinks to `rows` rows,
+    /// e.g. when the terminal is resized
+    pub fn fit_rows(&mut self, rows: usize) {
+        let rows = rows.clamp(1, PAGE_ROWS);
+        if self.selected >= self.scroll_offset + rows {
+            self.scroll_offset = self.selected + 1 - rows;
         }
+        self.scroll_offset = self.scroll_offset.min(self.selected);
     }
 
     pub fn next(&mut self) {
         if self.selected < self.results.len().saturating_sub(1) {
             self.selected += 1;
-            if self.selected >= self.scroll_offset + 10 {
+            if self.selected >= self.scroll_offset + PAGE_ROWS {
                 self.scroll_offset += 1;
             }
         }
     }
 
+    pub fn next_page(&mut self) {
+        for _ in 0..PAGE_ROWS {
+            self.next();
+        }
+    }
+
+    pub fn previous_page(&mut self) {
+        for _ in 0..PAGE_ROWS {
+            self.previous();
+        }
+    }
+
     pub fn previous(&mut self) {
         if self.selected > 0 {
             self.selected -= 1;
@@ -40,7 +190,35 @@ impl HistoryView {
         }
     }
 
-    pub fn draw(&self, frame: &mut Frame, area: Rect) {
+    pub fn selected_result_mut(&mut self) -> Option<&mut TestResult> {
+        self.results.get_mut(self.selected)
+    }
+
+    /// Draw the list, with the selected result's details beside it when
+    /// `show_details`; a long source there scrolls with `marquee`
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        show_details: bool,
+        marquee: &Marquee,
+        theme: &Theme,
+    ) {
+        let area = if show_details {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .split(area);
+            self.draw_details(frame, chunks[1], marquee);
+            chunks[0]
+        } else {
+            area
+        };
+        let [summary_area, area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+        frame.render_widget(Paragraph::new(self.summary_line()), summary_area);
+
+        let now = Local::now();
         let items: Vec<ListItem> = self
             .results
             .iter()
@@ -51,17 +229,23 @@ impl HistoryView {
                 let line = Line::from(vec![
                     Span::raw(format!(
                         "{:19} ",
-                        result.timestamp.format("%Y-%m-%d %H:%M:%S")
+                        display_time(result.timestamp, now, self.relative_time)
                     )),
                     Span::styled(
-                        format!("{:>6.1} WPM ", result.wpm),
+                        format!(
+                            "{:>6.1} {:<4} ",
+                            self.speed_unit
+                                .from_wpm(result.wpm, result.chars_per_word()),
+                            self.speed_unit.label()
+                        ),
                         Style::default().fg(Color::Cyan),
                     ),
                     Span::styled(
                         format!("{:>5.1}% ", result.accuracy),
-                        Style::default().fg(Color::Yellow),
+                        style_for_metric(result.accuracy, self.accuracy_thresholds, theme),
                     ),
                     Span::raw(format!("[{}]", result.mode)),
+                    anomaly_badge(result),
                 ]);
 
                 let style = if i == self.selected {
@@ -76,12 +260,253 @@ impl HistoryView {
             })
             .collect();
 
+        let status = self
+            .status
+            .as_ref()
+            .filter(|(_, _, at)| at.elapsed() < STATUS_DURATION);
+        let footer = match &self.pending_delete {
+            Some(PendingDelete::Selected) => Line::styled(
+                " Delete this result? y/n ",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Some(PendingDelete::All) => Line::styled(
+                " Delete ALL results? This cannot be undone. y/n ",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Some(PendingDelete::Restore(path)) => Line::styled(
+                format!(
+                    " Replace ALL data with {}? y/n ",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            None => match status {
+                Some((message, ok, _)) => Line::styled(
+                    format!(" {} ", message),
+                    Style::default().fg(if *ok { Color::Green } else { Color::Red }),
+                ),
+                None => Line::from(
+                    " ? = unusual · Enter: details · C: confirm · D: delete · M: mode · S: sort · W: window · B: backup · R: restore ",
+                ),
+            },
+        };
         let list = List::new(items).block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Test History "),
+                .title(format!(
+                    " Test History · {} / {} results · mode: {} · sort: {} · {} ",
+                    thousands((self.selected + 1).min(self.results.len())),
+                    thousands(self.summary.count),
+                    self.mode_filter.unwrap_or("all"),
+                    self.sort.label(),
+                    self.window.label()
+                ))
+                .title_bottom(footer),
         );
 
         frame.render_widget(list, area);
     }
+
+    /// Count, averages and best of everything the filter matches
+    fn summary_line(&self) -> Line<'static> {
+        let summary = &self.summary;
+        if summary.count == 0 {
+            return Line::styled(" No results", Style::default().fg(Color::DarkGray));
+        }
+        // The database only averages 5-character WPM, which has no per-quote word length
+        let unit = match self.speed_unit {
+            SpeedUnit::RealWords => SpeedUnit::Wpm5,
+            unit => unit,
+        };
+        let speed = |wpm| unit.from_wpm(wpm, CHARS_PER_WORD);
+        Line::from(vec![
+            Span::raw(format!(" {} tests · ", thousands(summary.count))),
+            Span::styled(
+                format!("avg {:.1} {}", speed(summary.avg_wpm), unit.label()),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw(" · "),
+            Span::styled(
+                format!("best {:.1} {}", speed(summary.best_wpm), unit.label()),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" · "),
+            Span::styled(
+                format!("avg {:.1}%", summary.avg_accuracy),
+                Style::default().fg(Color::Yellow),
+            ),
+        ])
+    }
+
+    fn draw_details(&self, frame: &mut Frame, area: Rect, marquee: &Marquee) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Details ")
+            .title_bottom(" ↑/↓ select · P replay · ESC close ");
+
+        let Some(result) = self.results.get(self.selected) else {
+            frame.render_widget(Paragraph::new("No result selected").block(block), area);
+            return;
+        };
+
+        let unit = self.speed_unit;
+        let field = |label: &str, value: String, color: Color| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<14}", label),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(value, Style::default().fg(color)),
+            ])
+        };
+
+        let local = result.timestamp.with_timezone(&Local);
+        let status = match result.anomaly {
+            Anomaly::None => "normal",
+            Anomaly::High if result.confirmed => "unusually fast (confirmed)",
+            Anomaly::High => "unusually fast (C to confirm)",
+            Anomaly::Low => "unusually slow",
+        };
+
+        let lines = vec![
+            Line::from(""),
+            field(
+                "Date",
+                local.format("%Y-%m-%d %H:%M:%S %Z").to_string(),
+                Color::White,
+            ),
+            field("Mode", result.mode.clone(), Color::White),
+            field(
+                "Source",
+                marquee.show(
+                    result.source.as_deref().unwrap_or("—"),
+                    // Inside the borders, after the label
+                    area.width.saturating_sub(2 + 14) as usize,
+                ),
+                Color::White,
+            ),
+            field(
+                unit.label(),
+                format!("{:.1}", unit.from_wpm(result.wpm, result.chars_per_word())),
+                Color::Cyan,
+            ),
+            field(
+                &format!("Raw {}", unit.label()),
+                format!(
+                    "{:.1}",
+                    unit.from_wpm(result.raw_wpm, result.chars_per_word())
+                ),
+                Color::Cyan,
+            ),
+            field(
+                "Accuracy",
+                format!("{:.1}%", result.accuracy),
+                Color::Yellow,
+            ),
+            field(
+                "Real accuracy",
+                result
+                    .real_accuracy
+                    .map_or("—".to_string(), |acc| format!("{:.1}%", acc)),
+                Color::Yellow,
+            ),
+            field(
+                "Consistency",
+                match result.consistency {
+                    None => "—".to_string(),
+                    Some(consistency) if result.consistency_version < CONSISTENCY_VERSION => {
+                        format!("{:.1}% (old formula)", consistency)
+                    }
+                    Some(consistency) => format!("{:.1}%", consistency),
+                },
+                Color::Magenta,
+            ),
+            field(
+                "Quote length",
+                format!("{} chars", result.quote_length),
+                Color::White,
+            ),
+            field(
+                "Duration",
+                format!("{}s", result.duration_seconds),
+                Color::White,
+            ),
+            field("Status", status.to_string(), Color::White),
+        ];
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+/// "?" next to results far outside the mode's usual WPM; confirmed runs lose it
+fn anomaly_badge(result: &TestResult) -> Span<'static> {
+    match result.anomaly {
+        Anomaly::High if !result.confirmed => Span::styled(
+            " ?",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Anomaly::Low => Span::styled(" ?", Style::default().fg(Color::DarkGray)),
+        _ => Span::raw(""),
+    }
+}
+
+/// A result's time in the local timezone. With `relative`, results from the
+/// last week read "just now", "5m ago", "2h ago", "yesterday 21:14" or "Mon 09:30".
+fn display_time(timestamp: DateTime<Utc>, now: DateTime<Local>, relative: bool) -> String {
+    let local = timestamp.with_timezone(&Local);
+    if !relative {
+        return local.format("%Y-%m-%d %H:%M:%S").to_string();
+    }
+
+    let ago = now.signed_duration_since(local);
+    let days = (now.date_naive() - local.date_naive()).num_days();
+    if ago.num_seconds() < 0 || days >= 7 {
+        local.format("%Y-%m-%d %H:%M").to_string()
+    } else if ago.num_minutes() < 1 {
+        "just now".to_string()
+    } else if ago.num_hours() < 1 {
+        format!("{}m ago", ago.num_minutes())
+    } else if days == 0 {
+        format!("{}h ago", ago.num_hours())
+    } else if days == 1 {
+        local.format("yesterday %H:%M").to_string()
+    } else {
+        local.format("%a %H:%M").to_string()
+    }
+}
+
+/// 1048 -> "1,048"
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_rows_keeps_the_selection_visible() {
+        let mut view = HistoryView::new(Vec::new(), FilteredStats::default(), false);
+        view.selected = 9;
+        for rows in [10, 4, 1, 0, 7, 25] {
+            view.fit_rows(rows);
+            let visible = rows.clamp(1, PAGE_ROWS);
+            assert!(
+                view.scroll_offset <= view.selected && view.selected < view.scroll_offset + visible,
+                "{} rows: offset {}",
+                rows,
+                view.scroll_offset
+            );
+        }
+    }
 }
@@ -1,170 +1,332 @@
-use ratatui::{buffer::Buffer, layout::Rect, style::Style};
+use std::collections::HashMap;
 
-use crate::keyboard::KeyboardLayout;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+};
+
+use crate::keyboard::{KeyboardLayout, KeyboardStyle};
 use crate::theme::Theme;
 
+/// Narrowest area that fits the full keyboard
+const FULL_MIN_WIDTH: u16 = 50;
+const FULL_HEIGHT: u16 = 11;
+/// Narrowest area that fits the compact keyboard
+const COMPACT_MIN_WIDTH: u16 = 34;
+const COMPACT_HEIGHT: u16 = 6;
+const TOO_SMALL: &str = "keyboard hidden: terminal too small";
+
+/// Rows the keyboard takes in an area `width` columns wide with at most
+/// `max_height` rows to spare: the full keyboard, the compact one, or a
+/// single line explaining it's hidden
+pub fn keyboard_height(width: u16, max_height: u16) -> u16 {
+    if width >= FULL_MIN_WIDTH && max_height >= FULL_HEIGHT {
+        FULL_HEIGHT
+    } else if width >= COMPACT_MIN_WIDTH && max_height >= COMPACT_HEIGHT {
+        COMPACT_HEIGHT
+    } else {
+        max_height.min(1)
+    }
+}
+
+/// How keys are colored besides the current and pressed ones
+pub enum KeyShading<'a> {
+    /// Labels in each key's finger color
+    Fingers,
+    /// A color per key, as in the heatmap; keys without an entry stay neutral
+    Heat(&'a HashMap<char, Color>),
+    /// Finger colors on these keys and the spacebar only; the rest are
+    /// dimmed, as in a drill
+    Only(&'a [char]),
+}
+
 pub fn render_keyboard(
     area: Rect,
     buf: &mut Buffer,
     current_key: Option<char>,
     pressed_keys: &[char],
+    shading: KeyShading,
+    style: KeyboardStyle,
     theme: &Theme,
 ) {
-    if area.width < 50 || area.height < 11 {
-        return;
-    }
-
     let layout = KeyboardLayout::new();
-    let rows = layout.get_rows();
+    // Full keys sit on every other row; compact ones on every row
+    let (placements, v_gap) = if area.width >= FULL_MIN_WIDTH && area.height >= FULL_HEIGHT {
+        (layout.geometry(style), 1u16)
+    } else if area.width >= COMPACT_MIN_WIDTH && area.height >= COMPACT_HEIGHT {
+        (layout.compact_geometry(), 0u16)
+    } else {
+        if area.height > 0 {
+            buf.set_stringn(
+                area.x,
+                area.y,
+                TOO_SMALL,
+                area.width as usize,
+                Style::default().fg(Color::DarkGray),
+            );
+        }
+        return;
+    };
     let key_height = 1u16;
-    let h_gap = 1u16;
-    let v_gap = 1u16;
-
-    // Calculate widths for each row based on actual key widths
-    let row_widths: Vec<i32> = rows
-        .iter()
-        .map(|row| {
-            row.iter().map(|k| k.width as i32).sum::<i32>()
-                + ((row.len() as i32) - 1).max(0) * h_gap as i32
-        })
-        .collect();
-
-    // Find maximum row width (row 0 is widest due to backspace)
-    let max_row_width = *row_widths.iter().max().unwrap_or(&55);
-
-    // Center the widest row in the full area
-    let start_x = area.x as i32 + (area.width as i32 - max_row_width) / 2;
+
+    // Center the keyboard as a whole; keys are placed at their own x offsets
+    let keyboard_width = placements.iter().map(|p| p.x + p.width).max().unwrap_or(0);
+    let start_x = area.x as i32 + (area.width as i32 - keyboard_width) / 2;
     let start_y = area.y + 1;
+    let area_right = (area.x + area.width) as i32;
 
-    // Standard ANSI keyboard stagger (in character positions)
-    // Keys are now 3 chars wide + 1 gap = 4 per key unit
-    // Row 0 (numbers): reference
-    // Row 1 (QWERTY): +2 chars (half a key right - Tab key)
-    // Row 2 (home):    +6 chars (one key right - Caps Lock key)
-    // Row 3 (bottom): +8 chars (one and a half keys right - Left Shift key)
-    let row_stagger = [0i32, 0, 0, 0, 0];
+    for placement in &placements {
+        let key_def = placement.key;
+        let y = start_y + (placement.row as u16) * (key_height + v_gap);
+        if y >= area.y + area.height {
+            continue;
+        }
 
-    // Calculate spacebar offset: centered under the home row
-    let home_row_stagger = row_stagger[2];
-    let home_row_width = row_widths[2];
-    let spacebar_width = row_widths[4];
-    let _spacebar_offset = home_row_stagger + (home_row_width - spacebar_width) / 2;
+        let key_width = placement.width;
+        let x = start_x + placement.x;
+        if x + key_width > area_right || x < area.x as i32 {
+            continue;
+        }
 
-    for (row_idx, row) in rows.iter().enumerate() {
-        let y = start_y + (row_idx as u16) * (key_height + v_gap);
+        let key_char = key_def.label.chars().next().unwrap_or(' ');
 
-        if y >= area.y + area.height {
-            break;
-        }
+        let is_current = current_key
+            .map(|c| c.eq_ignore_ascii_case(&key_char))
+            .unwrap_or(false);
 
-        // Use stagger for rows 0-3, calculated offset for spacebar row 4
-        let x_offset = if row_idx == 4 {
-            home_row_stagger + (home_row_width - spacebar_width) / 2
-        } else {
-            row_stagger[row_idx]
-        };
+        let is_pressed = !is_current
+            && pressed_keys
+                .iter()
+                .any(|&c| c.eq_ignore_ascii_case(&key_char));
+
+        let is_home = layout.is_home_row(key_char);
 
-        let mut col_pos = 0i32;
-        for key_def in row {
-            let key_width = key_def.width as i32;
-            let x = start_x + x_offset + col_pos;
+        let finger_fg = theme.finger_color(key_def.finger.kind);
 
-            let area_right = (area.x + area.width) as i32;
-            if x + key_width > area_right || x < area.x as i32 {
-                col_pos += key_width + h_gap as i32;
-                continue;
+        // Per-key override (used by the heatmap); keys without an entry stay neutral
+        let key_color = match &shading {
+            KeyShading::Heat(colors) => colors.get(&key_char.to_ascii_lowercase()),
+            _ => None,
+        };
+        let dimmed = match &shading {
+            KeyShading::Only(keys) => {
+                key_char != ' ' && !keys.contains(&key_char.to_ascii_lowercase())
             }
+            _ => false,
+        };
 
-            let key_char = key_def.label.chars().next().unwrap_or(' ');
+        let bg = if is_current {
+            theme.current_key_highlight
+        } else if is_pressed {
+            finger_fg
+        } else if let Some(&color) = key_color {
+            color
+        } else {
+            theme.keyboard_key
+        };
 
-            let is_current = current_key
-                .map(|c| c.to_ascii_lowercase() == key_char.to_ascii_lowercase())
-                .unwrap_or(false);
+        // Finger colors are unreadable on top of heat colors
+        let label_fg = if key_color.is_some() {
+            theme.keyboard_key_text
+        } else if dimmed {
+            Color::DarkGray
+        } else {
+            finger_fg
+        };
 
-            let is_pressed = !is_current
-                && pressed_keys
-                    .iter()
-                    .any(|&c| c.to_ascii_lowercase() == key_char.to_ascii_lowercase());
+        // Render key background
+        for dy in 0..key_height as i32 {
+            for dx in 0..key_width {
+                let px = x + dx;
+                let py = y as i32 + dy;
+                if py < (area.y + area.height) as i32
+                    && px < area_right
+                    && px >= area.x as i32
+                    && let Some(cell) = buf.cell_mut((px as u16, py as u16))
+                {
+                    let is_left_edge = dx == 0;
+                    let is_right_edge = dx == key_width - 1;
 
-            let is_home = layout.is_home_row(key_char);
+                    // Compact keys are too narrow for the bars around the current key
+                    let (char_to_render, bg_color, fg_color) = if is_current && key_width >= 3 {
+                        if is_left_edge || is_right_edge {
+                            ('|', theme.keyboard_key, finger_fg)
+                        } else {
+                            (' ', theme.keyboard_key, theme.keyboard_key_text)
+                        }
+                    } else {
+                        (' ', bg, theme.keyboard_key_text)
+                    };
 
-            let finger_fg = match key_def.finger {
-                crate::keyboard::Finger::Pinky => theme.finger_pinky,
-                crate::keyboard::Finger::Ring => theme.finger_ring,
-                crate::keyboard::Finger::Middle => theme.finger_middle,
-                crate::keyboard::Finger::IndexLeft | crate::keyboard::Finger::IndexRight => {
-                    theme.finger_index
+                    cell.set_char(char_to_render);
+                    cell.set_style(Style::default().bg(bg_color).fg(fg_color));
                 }
-                crate::keyboard::Finger::Thumb => theme.finger_thumb,
-            };
-
-            let bg = if is_current {
-                theme.current_key_highlight
-            } else if is_pressed {
-                finger_fg
-            } else {
-                theme.keyboard_key
-            };
-
-            // Render key background
-            for dy in 0..key_height as i32 {
-                for dx in 0..key_width {
-                    let px = x + dx;
-                    let py = y as i32 + dy;
-                    if py < (area.y + area.height) as i32 && px < area_right && px >= area.x as i32
+            }
+        }
+
+        // Render key label (centered within the key)
+        let label_len = key_def.visual_width.unwrap_or(key_def.label.len() as u8) as i32;
+        if label_len > 0 && key_width >= label_len {
+            let label_x = x + (key_width - label_len + 1) / 2;
+            if label_x < area_right {
+                for (i, ch) in key_def.label.chars().enumerate() {
+                    let px = label_x + (i as i32);
+                    if px < area_right
+                        && px >= area.x as i32
+                        && let Some(cell) = buf.cell_mut((px as u16, y))
                     {
-                        if let Some(cell) = buf.cell_mut((px as u16, py as u16)) {
-                            let is_left_edge = dx == 0;
-                            let is_right_edge = dx == key_width - 1;
-
-                            let (char_to_render, bg_color, fg_color) = if is_current {
-                                if is_left_edge {
-                                    ('|', theme.keyboard_key, finger_fg)
-                                } else if is_right_edge {
-                                    ('|', theme.keyboard_key, finger_fg)
-                                } else {
-                                    (' ', theme.keyboard_key, theme.keyboard_key_text)
-                                }
-                            } else {
-                                (' ', bg, theme.keyboard_key_text)
-                            };
-
-                            cell.set_char(char_to_render);
-                            cell.set_style(Style::default().bg(bg_color).fg(fg_color));
+                        cell.set_char(ch);
+                        let mut modifiers = if dimmed {
+                            Modifier::DIM
+                        } else {
+                            Modifier::BOLD
+                        };
+                        if is_home {
+                            modifiers |= Modifier::UNDERLINED;
                         }
+                        let label_bg = if is_current && key_width >= 3 {
+                            theme.keyboard_key
+                        } else {
+                            bg
+                        };
+                        cell.set_style(
+                            Style::default()
+                                .bg(label_bg)
+                                .fg(label_fg)
+                                .add_modifier(modifiers),
+                        );
                     }
                 }
             }
+        }
+    }
+}
 
-            // Render key label (centered within the key)
-            let label_len = key_def.visual_width.unwrap_or(key_def.label.len() as u8) as i32;
-            let key_width = key_def.width as i32;
-            if label_len > 0 && key_width >= label_len {
-                let label_x = x + (key_width - label_len + 1) / 2;
-                if label_x < area_right {
-                    for (i, ch) in key_def.label.chars().enumerate() {
-                        let px = label_x + (i as i32);
-                        if px < area_right && px >= area.x as i32 {
-                            if let Some(cell) = buf.cell_mut((px as u16, y)) {
-                                cell.set_char(ch);
-                                let mut modifiers = ratatui::style::Modifier::BOLD;
-                                if is_home {
-                                    modifiers |= ratatui::style::Modifier::UNDERLINED;
-                                }
-                                let label_bg = if is_current { theme.keyboard_key } else { bg };
-                                cell.set_style(
-                                    Style::default()
-                                        .bg(label_bg)
-                                        .fg(if is_current { finger_fg } else { finger_fg })
-                                        .add_modifier(modifiers),
-                                );
-                            }
-                        }
-                    }
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::SPLIT_GAP;
+
+    const AREA: Rect = Rect::new(0, 0, 100, FULL_HEIGHT);
+
+    fn render(style: KeyboardStyle) -> Buffer {
+        let mut buf = Buffer::empty(AREA);
+        render_keyboard(
+            AREA,
+            &mut buf,
+            None,
+            &[],
+            KeyShading::Fingers,
+            style,
+            &Theme::dark(),
+        );
+        buf
+    }
+
+    /// Columns painted as keys on keyboard row `row`
+    fn key_columns(buf: &Buffer, row: usize) -> Vec<u16> {
+        let y = 1 + row as u16 * 2;
+        let key_bg = Theme::dark().keyboard_key;
+        (0..AREA.width)
+            .filter(|&x| buf[(x, y)].bg == key_bg)
+            .collect()
+    }
+
+    /// Widest run of unpainted columns between two keys
+    fn widest_gap(columns: &[u16]) -> u16 {
+        columns
+            .windows(2)
+            .map(|pair| pair[1] - pair[0] - 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn keys_never_overlap() {
+        let layout = KeyboardLayout::new();
+        for style in [
+            KeyboardStyle::Ansi,
+            KeyboardStyle::Split,
+            KeyboardStyle::Ortho,
+        ] {
+            let buf = render(style);
+            let placements = layout.geometry(style);
+            for row in 0..5 {
+                // Overlapping keys would paint the same cells twice
+                let widths: i32 = placements
+                    .iter()
+                    .filter(|p| p.row == row)
+                    .map(|p| p.width)
+                    .sum();
+                assert_eq!(
+                    key_columns(&buf, row).len() as i32,
+                    widths,
+                    "{:?} row {}",
+                    style,
+                    row
+                );
             }
+        }
+    }
+
+    #[test]
+    fn split_keyboard_has_a_gap_between_the_hands() {
+        let split = render(KeyboardStyle::Split);
+        for row in 0..5 {
+            assert!(
+                widest_gap(&key_columns(&split, row)) >= SPLIT_GAP as u16,
+                "row {}",
+                row
+            );
+        }
+        // The halves meet between g and h on the home row
+        let home: String = (0..AREA.width).map(|x| split[(x, 5)].symbol()).collect();
+        let (g, h) = (home.find('g').unwrap(), home.find('h').unwrap());
+        assert!(h - g > SPLIT_GAP as usize, "{:?}", home);
 
-            col_pos += key_width + h_gap as i32;
+        let ansi = render(KeyboardStyle::Ansi);
+        for row in 0..4 {
+            assert_eq!(widest_gap(&key_columns(&ansi, row)), 1, "row {}", row);
+        }
+    }
+
+    #[test]
+    fn home_row_stays_underlined_in_every_style() {
+        for style in [
+            KeyboardStyle::Ansi,
+            KeyboardStyle::Split,
+            KeyboardStyle::Ortho,
+        ] {
+            let buf = render(style);
+            let underlined: String = (0..AREA.width)
+                .filter(|&x| buf[(x, 5)].modifier.contains(Modifier::UNDERLINED))
+                .map(|x| buf[(x, 5)].symbol())
+                .collect();
+            assert_eq!(underlined, "asdfjkl;", "{:?}", style);
+        }
+    }
+
+    #[test]
+    fn keys_past_the_right_edge_are_clipped() {
+        // A keyboard too wide for the area, in a buffer with room to spare
+        let area = Rect::new(0, 0, FULL_MIN_WIDTH, FULL_HEIGHT);
+        let mut buf = Buffer::empty(Rect::new(0, 0, FULL_MIN_WIDTH + 20, FULL_HEIGHT));
+        render_keyboard(
+            area,
+            &mut buf,
+            None,
+            &[],
+            KeyShading::Fingers,
+            KeyboardStyle::Ansi,
+            &Theme::dark(),
+        );
+        let key_bg = Theme::dark().keyboard_key;
+        for y in 0..FULL_HEIGHT {
+            for x in FULL_MIN_WIDTH..buf.area.width {
+                assert_ne!(buf[(x, y)].bg, key_bg, "painted at ({}, {})", x, y);
+            }
         }
+        assert!((0..FULL_MIN_WIDTH).any(|x| buf[(x, 5)].bg == key_bg));
     }
 }
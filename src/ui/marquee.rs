@@ -0,0 +1,162 @@
+use std::cell::Cell;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Steps a scrolling line rests at either end before moving on
+const PAUSE_STEPS: usize = 6;
+
+/// The scroll position shared by every marquee on screen, which also notes
+/// whether any of them had to scroll, so the timer only runs while one does
+#[derive(Debug, Default)]
+pub struct Marquee {
+    step: usize,
+    scrolling: Cell<bool>,
+}
+
+impl Marquee {
+    /// `text` as a marquee `width` cells wide at the current step
+    pub fn show(&self, text: &str, width: usize) -> String {
+        if scrolls(text, width) {
+            self.scrolling.set(true);
+        }
+        marquee(text, width, self.step)
+    }
+
+    pub fn advance(&mut self) {
+        self.step = self.step.wrapping_add(1);
+    }
+
+    /// Start again from the beginning of the text
+    pub fn reset(&mut self) {
+        self.step = 0;
+    }
+
+    /// Whether any text shown since the last call was too long to fit
+    pub fn take_scrolling(&self) -> bool {
+        self.scrolling.replace(false)
+    }
+}
+
+/// Whether `text` scrolls in a marquee `width` cells wide
+pub fn scrolls(text: &str, width: usize) -> bool {
+    text.width() > width && width >= 2
+}
+
+/// `text` cut to `width` terminal cells, ending in `…` when anything was cut
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    if width > 0 {
+        out.push('…');
+    }
+    out
+}
+
+/// The part of `text` on show at `step` of a marquee `width` cells wide.
+/// Text that fits is returned whole; longer text scrolls one cell per step,
+/// resting at both ends, with `…` marking whichever sides are cut off.
+pub fn marquee(text: &str, width: usize, step: usize) -> String {
+    if !scrolls(text, width) {
+        return truncate_to_width(text, width);
+    }
+    // The last offset leaves `…` plus the tail of the text
+    let last = text.width() + 1 - width;
+    let position = step % (last + 2 * PAUSE_STEPS);
+    let offset = position.saturating_sub(PAUSE_STEPS).min(last);
+    if offset == 0 {
+        return truncate_to_width(text, width);
+    }
+    format!(
+        "…{}",
+        truncate_to_width(skip_cells(text, offset), width - 1)
+    )
+}
+
+/// `text` without its first `cells` cells; a wide char straddling the cut is
+/// dropped too
+fn skip_cells(text: &str, cells: usize) -> &str {
+    let mut skipped = 0;
+    for (i, c) in text.char_indices() {
+        if skipped >= cells {
+            return &text[i..];
+        }
+        skipped += c.width().unwrap_or(0);
+    }
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_keeps_text_that_fits() {
+        assert_eq!(truncate_to_width("hello", 5), "hello");
+        assert_eq!(truncate_to_width("hello", 10), "hello");
+        assert_eq!(truncate_to_width("", 0), "");
+    }
+
+    #[test]
+    fn truncate_marks_the_cut_with_an_ellipsis() {
+        assert_eq!(truncate_to_width("hello world", 6), "hello…");
+        assert_eq!(truncate_to_width("hello", 1), "…");
+        assert_eq!(truncate_to_width("hello", 0), "");
+        // A wide character that would straddle the edge is left out
+        assert_eq!(truncate_to_width("ab漢字", 4), "ab…");
+        assert_eq!(truncate_to_width("ab漢字", 5), "ab漢…");
+    }
+
+    #[test]
+    fn marquee_scrolls_only_text_that_is_too_long() {
+        assert!(!scrolls("short", 5));
+        assert!(scrolls("longer", 5));
+        // Too narrow to scroll anything; cut instead
+        assert!(!scrolls("longer", 1));
+        for step in [0, 7, 100] {
+            assert_eq!(marquee("short", 5, step), "short");
+            assert_eq!(marquee("longer", 1, step), "…");
+        }
+    }
+
+    #[test]
+    fn marquee_rests_at_both_ends() {
+        let text = "abcdefgh";
+        // 8 cells in 5: offsets 0 to 4
+        assert_eq!(marquee(text, 5, 0), "abcd…");
+        assert_eq!(marquee(text, 5, PAUSE_STEPS), "abcd…");
+        assert_eq!(marquee(text, 5, PAUSE_STEPS + 1), "…bcd…");
+        assert_eq!(marquee(text, 5, PAUSE_STEPS + 4), "…efgh");
+        assert_eq!(marquee(text, 5, 2 * PAUSE_STEPS + 3), "…efgh");
+        // Then back to the start
+        assert_eq!(marquee(text, 5, 2 * PAUSE_STEPS + 5), "abcd…");
+    }
+
+    #[test]
+    fn marquee_notes_when_text_was_cut() {
+        let mut marquee = Marquee::default();
+        assert_eq!(marquee.show("fits", 10), "fits");
+        assert!(!marquee.take_scrolling());
+
+        assert_eq!(marquee.show("too long to fit", 10), "too long …");
+        assert!(marquee.take_scrolling());
+        assert!(!marquee.take_scrolling());
+
+        for _ in 0..=PAUSE_STEPS {
+            marquee.advance();
+        }
+        assert_eq!(marquee.show("too long to fit", 10), "…oo long …");
+        marquee.reset();
+        assert_eq!(marquee.show("too long to fit", 10), "too long …");
+    }
+}
@@ -0,0 +1,119 @@
+use ratatui::style::{Color, Style};
+
+use crate::models::AppConfig;
+use crate::theme::Theme;
+
+/// A live WPM this far below the mode's average is still shown as fair
+const FAIR_WPM_SHARE: f64 = 0.9;
+
+/// Where a metric turns from good to fair and from fair to poor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// Values at or above this are good
+    pub good: f64,
+    /// Values at or above this, but below `good`, are fair
+    pub fair: f64,
+}
+
+impl Thresholds {
+    /// `accuracy_good` and `accuracy_fair` from the config, in percent
+    pub fn accuracy(config: &AppConfig) -> Self {
+        Self {
+            good: config.accuracy_good,
+            fair: config.accuracy_fair,
+        }
+    }
+
+    /// Good at or above the mode's average WPM, fair a little below it
+    pub fn wpm(average: f64) -> Self {
+        Self {
+            good: average,
+            fair: average * FAIR_WPM_SHARE,
+        }
+    }
+}
+
+/// Green, yellow or red for `value` against `thresholds`, in the theme's
+/// success and error colors
+pub fn style_for_metric(value: f64, thresholds: Thresholds, theme: &Theme) -> Style {
+    let color = if value >= thresholds.good {
+        theme.success_color
+    } else if value >= thresholds.fair {
+        Color::Yellow
+    } else {
+        theme.error_color
+    };
+    Style::default().fg(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accuracy_colors_change_at_the_thresholds() {
+        let theme = Theme::dark();
+        let thresholds = Thresholds::accuracy(&AppConfig::default());
+        assert_eq!(
+            thresholds,
+            Thresholds {
+                good: 97.0,
+                fair: 92.0
+            }
+        );
+        for (value, color) in [
+            (100.0, theme.success_color),
+            (97.0, theme.success_color),
+            (96.99, Color::Yellow),
+            (92.0, Color::Yellow),
+            (91.99, theme.error_color),
+            (0.0, theme.error_color),
+        ] {
+            assert_eq!(
+                style_for_metric(value, thresholds, &theme).fg,
+                Some(color),
+                "{}%",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn wpm_colors_follow_the_average() {
+        let theme = Theme::dark();
+        let thresholds = Thresholds::wpm(60.0);
+        for (value, color) in [
+            (75.0, theme.success_color),
+            (60.0, theme.success_color),
+            (59.9, Color::Yellow),
+            (54.0, Color::Yellow),
+            (53.9, theme.error_color),
+        ] {
+            assert_eq!(
+                style_for_metric(value, thresholds, &theme).fg,
+                Some(color),
+                "{} WPM",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn configured_accuracy_thresholds_are_used() {
+        let config = AppConfig {
+            accuracy_good: 99.0,
+            accuracy_fair: 95.0,
+            ..AppConfig::default()
+        };
+        let theme = Theme::dark();
+        let thresholds = Thresholds::accuracy(&config);
+        assert_eq!(
+            style_for_metric(98.0, thresholds, &theme).fg,
+            Some(Color::Yellow)
+        );
+        assert_eq!(
+            style_for_metric(94.0, thresholds, &theme).fg,
+            Some(theme.error_color)
+        );
+    }
+}
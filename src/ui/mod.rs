@@ -1,5 +1,16 @@
+pub mod heatmap;
 pub mod history;
 pub mod keyboard;
+pub mod marquee;
+pub mod metric_style;
+pub mod profile_picker;
+pub mod quit_confirm;
+pub mod quote_picker;
 pub mod results_view;
+pub mod screensaver;
+pub mod settings;
 pub mod stats;
+pub mod status_line;
+pub mod theme_picker;
+pub mod too_small;
 pub mod typing_view;
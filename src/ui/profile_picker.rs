@@ -0,0 +1,123 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+/// Overlay listing the profiles found in the data dir. Typing a name
+/// creates a new one instead of picking from the list.
+pub struct ProfilePicker {
+    profiles: Vec<String>,
+    selected: usize,
+    /// The profile in use, marked in the list
+    current: String,
+    /// Name of a profile to create; picks from the list while empty
+    name: String,
+}
+
+impl ProfilePicker {
+    pub fn new(profiles: Vec<String>, current: &str) -> Self {
+        let selected = profiles.iter().position(|p| p == current).unwrap_or(0);
+        Self {
+            profiles,
+            selected,
+            current: current.to_string(),
+            name: String::new(),
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.profiles.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.name.push(c);
+    }
+
+    pub fn pop_char(&mut self) {
+        self.name.pop();
+    }
+
+    /// The typed name, or else the highlighted profile
+    pub fn chosen(&self) -> Option<&str> {
+        if self.name.is_empty() {
+            self.profiles.get(self.selected).map(String::as_str)
+        } else {
+            Some(&self.name)
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame, theme: &Theme) {
+        // The list plus the name field, each inside a border
+        let list_height =
+            (self.profiles.len() as u16 + 2).min(frame.area().height.saturating_sub(3));
+        let [area] = Layout::vertical([Constraint::Length(list_height + 3)])
+            .flex(Flex::Center)
+            .areas(frame.area());
+        let [area] = Layout::horizontal([Constraint::Length(40)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [list_area, name_area] =
+            Layout::vertical([Constraint::Length(list_height), Constraint::Length(3)]).areas(area);
+
+        let rows = list_area.height.saturating_sub(2) as usize;
+        let offset = (self.selected + 1).saturating_sub(rows);
+        let picking = self.name.is_empty();
+        let items: Vec<ListItem> = self
+            .profiles
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(rows)
+            .map(|(i, profile)| {
+                let marker = if *profile == self.current { "●" } else { " " };
+                let style = if picking && i == self.selected {
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(format!(" {} {}", marker, profile))).style(style)
+            })
+            .collect();
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            List::new(items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.border_color))
+                    .title(" Profile ")
+                    .title_style(Style::default().fg(theme.title_color)),
+            ),
+            list_area,
+        );
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(" New: ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format!("{}█", self.name),
+                    Style::default().fg(theme.correct_char),
+                ),
+            ]))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.border_color))
+                    .title_bottom(" ↑/↓ pick · type a new name · Enter switch · ESC "),
+            ),
+            name_area,
+        );
+    }
+}
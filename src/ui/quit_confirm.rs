@@ -0,0 +1,59 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+/// Prompt drawn over the typing screen when quitting would lose a test in progress
+pub struct QuitConfirm;
+
+impl QuitConfirm {
+    pub fn draw(frame: &mut Frame, theme: &Theme) {
+        let [area] = Layout::vertical([Constraint::Length(5)])
+            .flex(Flex::Center)
+            .areas(frame.area());
+        let [area] = Layout::horizontal([Constraint::Length(44)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let text = vec![
+            Line::from(Span::styled(
+                "Quit? This test will be lost.",
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(
+                    "Y",
+                    Style::default()
+                        .fg(theme.error_color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" quit   ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "any other key",
+                    Style::default()
+                        .fg(theme.success_color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" keep typing", Style::default().fg(Color::DarkGray)),
+            ]),
+        ];
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(text).alignment(Alignment::Center).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.error_color)),
+            ),
+            area,
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+    Frame,
+};
+
+use crate::quotes::QuoteManager;
+use crate::theme::Theme;
+
+// Characters of quote text shown per row
+const PREVIEW_CHARS: usize = 60;
+
+/// Searchable list of every quote; Enter starts a test with the selection
+pub struct QuotePicker {
+    query: String,
+    /// Ids of the quotes matching `query`, in file order
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl QuotePicker {
+    pub fn new(quotes: &QuoteManager) -> Self {
+        let mut picker = Self {
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+        };
+        picker.refresh(quotes);
+        picker
+    }
+
+    pub fn push_char(&mut self, c: char, quotes: &QuoteManager) {
+        self.query.push(c);
+        self.refresh(quotes);
+    }
+
+    pub fn pop_char(&mut self, quotes: &QuoteManager) {
+        if self.query.pop().is_some() {
+            self.refresh(quotes);
+        }
+    }
+
+    fn refresh(&mut self, quotes: &QuoteManager) {
+        self.matches = quotes.search(&self.query).iter().map(|q| q.id).collect();
+        self.selected = 0;
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Id of the highlighted quote
+    pub fn selected_id(&self) -> Option<usize> {
+        self.matches.get(self.selected).copied()
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect, quotes: &QuoteManager, theme: &Theme) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(3)])
+            .split(area);
+
+        let filter = Paragraph::new(Line::from(vec![
+            Span::styled("Search: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(self.query.clone(), Style::default().fg(Color::White)),
+            Span::styled("█", Style::default().fg(theme.cursor_bg)),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Quotes ")
+                .title_style(Style::default().fg(theme.title_color))
+                .title(Line::from(format!(" {} matches ", self.matches.len())).right_aligned()),
+        );
+        frame.render_widget(filter, chunks[0]);
+
+        // Only the visible window is built, keeping the selection on screen
+        let height = chunks[1].height.saturating_sub(2) as usize;
+        let offset = (self.selected + 1).saturating_sub(height);
+
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .filter_map(|(i, &id)| {
+                let quote = quotes.get_quote_by_id(id)?;
+                let mut preview: String = quote.text.chars().take(PREVIEW_CHARS).collect();
+                if quote.text.chars().count() > PREVIEW_CHARS {
+                    preview.push('…');
+                }
+                let line = Line::from(vec![
+                    Span::styled(format!("#{:<5} ", id), Style::default().fg(Color::DarkGray)),
+                    Span::raw(format!("{:<61} ", preview)),
+                    Span::styled(
+                        format!("{:>4} ", quote.length),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(quote.source.clone(), Style::default().fg(Color::DarkGray)),
+                ]);
+
+                let style = if i == self.selected {
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                Some(ListItem::new(line).style(style))
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title_bottom(" type to filter · ↑/↓ select · Enter: start · ESC back "),
+        );
+        frame.render_widget(list, chunks[1]);
+    }
+}
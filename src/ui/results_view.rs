@@ -6,13 +6,71 @@ use ratatui::{
     Frame,
 };
 
+use crate::core::achievements::Achievement;
+use crate::core::coach::Suggestion;
+use crate::core::metrics::{self, SpeedUnit};
 use crate::core::typing_session::TypingSession;
+use crate::keyboard::{Finger, FingerUsage, Hand};
+use crate::models::{BigramLatency, PersonalBest, Ranking, RecentForm, SourceSummary};
 use crate::theme::Theme;
+use crate::ui::marquee::Marquee;
+use crate::ui::metric_style::{style_for_metric, Thresholds};
+
+/// How many of the test's slowest bigrams are listed
+const SLOWEST_BIGRAMS: usize = 5;
+/// Mistyped words listed by name; the rest are only counted
+const MISTYPED_WORDS: usize = 10;
+/// Tips shown at most, so the screen doesn't lecture
+const MAX_SUGGESTIONS: usize = 2;
+
+/// Extra values shown on the results screen alongside the finished session
+pub struct ResultsViewContext<'a> {
+    pub quote_source: &'a str,
+    /// Scrolls a too-long attribution
+    pub marquee: &'a Marquee,
+    pub rating: Option<u8>,
+    pub personal_best: Option<PersonalBest>,
+    /// Where the run places among the saved results of its mode, named second
+    pub ranking: Option<(Ranking, &'a str)>,
+    /// Latest results in the run's mode against the ones before them
+    pub recent_form: Option<RecentForm>,
+    /// Tips for the run, most pressing first
+    pub suggestions: &'a [Suggestion],
+    /// Best WPM on this quote; `None` for text that isn't from the quote list
+    pub quote_best: Option<f64>,
+    /// Pace of the ghost raced during the test
+    pub ghost_wpm: Option<f64>,
+    pub source_finished: Option<&'a SourceSummary>,
+    pub finger_usage: &'a FingerUsage,
+    /// This test completed the daily goal
+    pub goal_reached: bool,
+    /// Achievements this test unlocked
+    pub achievements: &'a [&'static Achievement],
+    /// The run fell below the save thresholds and wasn't saved
+    pub below_threshold: bool,
+    /// Outcome of copying the share card
+    pub share_status: Option<&'a str>,
+    /// The share card when it couldn't be copied; shown in the footer instead
+    /// of the attribution so it can be selected by hand
+    pub share_card: Option<&'a str>,
+    /// Shown in the footer instead of the attribution, e.g. results that
+    /// couldn't be saved
+    pub warning: Option<&'a str>,
+    pub speed_unit: SpeedUnit,
+    pub accuracy_thresholds: Thresholds,
+    /// Against the mode's average before this run; `None` without one
+    pub wpm_thresholds: Option<Thresholds>,
+}
 
 pub struct ResultsView;
 
 impl ResultsView {
-    pub fn draw(frame: &mut Frame, session: &TypingSession, quote_source: &str, theme: &Theme) {
+    pub fn draw(
+        frame: &mut Frame,
+        session: &TypingSession,
+        theme: &Theme,
+        ctx: &ResultsViewContext,
+    ) {
         // Create centered vertical layout
         let vertical_chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -37,48 +95,63 @@ impl ResultsView {
         // Build results content
         let duration_secs = session.duration().as_secs_f64();
         let final_wpm = session.wpm();
+        let unit = ctx.speed_unit;
+        let chars_per_word = session.chars_per_word();
+        let speed = |wpm: f64| unit.from_wpm(wpm, chars_per_word);
         let final_accuracy = session.accuracy();
 
-        let results_text = vec![
+        // A failed master run shows what was reached before the mistake
+        let (banner, banner_color) = if session.is_failed() {
+            ("║       TEST FAILED!       ║", theme.error_color)
+        } else {
+            ("║      TEST COMPLETE!      ║", theme.success_color)
+        };
+
+        let mut results_text = vec![
             Line::from(""),
             Line::from(vec![Span::styled(
                 "╔══════════════════════════╗",
                 Style::default()
-                    .fg(theme.success_color)
+                    .fg(banner_color)
                     .add_modifier(Modifier::BOLD),
             )])
             .alignment(Alignment::Center),
             Line::from(vec![Span::styled(
-                "║      TEST COMPLETE!      ║",
+                banner,
                 Style::default()
-                    .fg(theme.success_color)
+                    .fg(banner_color)
                     .add_modifier(Modifier::BOLD),
             )])
             .alignment(Alignment::Center),
             Line::from(vec![Span::styled(
                 "╚══════════════════════════╝",
                 Style::default()
-                    .fg(theme.success_color)
+                    .fg(banner_color)
                     .add_modifier(Modifier::BOLD),
             )])
             .alignment(Alignment::Center),
             Line::from(""),
-            Line::from(""),
+            personal_best_line(ctx.personal_best, speed(final_wpm), speed, unit, theme),
+            goal_line(ctx.goal_reached, theme),
             Line::from(vec![
                 Span::styled(
-                    "WPM: ",
+                    format!("{}: ", unit.label()),
                     Style::default()
                         .fg(Color::White)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    format!("{:.1}", final_wpm),
-                    Style::default()
-                        .fg(theme.wpm_color)
+                    format!("{:.1}", speed(final_wpm)),
+                    ctx.wpm_thresholds
+                        .map_or(Style::default().fg(theme.wpm_color), |thresholds| {
+                            style_for_metric(final_wpm, thresholds, theme)
+                        })
                         .add_modifier(Modifier::BOLD),
                 ),
             ])
             .alignment(Alignment::Center),
+            burst_line(speed(session.burst_wpm()), unit, theme),
+            ranking_line(ctx.ranking, theme),
             Line::from(""),
             Line::from(vec![
                 Span::styled(
@@ -89,10 +162,13 @@ impl ResultsView {
                 ),
                 Span::styled(
                     format!("{:.1}%", final_accuracy),
-                    Style::default()
-                        .fg(theme.accuracy_color)
+                    style_for_metric(final_accuracy, ctx.accuracy_thresholds, theme)
                         .add_modifier(Modifier::BOLD),
                 ),
+                Span::styled(
+                    format!("  (real {:.1}%)", session.real_accuracy()),
+                    Style::default().fg(Color::DarkGray),
+                ),
             ])
             .alignment(Alignment::Center),
             Line::from(""),
@@ -111,6 +187,170 @@ impl ResultsView {
                 ),
             ])
             .alignment(Alignment::Center),
+        ];
+
+        if let Some(best) = ctx.quote_best {
+            results_text.push(
+                Line::from(vec![
+                    Span::styled("Best on this quote: ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        format!("{:.1} {}", speed(best), unit.label()),
+                        Style::default().fg(theme.wpm_color),
+                    ),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+
+        // Hidden until there are two full windows to compare
+        if let Some((trend, form)) = ctx.recent_form.and_then(|form| Some((form.trend()?, form))) {
+            let (arrow, color) = if trend >= 0.0 {
+                ('▲', theme.success_color)
+            } else {
+                ('▼', theme.error_color)
+            };
+            results_text.push(
+                Line::from(Span::styled(
+                    format!("{} {:+.1} vs prior {}", arrow, speed(trend), form.n),
+                    Style::default().fg(color),
+                ))
+                .alignment(Alignment::Center),
+            );
+        }
+
+        if let Some(ghost) = ctx.ghost_wpm {
+            let (text, color) = if final_wpm > ghost {
+                ("You beat the ghost", theme.success_color)
+            } else {
+                ("The ghost won", theme.error_color)
+            };
+            results_text.push(
+                Line::from(vec![
+                    Span::styled(text, Style::default().fg(color)),
+                    Span::styled(
+                        format!(" ({:.1} {})", speed(ghost), unit.label()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+
+        if ctx.below_threshold {
+            results_text.push(Line::from(""));
+            results_text.push(
+                Line::from(vec![
+                    Span::styled(
+                        "Not saved (below threshold) · ",
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        "S",
+                        Style::default()
+                            .fg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(" save anyway", Style::default().fg(Color::DarkGray)),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+        if session.is_failed() {
+            results_text.push(Line::from(""));
+            results_text.push(
+                Line::from(Span::styled(
+                    "Master difficulty: one mistake ends the test. Not saved.",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
+        } else if session.is_afk_discarded() {
+            results_text.push(Line::from(""));
+            results_text.push(
+                Line::from(Span::styled(
+                    "Abandoned while AFK. Not saved.",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
+        } else if session.uncorrected_errors() > 0 {
+            // Only worth a line when the test ended with mistakes still in place
+            results_text.push(Line::from(""));
+            results_text.push(
+                Line::from(vec![
+                    Span::styled(
+                        "Uncorrected errors: ",
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("{}", session.uncorrected_errors()),
+                        Style::default()
+                            .fg(theme.error_color)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+
+        if !ctx.achievements.is_empty() {
+            results_text.push(Line::from(""));
+            results_text.extend(ctx.achievements.iter().map(|achievement| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("🏆 {}", achievement.name),
+                        Style::default()
+                            .fg(theme.success_color)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!(" · {}", achievement.description),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ])
+                .alignment(Alignment::Center)
+            }));
+        }
+
+        if !ctx.suggestions.is_empty() {
+            results_text.push(Line::from(""));
+            results_text.extend(ctx.suggestions.iter().take(MAX_SUGGESTIONS).map(|tip| {
+                Line::from(Span::styled(
+                    tip.message(),
+                    Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::ITALIC),
+                ))
+                .alignment(Alignment::Center)
+            }));
+        }
+
+        let slowest = metrics::slowest_bigrams(&session.bigram_latencies(), SLOWEST_BIGRAMS);
+        if !slowest.is_empty() {
+            results_text.push(Line::from(""));
+            results_text.push(slowest_bigrams_line(&slowest, theme));
+        }
+
+        let mistyped = session.mistyped_words();
+        if !mistyped.is_empty() {
+            if slowest.is_empty() {
+                results_text.push(Line::from(""));
+            }
+            results_text.push(mistyped_words_line(&mistyped, theme));
+        }
+
+        if ctx.finger_usage.total() > 0 {
+            results_text.push(Line::from(""));
+            results_text.extend(finger_usage_lines(ctx.finger_usage, theme));
+        }
+
+        if let Some(summary) = ctx.source_finished {
+            results_text.extend(source_finished_lines(summary, speed, unit, theme));
+        }
+
+        results_text.extend([
             Line::from(""),
             Line::from(""),
             Line::from(vec![Span::styled(
@@ -119,6 +359,23 @@ impl ResultsView {
             )])
             .alignment(Alignment::Center),
             Line::from(""),
+            rating_line(ctx.rating, theme),
+            Line::from(""),
+        ]);
+        if let Some(message) = ctx.share_status {
+            results_text.push(
+                Line::from(Span::styled(
+                    message.to_string(),
+                    Style::default().fg(if ctx.share_card.is_none() {
+                        theme.success_color
+                    } else {
+                        theme.error_color
+                    }),
+                ))
+                .alignment(Alignment::Center),
+            );
+        }
+        results_text.extend([
             Line::from(vec![
                 Span::styled("Press ", Style::default().fg(Color::DarkGray)),
                 Span::styled(
@@ -127,7 +384,47 @@ impl ResultsView {
                         .fg(theme.success_color)
                         .add_modifier(Modifier::BOLD),
                 ),
-                Span::styled(" to restart", Style::default().fg(Color::DarkGray)),
+                Span::styled(" or ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "N",
+                    Style::default()
+                        .fg(theme.success_color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" for a new quote", Style::default().fg(Color::DarkGray)),
+            ])
+            .alignment(Alignment::Center),
+            Line::from(vec![
+                Span::styled("Press ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "R",
+                    Style::default()
+                        .fg(theme.mode_color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" to retry this text", Style::default().fg(Color::DarkGray)),
+            ])
+            .alignment(Alignment::Center),
+            Line::from(vec![
+                Span::styled("Press ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "C",
+                    Style::default()
+                        .fg(theme.mode_color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" to copy the result", Style::default().fg(Color::DarkGray)),
+            ])
+            .alignment(Alignment::Center),
+            Line::from(vec![
+                Span::styled("Press ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "P",
+                    Style::default()
+                        .fg(theme.mode_color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" to watch a replay", Style::default().fg(Color::DarkGray)),
             ])
             .alignment(Alignment::Center),
             Line::from(vec![
@@ -139,7 +436,7 @@ impl ResultsView {
                 Span::styled(" to quit", Style::default().fg(Color::DarkGray)),
             ])
             .alignment(Alignment::Center),
-        ];
+        ]);
 
         let results_block = Paragraph::new(results_text).block(
             Block::default()
@@ -156,16 +453,265 @@ impl ResultsView {
 
         frame.render_widget(results_block, horizontal_chunks[1]);
 
-        // Footer with quote source
-        let footer = Paragraph::new(format!("Source: {}", quote_source))
+        // Footer with quote source, or the share card that couldn't be copied,
+        // or a warning
+        let (title, text, color) = match (ctx.share_card, ctx.warning) {
+            (Some(card), _) => ("Share ", card.to_string(), Color::White),
+            (None, Some(warning)) => (
+                "Warning ",
+                format!("Warning: {}", warning),
+                theme.error_color,
+            ),
+            (None, None) => (
+                "Quote Attribution ",
+                ctx.marquee.show(
+                    &format!("Source: {}", ctx.quote_source),
+                    vertical_chunks[3].width as usize,
+                ),
+                Color::DarkGray,
+            ),
+        };
+        let footer = Paragraph::new(text)
             .block(
                 Block::default()
                     .borders(Borders::TOP)
-                    .title("Quote Attribution ")
+                    .title(title)
                     .title_style(Style::default().fg(theme.title_color)),
             )
-            .style(Style::default().fg(Color::DarkGray));
+            .style(Style::default().fg(color));
 
         frame.render_widget(footer, vertical_chunks[3]);
     }
 }
+
+/// Celebration for the test that completes the daily goal; blank otherwise
+fn goal_line(reached: bool, theme: &Theme) -> Line<'static> {
+    if !reached {
+        return Line::from("");
+    }
+    Line::from(Span::styled(
+        "🎯 Daily goal reached!",
+        Style::default()
+            .fg(theme.success_color)
+            .add_modifier(Modifier::BOLD),
+    ))
+    .alignment(Alignment::Center)
+}
+
+/// "Burst: 124 WPM", the best 5 seconds; blank for tests too short to have one
+fn burst_line(burst: f64, unit: SpeedUnit, theme: &Theme) -> Line<'static> {
+    if burst <= 0.0 {
+        return Line::from("");
+    }
+    Line::from(vec![
+        Span::styled("Burst: ", Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            format!("{:.0} {}", burst, unit.label()),
+            Style::default().fg(theme.wpm_color),
+        ),
+    ])
+    .alignment(Alignment::Center)
+}
+
+/// "Top 12% of your 214 medium tests", with a note inside your top ten
+fn ranking_line(ranking: Option<(Ranking, &str)>, theme: &Theme) -> Line<'static> {
+    let Some((ranking, mode)) = ranking else {
+        return Line::from("");
+    };
+    let mut spans = vec![Span::styled(
+        format!(
+            "Top {:.0}% of your {} {} tests",
+            ranking.top_percent().ceil(),
+            ranking.total,
+            mode
+        ),
+        Style::default().fg(Color::DarkGray),
+    )];
+    if ranking.rank <= 10 {
+        spans.push(Span::styled(
+            format!(" · #{} of your best", ranking.rank),
+            Style::default().fg(theme.success_color),
+        ));
+    }
+    Line::from(spans).alignment(Alignment::Center)
+}
+
+fn rating_line(rating: Option<u8>, theme: &Theme) -> Line<'static> {
+    let stars = rating.unwrap_or(0) as usize;
+    Line::from(vec![
+        Span::styled("Rate this quote: ", Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            "★".repeat(stars),
+            Style::default()
+                .fg(theme.accuracy_color)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("☆".repeat(5 - stars), Style::default().fg(Color::DarkGray)),
+        Span::styled(" (1-5)", Style::default().fg(Color::DarkGray)),
+    ])
+    .alignment(Alignment::Center)
+}
+
+/// e.g. "Slowest: th 412ms · br 388ms"
+fn slowest_bigrams_line(bigrams: &[BigramLatency], theme: &Theme) -> Line<'static> {
+    let mut spans = vec![Span::styled(
+        "Slowest: ",
+        Style::default()
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    )];
+    for (i, b) in bigrams.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(" · ", Style::default().fg(Color::DarkGray)));
+        }
+        spans.push(Span::styled(
+            format!("{} ", b.bigram),
+            Style::default()
+                .fg(theme.error_color)
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(
+            format!("{:.0}ms", b.avg_ms),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans).alignment(Alignment::Center)
+}
+
+/// One line per hand, e.g. "Left 48%  pinky 5% · ring 8% · middle 15% · index 20%",
+/// plus the share of keys that aren't on the keyboard
+fn finger_usage_lines(usage: &FingerUsage, theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines: Vec<Line> = [Hand::Left, Hand::Right]
+        .into_iter()
+        .map(|hand| {
+            let mut spans = vec![Span::styled(
+                format!(
+                    "{} {:.0}%  ",
+                    hand.name(),
+                    usage.percent(usage.hand_count(hand))
+                ),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )];
+            for (i, finger) in Finger::ALL
+                .iter()
+                .filter(|finger| finger.hand == hand)
+                .enumerate()
+            {
+                if i > 0 {
+                    spans.push(Span::styled(" · ", Style::default().fg(Color::DarkGray)));
+                }
+                spans.push(Span::styled(
+                    format!(
+                        "{} {:.0}%",
+                        finger.kind.name(),
+                        usage.percent(usage.count(*finger))
+                    ),
+                    Style::default().fg(theme.finger_color(finger.kind)),
+                ));
+            }
+            Line::from(spans).alignment(Alignment::Center)
+        })
+        .collect();
+    if usage.other > 0 {
+        lines.push(
+            Line::from(Span::styled(
+                format!("Off the keyboard {:.0}%", usage.percent(usage.other)),
+                Style::default().fg(Color::DarkGray),
+            ))
+            .alignment(Alignment::Center),
+        );
+    }
+    lines
+}
+
+/// e.g. "Mistyped: through, rhythm (+3 more)"
+fn mistyped_words_line(words: &[String], theme: &Theme) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled(
+            "Mistyped: ",
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            words
+                .iter()
+                .take(MISTYPED_WORDS)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", "),
+            Style::default().fg(theme.error_color),
+        ),
+    ];
+    if words.len() > MISTYPED_WORDS {
+        spans.push(Span::styled(
+            format!(" (+{} more)", words.len() - MISTYPED_WORDS),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans).alignment(Alignment::Center)
+}
+
+/// `speed` is the test's speed in `unit`; `convert` turns a stored WPM into it
+fn personal_best_line(
+    pb: Option<PersonalBest>,
+    speed: f64,
+    convert: impl Fn(f64) -> f64,
+    unit: SpeedUnit,
+    theme: &Theme,
+) -> Line<'static> {
+    let Some(pb) = pb else {
+        return Line::from("");
+    };
+    let text = match pb.previous {
+        Some(previous) => format!(
+            "NEW PERSONAL BEST ({:+.1} {})",
+            speed - convert(previous),
+            unit.label()
+        ),
+        None => "NEW PERSONAL BEST".to_string(),
+    };
+    Line::from(Span::styled(
+        text,
+        Style::default()
+            .fg(theme.success_color)
+            .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+    ))
+    .alignment(Alignment::Center)
+}
+
+/// Celebration and totals after the final chunk of a continuous source
+fn source_finished_lines(
+    summary: &SourceSummary,
+    convert: impl Fn(f64) -> f64,
+    unit: SpeedUnit,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let minutes = summary.duration_seconds / 60;
+    let seconds = summary.duration_seconds % 60;
+    vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("✦ Finished {} ✦", summary.title),
+            Style::default()
+                .fg(theme.success_color)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .alignment(Alignment::Center),
+        Line::from(Span::styled(
+            format!(
+                "{} chunks · {:.1} {} avg · {:.1}% accuracy · {}m {}s",
+                summary.tests,
+                convert(summary.avg_wpm),
+                unit.label(),
+                summary.avg_accuracy,
+                minutes,
+                seconds
+            ),
+            Style::default().fg(Color::DarkGray),
+        ))
+        .alignment(Alignment::Center),
+    ]
+}
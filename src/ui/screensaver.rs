@@ -0,0 +1,48 @@
+use chrono::Local;
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::theme::Theme;
+
+/// Shown in place of the typing screen after a period of inactivity, so the
+/// quote isn't left on screen
+pub struct Screensaver;
+
+impl Screensaver {
+    pub fn draw(frame: &mut Frame, theme: &Theme) {
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(45),
+                Constraint::Length(3),
+                Constraint::Percentage(45),
+            ])
+            .split(frame.area());
+
+        let text = vec![
+            Line::from(Span::styled(
+                Local::now().format("%H:%M").to_string(),
+                Style::default()
+                    .fg(theme.untyped_char)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "press any key",
+                Style::default()
+                    .fg(theme.untyped_char)
+                    .add_modifier(Modifier::DIM),
+            )),
+        ];
+
+        frame.render_widget(
+            Paragraph::new(text).alignment(Alignment::Center),
+            vertical_chunks[1],
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::storage::paths::Paths;
+use crate::theme::Theme;
+
+pub struct SettingsView {
+    paths: Paths,
+    warning: Option<String>,
+}
+
+impl SettingsView {
+    pub fn new(paths: Paths, warning: Option<String>) -> Self {
+        Self { paths, warning }
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Min(14),
+                Constraint::Percentage(20),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(15),
+                Constraint::Percentage(70),
+                Constraint::Percentage(15),
+            ])
+            .split(vertical_chunks[1]);
+
+        let path_line = |label: &'static str, path: String| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<18}", label),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(path, Style::default().fg(theme.wpm_color)),
+            ])
+        };
+
+        let mut lines = vec![
+            Line::from(""),
+            path_line(
+                "Config file:",
+                self.paths.config_file().display().to_string(),
+            ),
+            path_line("Database:", self.paths.db_file().display().to_string()),
+            path_line(
+                "Themes directory:",
+                self.paths.themes_dir().display().to_string(),
+            ),
+            path_line(
+                "Quotes directory:",
+                self.paths.quotes_dir().display().to_string(),
+            ),
+            Line::from(""),
+        ];
+
+        if let Some(ref warning) = self.warning {
+            lines.push(Line::from(Span::styled(
+                format!("Warning: {}", warning),
+                Style::default().fg(theme.error_color),
+            )));
+            lines.push(Line::from(""));
+        }
+
+        lines.extend([
+            Line::from(vec![
+                Span::styled("Press ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "E",
+                    Style::default()
+                        .fg(theme.success_color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    " to edit the config in $EDITOR",
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+            .alignment(Alignment::Center),
+            Line::from(vec![
+                Span::styled("Press ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "ESC",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" to go back", Style::default().fg(Color::DarkGray)),
+            ])
+            .alignment(Alignment::Center),
+        ]);
+
+        let settings_block = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(
+                    Style::default()
+                        .fg(theme.border_color)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .title(" ═══ SETTINGS ═══ ")
+                .title_style(Style::default().fg(theme.title_color))
+                .title_alignment(Alignment::Center),
+        );
+
+        frame.render_widget(settings_block, horizontal_chunks[1]);
+    }
+}
@@ -1,19 +1,239 @@
-use crate::models::UserStats;
+use crate::core::achievements::ACHIEVEMENTS;
+use crate::core::metrics::{self, SpeedUnit};
+use crate::keyboard::{Finger, FingerUsage, Hand};
+use crate::models::{
+    abandonment_rate, format_duration, AbandonmentStats, BigramLatency, DailyActivity, DateWindow,
+    LengthBucket, PeriodAggregate, RecentForm, TestResult, UserStats,
+};
+use chrono::{DateTime, Local, Utc};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols::Marker,
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph},
     Frame,
 };
+use std::collections::HashMap;
+
+// Buckets with fewer samples than this are dimmed as unreliable
+const MIN_BUCKET_SAMPLES: i64 = 5;
+
+// Trend points at or above these accuracies are drawn green / yellow, below in red
+const GOOD_ACCURACY: f64 = 97.0;
+const FAIR_ACCURACY: f64 = 92.0;
+
+// Rows of the daily and weekly tables visible at once
+const AGGREGATE_ROWS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsTab {
+    Summary,
+    Daily,
+    Weekly,
+    Trend,
+    Best,
+    Length,
+    Abandoned,
+    Bigrams,
+    Fingers,
+    Achievements,
+}
+
+impl StatsTab {
+    const ALL: [StatsTab; 10] = [
+        StatsTab::Summary,
+        StatsTab::Daily,
+        StatsTab::Weekly,
+        StatsTab::Trend,
+        StatsTab::Best,
+        StatsTab::Length,
+        StatsTab::Abandoned,
+        StatsTab::Bigrams,
+        StatsTab::Fingers,
+        StatsTab::Achievements,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            StatsTab::Summary => "Summary",
+            StatsTab::Daily => "Daily",
+            StatsTab::Weekly => "Weekly",
+            StatsTab::Trend => "Trend",
+            StatsTab::Best => "Best",
+            StatsTab::Length => "Length",
+            StatsTab::Abandoned => "Abandoned",
+            StatsTab::Bigrams => "Bigrams",
+            StatsTab::Fingers => "Fingers",
+            StatsTab::Achievements => "Achievements",
+        }
+    }
+}
 
 pub struct StatsView {
     stats: UserStats,
+    length_buckets: Vec<LengthBucket>,
+    abandonment: AbandonmentStats,
+    slowest_bigrams: Vec<BigramLatency>,
+    activity: DailyActivity,
+    /// Latest results, oldest first
+    recent: Vec<TestResult>,
+    finger_usage: FingerUsage,
+    /// Progress towards the daily goal, if one is set
+    goal: Option<String>,
+    /// Fastest runs across every mode, best first
+    top_results: Vec<TestResult>,
+    /// When each unlocked achievement was unlocked, by id
+    achievements: HashMap<String, DateTime<Utc>>,
+    /// Latest results across all modes, shown next to the lifetime average
+    recent_form: RecentForm,
+    /// Newest first
+    daily: Vec<PeriodAggregate>,
+    weekly: Vec<PeriodAggregate>,
+    /// First row shown in the daily or weekly table
+    table_scroll: usize,
+    tab: StatsTab,
+    /// How far back the totals, trend and leaderboard look
+    window: DateWindow,
+    speed_unit: SpeedUnit,
+    /// Average word length over the recent results, for converting totals
+    /// that span many texts
+    chars_per_word: f64,
 }
 
 impl StatsView {
-    pub fn new(stats: UserStats) -> Self {
-        Self { stats }
+    pub fn new(
+        stats: UserStats,
+        length_buckets: Vec<LengthBucket>,
+        abandonment: AbandonmentStats,
+        slowest_bigrams: Vec<BigramLatency>,
+        activity: DailyActivity,
+        recent: Vec<TestResult>,
+        finger_usage: FingerUsage,
+    ) -> Self {
+        Self {
+            stats,
+            length_buckets,
+            abandonment,
+            slowest_bigrams,
+            activity,
+            recent,
+            finger_usage,
+            goal: None,
+            top_results: Vec::new(),
+            achievements: HashMap::new(),
+            recent_form: RecentForm::default(),
+            daily: Vec::new(),
+            weekly: Vec::new(),
+            table_scroll: 0,
+            speed_unit: SpeedUnit::Wpm5,
+            chars_per_word: metrics::CHARS_PER_WORD,
+            tab: StatsTab::Summary,
+            window: DateWindow::All,
+        }
+    }
+
+    pub fn set_window(&mut self, window: DateWindow) {
+        self.window = window;
+    }
+
+    pub fn tab(&self) -> StatsTab {
+        self.tab
+    }
+
+    /// Open `tab`, e.g. to stay on it when the view is reloaded
+    pub fn set_tab(&mut self, tab: StatsTab) {
+        self.table_scroll = 0;
+        self.tab = tab;
+    }
+
+    pub fn set_goal(&mut self, goal: Option<String>) {
+        self.goal = goal;
+    }
+
+    pub fn set_top_results(&mut self, results: Vec<TestResult>) {
+        self.top_results = results;
+    }
+
+    pub fn set_achievements(&mut self, achievements: HashMap<String, DateTime<Utc>>) {
+        self.achievements = achievements;
+    }
+
+    pub fn set_recent_form(&mut self, form: RecentForm) {
+        self.recent_form = form;
+    }
+
+    pub fn set_speed_unit(&mut self, unit: SpeedUnit) {
+        self.speed_unit = unit;
+        if !self.recent.is_empty() {
+            self.chars_per_word = self.recent.iter().map(|r| r.chars_per_word()).sum::<f64>()
+                / self.recent.len() as f64;
+        }
+    }
+
+    /// A stored WPM from across many texts, in the chosen unit
+    fn speed(&self, wpm: f64) -> f64 {
+        self.speed_unit.from_wpm(wpm, self.chars_per_word)
+    }
+
+    /// Per-day and per-week totals, newest first
+    pub fn set_aggregates(&mut self, daily: Vec<PeriodAggregate>, weekly: Vec<PeriodAggregate>) {
+        self.daily = daily;
+        self.weekly = weekly;
+    }
+
+    fn table(&self) -> &[PeriodAggregate] {
+        match self.tab {
+            StatsTab::Daily => &self.daily,
+            StatsTab::Weekly => &self.weekly,
+            _ => &[],
+        }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.table_scroll = self.table_scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        let max = self.table().len().saturating_sub(AGGREGATE_ROWS);
+        self.table_scroll = (self.table_scroll + 1).min(max);
+    }
+
+    pub fn next_tab(&mut self) {
+        self.table_scroll = 0;
+        let i = StatsTab::ALL
+            .iter()
+            .position(|&t| t == self.tab)
+            .unwrap_or(0);
+        self.tab = StatsTab::ALL[(i + 1) % StatsTab::ALL.len()];
+    }
+
+    pub fn previous_tab(&mut self) {
+        self.table_scroll = 0;
+        let i = StatsTab::ALL
+            .iter()
+            .position(|&t| t == self.tab)
+            .unwrap_or(0);
+        self.tab = StatsTab::ALL[(i + StatsTab::ALL.len() - 1) % StatsTab::ALL.len()];
+    }
+
+    /// Per-mode personal bests, e.g. "short 88.1 · medium 80.2"
+    fn mode_bests_line(&self) -> Line<'static> {
+        let mut spans = Vec::new();
+        for (i, (mode, wpm)) in self.stats.best_wpm_by_mode.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::styled(" · ", Style::default().fg(Color::DarkGray)));
+            }
+            spans.push(Span::styled(
+                format!("{} ", mode),
+                Style::default().fg(Color::DarkGray),
+            ));
+            spans.push(Span::styled(
+                format!("{:.1}", self.speed(*wpm)),
+                Style::default().fg(Color::Green),
+            ));
+        }
+        Line::from(spans).alignment(Alignment::Center)
     }
 
     pub fn draw(&self, frame: &mut Frame, area: Rect) {
@@ -22,7 +242,7 @@ impl StatsView {
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Percentage(20),
-                Constraint::Min(20),
+                Constraint::Min(22),
                 Constraint::Percentage(20),
             ])
             .split(area);
@@ -36,21 +256,251 @@ impl StatsView {
             ])
             .split(vertical_chunks[1]);
 
-        // Format time
-        let hours = self.stats.total_time_seconds / 3600;
-        let minutes = (self.stats.total_time_seconds % 3600) / 60;
-        let seconds = self.stats.total_time_seconds % 60;
+        let stats_block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .title(format!(" ═══ STATISTICS · {} ═══ ", self.window.label()))
+            .title_alignment(Alignment::Center);
+
+        // The chart is a widget of its own, so the trend tab splits the box
+        // into heading, chart and footer instead of one paragraph
+        if self.tab == StatsTab::Trend && self.recent.len() >= 2 {
+            let inner = stats_block.inner(horizontal_chunks[1]);
+            frame.render_widget(stats_block, horizontal_chunks[1]);
+            let footer = self.footer_lines();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(4),
+                    Constraint::Min(5),
+                    Constraint::Length(footer.len() as u16),
+                ])
+                .split(inner);
+            let mut heading = vec![self.tab_bar()];
+            heading.extend(self.trend_heading());
+            frame.render_widget(Paragraph::new(heading), chunks[0]);
+            self.draw_trend(frame, chunks[1]);
+            frame.render_widget(Paragraph::new(footer), chunks[2]);
+            return;
+        }
+
+        let mut stats_text = vec![self.tab_bar()];
+        stats_text.extend(match self.tab {
+            StatsTab::Summary => self.summary_lines(),
+            StatsTab::Daily => self.aggregate_lines("TESTS PER DAY", "Day"),
+            StatsTab::Weekly => self.aggregate_lines("TESTS PER WEEK", "Week of"),
+            StatsTab::Trend => self.trend_heading(),
+            StatsTab::Best => self.leaderboard_lines(),
+            StatsTab::Length => self.length_lines(),
+            StatsTab::Abandoned => self.abandoned_lines(),
+            StatsTab::Bigrams => self.bigram_lines(),
+            StatsTab::Fingers => self.finger_lines(),
+            StatsTab::Achievements => self.achievement_lines(),
+        });
+        stats_text.extend(self.footer_lines());
+
+        frame.render_widget(
+            Paragraph::new(stats_text).block(stats_block),
+            horizontal_chunks[1],
+        );
+    }
+
+    fn footer_lines(&self) -> Vec<Line<'static>> {
+        vec![
+            Line::from(""),
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "───────────────────────────",
+                Style::default().fg(Color::DarkGray),
+            )])
+            .alignment(Alignment::Center),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(
+                    "←/→",
+                    Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" switch view · ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "W",
+                    Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" window · ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "R",
+                    Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" weekly report · ", Style::default().fg(Color::DarkGray)),
+                Span::styled("Press ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    "ESC",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" to go back", Style::default().fg(Color::DarkGray)),
+            ])
+            .alignment(Alignment::Center),
+        ]
+    }
 
-        let time_str = if hours > 0 {
-            format!("{}h {}m {}s", hours, minutes, seconds)
-        } else if minutes > 0 {
-            format!("{}m {}s", minutes, seconds)
+    /// Title of the trend tab, or why there is no chart yet
+    fn trend_heading(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                format!(
+                    "{} OVER THE LAST {} TESTS",
+                    self.speed_unit.label().to_uppercase(),
+                    self.recent.len()
+                ),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )])
+            .alignment(Alignment::Center),
+        ];
+        if self.recent.len() < 2 {
+            lines.push(Line::from(""));
+            lines.push(
+                Line::from(Span::styled(
+                    "Complete at least two tests to see a trend",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
         } else {
-            format!("{}s", seconds)
+            lines.push(
+                Line::from(vec![
+                    Span::styled("● ", Style::default().fg(Color::Green)),
+                    Span::styled(
+                        format!("≥{:.0}%  ", GOOD_ACCURACY),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled("● ", Style::default().fg(Color::Yellow)),
+                    Span::styled(
+                        format!("≥{:.0}%  ", FAIR_ACCURACY),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled("● ", Style::default().fg(Color::Red)),
+                    Span::styled("lower accuracy  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled("┄ ", Style::default().fg(Color::Cyan)),
+                    Span::styled("running average", Style::default().fg(Color::DarkGray)),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+        lines
+    }
+
+    /// Speed per test, oldest on the left, with each point colored by accuracy
+    fn draw_trend(&self, frame: &mut Frame, area: Rect) {
+        // Leave room for the y-axis labels; narrow terminals average neighbours
+        let points = downsample(
+            &self.recent,
+            self.speed_unit,
+            area.width.saturating_sub(8).max(2) as usize,
+        );
+
+        let mut good = Vec::new();
+        let mut fair = Vec::new();
+        let mut poor = Vec::new();
+        let mut line = Vec::new();
+        let mut average = Vec::new();
+        let mut sum = 0.0;
+        for (i, &(wpm, accuracy)) in points.iter().enumerate() {
+            let point = (i as f64, wpm);
+            line.push(point);
+            match accuracy {
+                a if a >= GOOD_ACCURACY => good.push(point),
+                a if a >= FAIR_ACCURACY => fair.push(point),
+                _ => poor.push(point),
+            }
+            sum += wpm;
+            // Every other point only, which reads as a dashed line
+            if i.is_multiple_of(2) {
+                average.push((i as f64, sum / (i + 1) as f64));
+            }
+        }
+
+        let (min, max) = line
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(lo, hi), &(_, wpm)| {
+                (lo.min(wpm), hi.max(wpm))
+            });
+        let low = ((min - 5.0) / 10.0).floor().max(0.0) * 10.0;
+        let high = ((max + 5.0) / 10.0).ceil() * 10.0;
+
+        let scatter = |data, color| {
+            Dataset::default()
+                .marker(Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(color))
+                .data(data)
         };
+        let datasets = vec![
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::DarkGray))
+                .data(&line),
+            scatter(&average, Color::Cyan),
+            scatter(&good, Color::Green),
+            scatter(&fair, Color::Yellow),
+            scatter(&poor, Color::Red),
+        ];
+
+        let label_style = Style::default().fg(Color::DarkGray);
+        let chart = Chart::new(datasets)
+            .x_axis(
+                Axis::default()
+                    .style(label_style)
+                    .bounds([0.0, (points.len() - 1) as f64])
+                    .labels(["oldest", "newest"]),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(label_style)
+                    .bounds([low, high])
+                    .labels([
+                        format!("{:.0}", low),
+                        format!("{:.0}", (low + high) / 2.0),
+                        format!("{:.0}", high),
+                    ]),
+            );
+        frame.render_widget(chart, area);
+    }
 
-        // Build stats content
-        let stats_text = vec![
+    fn tab_bar(&self) -> Line<'static> {
+        let mut spans = Vec::new();
+        for (i, tab) in StatsTab::ALL.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
+            }
+            let style = if *tab == self.tab {
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            spans.push(Span::styled(tab.title(), style));
+        }
+        Line::from(spans).alignment(Alignment::Center)
+    }
+
+    fn summary_lines(&self) -> Vec<Line<'static>> {
+        let time_str = format_duration(self.stats.total_time_seconds);
+
+        vec![
             Line::from(""),
             Line::from(vec![Span::styled(
                 "═══════════════════════════",
@@ -93,33 +543,57 @@ impl StatsView {
             Line::from(""),
             Line::from(vec![
                 Span::styled(
-                    "Best WPM: ",
+                    format!("Best {}: ", self.speed_unit.label()),
                     Style::default()
                         .fg(Color::White)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    format!("{:.1}", self.stats.best_wpm),
+                    format!("{:.1}", self.speed(self.stats.best_wpm)),
                     Style::default()
                         .fg(Color::Green)
                         .add_modifier(Modifier::BOLD),
                 ),
             ])
             .alignment(Alignment::Center),
+            self.mode_bests_line(),
+            Line::from(vec![
+                Span::styled("Best burst: ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format!(
+                        "{:.0} {}",
+                        self.speed(self.stats.best_burst_wpm),
+                        self.speed_unit.label()
+                    ),
+                    Style::default().fg(Color::Green),
+                ),
+            ])
+            .alignment(Alignment::Center),
             Line::from(""),
             Line::from(vec![
                 Span::styled(
-                    "Average WPM: ",
+                    format!("Average {}: ", self.speed_unit.label()),
                     Style::default()
                         .fg(Color::White)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    format!("{:.1}", self.stats.avg_wpm),
+                    format!("{:.1}", self.speed(self.stats.avg_wpm)),
                     Style::default()
                         .fg(Color::Cyan)
                         .add_modifier(Modifier::BOLD),
                 ),
+                Span::styled(
+                    match self.recent_form.last {
+                        Some(last) => format!(
+                            "  (last {} avg: {:.1})",
+                            self.recent_form.n,
+                            self.speed(last)
+                        ),
+                        None => String::new(),
+                    },
+                    Style::default().fg(Color::DarkGray),
+                ),
             ])
             .alignment(Alignment::Center),
             Line::from(""),
@@ -155,36 +629,595 @@ impl StatsView {
             ])
             .alignment(Alignment::Center),
             Line::from(""),
+            self.streak_line(),
+            match &self.goal {
+                Some(goal) => Line::from(Span::styled(
+                    goal.clone(),
+                    Style::default().fg(Color::Green),
+                ))
+                .alignment(Alignment::Center),
+                None => Line::from(""),
+            },
+        ]
+    }
+
+    /// e.g. "Streak: 12 days 🔥 (longest 30 · 41 active days)"
+    fn streak_line(&self) -> Line<'static> {
+        let current = self.activity.current_streak;
+        let days = if current == 1 { "day" } else { "days" };
+        let flame = if current > 0 { " 🔥" } else { "" };
+        Line::from(vec![
+            Span::styled(
+                "Streak: ",
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{} {}{}", current, days, flame),
+                Style::default()
+                    .fg(Color::LightRed)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(
+                    " (longest {} · {} active days)",
+                    self.activity.longest_streak,
+                    self.activity.tests_by_day.len()
+                ),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])
+        .alignment(Alignment::Center)
+    }
+
+    /// Average WPM per quote-length bucket as horizontal bars, plus the trend
+    fn length_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                format!("{} BY QUOTE LENGTH", self.speed_unit.label().to_uppercase()),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )])
+            .alignment(Alignment::Center),
+            Line::from(""),
+        ];
+
+        if self.length_buckets.is_empty() {
+            lines.push(
+                Line::from(Span::styled(
+                    "No tests yet",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
+            return lines;
+        }
+
+        const BAR_WIDTH: usize = 20;
+        let max_wpm = self
+            .length_buckets
+            .iter()
+            .map(|b| b.avg_wpm)
+            .fold(0.0, f64::max)
+            .max(1.0);
+
+        for bucket in &self.length_buckets {
+            let label = if bucket.is_last() {
+                format!("{}+", bucket.min_length)
+            } else {
+                format!("{}-{}", bucket.min_length, bucket.min_length + 99)
+            };
+            let filled = ((bucket.avg_wpm / max_wpm) * BAR_WIDTH as f64).round() as usize;
+            let dimmed = bucket.tests < MIN_BUCKET_SAMPLES;
+            let (bar_color, text_color) = if dimmed {
+                (Color::DarkGray, Color::DarkGray)
+            } else {
+                (Color::Cyan, Color::White)
+            };
+
+            lines.push(
+                Line::from(vec![
+                    Span::styled(format!("{:>7} ", label), Style::default().fg(text_color)),
+                    Span::styled("█".repeat(filled), Style::default().fg(bar_color)),
+                    Span::styled(
+                        "░".repeat(BAR_WIDTH - filled),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!(
+                            " {:>5.1} {} ",
+                            self.speed(bucket.avg_wpm),
+                            self.speed_unit.label()
+                        ),
+                        Style::default().fg(text_color),
+                    ),
+                    Span::styled(
+                        format!("(n={})", bucket.tests),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+
+        let points: Vec<(f64, f64)> = self
+            .length_buckets
+            .iter()
+            .map(|b| (b.min_length as f64 / 100.0, self.speed(b.avg_wpm)))
+            .collect();
+        let takeaway = match metrics::least_squares_slope(&points) {
+            Some(slope) if slope <= -0.5 => {
+                format!(
+                    "You lose ~{:.0} {} per +100 chars",
+                    -slope,
+                    self.speed_unit.label()
+                )
+            }
+            Some(slope) if slope >= 0.5 => {
+                format!(
+                    "You gain ~{:.0} {} per +100 chars",
+                    slope,
+                    self.speed_unit.label()
+                )
+            }
+            Some(_) => "Your speed holds steady as quotes get longer".to_string(),
+            None => "Type quotes of different lengths to see a trend".to_string(),
+        };
+
+        lines.push(Line::from(""));
+        lines.push(
+            Line::from(Span::styled(
+                takeaway,
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .alignment(Alignment::Center),
+        );
+        lines
+    }
+
+    /// Share of started tests thrown away, overall and per week
+    fn abandoned_lines(&self) -> Vec<Line<'static>> {
+        let ab = &self.abandonment;
+        let mut lines = vec![
             Line::from(""),
             Line::from(vec![Span::styled(
-                "───────────────────────────",
-                Style::default().fg(Color::DarkGray),
+                "ABANDONED TESTS",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )])
+            .alignment(Alignment::Center),
+            Line::from(""),
+        ];
+
+        if ab.started == 0 {
+            lines.push(
+                Line::from(Span::styled(
+                    "No tests yet",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
+            return lines;
+        }
+
+        let mut summary = format!(
+            "You abandon {:.0}% of started tests",
+            abandonment_rate(ab.started, ab.abandoned)
+        );
+        if let Some(chars) = ab.median_chars {
+            summary.push_str(&format!(
+                ", usually within the first {} characters",
+                chars.max(1)
+            ));
+        }
+        lines.push(
+            Line::from(Span::styled(
+                summary,
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .alignment(Alignment::Center),
+        );
+        lines.push(Line::from(""));
+
+        const BAR_WIDTH: usize = 20;
+        for week in &ab.weekly {
+            let rate = abandonment_rate(week.started, week.abandoned);
+            let filled = ((rate / 100.0) * BAR_WIDTH as f64).round() as usize;
+            lines.push(
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:>4} ", week.label),
+                        Style::default().fg(Color::White),
+                    ),
+                    Span::styled("█".repeat(filled), Style::default().fg(Color::Red)),
+                    Span::styled(
+                        "░".repeat(BAR_WIDTH - filled),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!(" {:>3.0}% ", rate),
+                        Style::default().fg(Color::White),
+                    ),
+                    Span::styled(
+                        format!("(n={})", week.started),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+        lines
+    }
+
+    /// Lifetime slowest bigrams as bars scaled to the slowest one
+    /// The daily or weekly table, newest period first, scrolled with ↑/↓
+    fn aggregate_lines(&self, title: &str, period_label: &str) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                title.to_string(),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )])
+            .alignment(Alignment::Center),
+            Line::from(""),
+        ];
+
+        let rows = self.table();
+        if rows.is_empty() {
+            lines.push(
+                Line::from(Span::styled(
+                    "No tests yet",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
+            return lines;
+        }
+
+        lines.push(
+            Line::from(Span::styled(
+                format!(
+                    "{:<11}{:>6}{:>9}{:>7}{:>8}{:>7}",
+                    period_label,
+                    "Tests",
+                    format!("Avg {}", self.speed_unit.label()),
+                    "Best",
+                    "Acc",
+                    "Min"
+                ),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .alignment(Alignment::Center),
+        );
+        for row in rows.iter().skip(self.table_scroll).take(AGGREGATE_ROWS) {
+            lines.push(
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:<11}", row.start.format("%Y-%m-%d")),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!("{:>6}", row.tests),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::styled(
+                        format!("{:>9.1}", self.speed(row.avg_wpm)),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(
+                        format!("{:>7.1}", self.speed(row.best_wpm)),
+                        Style::default().fg(Color::Green),
+                    ),
+                    Span::styled(
+                        format!("{:>7.1}%", row.avg_accuracy),
+                        Style::default().fg(Color::Magenta),
+                    ),
+                    Span::styled(
+                        format!("{:>7.0}", row.minutes),
+                        Style::default().fg(Color::White),
+                    ),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+        if rows.len() > AGGREGATE_ROWS {
+            lines.push(
+                Line::from(Span::styled(
+                    format!(
+                        "{}–{} of {} · ↑/↓ scroll",
+                        self.table_scroll + 1,
+                        (self.table_scroll + AGGREGATE_ROWS).min(rows.len()),
+                        rows.len()
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
+        }
+        lines
+    }
+
+    fn bigram_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "SLOWEST BIGRAMS",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
             )])
             .alignment(Alignment::Center),
             Line::from(""),
+        ];
+
+        let Some(slowest) = self.slowest_bigrams.first() else {
+            lines.push(
+                Line::from(Span::styled(
+                    "Not enough keystroke data yet",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
+            return lines;
+        };
+
+        const BAR_WIDTH: usize = 20;
+        for b in &self.slowest_bigrams {
+            let filled = ((b.avg_ms / slowest.avg_ms) * BAR_WIDTH as f64).round() as usize;
+            lines.push(
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:>3} ", b.bigram),
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled("█".repeat(filled), Style::default().fg(Color::Red)),
+                    Span::styled(
+                        "░".repeat(BAR_WIDTH - filled),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!(" {:>4.0}ms ", b.avg_ms),
+                        Style::default().fg(Color::White),
+                    ),
+                    Span::styled(
+                        format!("(n={})", b.count),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+        lines
+    }
+
+    /// Lifetime share of keystrokes per finger, left hand on top
+    /// Personal leaderboard: the fastest runs with their mode and date
+    fn leaderboard_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "PERSONAL LEADERBOARD",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )])
+            .alignment(Alignment::Center),
+            Line::from(""),
+        ];
+
+        if self.top_results.is_empty() {
+            lines.push(
+                Line::from(Span::styled(
+                    "No tests yet",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
+            return lines;
+        }
+
+        for (i, result) in self.top_results.iter().enumerate() {
+            lines.push(
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:>3}. ", i + 1),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!(
+                            "{:>6.1} {:<4} ",
+                            self.speed_unit
+                                .from_wpm(result.wpm, result.chars_per_word()),
+                            self.speed_unit.label()
+                        ),
+                        Style::default().fg(Color::Green),
+                    ),
+                    Span::styled(
+                        format!("{:>5.1}% ", result.accuracy),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::styled(
+                        format!("{:<12} ", result.mode),
+                        Style::default().fg(Color::White),
+                    ),
+                    Span::styled(
+                        result
+                            .timestamp
+                            .with_timezone(&Local)
+                            .format("%Y-%m-%d")
+                            .to_string(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+        lines
+    }
+
+    /// Every achievement, unlocked ones with their date
+    fn achievement_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                format!(
+                    "ACHIEVEMENTS · {}/{}",
+                    self.achievements.len(),
+                    ACHIEVEMENTS.len()
+                ),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )])
+            .alignment(Alignment::Center),
+            Line::from(""),
+        ];
+        for achievement in &ACHIEVEMENTS {
+            let line = match self.achievements.get(achievement.id) {
+                Some(at) => Line::from(vec![
+                    Span::styled(
+                        format!("🏆 {:<14}", achievement.name),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("{:<30}", achievement.description),
+                        Style::default().fg(Color::White),
+                    ),
+                    Span::styled(
+                        at.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]),
+                None => Line::from(vec![
+                    Span::styled(
+                        format!("🔒 {:<14}", achievement.name),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!("{:<30}", achievement.description),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled("locked    ", Style::default().fg(Color::DarkGray)),
+                ]),
+            };
+            lines.push(line.alignment(Alignment::Center));
+        }
+        lines
+    }
+
+    fn finger_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "FINGER BALANCE",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )])
+            .alignment(Alignment::Center),
+            Line::from(""),
+        ];
+
+        let usage = &self.finger_usage;
+        if usage.total() == 0 {
+            lines.push(
+                Line::from(Span::styled(
+                    "No keystrokes recorded yet",
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .alignment(Alignment::Center),
+            );
+            return lines;
+        }
+
+        lines.push(
             Line::from(vec![
-                Span::styled("Press ", Style::default().fg(Color::DarkGray)),
                 Span::styled(
-                    "ESC",
-                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                    format!("Left {:.0}%", usage.percent(usage.hand_count(Hand::Left))),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" · ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format!("Right {:.0}%", usage.percent(usage.hand_count(Hand::Right))),
+                    Style::default().fg(Color::White),
                 ),
-                Span::styled(" to go back", Style::default().fg(Color::DarkGray)),
             ])
             .alignment(Alignment::Center),
-        ];
-
-        let stats_block = Paragraph::new(stats_text).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(
-                    Style::default()
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD),
-                )
-                .title(" ═══ STATISTICS ═══ ")
-                .title_alignment(Alignment::Center),
         );
+        lines.push(Line::from(""));
 
-        frame.render_widget(stats_block, horizontal_chunks[1]);
+        // Bars are scaled to the busiest finger
+        const BAR_WIDTH: usize = 20;
+        let busiest = Finger::ALL
+            .iter()
+            .map(|&finger| usage.count(finger))
+            .chain([usage.other])
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        let rows = Finger::ALL
+            .iter()
+            .map(|&finger| (finger.name(), usage.count(finger)))
+            .chain((usage.other > 0).then_some(("other", usage.other)));
+        for (name, count) in rows {
+            let filled = (count as f64 / busiest as f64 * BAR_WIDTH as f64).round() as usize;
+            lines.push(
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:>8} ", name),
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled("█".repeat(filled), Style::default().fg(Color::Cyan)),
+                    Span::styled(
+                        "░".repeat(BAR_WIDTH - filled),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!(" {:>3.0}%", usage.percent(count)),
+                        Style::default().fg(Color::White),
+                    ),
+                ])
+                .alignment(Alignment::Center),
+            );
+        }
+        lines
     }
 }
+
+/// (speed in `unit`, accuracy) per result, averaged in runs of consecutive
+/// results so there are at most `max_points`
+fn downsample(results: &[TestResult], unit: SpeedUnit, max_points: usize) -> Vec<(f64, f64)> {
+    let per_point = results.len().div_ceil(max_points.max(1)).max(1);
+    results
+        .chunks(per_point)
+        .map(|chunk| {
+            let n = chunk.len() as f64;
+            (
+                chunk
+                    .iter()
+                    .map(|r| unit.from_wpm(r.wpm, r.chars_per_word()))
+                    .sum::<f64>()
+                    / n,
+                chunk.iter().map(|r| r.accuracy).sum::<f64>() / n,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+/// How a status message is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusLevel {
+    Info,
+    Success,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+struct Message {
+    text: String,
+    level: StatusLevel,
+    ttl: Duration,
+    /// When it reached the screen; `None` while queued behind another
+    shown_at: Option<Instant>,
+}
+
+/// Short notices drawn over the bottom row of every screen. Messages are
+/// shown one at a time, each for its own time to live, in the order they
+/// arrived. Like the scheduler, every method takes `now` explicitly.
+#[derive(Debug, Default)]
+pub struct StatusLine {
+    queue: VecDeque<Message>,
+}
+
+impl StatusLine {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Queue `text` to show for `ttl`; it appears straight away when nothing
+    /// else is showing
+    pub fn set_message(&mut self, text: String, level: StatusLevel, ttl: Duration, now: Instant) {
+        self.queue.push_back(Message {
+            text,
+            level,
+            ttl,
+            shown_at: None,
+        });
+        self.expire(now);
+    }
+
+    /// Drop messages whose time is up and show the next one in line
+    pub fn expire(&mut self, now: Instant) {
+        while let Some(front) = self.queue.front_mut() {
+            match front.shown_at {
+                None => {
+                    front.shown_at = Some(now);
+                    return;
+                }
+                Some(at) if now.saturating_duration_since(at) < front.ttl => return,
+                Some(_) => {
+                    self.queue.pop_front();
+                }
+            }
+        }
+    }
+
+    /// When the message on screen runs out
+    pub fn deadline(&self) -> Option<Instant> {
+        let front = self.queue.front()?;
+        Some(front.shown_at? + front.ttl)
+    }
+
+    pub fn current(&self) -> Option<(&str, StatusLevel)> {
+        self.queue
+            .front()
+            .filter(|message| message.shown_at.is_some())
+            .map(|message| (message.text.as_str(), message.level))
+    }
+
+    pub fn draw(&self, frame: &mut Frame, theme: &Theme) {
+        let Some((text, level)) = self.current() else {
+            return;
+        };
+        let area = frame.area();
+        if area.height == 0 {
+            return;
+        }
+        let row = Rect::new(area.x, area.bottom() - 1, area.width, 1);
+        let color = match level {
+            StatusLevel::Info => theme.title_color,
+            StatusLevel::Success => theme.success_color,
+            StatusLevel::Error => theme.error_color,
+        };
+        frame.render_widget(Clear, row);
+        frame.render_widget(
+            Paragraph::new(Line::from(format!(" {} ", text))).style(
+                Style::default()
+                    .fg(color)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            ),
+            row,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(3);
+
+    #[test]
+    fn message_expires_after_its_ttl() {
+        let start = Instant::now();
+        let mut line = StatusLine::new();
+        line.set_message("saved".to_string(), StatusLevel::Success, TTL, start);
+        assert_eq!(line.current(), Some(("saved", StatusLevel::Success)));
+        assert_eq!(line.deadline(), Some(start + TTL));
+
+        line.expire(start + TTL - Duration::from_millis(1));
+        assert_eq!(line.current(), Some(("saved", StatusLevel::Success)));
+        line.expire(start + TTL);
+        assert_eq!(line.current(), None);
+        assert_eq!(line.deadline(), None);
+    }
+
+    #[test]
+    fn messages_show_in_order_each_for_its_own_ttl() {
+        let start = Instant::now();
+        let mut line = StatusLine::new();
+        line.set_message("first".to_string(), StatusLevel::Info, TTL, start);
+        let later = start + Duration::from_secs(1);
+        let short = Duration::from_secs(1);
+        line.set_message("second".to_string(), StatusLevel::Error, short, later);
+        line.set_message("third".to_string(), StatusLevel::Info, TTL, later);
+        // Queued messages wait for the one on screen
+        assert_eq!(line.current(), Some(("first", StatusLevel::Info)));
+        assert_eq!(line.deadline(), Some(start + TTL));
+
+        // The second's clock starts when it is shown, not when it was queued
+        let shown = start + TTL;
+        line.expire(shown);
+        assert_eq!(line.current(), Some(("second", StatusLevel::Error)));
+        assert_eq!(line.deadline(), Some(shown + short));
+
+        line.expire(shown + short);
+        assert_eq!(line.current(), Some(("third", StatusLevel::Info)));
+        line.expire(shown + short + TTL);
+        assert_eq!(line.current(), None);
+    }
+
+    #[test]
+    fn a_late_expire_skips_to_the_next_message() {
+        let start = Instant::now();
+        let mut line = StatusLine::new();
+        line.set_message("first".to_string(), StatusLevel::Info, TTL, start);
+        line.set_message("second".to_string(), StatusLevel::Info, TTL, start);
+        let late = start + TTL * 10;
+        line.expire(late);
+        assert_eq!(line.current(), Some(("second", StatusLevel::Info)));
+        assert_eq!(line.deadline(), Some(late + TTL));
+    }
+}
@@ -0,0 +1,130 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+/// Widest theme name shown before it's cut short
+const NAME_WIDTH: usize = 18;
+
+/// Overlay listing every theme, built-in and user, with a few swatches each.
+/// The app previews the highlighted theme while it is open.
+pub struct ThemePicker {
+    /// Each theme by name; `None` when a user theme fails to load
+    themes: Vec<(String, Option<Theme>)>,
+    selected: usize,
+    /// The theme in use when the picker opened, restored on Escape
+    original: Theme,
+}
+
+impl ThemePicker {
+    pub fn new(current: &Theme) -> Self {
+        let themes: Vec<(String, Option<Theme>)> = Theme::available_themes()
+            .into_iter()
+            .map(|name| {
+                let theme = Theme::try_from_name(&name).ok();
+                (name, theme)
+            })
+            .collect();
+        let selected = themes
+            .iter()
+            .position(|(name, _)| name.eq_ignore_ascii_case(&current.name))
+            .unwrap_or(0);
+        Self {
+            themes,
+            selected,
+            original: current.clone(),
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.themes.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// The highlighted theme, or `None` if it couldn't be loaded
+    pub fn selected(&self) -> Option<&Theme> {
+        self.themes.get(self.selected)?.1.as_ref()
+    }
+
+    pub fn original(&self) -> &Theme {
+        &self.original
+    }
+
+    pub fn draw(&self, frame: &mut Frame, theme: &Theme) {
+        let height = (self.themes.len() as u16 + 2).min(frame.area().height);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(frame.area());
+        let [area] = Layout::horizontal([Constraint::Length(40)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        // Only the visible window is built, keeping the selection on screen
+        let rows = area.height.saturating_sub(2) as usize;
+        let offset = (self.selected + 1).saturating_sub(rows);
+        let items: Vec<ListItem> = self
+            .themes
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(rows)
+            .map(|(i, (name, theme))| {
+                let mut spans = vec![Span::raw(format!(" {:<w$.w$} ", name, w = NAME_WIDTH))];
+                match theme {
+                    Some(theme) => spans.extend(swatches(theme)),
+                    None => spans.push(Span::styled(
+                        "failed to load",
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                }
+                let style = if i == self.selected {
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(spans)).style(style)
+            })
+            .collect();
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            List::new(items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.border_color))
+                    .title(" Theme ")
+                    .title_style(Style::default().fg(theme.title_color))
+                    .title_bottom(" ↑/↓ preview · Enter keep · ESC cancel "),
+            ),
+            area,
+        );
+    }
+}
+
+/// A block in each of the theme's most telling colors
+fn swatches(theme: &Theme) -> Vec<Span<'static>> {
+    [
+        theme.correct_char,
+        theme.incorrect_char,
+        theme.untyped_char,
+        theme.cursor_bg,
+        theme.wpm_color,
+        theme.accuracy_color,
+        theme.title_color,
+    ]
+    .into_iter()
+    .map(|color| Span::styled("██", Style::default().fg(color)))
+    .collect()
+}
@@ -0,0 +1,41 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Style},
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 18;
+
+/// Replace the whole frame with a notice when the terminal is below the
+/// minimum size. Returns `true` if it did, in which case the caller should
+/// skip its normal layout.
+pub fn draw_if_too_small(frame: &mut Frame) -> bool {
+    let area = frame.area();
+    if area.width >= MIN_WIDTH && area.height >= MIN_HEIGHT {
+        return false;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Fill(1),
+            Constraint::Length(2),
+            Constraint::Fill(1),
+        ])
+        .split(area);
+
+    let message = format!(
+        "Terminal too small: need {}x{}, have {}x{}",
+        MIN_WIDTH, MIN_HEIGHT, area.width, area.height
+    );
+    frame.render_widget(
+        Paragraph::new(message)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(Color::Yellow)),
+        chunks[1],
+    );
+    true
+}
@@ -1,19 +1,131 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, LineGauge, Paragraph, Wrap},
     Frame,
 };
+use unicode_width::UnicodeWidthChar;
 
-use crate::core::typing_session::TypingSession;
-use crate::quotes::QuoteMode;
+use crate::core::metrics::SpeedUnit;
+use crate::core::typing_session::{Confidence, Difficulty, TypingSession};
+use crate::keyboard::KeyboardStyle;
+use crate::quotes::{QuoteMode, MAX_DIFFICULTY};
 use crate::theme::Theme;
-use crate::ui::keyboard::render_keyboard;
+use crate::ui::keyboard::{keyboard_height, render_keyboard, KeyShading};
+use crate::ui::marquee::{truncate_to_width, Marquee};
+use crate::ui::metric_style::{style_for_metric, Thresholds};
+use crate::ui::too_small::{draw_if_too_small, MIN_HEIGHT, MIN_WIDTH};
+
+/// Rows the quote box keeps before the keyboard gives way: three lines of text
+const MIN_QUOTE_HEIGHT: u16 = 5;
+/// Narrower than this, the next quote's preview is left out
+const MIN_PREVIEW_WIDTH: u16 = 20;
+const KEYBINDS_LINE1: &str =
+    " TAB: Mode | Ctrl+H: History | Ctrl+S: Stats | Ctrl+E: Heatmap | Ctrl+F: Keyboard ";
+const KEYBINDS_LINE2: &str =
+    " Ctrl+T: Theme | Ctrl+D: Difficulty | Ctrl+N: New Quote | Ctrl+R: Restart | `: Quit ";
+
+/// Header hints that can be clicked: (row, hint, key it presses)
+const HEADER_BUTTONS: [(u16, &str, KeyCode, KeyModifiers); 4] = [
+    (0, "TAB: Mode", KeyCode::Tab, KeyModifiers::NONE),
+    (
+        0,
+        "Ctrl+H: History",
+        KeyCode::Char('h'),
+        KeyModifiers::CONTROL,
+    ),
+    (
+        0,
+        "Ctrl+S: Stats",
+        KeyCode::Char('s'),
+        KeyModifiers::CONTROL,
+    ),
+    (
+        1,
+        "Ctrl+T: Theme",
+        KeyCode::Char('t'),
+        KeyModifiers::CONTROL,
+    ),
+];
+
+/// Where the clickable header hints sit on a typing screen filling `area`,
+/// with the key each stands for. Empty in focus mode, which has no hints.
+pub fn header_buttons(area: Rect, focus: bool) -> Vec<(Rect, KeyEvent)> {
+    if focus || area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        return Vec::new();
+    }
+    HEADER_BUTTONS
+        .iter()
+        .filter_map(|&(row, hint, code, modifiers)| {
+            let line = if row == 0 {
+                KEYBINDS_LINE1
+            } else {
+                KEYBINDS_LINE2
+            };
+            let x = line.find(hint)? as u16;
+            let rect = Rect::new(area.x + x, area.y + row, hint.len() as u16, 1);
+            Some((rect.intersection(area), KeyEvent::new(code, modifiers)))
+        })
+        .collect()
+}
+
+/// Per-frame values shown around the session on the typing screen
+pub struct TypingViewContext<'a> {
+    pub quote_source: &'a str,
+    /// Scrolls a too-long attribution
+    pub marquee: &'a Marquee,
+    /// Word count and time estimate, shown after the attribution
+    pub quote_size: Option<&'a str>,
+    /// The quote's 1–5 difficulty, shown as dots after the attribution
+    pub quote_difficulty: Option<u8>,
+    /// Quote language, shown next to the mode for quote tests
+    pub language: &'a str,
+    pub animated_wpm: f64,
+    pub speed_unit: SpeedUnit,
+    /// Colors the live accuracy
+    pub accuracy_thresholds: Thresholds,
+    /// Colors the live WPM against the mode's average; `None` without one
+    pub wpm_thresholds: Option<Thresholds>,
+    pub cursor_visible: bool,
+    pub warning: Option<&'a str>,
+    pub keyboard_style: KeyboardStyle,
+    pub show_progress: bool,
+    /// Speeds of the last few results in the chosen unit, oldest first, and
+    /// whether each beat the average; empty hides the strip
+    pub recent: &'a [(f64, bool)],
+    /// Hide everything but the stats line, progress and quote
+    pub focus: bool,
+    pub streak: u32,
+    /// Active profile, named in the header's title
+    pub profile: Option<&'a str>,
+    /// Progress towards the daily goal, e.g. "Goal: 6/10"
+    pub goal: Option<&'a str>,
+    /// The run just auto-advanced past, e.g. "✓ 84.1 WPM · 97.5%"
+    pub advance_flash: Option<&'a str>,
+    /// Runs auto-advanced past this session, e.g. "tests this session: 14, avg 87.2"
+    pub advance_tally: Option<&'a str>,
+    /// Briefly set after a wrong key when `error_feedback = "flash"`
+    pub error_flash: bool,
+    /// Keyboard labels of recent keystrokes, lit until they fade
+    pub pressed_keys: &'a [char],
+    /// Keys of the drill being typed; the keyboard dims the others
+    pub drill_keys: Option<&'a [char]>,
+    /// Character index the ghost pacer has reached, when racing one
+    pub ghost: Option<usize>,
+    /// Per character of the quote, whether it is in a word often mistyped;
+    /// empty when they aren't marked
+    pub hard_chars: &'a [bool],
+    /// Opening of the quote the next test will use, shown dimmed under the
+    /// quote box
+    pub next_quote: Option<&'a str>,
+    /// Replay status shown in the quote box's title during playback
+    pub replay: Option<&'a str>,
+}
 
 pub struct TypingView {
     show_keyboard: bool,
-    pressed_keys: Vec<char>,
     quote_mode: QuoteMode,
 }
 
@@ -21,7 +133,6 @@ impl TypingView {
     pub fn new(show_keyboard: bool, quote_mode: QuoteMode) -> Self {
         Self {
             show_keyboard,
-            pressed_keys: Vec::new(),
             quote_mode,
         }
     }
@@ -34,80 +145,216 @@ impl TypingView {
         &self,
         frame: &mut Frame,
         session: &TypingSession,
-        quote_source: &str,
         theme: &Theme,
-        animated_wpm: f64,
+        ctx: &TypingViewContext,
     ) {
-        let keyboard_height: u16 = if self.show_keyboard { 11 } else { 0 };
+        if draw_if_too_small(frame) {
+            return;
+        }
+
+        let show_keyboard = self.show_keyboard && !ctx.focus;
+        // Warnings stay visible in focus mode, the attribution doesn't
+        let show_footer = !ctx.focus || ctx.warning.is_some();
+        let header_height: u16 = if ctx.focus { 2 } else { 5 };
+        let progress_height: u16 = if ctx.show_progress { 1 } else { 0 };
+        let show_recent = !ctx.focus && !ctx.recent.is_empty();
+        let recent_height: u16 = if show_recent { 1 } else { 0 };
+        let footer_height: u16 = if show_footer { 3 } else { 0 };
+        // The keyboard shrinks, then hides, before the quote box gets too short
+        let keyboard_height: u16 = if show_keyboard {
+            let fixed = header_height + progress_height + recent_height + footer_height;
+            let room = frame.area().height.saturating_sub(fixed + MIN_QUOTE_HEIGHT);
+            keyboard_height(frame.area().width, room)
+        } else {
+            0
+        };
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
                 [
-                    Constraint::Length(5),               // header
+                    Constraint::Length(header_height),   // header
+                    Constraint::Length(progress_height), // progress (optional)
+                    Constraint::Length(recent_height),   // recent results (optional)
                     Constraint::Min(3),                  // quote
                     Constraint::Length(keyboard_height), // keyboard (optional)
-                    Constraint::Length(3),               // footer
+                    Constraint::Length(footer_height),   // footer (optional)
                 ]
                 .as_ref(),
             )
             .split(frame.area());
 
-        // Build mode string
-        let mode_str = match self.quote_mode {
-            QuoteMode::Short => "SHORT",
-            QuoteMode::Medium => "MEDIUM",
-            QuoteMode::Long => "LONG",
-        };
+        let mode_str = self.quote_mode.as_str().to_uppercase();
 
         // First line: Keybinds
         let keybinds_line1 = Line::from(vec![Span::styled(
-            " TAB: Mode | Ctrl+H: History | Ctrl+S: Stats | Ctrl+F: Keyboard ",
+            KEYBINDS_LINE1,
             Style::default().fg(Color::DarkGray),
         )]);
         // Second line: Keybinds
         let keybinds_line2 = Line::from(vec![Span::styled(
-            " Ctrl+T: Theme | Ctrl+N: New Quote | Ctrl+R: Restart | `: Quit ",
+            KEYBINDS_LINE2,
             Style::default().fg(Color::DarkGray),
         )]);
 
-        // Third line: Stats
-        let stats_line = Line::from(vec![
-            Span::styled(
-                format!(" [{}] ", mode_str),
-                Style::default()
-                    .fg(theme.mode_color)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::raw(" | "),
+        // Third line: Stats (zen has nothing to be accurate against)
+        let mut stats_spans = vec![Span::styled(
+            format!(" [{}] ", mode_str),
+            Style::default()
+                .fg(theme.mode_color)
+                .add_modifier(Modifier::BOLD),
+        )];
+        if matches!(
+            self.quote_mode,
+            QuoteMode::Short
+                | QuoteMode::Medium
+                | QuoteMode::Long
+                | QuoteMode::Code
+                | QuoteMode::Drill
+        ) {
+            stats_spans.push(Span::styled(
+                format!("[{}] ", ctx.language.to_uppercase()),
+                Style::default().fg(theme.mode_color),
+            ));
+        }
+        stats_spans.extend([
             Span::styled(
-                format!(" WPM: {:>5.1} ", animated_wpm),
-                Style::default().fg(theme.wpm_color),
+                format!("[{}] ", session.difficulty().as_str().to_uppercase()),
+                Style::default().fg(match session.difficulty() {
+                    Difficulty::Normal => Color::DarkGray,
+                    Difficulty::Stop => theme.mode_color,
+                    Difficulty::Master => theme.error_color,
+                }),
             ),
-            Span::raw(" | "),
             Span::styled(
-                format!(" Acc: {:>5.1}% ", session.accuracy()),
-                Style::default().fg(theme.accuracy_color),
+                match session.confidence() {
+                    Confidence::Off => String::new(),
+                    confidence => format!("[CONFIDENCE {}] ", confidence.as_str().to_uppercase()),
+                },
+                Style::default().fg(theme.mode_color),
             ),
             Span::raw(" | "),
             Span::styled(
-                format!(" Errors: {} ", session.mistakes()),
-                Style::default().fg(theme.error_color),
+                format!(
+                    " {}: {:>5.1} ",
+                    ctx.speed_unit.label(),
+                    ctx.speed_unit
+                        .from_wpm(ctx.animated_wpm, session.chars_per_word())
+                ),
+                // Plain until typing starts, rather than red at zero
+                match ctx.wpm_thresholds {
+                    Some(thresholds) if session.is_started() => {
+                        style_for_metric(ctx.animated_wpm, thresholds, theme)
+                    }
+                    _ => Style::default().fg(theme.wpm_color),
+                },
             ),
         ]);
+        if !session.is_zen() {
+            stats_spans.extend([
+                Span::raw(" | "),
+                Span::styled(
+                    format!(" Acc: {:>5.1}% ", session.accuracy()),
+                    style_for_metric(session.accuracy(), ctx.accuracy_thresholds, theme),
+                ),
+                Span::raw(" | "),
+                Span::styled(
+                    format!(" Errors: {} ", session.mistakes()),
+                    Style::default().fg(theme.error_color),
+                ),
+            ]);
+        }
+        if let Some(ghost) = ctx.ghost {
+            let gap = session.typed().chars().count() as i64 - ghost as i64;
+            let (text, color) = match gap {
+                0 => ("level with ghost".to_string(), Color::DarkGray),
+                1.. => (format!("+{} chars ahead", gap), theme.success_color),
+                _ => (format!("−{} behind", -gap), theme.error_color),
+            };
+            stats_spans.push(Span::raw(" | "));
+            stats_spans.push(Span::styled(
+                format!(" {} ", text),
+                Style::default().fg(color),
+            ));
+        }
+        if session.is_afk() {
+            let note = if session.is_afk_discarded() {
+                " test abandoned (AFK) "
+            } else {
+                " paused (AFK) "
+            };
+            stats_spans.push(Span::raw(" | "));
+            stats_spans.push(Span::styled(
+                note,
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::ITALIC),
+            ));
+        }
+        if let Some(flash) = ctx.advance_flash {
+            stats_spans.push(Span::raw(" | "));
+            stats_spans.push(Span::styled(
+                format!(" {} ", flash),
+                Style::default()
+                    .fg(theme.success_color)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        let stats_line = Line::from(stats_spans);
 
-        // Combine both lines
-        let header_text = vec![keybinds_line1, keybinds_line2, stats_line];
+        // Focus mode keeps only the stats
+        let header_text = if ctx.focus {
+            vec![stats_line]
+        } else {
+            vec![keybinds_line1, keybinds_line2, stats_line]
+        };
 
-        let header = Paragraph::new(header_text).block(
-            Block::default()
-                .borders(Borders::BOTTOM)
-                .title(" TUItype ")
-                .title_style(Style::default().fg(theme.title_color)),
-        );
+        let title = match ctx.profile {
+            Some(profile) => format!(" TUItype · {} ", profile),
+            None => " TUItype ".to_string(),
+        };
+        let mut header_block = Block::default()
+            .borders(Borders::BOTTOM)
+            .title(title)
+            .title_style(Style::default().fg(theme.title_color));
+        if let Some(goal) = ctx.goal {
+            header_block = header_block.title(
+                Line::from(Span::styled(
+                    format!(" {} ", goal),
+                    Style::default().fg(theme.success_color),
+                ))
+                .right_aligned(),
+            );
+        }
+        if let Some(tally) = ctx.advance_tally {
+            header_block = header_block.title(
+                Line::from(Span::styled(
+                    format!(" {} ", tally),
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .right_aligned(),
+            );
+        }
+        if ctx.streak > 0 {
+            header_block = header_block.title(
+                Line::from(Span::styled(
+                    format!(" 🔥 {} ", ctx.streak),
+                    Style::default().fg(theme.mode_color),
+                ))
+                .right_aligned(),
+            );
+        }
+        let header = Paragraph::new(header_text).block(header_block);
         frame.render_widget(header, chunks[0]);
 
-        let quote_area = chunks[1];
+        if ctx.show_progress {
+            render_progress(frame, chunks[1], session, theme);
+        }
+        if show_recent {
+            render_recent(frame, chunks[2], ctx.recent, theme);
+        }
+
+        let quote_area = chunks[3];
         let horizontal_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
@@ -126,7 +373,15 @@ impl TypingView {
             ])
             .split(horizontal_chunks[1]);
 
-        let quote_spans = render_quote(session, theme);
+        let quote_text = render_quote(
+            session,
+            theme,
+            ctx.cursor_visible,
+            ctx.ghost,
+            ctx.hard_chars,
+        );
+        // Code keeps its line breaks and indentation, so it isn't centered or trimmed
+        let is_code = session.quote().contains('\n');
 
         // Calculate scroll to keep cursor visible
         let inner_width = vertical_chunks[1].width.saturating_sub(2); // subtract borders
@@ -134,74 +389,206 @@ impl TypingView {
         let height = vertical_chunks[1].height.saturating_sub(2); // subtract borders
 
         // Center the cursor
-        let scroll_offset = if cursor_row > height / 2 {
-            cursor_row - height / 2
-        } else {
-            0
-        };
+        let scroll_offset = cursor_row.saturating_sub(height / 2);
 
-        let quote_block = Paragraph::new(quote_spans)
+        let quote_block = Paragraph::new(quote_text)
             .scroll((scroll_offset, 0))
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .border_style(
+                    .border_style(if ctx.error_flash {
+                        // Only the border inverts, the text and cursor are untouched
+                        Style::default()
+                            .fg(theme.error_color)
+                            .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+                    } else {
                         Style::default()
                             .fg(theme.border_color)
-                            .add_modifier(Modifier::BOLD),
-                    )
-                    .title(" ═══ QUOTE ═══ ")
+                            .add_modifier(Modifier::BOLD)
+                    })
+                    .title(match ctx.replay {
+                        Some(status) => format!(" ═══ {} ═══ ", status),
+                        None => " ═══ QUOTE ═══ ".to_string(),
+                    })
                     .title_style(Style::default().fg(theme.title_color))
                     .title_alignment(Alignment::Center),
             )
-            .alignment(Alignment::Center)
-            .wrap(Wrap { trim: true })
+            .alignment(if is_code {
+                Alignment::Left
+            } else {
+                Alignment::Center
+            })
+            .wrap(Wrap { trim: !is_code })
             .style(Style::default().add_modifier(Modifier::BOLD));
 
         frame.render_widget(quote_block, vertical_chunks[1]);
 
-        // Footer with quote source
-        let footer = Paragraph::new(format!("Source: {}", quote_source))
+        // The next quote's opening, on the first row under the box when it fits
+        let below = vertical_chunks[2];
+        if let Some(next) = ctx.next_quote
+            && !ctx.focus
+            && below.height > 0
+            && below.width >= MIN_PREVIEW_WIDTH
+        {
+            frame.render_widget(
+                Paragraph::new(truncate_to_width(
+                    &format!("next: {}", next),
+                    below.width as usize,
+                ))
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(Color::DarkGray)),
+                Rect { height: 1, ..below },
+            );
+        }
+
+        // Footer with quote source, replaced by a warning when there is one
+        let footer = match ctx.warning {
+            Some(warning) => Paragraph::new(format!("Warning: {}", warning))
+                .block(
+                    Block::default()
+                        .borders(Borders::TOP)
+                        .title("Warning ")
+                        .title_style(Style::default().fg(theme.error_color)),
+                )
+                .style(Style::default().fg(theme.error_color)),
+            None => {
+                let mut text = format!("Source: {}", ctx.quote_source);
+                if let Some(size) = ctx.quote_size {
+                    text.push_str(&format!(" · {}", size));
+                }
+                if let Some(level) = ctx.quote_difficulty {
+                    text.push_str(&format!(" · {}", difficulty_dots(level)));
+                }
+                Paragraph::new(ctx.marquee.show(&text, chunks[5].width as usize))
+            }
             .block(
                 Block::default()
                     .borders(Borders::TOP)
                     .title("Quote Attribution ")
                     .title_style(Style::default().fg(theme.title_color)),
             )
-            .style(Style::default().fg(Color::DarkGray));
+            .style(Style::default().fg(Color::DarkGray)),
+        };
 
-        frame.render_widget(footer, chunks[3]);
+        if show_footer {
+            frame.render_widget(footer, chunks[5]);
+        }
 
-        if self.show_keyboard {
-            let next_char = session.quote().chars().nth(session.typed().len());
+        if show_keyboard {
+            let next_char = session.expected_char();
             render_keyboard(
-                chunks[2],
+                chunks[4],
                 frame.buffer_mut(),
                 next_char,
-                &self.pressed_keys,
+                ctx.pressed_keys,
+                match ctx.drill_keys {
+                    Some(keys) => KeyShading::Only(keys),
+                    None => KeyShading::Fingers,
+                },
+                ctx.keyboard_style,
                 theme,
             );
         }
     }
 }
 
-fn render_quote<'a>(session: &'a TypingSession, theme: &'a Theme) -> Line<'a> {
+/// Percentage bar and "word n/m" under the header; zen only counts words
+fn render_progress(frame: &mut Frame, area: Rect, session: &TypingSession, theme: &Theme) {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(20),
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+        ])
+        .split(area);
+
+    if session.is_zen() {
+        let words = Paragraph::new(format!("word {}", session.word_index()))
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(words, columns[1]);
+        return;
+    }
+
+    let gauge = LineGauge::default()
+        .ratio(session.progress())
+        .label(format!(
+            "{:>3.0}%  word {}/{}",
+            session.progress() * 100.0,
+            session.word_index(),
+            session.word_count()
+        ))
+        .filled_style(Style::default().fg(theme.correct_char))
+        .unfilled_style(Style::default().fg(theme.untyped_char));
+    frame.render_widget(gauge, columns[1]);
+}
+
+/// "prev: 84 · 91 · 78", oldest first, each green or red against the average
+fn render_recent(frame: &mut Frame, area: Rect, recent: &[(f64, bool)], theme: &Theme) {
+    let mut spans = vec![Span::styled("prev: ", Style::default().fg(Color::DarkGray))];
+    for (i, &(speed, above_average)) in recent.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(" · ", Style::default().fg(Color::DarkGray)));
+        }
+        spans.push(Span::styled(
+            format!("{:.0}", speed),
+            Style::default().fg(if above_average {
+                theme.success_color
+            } else {
+                theme.error_color
+            }),
+        ));
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).alignment(Alignment::Center),
+        area,
+    );
+}
+
+fn render_quote<'a>(
+    session: &'a TypingSession,
+    theme: &'a Theme,
+    cursor_visible: bool,
+    ghost: Option<usize>,
+    hard_chars: &[bool],
+) -> Text<'a> {
     let mut line = Line::default();
 
+    if session.is_zen() {
+        // No expected text: show what was typed with the cursor after it
+        line.spans.push(Span::styled(
+            session.typed(),
+            Style::default().fg(theme.correct_char),
+        ));
+        if cursor_visible && !session.is_complete() {
+            line.spans.push(Span::styled(
+                " ",
+                Style::default()
+                    .fg(theme.cursor_fg)
+                    .bg(theme.cursor_bg)
+                    .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            ));
+        }
+        return Text::from(line);
+    }
+    let mut lines = Vec::new();
+
     let quote_chars: Vec<char> = session.quote().chars().collect();
     let typed_chars: Vec<char> = session.typed().chars().collect();
-    let len = quote_chars.len();
 
-    for i in 0..len {
-        let expected = quote_chars[i];
+    for (i, &expected) in quote_chars.iter().enumerate() {
         let typed = typed_chars.get(i).copied();
 
         let (ch_to_show, style) = match typed {
             Some(c) => {
                 if expected == ' ' && c != ' ' {
-                    // SPECIAL CASE: space expected, wrong char typed
+                    // SPECIAL CASE: space expected, wrong char typed. A wide
+                    // glyph would shift the wrapping `calculate_cursor_row`
+                    // measured on the quote, so those show as the space.
+                    let shown = if c.width() == Some(1) { c } else { expected };
                     (
-                        c,
+                        shown,
                         Style::default()
                             .fg(theme.incorrect_char)
                             .add_modifier(Modifier::BOLD),
@@ -219,6 +606,16 @@ fn render_quote<'a>(session: &'a TypingSession, theme: &'a Theme) -> Line<'a> {
                     )
                 }
             }
+            None if hard_chars.get(i) == Some(&true) => {
+                // Not yet typed, in a word often mistyped
+                (
+                    expected,
+                    Style::default()
+                        .fg(theme.untyped_char)
+                        .add_modifier(Modifier::UNDERLINED)
+                        .underline_color(theme.caution_char),
+                )
+            }
             None => {
                 // Not yet typed
                 (expected, Style::default().fg(theme.untyped_char))
@@ -226,31 +623,85 @@ fn render_quote<'a>(session: &'a TypingSession, theme: &'a Theme) -> Line<'a> {
         };
 
         // Cursor highlight on next char to type
-        let style = if i == typed_chars.len() && !session.is_complete() {
+        let style = if i == typed_chars.len() && !session.is_complete() && cursor_visible {
             style
                 .fg(theme.cursor_fg)
                 .bg(theme.cursor_bg)
                 .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        } else if ghost == Some(i) {
+            // The ghost pacer: an underline in the mode color
+            style
+                .add_modifier(Modifier::UNDERLINED)
+                .underline_color(theme.mode_color)
         } else {
             style
         };
 
+        // A newline shows as ↵ at the end of its line, then breaks it
+        if expected == '\n' {
+            line.spans.push(Span::styled("↵", style));
+            lines.push(std::mem::take(&mut line));
+            continue;
+        }
         line.spans.push(Span::styled(ch_to_show.to_string(), style));
     }
 
-    line
+    // Characters typed past the end of the quote, followed by the cursor
+    let extras = session.extra_chars();
+    if !extras.is_empty() {
+        line.spans.push(Span::styled(
+            extras,
+            Style::default()
+                .fg(theme.incorrect_char)
+                .add_modifier(Modifier::BOLD | Modifier::CROSSED_OUT),
+        ));
+    }
+    if typed_chars.len() >= quote_chars.len() && !session.is_complete() && cursor_visible {
+        line.spans.push(Span::styled(
+            " ",
+            Style::default()
+                .fg(theme.cursor_fg)
+                .bg(theme.cursor_bg)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        ));
+    }
+
+    lines.push(line);
+    Text::from(lines)
 }
 
 fn calculate_cursor_row(session: &TypingSession, width: usize) -> u16 {
     if width < 2 {
         return 0;
     }
-    let cursor = session.typed().len();
+    let cursor = session.typed().chars().count();
 
+    // In zen mode the typed text is the only text on screen
+    let text = if session.is_zen() {
+        session.typed()
+    } else {
+        session.quote()
+    };
+
+    // Newlines are hard breaks; each one ends its line as a one-cell ↵
     let mut row = 0;
-    let mut line_len = 0;
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let chars: Vec<char> = line.chars().collect();
+        let end = start + chars.len();
+        if cursor < end || (cursor == end && !line.ends_with('\n')) {
+            return row + wrapped_row(&chars, cursor - start, width);
+        }
+        row += wrapped_row(&chars, chars.len(), width) + 1;
+        start = end;
+    }
+    row.saturating_sub(1)
+}
 
-    let chars: Vec<char> = session.quote().chars().collect();
+/// Row of `cursor` within one line of text that word-wraps at `width`
+fn wrapped_row(chars: &[char], cursor: usize, width: usize) -> u16 {
+    let mut row = 0;
+    let mut line_len = 0;
     let mut i = 0;
 
     while i < chars.len() {
@@ -260,7 +711,12 @@ fn calculate_cursor_row(session: &TypingSession, width: usize) -> u16 {
             i += 1;
         }
         let end = i;
-        let word_len = end - start;
+        // Display width, not char count: CJK and other wide glyphs take two
+        // cells, and a trailing newline is drawn as a one-cell ↵
+        let word_len: usize = chars[start..end]
+            .iter()
+            .map(|&c| if c == '\n' { 1 } else { c.width().unwrap_or(0) })
+            .sum();
 
         // Calculate if word fits
         // Space is needed if not start of line
@@ -289,11 +745,67 @@ fn calculate_cursor_row(session: &TypingSession, width: usize) -> u16 {
 
         // Check cursor (spaces)
         // If cursor is in the spaces we just skipped (start was `end`, now `i`)
-        // Range (end, i]
-        if cursor > end && cursor <= i {
+        // Range (end, i); at `i` it is on the next word, which may wrap
+        if cursor > end && (cursor < i || i == chars.len()) {
             return row;
         }
     }
 
     row
 }
+
+/// "●●●○○" for a quote of difficulty 3
+fn difficulty_dots(level: u8) -> String {
+    (1..=MAX_DIFFICULTY)
+        .map(|i| if i <= level { '●' } else { '○' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn wide_word_wraps_by_display_width() {
+        // "漢" is one char but two cells: it fits with two cells left on the
+        // row and wraps with only one, where a char count would let it stay
+        let fits = chars("abcdefg 漢");
+        assert_eq!(wrapped_row(&fits, 8, 10), 0);
+        let wraps = chars("abcdefgh 漢");
+        assert_eq!(wrapped_row(&wraps, 8, 10), 0);
+        assert_eq!(wrapped_row(&wraps, 9, 10), 1);
+        assert_eq!(wrapped_row(&wraps, 10, 10), 1);
+
+        // Two-glyph words, four cells each
+        let words = chars("漢字 漢字 漢字");
+        for (cursor, row) in [(0, 0), (2, 0), (3, 0), (5, 0), (6, 1), (8, 1)] {
+            assert_eq!(wrapped_row(&words, cursor, 9), row, "cursor {}", cursor);
+        }
+    }
+
+    #[test]
+    fn dashes_and_curly_quotes_take_one_cell() {
+        // "“hi—”" is five cells, ending exactly on the edge at width 11
+        let text = chars("abcde “hi—”");
+        assert_eq!(wrapped_row(&text, 10, 11), 0);
+        assert_eq!(wrapped_row(&text, 11, 11), 0);
+        assert_eq!(wrapped_row(&text, 6, 10), 1);
+        assert_eq!(wrapped_row(&text, 11, 10), 1);
+    }
+
+    #[test]
+    fn cursor_row_after_a_line_break_into_wide_glyphs() {
+        let mut session = TypingSession::new("ab\n漢字 漢字".to_string());
+        let mut rows = vec![calculate_cursor_row(&session, 6)];
+        for c in "ab\n漢字 漢字".chars() {
+            session.type_char(c);
+            rows.push(calculate_cursor_row(&session, 6));
+        }
+        // The second line is nine cells, so its second word wraps at six
+        assert_eq!(rows, [0, 0, 0, 1, 1, 1, 2, 2, 2]);
+    }
+}
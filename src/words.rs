@@ -0,0 +1,83 @@
+use rand::prelude::*;
+
+/// Characters a generated test aims for, about a medium quote
+pub const PRACTICE_LENGTH: usize = 200;
+
+/// Fewest distinct keys a drill can make varied words from
+pub const MIN_DRILL_KEYS: usize = 4;
+/// Length range of a drill's pseudo-words
+const DRILL_WORD_LENGTH: std::ops::RangeInclusive<usize> = 3..=7;
+
+/// Shuffled copies of `words` joined by spaces until the text reaches
+/// `target_len` characters. A word never follows itself across copies.
+pub fn practice_text(words: &[String], target_len: usize) -> String {
+    let mut rng = rand::rng();
+    let mut text = String::new();
+    let mut last: Option<&String> = None;
+    while !words.is_empty() && text.chars().count() < target_len {
+        let mut round: Vec<&String> = words.iter().collect();
+        round.shuffle(&mut rng);
+        if round.len() > 1 && round.first() == last.as_ref() {
+            round.swap(0, 1);
+        }
+        for word in round {
+            if text.chars().count() >= target_len {
+                break;
+            }
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(word);
+            last = Some(word);
+        }
+    }
+    text
+}
+
+/// `n` words picked at random from `texts`, lowercased and without
+/// surrounding punctuation
+pub fn random_words<'a>(texts: impl IntoIterator<Item = &'a str>, n: usize) -> Vec<String> {
+    let mut pool: Vec<String> = texts
+        .into_iter()
+        .flat_map(str::split_whitespace)
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    pool.sort_unstable();
+    pool.dedup();
+    pool.choose_multiple(&mut rand::rng(), n).cloned().collect()
+}
+
+/// Pseudo-words of 3 to 7 characters drawn from `keys`, joined by spaces
+/// until the text reaches `target_len` characters. A key never repeats
+/// straight after itself, so the words stay typeable rather than stuttering.
+pub fn drill_text(keys: &[char], target_len: usize) -> anyhow::Result<String> {
+    if keys.len() < MIN_DRILL_KEYS {
+        anyhow::bail!(
+            "a drill needs at least {} different keys, got {}",
+            MIN_DRILL_KEYS,
+            keys.len()
+        );
+    }
+    let mut rng = rand::rng();
+    let mut text = String::new();
+    while text.chars().count() < target_len {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        let mut last = None;
+        for _ in 0..rng.random_range(DRILL_WORD_LENGTH) {
+            let key = *keys
+                .iter()
+                .filter(|&&key| Some(key) != last)
+                .choose(&mut rng)
+                .expect("a drill has several keys");
+            text.push(key);
+            last = Some(key);
+        }
+    }
+    Ok(text)
+}
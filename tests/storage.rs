@@ -0,0 +1,61 @@
+//! Results saved and read back through the public storage API
+
+use tuitype::core::typing_session::TypingSession;
+use tuitype::models::{SortOrder, TestResult};
+use tuitype::storage::db::Database;
+
+fn finished(text: &str, mode: &str) -> TestResult {
+    let mut session = TypingSession::new(text.to_string());
+    for c in text.chars() {
+        session.type_char(c);
+    }
+    session.final_result(mode).unwrap()
+}
+
+#[test]
+fn saved_results_read_back() {
+    let db = Database::open_in_memory().unwrap();
+    let short = finished("a short one", "short");
+    let medium = finished("a somewhat longer medium one", "medium");
+    let short_id = db.save_result(&short).unwrap();
+    db.save_result(&medium).unwrap();
+
+    let all = db
+        .get_results_filtered(None, None, SortOrder::Date, 10, 0)
+        .unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(db.count_results(None).unwrap(), 2);
+
+    let shorts = db
+        .get_results_filtered(Some("short"), None, SortOrder::Date, 10, 0)
+        .unwrap();
+    assert_eq!(shorts.len(), 1);
+    let read = &shorts[0];
+    assert_eq!(read.id, Some(short_id));
+    assert_eq!(read.mode, "short");
+    assert_eq!(read.quote_length, short.quote_length);
+    assert_eq!(read.accuracy, short.accuracy);
+    assert_eq!(
+        read.timestamp.timestamp_millis(),
+        short.timestamp.timestamp_millis()
+    );
+}
+
+#[test]
+fn stats_cover_every_saved_result() {
+    let db = Database::open_in_memory().unwrap();
+    assert_eq!(db.get_stats(None).unwrap().total_tests, 0);
+    db.save_results(&[finished("one", "short"), finished("two", "short")])
+        .unwrap();
+    let stats = db.get_stats(None).unwrap();
+    assert_eq!(stats.total_tests, 2);
+    assert_eq!(stats.avg_accuracy, 100.0);
+}
+
+#[test]
+fn deleted_results_are_gone() {
+    let db = Database::open_in_memory().unwrap();
+    let id = db.save_result(&finished("gone soon", "short")).unwrap();
+    db.delete_result(id).unwrap();
+    assert_eq!(db.count_results(None).unwrap(), 0);
+}
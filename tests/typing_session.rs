@@ -0,0 +1,68 @@
+//! A test driven through the public API from start to result
+
+use tuitype::core::typing_session::TypingSession;
+
+fn type_str(session: &mut TypingSession, text: &str) {
+    for c in text.chars() {
+        session.type_char(c);
+    }
+}
+
+#[test]
+fn clean_run_scores_full_accuracy() {
+    let text = "the quick brown fox";
+    let mut session = TypingSession::new(text.to_string());
+    assert!(!session.is_complete());
+    assert!(session.final_result("custom").is_none());
+
+    type_str(&mut session, text);
+    assert!(session.is_complete());
+    assert_eq!(session.progress(), 1.0);
+
+    let result = session.final_result("custom").unwrap();
+    assert_eq!(result.mode, "custom");
+    assert_eq!(result.accuracy, 100.0);
+    assert_eq!(result.real_accuracy, Some(100.0));
+    assert_eq!(result.quote_length, text.chars().count() as i64);
+    assert_eq!(result.word_count, Some(4));
+    assert!(result.id.is_none());
+}
+
+#[test]
+fn corrected_typo_counts_only_towards_real_accuracy() {
+    let text = "hello world";
+    let mut session = TypingSession::new(text.to_string());
+    type_str(&mut session, "hx");
+    session.backspace();
+    type_str(&mut session, &text[1..]);
+    assert!(session.is_complete());
+
+    let result = session.final_result("custom").unwrap();
+    assert_eq!(result.accuracy, 100.0);
+    let real = result.real_accuracy.unwrap();
+    assert!(real < 100.0, "{}", real);
+}
+
+#[test]
+fn deleted_word_can_be_retyped() {
+    let text = "one two three";
+    let mut session = TypingSession::new(text.to_string());
+    type_str(&mut session, "one twx");
+    session.delete_word();
+    assert_eq!(session.typed(), "one ");
+    type_str(&mut session, "two three");
+    assert!(session.is_complete());
+    assert_eq!(session.final_result("custom").unwrap().accuracy, 100.0);
+}
+
+#[test]
+fn expected_char_walks_the_quote() {
+    let mut session = TypingSession::new("añb".to_string());
+    assert_eq!(session.expected_char(), Some('a'));
+    session.type_char('a');
+    assert_eq!(session.expected_char(), Some('ñ'));
+    session.type_char('ñ');
+    assert_eq!(session.expected_char(), Some('b'));
+    session.type_char('b');
+    assert_eq!(session.expected_char(), None);
+}